@@ -0,0 +1,48 @@
+/*
+Delete the source entry of a relation join table row when its target entry was deleted.
+source_table: Name of the table containing the relation field
+target_table: Name of the table referenced by the relation field
+*/
+CREATE OR REPLACE FUNCTION relation_cascade_delete()
+RETURNS TRIGGER AS $$
+DECLARE
+    source_table TEXT := TG_ARGV[0];
+    target_table TEXT := TG_ARGV[1];
+BEGIN
+    EXECUTE format(
+        'DELETE FROM %1$s
+        WHERE entry_id = $1
+            AND NOT EXISTS (
+                SELECT 1 FROM %2$s
+                WHERE entry_id = $2
+            )',
+        source_table, target_table
+    )
+    USING OLD.source_id, OLD.target_id;
+
+    RETURN OLD;
+END;
+$$ LANGUAGE plpgsql;
+
+/*
+Create the relation_cascade_delete trigger.
+table_name: Name of the relation join table for the trigger
+source_table: Name of the table containing the relation field
+target_table: Name of the table referenced by the relation field
+*/
+CREATE OR REPLACE FUNCTION trigger_relation_cascade_delete(
+    table_name TEXT,
+    source_table TEXT,
+    target_table TEXT
+) RETURNS VOID AS
+$$
+BEGIN
+    EXECUTE format('
+        CREATE OR REPLACE TRIGGER relation_cascade_delete
+        AFTER DELETE
+        ON %1$s
+        FOR EACH ROW
+        EXECUTE FUNCTION relation_cascade_delete(%2$L, %3$L);
+    ', table_name, source_table, target_table);
+end;
+$$ language plpgsql;
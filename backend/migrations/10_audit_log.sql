@@ -0,0 +1,47 @@
+/*
+Kind of change recorded in the audit log.
+*/
+DO $$ BEGIN
+    CREATE TYPE audit_action AS ENUM (
+        'Create',
+        'Update',
+        'Delete'
+    );
+EXCEPTION
+    WHEN duplicate_object THEN null;
+END $$;
+
+
+/*
+Kind of resource changed in the audit log.
+*/
+DO $$ BEGIN
+    CREATE TYPE audit_resource AS ENUM (
+        'Table',
+        'Field',
+        'Entry'
+    );
+EXCEPTION
+    WHEN duplicate_object THEN null;
+END $$;
+
+/*
+History of the changes made to user tables, fields and entries.
+Records are kept after the table is deleted.
+old_value and new_value contain the table, the field or the cells of the entry
+before and after the change.
+*/
+CREATE TABLE IF NOT EXISTS audit_log (
+    audit_id SERIAL PRIMARY KEY,
+    table_id INT NOT NULL,
+    resource audit_resource NOT NULL,
+    resource_id INT NOT NULL,
+    action audit_action NOT NULL,
+    user_id INT REFERENCES app_user(user_id) ON DELETE SET NULL,
+    old_value JSONB,
+    new_value JSONB,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS audit_log_table_idx ON audit_log (table_id, audit_id);
+CREATE INDEX IF NOT EXISTS audit_log_resource_idx ON audit_log (resource, resource_id);
@@ -0,0 +1,37 @@
+/*
+Set when the table is moved to the trash. Child tables are trashed along with
+their parent and share its deleted_at.
+*/
+ALTER TABLE meta_table ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMPTZ;
+
+/*
+Trashed tables are hidden from every user until they are restored.
+*/
+CREATE OR REPLACE VIEW meta_table_access_v AS
+SELECT a.user_id, a.resource_id, a.access_role
+FROM meta_table_access AS a
+JOIN meta_table AS t
+ON a.resource_id = t.table_id
+WHERE t.deleted_at IS NULL;
+
+/*
+Tables, fields and entries deleted by users. They can be restored until they
+are purged after the retention period.
+Trashed tables keep their SQL table and trashed fields keep their SQL column.
+Trashed entries are deleted from the SQL table and snapshot holds the row.
+value contains the table, the field or the cells of the entry for display.
+*/
+CREATE TABLE IF NOT EXISTS trash (
+    trash_id SERIAL PRIMARY KEY,
+    table_id INT NOT NULL REFERENCES meta_table(table_id) ON DELETE CASCADE,
+    resource audit_resource NOT NULL,
+    resource_id INT NOT NULL,
+    name TEXT,
+    value JSONB NOT NULL,
+    snapshot JSONB,
+    deleted_by INT REFERENCES app_user(user_id) ON DELETE SET NULL,
+    deleted_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    UNIQUE (table_id, resource, resource_id)
+);
+
+CREATE INDEX IF NOT EXISTS trash_deleted_at_idx ON trash (deleted_at);
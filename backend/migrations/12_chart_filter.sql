@@ -0,0 +1,15 @@
+/*
+A predicate on a field of the chart's table. The filters of a chart are
+combined with AND in the WHERE clause of its SQL view.
+operator holds the filter operator and its values as JSON.
+*/
+CREATE TABLE IF NOT EXISTS chart_filter (
+    chart_filter_id SERIAL PRIMARY KEY,
+    chart_id INT NOT NULL REFERENCES chart(chart_id) ON DELETE CASCADE,
+    field_id INT NOT NULL REFERENCES meta_field(field_id),
+    operator JSONB NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at TIMESTAMPTZ
+);
+
+SELECT trigger_updated_at('chart_filter');
@@ -0,0 +1,6 @@
+/*
+Time bucket of an axis on a DateTime field. The timestamps are truncated to
+the start of the bucket in the time zone before being grouped or aggregated.
+bucket holds the unit and the time zone as JSON.
+*/
+ALTER TABLE axis ADD COLUMN IF NOT EXISTS bucket JSONB;
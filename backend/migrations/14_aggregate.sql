@@ -0,0 +1,19 @@
+/*
+Aggregates added after the initial set.
+*/
+ALTER TYPE aggregate ADD VALUE IF NOT EXISTS 'CountDistinct';
+ALTER TYPE aggregate ADD VALUE IF NOT EXISTS 'Median';
+ALTER TYPE aggregate ADD VALUE IF NOT EXISTS 'Percentile';
+ALTER TYPE aggregate ADD VALUE IF NOT EXISTS 'StdDev';
+ALTER TYPE aggregate ADD VALUE IF NOT EXISTS 'Variance';
+ALTER TYPE aggregate ADD VALUE IF NOT EXISTS 'First';
+ALTER TYPE aggregate ADD VALUE IF NOT EXISTS 'Last';
+
+/*
+Parameters of the aggregate of an axis.
+percentile is the percentile of the Percentile aggregate, between 0 and 100.
+order_field_id is the DateTime field ordering the rows of the First and Last
+aggregates.
+*/
+ALTER TABLE axis ADD COLUMN IF NOT EXISTS percentile SMALLINT;
+ALTER TABLE axis ADD COLUMN IF NOT EXISTS order_field_id INT REFERENCES meta_field(field_id);
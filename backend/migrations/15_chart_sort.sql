@@ -0,0 +1,12 @@
+/*
+Sort of an axis. The rows of the chart view are ordered by the sorted axes by
+ascending priority.
+sort holds the direction and the priority as JSON.
+*/
+ALTER TABLE axis ADD COLUMN IF NOT EXISTS sort JSONB;
+
+/*
+Only the first top_n rows of the chart view are kept and the rows of the
+remaining groups are aggregated into a single Other row.
+*/
+ALTER TABLE chart ADD COLUMN IF NOT EXISTS top_n INT;
@@ -0,0 +1,19 @@
+/*
+Chart kinds added after the initial set.
+*/
+ALTER TYPE chart_kind ADD VALUE IF NOT EXISTS 'Area';
+ALTER TYPE chart_kind ADD VALUE IF NOT EXISTS 'StackedBar';
+ALTER TYPE chart_kind ADD VALUE IF NOT EXISTS 'Pie';
+ALTER TYPE chart_kind ADD VALUE IF NOT EXISTS 'Donut';
+ALTER TYPE chart_kind ADD VALUE IF NOT EXISTS 'Scatter';
+ALTER TYPE chart_kind ADD VALUE IF NOT EXISTS 'Histogram';
+ALTER TYPE chart_kind ADD VALUE IF NOT EXISTS 'Heatmap';
+ALTER TYPE chart_kind ADD VALUE IF NOT EXISTS 'Kpi';
+
+ALTER TYPE axis_kind ADD VALUE IF NOT EXISTS 'Detail';
+
+/*
+Number of equal-width bins between the minimum and the maximum of the X axis
+of a Histogram chart.
+*/
+ALTER TABLE axis ADD COLUMN IF NOT EXISTS bins INT;
@@ -0,0 +1,10 @@
+/*
+Names of the tabs of a dashboard in display order.
+*/
+ALTER TABLE dashboard ADD COLUMN IF NOT EXISTS tabs TEXT[] NOT NULL DEFAULT '{}';
+
+/*
+Position and size of a chart on the dashboard grid, optionally in a tab.
+layout holds x, y, width, height and tab as JSON and is NULL until the chart is placed.
+*/
+ALTER TABLE chart ADD COLUMN IF NOT EXISTS layout JSONB;
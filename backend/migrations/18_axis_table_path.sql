@@ -0,0 +1,5 @@
+/*
+Tables joined along parent_id from the chart table to the table of the axis field,
+excluding the chart table. Empty if the field belongs to the chart table.
+*/
+ALTER TABLE axis ADD COLUMN IF NOT EXISTS table_path INT[] NOT NULL DEFAULT '{}';
@@ -0,0 +1,22 @@
+/*
+Saved view of a table. Shared views are visible to every user with access to the table,
+personal views only to the user who created them.
+filters and sort hold the entry filters and sorts as JSON, applied when querying
+entries through the view. column_widths maps field IDs to a width in pixels.
+*/
+CREATE TABLE IF NOT EXISTS table_view (
+    view_id SERIAL PRIMARY KEY,
+    table_id INT NOT NULL REFERENCES meta_table(table_id) ON DELETE CASCADE,
+    user_id INT NOT NULL REFERENCES app_user(user_id) ON DELETE CASCADE,
+    name TEXT COLLATE case_insensitive NOT NULL,
+    is_shared BOOLEAN NOT NULL DEFAULT FALSE,
+    filters JSONB NOT NULL DEFAULT '[]',
+    sort JSONB NOT NULL DEFAULT '[]',
+    hidden_field_ids INT[] NOT NULL DEFAULT '{}',
+    column_widths JSONB NOT NULL DEFAULT '{}',
+    group_field_id INT REFERENCES meta_field(field_id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ
+);
+
+SELECT trigger_updated_at('table_view');
@@ -0,0 +1,38 @@
+/*
+Kind of dashboard filter control, deciding the fields it can be bound to.
+*/
+DO $$ BEGIN
+    CREATE TYPE control_kind AS ENUM (
+        'DateRange',
+        'NumberRange',
+        'Enumeration'
+    );
+EXCEPTION
+    WHEN duplicate_object THEN null;
+END $$;
+
+/*
+A filter control shared by the charts of a dashboard.
+Its values are given when fetching the chart data and are not stored.
+*/
+CREATE TABLE IF NOT EXISTS dashboard_control (
+    control_id SERIAL PRIMARY KEY,
+    dashboard_id INT NOT NULL REFERENCES dashboard(dashboard_id) ON DELETE CASCADE,
+    name TEXT COLLATE case_insensitive NOT NULL,
+    control_kind control_kind NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at TIMESTAMPTZ
+);
+
+SELECT trigger_updated_at('dashboard_control');
+
+/*
+The field of a chart's table filtered by a dashboard control.
+Charts without a binding are not filtered by the control.
+*/
+CREATE TABLE IF NOT EXISTS dashboard_control_binding (
+    control_id INT NOT NULL REFERENCES dashboard_control(control_id) ON DELETE CASCADE,
+    chart_id INT NOT NULL REFERENCES chart(chart_id) ON DELETE CASCADE,
+    field_id INT NOT NULL REFERENCES meta_field(field_id) ON DELETE CASCADE,
+    PRIMARY KEY (control_id, chart_id)
+);
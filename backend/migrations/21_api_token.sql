@@ -0,0 +1,29 @@
+/*
+What a personal API token is allowed to do.
+*/
+DO $$ BEGIN
+    CREATE TYPE token_permission AS ENUM (
+        'ReadOnly',
+        'ReadWrite'
+    );
+EXCEPTION
+    WHEN duplicate_object THEN null;
+END $$;
+
+/*
+Personal API token authenticating a user with an Authorization: Bearer header.
+Only the SHA-256 hash of the secret is stored.
+table_ids and dashboard_ids limit the token to these resources and are NULL if not limited.
+*/
+CREATE TABLE IF NOT EXISTS api_token (
+    token_id SERIAL PRIMARY KEY,
+    user_id INT NOT NULL REFERENCES app_user(user_id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    token_hash TEXT UNIQUE NOT NULL,
+    permission token_permission NOT NULL,
+    table_ids INT[],
+    dashboard_ids INT[],
+    expires_at TIMESTAMPTZ,
+    last_used_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
@@ -0,0 +1,4 @@
+/*
+Subject identifier of the users provisioned through OpenID Connect single sign-on.
+*/
+ALTER TABLE app_user ADD COLUMN IF NOT EXISTS oidc_subject TEXT UNIQUE;
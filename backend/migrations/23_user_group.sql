@@ -0,0 +1,82 @@
+/*
+Group of users that can be granted access to tables and dashboards together.
+Group owners manage the members of the group along with admins.
+*/
+CREATE TABLE IF NOT EXISTS user_group (
+    group_id SERIAL PRIMARY KEY,
+    name TEXT COLLATE case_insensitive UNIQUE NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at TIMESTAMPTZ
+);
+
+SELECT trigger_updated_at('user_group');
+
+CREATE TABLE IF NOT EXISTS user_group_member (
+    group_id INT NOT NULL REFERENCES user_group(group_id) ON DELETE CASCADE,
+    user_id INT NOT NULL REFERENCES app_user(user_id) ON DELETE CASCADE,
+    is_owner BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at TIMESTAMPTZ,
+    PRIMARY KEY (group_id, user_id)
+);
+
+SELECT trigger_updated_at('user_group_member');
+
+/*
+Access roles granted to groups. Groups are never owners of a resource
+so a resource is only left without an owner when its owner users are deleted.
+*/
+CREATE TABLE IF NOT EXISTS meta_table_group_access (
+    group_id INT NOT NULL REFERENCES user_group(group_id) ON DELETE CASCADE,
+    resource_id INT NOT NULL REFERENCES meta_table(table_id) ON DELETE CASCADE,
+    access_role access_role NOT NULL CHECK (access_role <> 'Owner'),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at TIMESTAMPTZ,
+    PRIMARY KEY (group_id, resource_id)
+);
+
+SELECT trigger_updated_at('meta_table_group_access');
+
+CREATE TABLE IF NOT EXISTS dashboard_group_access (
+    group_id INT NOT NULL REFERENCES user_group(group_id) ON DELETE CASCADE,
+    resource_id INT NOT NULL REFERENCES dashboard(dashboard_id) ON DELETE CASCADE,
+    access_role access_role NOT NULL CHECK (access_role <> 'Owner'),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at TIMESTAMPTZ,
+    PRIMARY KEY (group_id, resource_id)
+);
+
+SELECT trigger_updated_at('dashboard_group_access');
+
+/*
+The effective access role of a user is the highest of their direct role and
+the roles of their groups. Roles are declared from highest to lowest, hence min().
+*/
+CREATE OR REPLACE VIEW meta_table_access_v AS
+SELECT a.user_id, a.resource_id, min(a.access_role) AS access_role
+FROM (
+    SELECT user_id, resource_id, access_role
+    FROM meta_table_access
+    UNION ALL
+    SELECT m.user_id, g.resource_id, g.access_role
+    FROM meta_table_group_access AS g
+    JOIN user_group_member AS m
+    ON g.group_id = m.group_id
+) AS a
+JOIN meta_table AS t
+ON a.resource_id = t.table_id
+WHERE t.deleted_at IS NULL
+GROUP BY a.user_id, a.resource_id;
+
+CREATE OR REPLACE VIEW dashboard_access_v AS
+SELECT a.user_id, a.resource_id, min(a.access_role) AS access_role
+FROM (
+    SELECT user_id, resource_id, access_role
+    FROM dashboard_access
+    UNION ALL
+    SELECT m.user_id, g.resource_id, g.access_role
+    FROM dashboard_group_access AS g
+    JOIN user_group_member AS m
+    ON g.group_id = m.group_id
+) AS a
+GROUP BY a.user_id, a.resource_id;
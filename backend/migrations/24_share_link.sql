@@ -0,0 +1,17 @@
+/*
+Public read-only link sharing a table or a dashboard with anyone, without an account.
+Only the SHA-256 hash of the secret in the link and the hash of the optional password are stored.
+Exactly one of table_id and dashboard_id is set.
+*/
+CREATE TABLE IF NOT EXISTS share_link (
+    share_id SERIAL PRIMARY KEY,
+    user_id INT NOT NULL REFERENCES app_user(user_id) ON DELETE CASCADE,
+    table_id INT REFERENCES meta_table(table_id) ON DELETE CASCADE,
+    dashboard_id INT REFERENCES dashboard(dashboard_id) ON DELETE CASCADE,
+    secret_hash TEXT UNIQUE NOT NULL,
+    password_hash TEXT,
+    expires_at TIMESTAMPTZ,
+    last_used_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    CHECK (num_nonnulls(table_id, dashboard_id) = 1)
+);
@@ -1,5 +1,8 @@
 //! Routes for managing access to Chronicle resources (tables and dashboards).
 //! Users must be the owner of a resource to use its access control API.
+//!
+//! Access roles are granted to users by username or to user groups by group name.
+//! Groups can be viewers or editors but ownership is only granted to users.
 
 use crate::{
     AppState, Id,
@@ -8,7 +11,7 @@ use crate::{
     db,
     error::{ApiError, ApiResult},
     model::access::{
-        AccessRole, AccessRoleCheck, CreateAccess, DeleteAccess, GetAccess, Resource,
+        AccessRole, AccessRoleCheck, CreateAccess, DeleteAccess, GetAccess, Grantee, Resource,
         SelectResource, UpdateAccess,
     },
 };
@@ -21,12 +24,15 @@ use axum::{
     extract::{Path, State},
 };
 use axum_login::AuthSession;
-use itertools::Itertools;
+use itertools::{Either, Itertools};
 use sqlx::{Acquire, Postgres};
 
 const USERNAME_NOT_FOUND: &str = "Username not found";
 const USER_ALREADY_HAS_ACCESS: &str = "User already has access";
 const OWNER_CANNOT_MODIFY_THEIR_OWN_ACCESS: &str = "Owner cannot modify their own access";
+const GROUP_NOT_FOUND: &str = "Group not found";
+const GROUP_ALREADY_HAS_ACCESS: &str = "Group already has access";
+const GROUP_CANNOT_BE_OWNER: &str = "Group cannot be an owner";
 
 pub fn router() -> ApiRouter<AppState> {
     ApiRouter::new().api_route(
@@ -55,26 +61,48 @@ async fn create_access(
         .await?
         .check(AccessRole::Owner)?;
 
-    let user_id = db::get_user_by_username(tx.as_mut(), create_access.username)
-        .await?
-        .ok_or(ApiError::UnprocessableEntity(USERNAME_NOT_FOUND.into()))?
-        .user_id;
+    match create_access.grantee {
+        Grantee::User { username } => {
+            let user_id = db::get_user_by_username(tx.as_mut(), username)
+                .await?
+                .ok_or(ApiError::UnprocessableEntity(USERNAME_NOT_FOUND.into()))?
+                .user_id;
 
-    if db::get_access_role(tx.as_mut(), resource, resource_id, user_id)
-        .await?
-        .is_some()
-    {
-        return Err(ApiError::Conflict(USER_ALREADY_HAS_ACCESS.into()));
-    }
+            if db::user_access_exists(tx.as_mut(), resource, resource_id, user_id).await? {
+                return Err(ApiError::Conflict(USER_ALREADY_HAS_ACCESS.into()));
+            }
 
-    db::create_access(
-        tx.as_mut(),
-        resource,
-        resource_id,
-        user_id,
-        create_access.access_role,
-    )
-    .await?;
+            db::create_access(
+                tx.as_mut(),
+                resource,
+                resource_id,
+                user_id,
+                create_access.access_role,
+            )
+            .await?;
+        }
+        Grantee::Group { group_name } => {
+            if create_access.access_role == AccessRole::Owner {
+                return Err(ApiError::UnprocessableEntity(GROUP_CANNOT_BE_OWNER.into()));
+            }
+            let group_id = db::get_user_group_id(tx.as_mut(), &group_name)
+                .await?
+                .ok_or(ApiError::UnprocessableEntity(GROUP_NOT_FOUND.into()))?;
+
+            if db::group_access_exists(tx.as_mut(), resource, resource_id, group_id).await? {
+                return Err(ApiError::Conflict(GROUP_ALREADY_HAS_ACCESS.into()));
+            }
+
+            db::create_group_access(
+                tx.as_mut(),
+                resource,
+                resource_id,
+                group_id,
+                create_access.access_role,
+            )
+            .await?;
+        }
+    }
 
     tx.commit().await?;
     Ok(())
@@ -101,20 +129,47 @@ async fn update_many_access(
     if update_access_vec.is_empty() {
         return Err(ApiError::BadRequest(NO_DATA_IN_REQUEST_BODY.into()));
     }
-    let (usernames, access_roles): (Vec<_>, Vec<_>) = update_access_vec
+    let (user_access, group_access): (Vec<_>, Vec<_>) = update_access_vec
         .into_iter()
-        .map(|a| (a.username, a.access_role))
-        .unzip();
-    let user_ids =
-        get_users_with_access(tx.as_mut(), auth_user_id, resource, resource_id, usernames).await?;
+        .partition_map(|a| match a.grantee {
+            Grantee::User { username } => Either::Left((username, a.access_role)),
+            Grantee::Group { group_name } => Either::Right((group_name, a.access_role)),
+        });
 
-    db::update_many_access(
-        tx.as_mut(),
-        resource,
-        resource_id,
-        user_ids.into_iter().zip(access_roles),
-    )
-    .await?;
+    if !user_access.is_empty() {
+        let (usernames, access_roles): (Vec<_>, Vec<_>) = user_access.into_iter().unzip();
+        let user_ids =
+            get_users_with_access(tx.as_mut(), auth_user_id, resource, resource_id, usernames)
+                .await?;
+
+        db::update_many_access(
+            tx.as_mut(),
+            resource,
+            resource_id,
+            user_ids.into_iter().zip(access_roles),
+        )
+        .await?;
+    }
+
+    if !group_access.is_empty() {
+        if group_access
+            .iter()
+            .any(|(_, access_role)| *access_role == AccessRole::Owner)
+        {
+            return Err(ApiError::UnprocessableEntity(GROUP_CANNOT_BE_OWNER.into()));
+        }
+        let (group_names, access_roles): (Vec<_>, Vec<_>) = group_access.into_iter().unzip();
+        let group_ids =
+            get_groups_with_access(tx.as_mut(), resource, resource_id, group_names).await?;
+
+        db::update_many_group_access(
+            tx.as_mut(),
+            resource,
+            resource_id,
+            group_ids.into_iter().zip(access_roles),
+        )
+        .await?;
+    }
 
     tx.commit().await?;
     Ok(())
@@ -141,16 +196,26 @@ async fn delete_many_access(
         return Err(ApiError::BadRequest(NO_DATA_IN_REQUEST_BODY.into()));
     }
 
-    let user_ids: Vec<Id> = get_users_with_access(
-        tx.as_mut(),
-        auth_user_id,
-        resource,
-        resource_id,
-        delete_access_vec.into_iter().map(|a| a.username),
-    )
-    .await?;
+    let (usernames, group_names): (Vec<_>, Vec<_>) =
+        delete_access_vec
+            .into_iter()
+            .partition_map(|a| match a.grantee {
+                Grantee::User { username } => Either::Left(username),
+                Grantee::Group { group_name } => Either::Right(group_name),
+            });
 
-    db::delete_many_access(tx.as_mut(), resource, resource_id, user_ids).await?;
+    if !usernames.is_empty() {
+        let user_ids =
+            get_users_with_access(tx.as_mut(), auth_user_id, resource, resource_id, usernames)
+                .await?;
+        db::delete_many_access(tx.as_mut(), resource, resource_id, user_ids).await?;
+    }
+
+    if !group_names.is_empty() {
+        let group_ids =
+            get_groups_with_access(tx.as_mut(), resource, resource_id, group_names).await?;
+        db::delete_many_group_access(tx.as_mut(), resource, resource_id, group_ids).await?;
+    }
 
     tx.commit().await?;
     Ok(())
@@ -196,10 +261,7 @@ async fn get_users_with_access(
                     OWNER_CANNOT_MODIFY_THEIR_OWN_ACCESS.into(),
                 ));
             }
-            if db::get_access_role(tx.as_mut(), resource, resource_id, user.user_id)
-                .await?
-                .is_none()
-            {
+            if !db::user_access_exists(tx.as_mut(), resource, resource_id, user.user_id).await? {
                 not_found_usernames.push(username);
             }
             user_ids.push(user.user_id);
@@ -218,10 +280,46 @@ async fn get_users_with_access(
     Ok(user_ids)
 }
 
+/// Return all group IDs corresponding to the group names or return the appropriate API error
+/// if groups with access are not found.
+async fn get_groups_with_access(
+    conn: impl Acquire<'_, Database = Postgres>,
+    resource: Resource,
+    resource_id: Id,
+    group_names: impl IntoIterator<Item = String>,
+) -> ApiResult<Vec<Id>> {
+    let mut tx = conn.begin().await?;
+    let mut group_ids: Vec<Id> = Vec::new();
+    let mut not_found_group_names: Vec<String> = Vec::new();
+    for group_name in group_names {
+        match db::get_user_group_id(tx.as_mut(), &group_name).await? {
+            Some(group_id)
+                if db::group_access_exists(tx.as_mut(), resource, resource_id, group_id)
+                    .await? =>
+            {
+                group_ids.push(group_id)
+            }
+            _ => not_found_group_names.push(group_name),
+        }
+    }
+
+    if !not_found_group_names.is_empty() {
+        return Err(ApiError::UnprocessableEntity(format!(
+            "{GROUP_NOT_FOUND}: {}",
+            not_found_group_names.into_iter().join(", ")
+        )));
+    }
+    tx.commit().await?;
+    Ok(group_ids)
+}
+
 #[cfg_attr(coverage_nightly, coverage(off))]
 mod docs {
     use crate::{
-        api::access::{USER_ALREADY_HAS_ACCESS, USERNAME_NOT_FOUND},
+        api::access::{
+            GROUP_ALREADY_HAS_ACCESS, GROUP_CANNOT_BE_OWNER, GROUP_NOT_FOUND,
+            USER_ALREADY_HAS_ACCESS, USERNAME_NOT_FOUND,
+        },
         docs::{ACCESS_TAG, TransformOperationExt, template},
         model::access::{AccessRole, GetAccess, Resource},
     };
@@ -245,34 +343,52 @@ mod docs {
         access::<()>(
             op,
             "create_access",
-            "Create a new user access to the resource.",
+            "Create a new user or group access to the resource.",
         )
-        .response_description::<409, String>(USER_ALREADY_HAS_ACCESS)
-        .response_description::<422, String>(USERNAME_NOT_FOUND)
+        .response_description::<409, String>(&format!(
+            "{USER_ALREADY_HAS_ACCESS}\n\n{GROUP_ALREADY_HAS_ACCESS}"
+        ))
+        .response_description::<422, String>(&format!(
+            "{USERNAME_NOT_FOUND}\n\n{GROUP_NOT_FOUND}\n\n{GROUP_CANNOT_BE_OWNER}"
+        ))
     }
 
     pub fn update_access(op: TransformOperation) -> TransformOperation {
         access::<()>(
             op,
             "update_access",
-            "Update a list of user access roles for the resource.",
+            "Update a list of user and group access roles for the resource.",
         )
-        .response_description::<422, String>(&format!("{USERNAME_NOT_FOUND}: <username>, ..."))
+        .response_description::<422, String>(&format!(
+            "{USERNAME_NOT_FOUND}: <username>, ...\n\n{GROUP_NOT_FOUND}: <group_name>, ...\n\n\
+            {GROUP_CANNOT_BE_OWNER}"
+        ))
     }
 
     pub fn delete_access(op: TransformOperation) -> TransformOperation {
-        access::<()>(op, "delete_access", "Delete a list of user access.")
-            .response_description::<422, String>(&format!("{USERNAME_NOT_FOUND}: <username>, ..."))
+        access::<()>(
+            op,
+            "delete_access",
+            "Delete a list of user and group access.",
+        )
+        .response_description::<422, String>(&format!(
+            "{USERNAME_NOT_FOUND}: <username>, ...\n\n{GROUP_NOT_FOUND}: <group_name>, ..."
+        ))
     }
 
     pub fn get_all_access(op: TransformOperation) -> TransformOperation {
-        access::<Json<Vec<GetAccess>>>(op, "get_all_access", "Get all user access to the resource.")
+        access::<Json<Vec<GetAccess>>>(
+            op,
+            "get_all_access",
+            "Get all user and group access to the resource.",
+        )
     }
 }
 
 #[cfg(test)]
 #[cfg_attr(coverage_nightly, coverage(off))]
 mod test {
+    use super::{GROUP_CANNOT_BE_OWNER, GROUP_NOT_FOUND};
     use rand::Rng;
     use serde_json::json;
     use sqlx::PgPool;
@@ -281,8 +397,11 @@ mod test {
         db,
         error::ApiError,
         model::{
-            access::{AccessRole, CreateAccess, DeleteAccess, GetAccess, Resource, UpdateAccess},
+            access::{
+                AccessRole, CreateAccess, DeleteAccess, GetAccess, Grantee, Resource, UpdateAccess,
+            },
             data::CreateTable,
+            groups::CreateUserGroup,
             viz::CreateDashboard,
         },
         setup_tracing, test_util,
@@ -311,7 +430,9 @@ mod test {
         );
 
         let create_access = CreateAccess {
-            username: user.username.clone(),
+            grantee: Grantee::User {
+                username: user.username.clone(),
+            },
             access_role: AccessRole::Viewer,
         };
 
@@ -337,7 +458,7 @@ mod test {
                         .unwrap()
                         .username;
                 let create_access = CreateAccess {
-                    username,
+                    grantee: Grantee::User { username },
                     access_role: AccessRole::Viewer,
                 };
                 server.post(&path).json(&create_access).await
@@ -370,7 +491,9 @@ mod test {
         server
             .post(&path)
             .json(&CreateAccess {
-                username: "wrong".into(),
+                grantee: Grantee::User {
+                    username: "wrong".into(),
+                },
                 access_role: AccessRole::Viewer,
             })
             .await
@@ -418,11 +541,15 @@ mod test {
 
         let update_access_vec = vec![
             UpdateAccess {
-                username: user_1.username.clone(),
+                grantee: Grantee::User {
+                    username: user_1.username.clone(),
+                },
                 access_role: AccessRole::Editor,
             },
             UpdateAccess {
-                username: user_2.username.clone(),
+                grantee: Grantee::User {
+                    username: user_2.username.clone(),
+                },
                 access_role: AccessRole::Viewer,
             },
         ];
@@ -454,11 +581,15 @@ mod test {
         let user_2_access_role = AccessRole::Editor;
         let update_access_vec = vec![
             UpdateAccess {
-                username: user_1.username,
+                grantee: Grantee::User {
+                    username: user_1.username,
+                },
                 access_role: user_1_access_role,
             },
             UpdateAccess {
-                username: user_2.username,
+                grantee: Grantee::User {
+                    username: user_2.username,
+                },
                 access_role: user_2_access_role,
             },
         ];
@@ -490,7 +621,9 @@ mod test {
         server
             .patch(&path)
             .json(&vec![UpdateAccess {
-                username: auth_user.username,
+                grantee: Grantee::User {
+                    username: auth_user.username,
+                },
                 access_role: AccessRole::Viewer,
             }])
             .await
@@ -538,10 +671,14 @@ mod test {
 
         let delete_access_vec = vec![
             DeleteAccess {
-                username: user_1.username.clone(),
+                grantee: Grantee::User {
+                    username: user_1.username.clone(),
+                },
             },
             DeleteAccess {
-                username: user_2.username.clone(),
+                grantee: Grantee::User {
+                    username: user_2.username.clone(),
+                },
             },
         ];
         server
@@ -569,7 +706,9 @@ mod test {
                 server
                     .delete(&path)
                     .json(&vec![DeleteAccess {
-                        username: user.username,
+                        grantee: Grantee::User {
+                            username: user.username,
+                        },
                     }])
                     .await
             },
@@ -635,7 +774,9 @@ mod test {
             let user = db::create_user(&db, idx.to_string(), "".into(), false).await?;
             db::create_access(&db, resource, resource_id, user.user_id, access_role).await?;
             user_access_1.push(GetAccess {
-                username: user.username,
+                grantee: Grantee::User {
+                    username: user.username,
+                },
                 access_role,
             });
         }
@@ -659,7 +800,9 @@ mod test {
         .await;
 
         user_access_1.push(GetAccess {
-            username: auth_user.username,
+            grantee: Grantee::User {
+                username: auth_user.username,
+            },
             access_role: AccessRole::Owner,
         });
 
@@ -671,7 +814,7 @@ mod test {
         let response = server.get(&path).await;
         response.assert_status_ok();
         let user_access_2: Vec<GetAccess> = response.json();
-        test_util::assert_eq_vec(user_access_1, user_access_2, |a| a.username.to_owned());
+        test_util::assert_eq_vec(user_access_1, user_access_2, |a| format!("{:?}", a.grantee));
 
         Ok(())
     }
@@ -763,4 +906,129 @@ mod test {
 
         Ok(())
     }
+
+    #[sqlx::test]
+    async fn group_access(db: PgPool) -> anyhow::Result<()> {
+        let mut server = test_util::server(db.clone()).await;
+        let owner = db::create_user(&db, "owner".into(), "".into(), false).await?;
+        let member = db::create_user(&db, "member".into(), "".into(), false).await?;
+        let group_id = db::create_user_group(
+            &db,
+            owner.user_id,
+            CreateUserGroup {
+                name: "team".into(),
+            },
+        )
+        .await?
+        .group_id;
+        db::create_group_member(&db, group_id, member.user_id, false).await?;
+        test_util::login_session(&mut server, &owner).await;
+
+        for (resource, resource_id) in [
+            (
+                Resource::Table,
+                db::create_table(
+                    &db,
+                    CreateTable {
+                        parent_id: None,
+                        name: "test".into(),
+                        description: "".into(),
+                    },
+                )
+                .await?
+                .table_id,
+            ),
+            (
+                Resource::Dashboard,
+                db::create_dashboard(
+                    &db,
+                    CreateDashboard {
+                        name: "test".into(),
+                        description: "".into(),
+                    },
+                )
+                .await?
+                .dashboard_id,
+            ),
+        ] {
+            db::create_access(&db, resource, resource_id, owner.user_id, AccessRole::Owner).await?;
+            let path = format!(
+                "/api/{}/{resource_id}/access",
+                serde_json::to_string(&resource)?.replace("\"", "")
+            );
+
+            for (access_wrong, error) in [
+                (
+                    json!({ "group_name": "team", "access_role": "Owner" }),
+                    GROUP_CANNOT_BE_OWNER,
+                ),
+                (
+                    json!({ "group_name": "other", "access_role": "Viewer" }),
+                    GROUP_NOT_FOUND,
+                ),
+            ] {
+                let response = server.post(&path).json(&access_wrong).await;
+                response.assert_status_unprocessable_entity();
+                response.assert_text_contains(error);
+            }
+            server
+                .post(&path)
+                .json(&json!({ "group_name": "team", "access_role": "Editor" }))
+                .await
+                .assert_status_ok();
+            server
+                .post(&path)
+                .json(&json!({ "group_name": "team", "access_role": "Editor" }))
+                .await
+                .assert_status_conflict();
+            assert_eq!(
+                db::get_access_role(&db, resource, resource_id, member.user_id).await?,
+                Some(AccessRole::Editor)
+            );
+
+            server
+                .post(&path)
+                .json(&json!({ "username": "member", "access_role": "Viewer" }))
+                .await
+                .assert_status_ok();
+            assert_eq!(
+                db::get_access_role(&db, resource, resource_id, member.user_id).await?,
+                Some(AccessRole::Editor)
+            );
+
+            server
+                .patch(&path)
+                .json(&json!([
+                    { "group_name": "team", "access_role": "Viewer" },
+                    { "username": "member", "access_role": "Editor" },
+                ]))
+                .await
+                .assert_status_ok();
+            let access: Vec<GetAccess> = server.get(&path).await.json();
+            assert!(access.contains(&GetAccess {
+                grantee: Grantee::Group {
+                    group_name: "team".into(),
+                },
+                access_role: AccessRole::Viewer,
+            }));
+            assert_eq!(access.len(), 3);
+
+            server
+                .delete(&path)
+                .json(&json!([{ "group_name": "team" }, { "username": "member" }]))
+                .await
+                .assert_status_ok();
+            server
+                .delete(&path)
+                .json(&json!([{ "group_name": "team" }]))
+                .await
+                .assert_status_unprocessable_entity();
+            assert_eq!(
+                db::get_access_role(&db, resource, resource_id, member.user_id).await?,
+                None
+            );
+        }
+
+        Ok(())
+    }
 }
@@ -0,0 +1,262 @@
+//! Routes for reading the audit log of user tables.
+
+use crate::{
+    AppState, Id,
+    auth::AppAuthSession,
+    db,
+    error::{ApiError, ApiResult},
+    model::{
+        access::{AccessRole, AccessRoleCheck, Resource},
+        data::{AuditLog, AuditLogPage, AuditLogQuery, SelectTable},
+    },
+};
+use aide::{NoApi, axum::ApiRouter, axum::routing::get_with};
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+};
+use axum_login::AuthSession;
+
+const DEFAULT_LIMIT: i64 = 50;
+const MAX_LIMIT: i64 = 500;
+
+const INVALID_LIMIT: &str = "Limit must be between 1 and 500";
+
+pub fn router() -> ApiRouter<AppState> {
+    ApiRouter::new()
+        .api_route(
+            "/tables/{table_id}/entries/{entry_id}/history",
+            get_with(get_entry_history, docs::get_entry_history),
+        )
+        .api_route(
+            "/tables/{table_id}/activity",
+            get_with(get_table_activity, docs::get_table_activity),
+        )
+}
+
+async fn get_entry_history(
+    NoApi(AuthSession { user, .. }): AppAuthSession,
+    State(AppState { db, .. }): State<AppState>,
+    Path((table_id, entry_id)): Path<(Id, Id)>,
+) -> ApiResult<Json<Vec<AuditLog>>> {
+    let user_id = user.ok_or(ApiError::Unauthorized)?.user_id;
+
+    db::get_access_role(&db, Resource::Table, table_id, user_id)
+        .await?
+        .check(AccessRole::Viewer)?;
+
+    let history = db::get_entry_history(&db, table_id, entry_id).await?;
+    if history.is_empty() && !db::entry_exists(&db, table_id, entry_id).await? {
+        return Err(ApiError::NotFound);
+    }
+
+    Ok(Json(history))
+}
+
+async fn get_table_activity(
+    NoApi(AuthSession { user, .. }): AppAuthSession,
+    State(AppState { db, .. }): State<AppState>,
+    Path(SelectTable { table_id }): Path<SelectTable>,
+    Query(AuditLogQuery { limit, cursor }): Query<AuditLogQuery>,
+) -> ApiResult<Json<AuditLogPage>> {
+    let user_id = user.ok_or(ApiError::Unauthorized)?.user_id;
+
+    db::get_access_role(&db, Resource::Table, table_id, user_id)
+        .await?
+        .check(AccessRole::Viewer)?;
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT);
+    if !(1..=MAX_LIMIT).contains(&limit) {
+        return Err(ApiError::UnprocessableEntity(INVALID_LIMIT.into()));
+    }
+
+    let page = db::get_table_activity(&db, table_id, limit, cursor).await?;
+
+    Ok(Json(page))
+}
+
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod docs {
+    use crate::{
+        api::data::audit::INVALID_LIMIT,
+        docs::{AUDIT_TAG, TransformOperationExt, template},
+        model::{
+            access::{AccessRole, Resource},
+            data::{AuditLog, AuditLogPage},
+        },
+    };
+    use aide::{OperationOutput, transform::TransformOperation};
+    use axum::Json;
+
+    const TABLE_VIEWER: [(Resource, AccessRole); 1] = [(Resource::Table, AccessRole::Viewer)];
+
+    fn audit<'a, R: OperationOutput>(
+        op: TransformOperation<'a>,
+        summary: &'a str,
+        description: &'a str,
+    ) -> TransformOperation<'a> {
+        template::<R>(op, summary, description, true, AUDIT_TAG)
+    }
+
+    pub fn get_entry_history(op: TransformOperation) -> TransformOperation {
+        audit::<Json<Vec<AuditLog>>>(
+            op,
+            "get_entry_history",
+            "Get the changes made to an entry, oldest first. The history of deleted entries is kept.",
+        )
+        .response_description::<404, ()>("Table not found\n\nEntry not found")
+        .required_access(TABLE_VIEWER)
+    }
+
+    pub fn get_table_activity(op: TransformOperation) -> TransformOperation {
+        audit::<Json<AuditLogPage>>(
+            op,
+            "get_table_activity",
+            "Get a page of the changes made to a table and its fields and entries, newest first. Returns 50 records by default and at most 500.",
+        )
+        .response_description::<404, ()>("Table not found")
+        .response_description::<422, String>(INVALID_LIMIT)
+        .required_access(TABLE_VIEWER)
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod test {
+    use crate::{
+        db,
+        model::{
+            access::{AccessRole, Resource},
+            data::{
+                AuditAction, AuditLog, AuditLogPage, AuditResource, CreateEntries, CreateField,
+                CreateTable, FieldKind, Table, UpdateEntry,
+            },
+        },
+        test_util,
+    };
+    use itertools::Itertools;
+    use serde_json::json;
+    use sqlx::PgPool;
+    use std::collections::HashMap;
+
+    #[sqlx::test]
+    async fn audit_log(db: PgPool) -> anyhow::Result<()> {
+        let mut server = test_util::server(db.clone()).await;
+        server
+            .get("/api/tables/1/activity")
+            .await
+            .assert_status_unauthorized();
+
+        let user = db::create_user(&db, "test".into(), "".into(), false).await?;
+        test_util::login_session(&mut server, &user).await;
+
+        let table: Table = server
+            .post("/api/tables")
+            .json(&CreateTable {
+                name: "test".into(),
+                description: "".into(),
+                parent_id: None,
+            })
+            .await
+            .json();
+        let table_id = table.table_id;
+        let field_id = server
+            .post(&format!("/api/tables/{table_id}/fields"))
+            .json(&CreateField {
+                name: "test".into(),
+                field_kind: FieldKind::Text { is_required: false },
+            })
+            .await
+            .json::<serde_json::Value>()["field_id"]
+            .as_i64()
+            .unwrap();
+        let entry_path = format!("/api/tables/{table_id}/entries");
+        let entry_id = server
+            .post(&entry_path)
+            .json(&CreateEntries {
+                parent_id: None,
+                entries: vec![HashMap::from_iter([(field_id as i32, json!("a"))])],
+            })
+            .await
+            .json::<serde_json::Value>()[0]["entry_id"]
+            .as_i64()
+            .unwrap();
+        server
+            .patch(&format!("{entry_path}/{entry_id}"))
+            .json(&UpdateEntry {
+                parent_id: None,
+                cells: HashMap::from_iter([(field_id as i32, json!("b"))]),
+            })
+            .await
+            .assert_status_ok();
+        server
+            .delete(&format!("{entry_path}/{entry_id}"))
+            .await
+            .assert_status_ok();
+
+        let history_path = format!("{entry_path}/{entry_id}/history");
+        let response = server.get(&history_path).await;
+        response.assert_status_ok();
+        let history: Vec<AuditLog> = response.json();
+        assert_eq!(
+            history.iter().map(|log| log.action).collect_vec(),
+            [
+                AuditAction::Create,
+                AuditAction::Update,
+                AuditAction::Delete
+            ]
+        );
+        assert!(history.iter().all(|log| log.user_id == Some(user.user_id)));
+        assert_eq!(
+            history[1].old_value.as_ref().map(|v| &v.0),
+            Some(&json!({ field_id.to_string(): "a" }))
+        );
+        assert_eq!(
+            history[1].new_value.as_ref().map(|v| &v.0),
+            Some(&json!({ field_id.to_string(): "b" }))
+        );
+        assert_eq!(history[2].new_value, None);
+
+        server
+            .get(&format!("{entry_path}/1000/history"))
+            .await
+            .assert_status_not_found();
+
+        let activity_path = format!("/api/tables/{table_id}/activity");
+        let response = server.get(&format!("{activity_path}?limit=3")).await;
+        response.assert_status_ok();
+        let page: AuditLogPage = response.json();
+        assert_eq!(
+            page.logs.iter().map(|log| log.resource).collect_vec(),
+            [AuditResource::Entry; 3]
+        );
+        let cursor = page.next_cursor.unwrap();
+        let page: AuditLogPage = server
+            .get(&format!("{activity_path}?limit=3&cursor={cursor}"))
+            .await
+            .json();
+        assert_eq!(
+            page.logs.iter().map(|log| log.resource).collect_vec(),
+            [AuditResource::Field, AuditResource::Table]
+        );
+        assert_eq!(page.next_cursor, None);
+
+        for limit in [-1, 0, 501] {
+            server
+                .get(&format!("{activity_path}?limit={limit}"))
+                .await
+                .assert_status_unprocessable_entity();
+        }
+
+        test_util::test_access_control(
+            &db,
+            Resource::Table,
+            table_id,
+            user.user_id,
+            AccessRole::Viewer,
+            async || server.get(&activity_path).await,
+        )
+        .await;
+        Ok(())
+    }
+}
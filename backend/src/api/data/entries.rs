@@ -6,10 +6,15 @@ use crate::{
     auth::AppAuthSession,
     db,
     error::{ApiError, ApiResult},
+    io::{ENUMERATION_VALUE_MISSING, IS_REQUIRED, OUT_OF_RANGE, check_range},
     model::{
         Cell,
         access::{AccessRole, AccessRoleCheck, Resource},
-        data::{CreateEntries, Entry, FieldKind, FieldMetadata, SelectTable, UpdateEntry},
+        data::{
+            CreateAuditLog, CreateEntries, DeleteEntries, Entry, EntryCursor, EntryFilter,
+            EntryPage, EntryQuery, FieldKind, FieldMetadata, FilterOperator, QueryEntries,
+            SelectTable, UpdateEntries, UpdateEntriesItem, UpdateEntry,
+        },
     },
 };
 use aide::{
@@ -31,19 +36,34 @@ use serde_json::Value;
 use sqlx::{Acquire, Postgres};
 use std::{collections::HashMap, str::FromStr};
 
-const IS_REQUIRED: &str = "A value is required";
-const OUT_OF_RANGE: &str = "Value is out of range";
-const ENUMERATION_VALUE_MISSING: &str = "Enumeration value does not exist";
-const INVALID_TYPE: &str = "Value is not the correct type";
-const INVALID_FIELD_ID: &str = "Field ID key is invalid";
+pub(crate) const INVALID_TYPE: &str = "Value is not the correct type";
+pub(crate) const INVALID_FIELD_ID: &str = "Field ID key is invalid";
 const PARENT_ID_NOT_FOUND: &str = "Entry parent ID not found";
-const NO_PARENT_TABLE: &str = "This table has no parent table";
+pub(super) const NO_PARENT_TABLE: &str = "This table has no parent table";
+pub(crate) const INVALID_OPERATOR: &str = "Filter operator is not supported for this field kind";
+pub(crate) const INVALID_DAYS: &str = "Number of days is out of range";
+pub(super) const INVALID_PAGINATION: &str = "Limit and offset must not be negative";
+pub(super) const OFFSET_WITH_CURSOR: &str = "Offset and cursor cannot be used together";
+pub(super) const INVALID_CURSOR: &str = "Cursor is invalid";
+pub(super) const INVALID_SORT: &str = "Cannot sort by a relation to many entries";
+const RELATION_ENTRY_NOT_FOUND: &str = "Related entry not found";
+pub(super) const ENTRY_REFERENCED: &str = "Entry is referenced by other entries";
+const ENTRY_NOT_FOUND: &str = "Entry not found";
+const DUPLICATE_ENTRY_ID: &str = "Entry is listed more than once";
+/// Largest number of days of the `WithinLastDays` filter operator, about a century.
+const MAX_WITHIN_LAST_DAYS: u32 = 36_500;
 
 pub fn router() -> ApiRouter<AppState> {
     ApiRouter::new().nest(
         "/tables/{table_id}/entries",
         ApiRouter::new()
-            .api_route("/", post_with(create_entries, docs::create_entries))
+            .api_route(
+                "/",
+                post_with(create_entries, docs::create_entries)
+                    .patch_with(update_entries, docs::update_entries)
+                    .delete_with(delete_entries, docs::delete_entries),
+            )
+            .api_route("/query", post_with(query_entries, docs::query_entries))
             .api_route(
                 "/{entry_id}",
                 patch_with(update_entry, docs::update_entry)
@@ -74,12 +94,22 @@ async fn create_entries(
     }
 
     let fields = db::get_fields_metadata(tx.as_mut(), table_id).await?;
-    let entries = entries
+    let entries: Vec<_> = entries
         .into_iter()
         .map(|cells| convert_cells(cells, &fields))
         .try_collect()?;
+    check_relations(tx.as_mut(), &fields, &entries).await?;
 
     let entries = db::create_entries(tx.as_mut(), table_id, parent_id, fields, entries).await?;
+    db::create_audit_logs(
+        tx.as_mut(),
+        Some(user_id),
+        entries
+            .iter()
+            .map(|entry| CreateAuditLog::entry(table_id, None, Some(entry)))
+            .collect(),
+    )
+    .await?;
 
     tx.commit().await?;
     Ok(Json(entries))
@@ -107,8 +137,20 @@ async fn update_entry(
     let fields = db::get_fields_metadata(tx.as_mut(), table_id).await?;
 
     let cells = convert_cells(cells, &fields)?;
+    check_relations(tx.as_mut(), &fields, std::slice::from_ref(&cells)).await?;
 
+    let old_entry = db::get_entry(tx.as_mut(), table_id, entry_id, &fields).await?;
     let entry = db::update_entry(tx.as_mut(), table_id, entry_id, parent_id, fields, cells).await?;
+    db::create_audit_logs(
+        tx.as_mut(),
+        Some(user_id),
+        vec![CreateAuditLog::entry(
+            table_id,
+            Some(&old_entry),
+            Some(&entry),
+        )],
+    )
+    .await?;
 
     tx.commit().await?;
     Ok(Json(entry))
@@ -129,12 +171,224 @@ async fn delete_entry(
         return Err(ApiError::NotFound);
     }
 
-    db::delete_entry(tx.as_mut(), table_id, entry_id).await?;
+    let fields = db::get_fields_metadata(tx.as_mut(), table_id).await?;
+    let old_entry = db::get_entry(tx.as_mut(), table_id, entry_id, &fields).await?;
+
+    db::trash_entry(tx.as_mut(), user_id, table_id, entry_id)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(e) if e.is_foreign_key_violation() => {
+                ApiError::Conflict(ENTRY_REFERENCED.into())
+            }
+            e => e.into(),
+        })?;
+    db::create_audit_logs(
+        tx.as_mut(),
+        Some(user_id),
+        vec![CreateAuditLog::entry(table_id, Some(&old_entry), None)],
+    )
+    .await?;
 
     tx.commit().await?;
     Ok(())
 }
 
+async fn update_entries(
+    NoApi(AuthSession { user, .. }): AppAuthSession,
+    State(AppState { db, .. }): State<AppState>,
+    Path(SelectTable { table_id }): Path<SelectTable>,
+    Json(UpdateEntries { entries }): Json<UpdateEntries>,
+) -> ApiResult<Json<Vec<Entry>>> {
+    let user_id = user.ok_or(ApiError::Unauthorized)?.user_id;
+    let mut tx = db.begin().await?;
+
+    db::get_access_role(tx.as_mut(), Resource::Table, table_id, user_id)
+        .await?
+        .check(AccessRole::Editor)?;
+
+    if entries.is_empty() {
+        return Err(ApiError::BadRequest(NO_DATA_IN_REQUEST_BODY.into()));
+    }
+
+    let fields = db::get_fields_metadata(tx.as_mut(), table_id).await?;
+    let existing_ids = db::get_existing_entry_ids(
+        tx.as_mut(),
+        table_id,
+        entries.iter().map(|entry| entry.entry_id).collect(),
+    )
+    .await?;
+    let duplicate_ids = entries
+        .iter()
+        .map(|entry| entry.entry_id)
+        .duplicates()
+        .collect_vec();
+
+    let mut error_messages = Vec::new();
+    let mut updates = Vec::new();
+    for UpdateEntriesItem {
+        entry_id,
+        parent_id,
+        cells,
+    } in entries
+    {
+        let mut item_messages = Vec::new();
+        if !existing_ids.contains(&entry_id) {
+            item_messages.push(ENTRY_NOT_FOUND.to_string());
+        }
+        if duplicate_ids.contains(&entry_id) {
+            item_messages.push(DUPLICATE_ENTRY_ID.to_string());
+        }
+        if let Some(parent_entry_id) = parent_id {
+            match check_parent_id(tx.as_mut(), parent_entry_id, table_id).await {
+                Ok(()) => {}
+                Err(ApiError::UnprocessableEntity(message)) => item_messages.push(message),
+                Err(e) => return Err(e),
+            }
+        }
+        match try_convert_cells(cells, &fields) {
+            Ok(cells) => {
+                item_messages.extend(
+                    relation_errors(tx.as_mut(), &fields, std::slice::from_ref(&cells)).await?,
+                );
+                updates.push((entry_id, parent_id, cells));
+            }
+            Err(messages) => item_messages.extend(messages),
+        }
+        error_messages.extend(
+            item_messages
+                .into_iter()
+                .unique()
+                .map(|message| format!("{entry_id}: {message}")),
+        );
+    }
+
+    if !error_messages.is_empty() {
+        return Err(ApiError::UnprocessableEntity(error_messages.join(", ")));
+    }
+
+    let mut updated_entries = Vec::new();
+    let mut logs = Vec::new();
+    for (entry_id, parent_id, cells) in updates {
+        let old_entry = db::get_entry(tx.as_mut(), table_id, entry_id, &fields).await?;
+        let entry = db::update_entry(
+            tx.as_mut(),
+            table_id,
+            entry_id,
+            parent_id,
+            fields.clone(),
+            cells,
+        )
+        .await?;
+        logs.push(CreateAuditLog::entry(
+            table_id,
+            Some(&old_entry),
+            Some(&entry),
+        ));
+        updated_entries.push(entry);
+    }
+    db::create_audit_logs(tx.as_mut(), Some(user_id), logs).await?;
+
+    tx.commit().await?;
+    Ok(Json(updated_entries))
+}
+
+async fn delete_entries(
+    NoApi(AuthSession { user, .. }): AppAuthSession,
+    State(AppState { db, .. }): State<AppState>,
+    Path(SelectTable { table_id }): Path<SelectTable>,
+    Json(request): Json<DeleteEntries>,
+) -> ApiResult<Json<Vec<Id>>> {
+    let user_id = user.ok_or(ApiError::Unauthorized)?.user_id;
+    let mut tx = db.begin().await?;
+
+    db::get_access_role(tx.as_mut(), Resource::Table, table_id, user_id)
+        .await?
+        .check(AccessRole::Editor)?;
+
+    let entry_ids = match request {
+        DeleteEntries::Ids { entry_ids } => {
+            if entry_ids.is_empty() {
+                return Err(ApiError::BadRequest(NO_DATA_IN_REQUEST_BODY.into()));
+            }
+            let entry_ids = entry_ids.into_iter().unique().collect_vec();
+            let existing_ids =
+                db::get_existing_entry_ids(tx.as_mut(), table_id, entry_ids.clone()).await?;
+            let error_messages = entry_ids
+                .iter()
+                .filter(|entry_id| !existing_ids.contains(entry_id))
+                .map(|entry_id| format!("{entry_id}: {ENTRY_NOT_FOUND}"))
+                .collect_vec();
+            if !error_messages.is_empty() {
+                return Err(ApiError::UnprocessableEntity(error_messages.join(", ")));
+            }
+            entry_ids
+        }
+        DeleteEntries::Filters { filters } => {
+            if filters.is_empty() {
+                return Err(ApiError::BadRequest(NO_DATA_IN_REQUEST_BODY.into()));
+            }
+            let fields = db::get_fields_metadata(tx.as_mut(), table_id).await?;
+            let mut error_messages = Vec::new();
+            let filters = convert_filters(filters, &fields, &mut error_messages);
+            if !error_messages.is_empty() {
+                return Err(ApiError::UnprocessableEntity(error_messages.join(", ")));
+            }
+            db::get_filtered_entry_ids(tx.as_mut(), table_id, filters).await?
+        }
+    };
+
+    let fields = db::get_fields_metadata(tx.as_mut(), table_id).await?;
+    let mut referenced_ids = Vec::new();
+    let mut logs = Vec::new();
+    for &entry_id in &entry_ids {
+        let old_entry = db::get_entry(tx.as_mut(), table_id, entry_id, &fields).await?;
+        match db::trash_entry(tx.as_mut(), user_id, table_id, entry_id).await {
+            Ok(_) => logs.push(CreateAuditLog::entry(table_id, Some(&old_entry), None)),
+            Err(sqlx::Error::Database(e)) if e.is_foreign_key_violation() => {
+                referenced_ids.push(entry_id);
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    if !referenced_ids.is_empty() {
+        return Err(ApiError::Conflict(
+            referenced_ids
+                .into_iter()
+                .map(|entry_id| format!("{entry_id}: {ENTRY_REFERENCED}"))
+                .join(", "),
+        ));
+    }
+    db::create_audit_logs(tx.as_mut(), Some(user_id), logs).await?;
+
+    tx.commit().await?;
+    Ok(Json(entry_ids))
+}
+
+async fn query_entries(
+    NoApi(AuthSession { user, .. }): AppAuthSession,
+    State(AppState { db, .. }): State<AppState>,
+    Path(SelectTable { table_id }): Path<SelectTable>,
+    Json(query): Json<QueryEntries>,
+) -> ApiResult<Json<EntryPage>> {
+    let user_id = user.ok_or(ApiError::Unauthorized)?.user_id;
+
+    db::get_access_role(&db, Resource::Table, table_id, user_id)
+        .await?
+        .check(AccessRole::Viewer)?;
+
+    if query.parent_id.is_some() && db::get_table_parent_id(&db, table_id).await?.is_none() {
+        return Err(ApiError::UnprocessableEntity(NO_PARENT_TABLE.into()));
+    }
+
+    let fields = db::get_fields_metadata(&db, table_id).await?;
+    let query = convert_query(query, &fields)?;
+
+    let page = db::query_entries(&db, table_id, fields, query).await?;
+
+    Ok(Json(page))
+}
+
 /// Check that the entry's parent ID points to a valid entry.
 async fn check_parent_id(
     conn: impl Acquire<'_, Database = Postgres>,
@@ -152,11 +406,74 @@ async fn check_parent_id(
     Ok(())
 }
 
+/// Check that the entries referenced by relation fields exist.
+async fn check_relations(
+    conn: impl Acquire<'_, Database = Postgres>,
+    fields: &[FieldMetadata],
+    entries: &[Vec<Cell>],
+) -> ApiResult<()> {
+    let error_messages = relation_errors(conn, fields, entries).await?;
+    if !error_messages.is_empty() {
+        return Err(ApiError::UnprocessableEntity(error_messages.join(", ")));
+    }
+    Ok(())
+}
+
+/// Return an error message for each relation field referencing entries which do not exist.
+async fn relation_errors(
+    conn: impl Acquire<'_, Database = Postgres>,
+    fields: &[FieldMetadata],
+    entries: &[Vec<Cell>],
+) -> sqlx::Result<Vec<String>> {
+    let mut tx = conn.begin().await?;
+    let mut error_messages = Vec::new();
+
+    for (idx, field) in fields.iter().enumerate() {
+        let FieldKind::Relation {
+            target_table_id, ..
+        } = field.field_kind.0
+        else {
+            continue;
+        };
+        // IDs out of the range of entry IDs cannot match an entry.
+        let mut out_of_range = false;
+        let entry_ids = entries
+            .iter()
+            .flat_map(|cells| match &cells[idx] {
+                Cell::Integer(v) => Id::try_from(*v)
+                    .inspect_err(|_| out_of_range = true)
+                    .into_iter()
+                    .collect(),
+                Cell::EntryIds(v) => v.clone(),
+                _ => Vec::new(),
+            })
+            .unique()
+            .collect_vec();
+        if entry_ids.is_empty() && !out_of_range {
+            continue;
+        }
+        let existing_ids =
+            db::get_existing_entry_ids(tx.as_mut(), target_table_id, entry_ids.clone()).await?;
+        if out_of_range || existing_ids.len() != entry_ids.len() {
+            error_messages.push(format!("{}: {RELATION_ENTRY_NOT_FOUND}", field.field_id));
+        }
+    }
+
+    tx.commit().await?;
+    Ok(error_messages)
+}
+
 /// Convert a map of field IDs and JSON values to a list of [Cell]s.
-fn convert_cells(
+fn convert_cells(raw_cells: HashMap<Id, Value>, fields: &[FieldMetadata]) -> ApiResult<Vec<Cell>> {
+    try_convert_cells(raw_cells, fields)
+        .map_err(|error_messages| ApiError::UnprocessableEntity(error_messages.join(", ")))
+}
+
+/// Same as [convert_cells] but return an error message for each invalid cell.
+fn try_convert_cells(
     mut raw_cells: HashMap<Id, Value>,
     fields: &[FieldMetadata],
-) -> ApiResult<Vec<Cell>> {
+) -> Result<Vec<Cell>, Vec<String>> {
     let (new_cells, mut error_messages): (Vec<_>, Vec<_>) = fields
         .iter()
         .map(|field| {
@@ -173,7 +490,7 @@ fn convert_cells(
     );
 
     if !error_messages.is_empty() {
-        return Err(ApiError::UnprocessableEntity(error_messages.join(", ")));
+        return Err(error_messages);
     }
 
     Ok(new_cells)
@@ -182,6 +499,21 @@ fn convert_cells(
 /// Converts a JSON value to a [Cell] and return the correct error message on failure.
 fn json_to_cell(value: Value, field_kind: &FieldKind) -> Result<Cell, &'static str> {
     match (value, field_kind) {
+        (_, FieldKind::Formula { .. }) => Ok(Cell::Null),
+        (
+            Value::Null,
+            FieldKind::Relation {
+                is_required,
+                multiple: true,
+                ..
+            },
+        ) => {
+            if *is_required {
+                Err(IS_REQUIRED)
+            } else {
+                Ok(Cell::EntryIds(Vec::new()))
+            }
+        }
         (
             Value::Null,
             FieldKind::Text { is_required }
@@ -190,7 +522,8 @@ fn json_to_cell(value: Value, field_kind: &FieldKind) -> Result<Cell, &'static s
             | FieldKind::Money { is_required, .. }
             | FieldKind::DateTime { is_required, .. }
             | FieldKind::WebLink { is_required, .. }
-            | FieldKind::Enumeration { is_required, .. },
+            | FieldKind::Enumeration { is_required, .. }
+            | FieldKind::Relation { is_required, .. },
         ) => {
             if *is_required {
                 Err(IS_REQUIRED)
@@ -285,40 +618,218 @@ fn json_to_cell(value: Value, field_kind: &FieldKind) -> Result<Cell, &'static s
                 Err(INVALID_TYPE)
             }
         }
+        (
+            Value::Number(value),
+            FieldKind::Relation {
+                multiple: false, ..
+            },
+        ) => value
+            .as_i64()
+            .and_then(|value| Id::try_from(value).ok())
+            .map(|value| Cell::Integer(value.into()))
+            .ok_or(INVALID_TYPE),
+        (
+            Value::Array(values),
+            FieldKind::Relation {
+                is_required,
+                multiple: true,
+                ..
+            },
+        ) => {
+            let entry_ids: Vec<Id> = values
+                .into_iter()
+                .map(|value| {
+                    value
+                        .as_i64()
+                        .and_then(|value| Id::try_from(value).ok())
+                        .ok_or(INVALID_TYPE)
+                })
+                .try_collect()?;
+            if *is_required && entry_ids.is_empty() {
+                Err(IS_REQUIRED)
+            } else {
+                Ok(Cell::EntryIds(
+                    entry_ids.into_iter().sorted().dedup().collect(),
+                ))
+            }
+        }
         _ => Err(INVALID_TYPE),
     }
 }
 
-/// Check that cell value is within the range specified by the field options.
-fn check_range<T>(
-    value: &T,
-    range_start: Option<&T>,
-    range_end: Option<&T>,
-) -> Result<(), &'static str>
-where
-    T: PartialOrd,
-{
-    if range_start.is_some_and(|start| value < start) || range_end.is_some_and(|end| value > end) {
-        Err(OUT_OF_RANGE)
-    } else {
-        Ok(())
+/// Validate a query request and convert its filter values and cursor to [Cell]s.
+pub(super) fn convert_query(
+    QueryEntries {
+        parent_id,
+        filters,
+        sort,
+        limit,
+        offset,
+        cursor,
+    }: QueryEntries,
+    fields: &[FieldMetadata],
+) -> ApiResult<EntryQuery> {
+    let field_kinds: HashMap<_, _> = fields
+        .iter()
+        .map(|field| (field.field_id, &field.field_kind))
+        .collect();
+    let mut error_messages = Vec::new();
+
+    let filters = convert_filters(filters, fields, &mut error_messages);
+
+    error_messages.extend(
+        sort.iter()
+            .filter_map(|sort| match field_kinds.get(&sort.field_id) {
+                None => Some(format!("{}: {INVALID_FIELD_ID}", sort.field_id)),
+                Some(field_kind) if field_kind.is_multiple_relation() => {
+                    Some(format!("{}: {INVALID_SORT}", sort.field_id))
+                }
+                Some(_) => None,
+            }),
+    );
+
+    if !error_messages.is_empty() {
+        return Err(ApiError::UnprocessableEntity(error_messages.join(", ")));
     }
+
+    if limit.is_some_and(|limit| limit < 0) || offset.is_some_and(|offset| offset < 0) {
+        return Err(ApiError::UnprocessableEntity(INVALID_PAGINATION.into()));
+    }
+    if offset.is_some() && cursor.is_some() {
+        return Err(ApiError::UnprocessableEntity(OFFSET_WITH_CURSOR.into()));
+    }
+
+    let cursor = cursor
+        .map(|cursor| {
+            let (entry_id, values) = EntryCursor::decode(&cursor)
+                .filter(|(_, values)| values.len() == sort.len())
+                .ok_or(ApiError::UnprocessableEntity(INVALID_CURSOR.into()))?;
+            let cells = values
+                .into_iter()
+                .zip(&sort)
+                .map(|(value, sort)| match value {
+                    Value::Null => Ok(Cell::Null),
+                    value => filter_value_to_cell(value, field_kinds[&sort.field_id]),
+                })
+                .try_collect()
+                .map_err(|_| ApiError::UnprocessableEntity(INVALID_CURSOR.into()))?;
+            Ok::<_, ApiError>(EntryCursor { cells, entry_id })
+        })
+        .transpose()?;
+
+    Ok(EntryQuery {
+        parent_id,
+        filters,
+        sort,
+        limit,
+        offset,
+        cursor,
+    })
+}
+
+/// Validate the filters and convert their values to [Cell]s.
+/// Error messages are pushed for the invalid filters.
+pub(crate) fn convert_filters(
+    filters: Vec<EntryFilter>,
+    fields: &[FieldMetadata],
+    error_messages: &mut Vec<String>,
+) -> Vec<EntryFilter<Cell>> {
+    let field_kinds: HashMap<_, _> = fields
+        .iter()
+        .map(|field| (field.field_id, &field.field_kind))
+        .collect();
+
+    filters
+        .into_iter()
+        .filter_map(|EntryFilter { field_id, operator }| {
+            let Some(field_kind) = field_kinds.get(&field_id) else {
+                error_messages.push(format!("{field_id}: {INVALID_FIELD_ID}"));
+                return None;
+            };
+            if !operator_supported(&operator, field_kind) {
+                error_messages.push(format!("{field_id}: {INVALID_OPERATOR}"));
+                return None;
+            }
+            if let Err(message) = check_days(&operator) {
+                error_messages.push(format!("{field_id}: {message}"));
+                return None;
+            }
+            match operator.try_map(|value| filter_value_to_cell(value, field_kind)) {
+                Ok(operator) => Some(EntryFilter { field_id, operator }),
+                Err(message) => {
+                    error_messages.push(format!("{field_id}: {message}"));
+                    None
+                }
+            }
+        })
+        .collect_vec()
+}
+
+/// Check that the filter operator can be applied to fields of this kind.
+fn operator_supported<T>(operator: &FilterOperator<T>, field_kind: &FieldKind) -> bool {
+    if field_kind.is_multiple_relation() {
+        return false;
+    }
+    let field_kind = &*field_kind.value_kind();
+    match operator {
+        FilterOperator::Contains { .. } => {
+            matches!(
+                field_kind,
+                FieldKind::Text { .. } | FieldKind::WebLink { .. }
+            )
+        }
+        FilterOperator::WithinLastDays { .. } => {
+            matches!(field_kind, FieldKind::DateTime { .. })
+        }
+        FilterOperator::Range { .. } => matches!(
+            field_kind,
+            FieldKind::Integer { .. }
+                | FieldKind::Float { .. }
+                | FieldKind::Money { .. }
+                | FieldKind::Progress { .. }
+                | FieldKind::DateTime { .. }
+        ),
+        FilterOperator::In { .. } => matches!(
+            field_kind,
+            FieldKind::Enumeration { .. } | FieldKind::Relation { .. }
+        ),
+        FilterOperator::Equals { .. } | FilterOperator::IsNull | FilterOperator::IsNotNull => true,
+    }
+}
+
+/// Check that the number of days of a `WithinLastDays` filter operator is not too large.
+pub(crate) fn check_days<T>(operator: &FilterOperator<T>) -> Result<(), &'static str> {
+    match operator {
+        FilterOperator::WithinLastDays { days } if *days > MAX_WITHIN_LAST_DAYS => {
+            Err(INVALID_DAYS)
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Converts a JSON filter value to a [Cell]. Unlike [json_to_cell],
+/// the value is not checked against the field options and cannot be null.
+fn filter_value_to_cell(value: Value, field_kind: &FieldKind) -> Result<Cell, &'static str> {
+    Cell::from_filter_value(value, field_kind).ok_or(INVALID_TYPE)
 }
 
 #[cfg_attr(coverage_nightly, coverage(off))]
 mod docs {
     use crate::{
+        Id,
         api::{
             NO_DATA_IN_REQUEST_BODY,
             data::entries::{
-                ENUMERATION_VALUE_MISSING, INVALID_FIELD_ID, INVALID_TYPE, IS_REQUIRED,
-                NO_PARENT_TABLE, PARENT_ID_NOT_FOUND,
+                DUPLICATE_ENTRY_ID, ENTRY_NOT_FOUND, ENTRY_REFERENCED, ENUMERATION_VALUE_MISSING,
+                INVALID_CURSOR, INVALID_DAYS, INVALID_FIELD_ID, INVALID_OPERATOR,
+                INVALID_PAGINATION, INVALID_SORT, INVALID_TYPE, IS_REQUIRED, NO_PARENT_TABLE,
+                OFFSET_WITH_CURSOR, PARENT_ID_NOT_FOUND, RELATION_ENTRY_NOT_FOUND,
             },
         },
         docs::{ENTRIES_TAG, TransformOperationExt, template},
         model::{
             access::{AccessRole, Resource},
-            data::Entry,
+            data::{Entry, EntryPage},
         },
     };
     use aide::{OperationOutput, transform::TransformOperation};
@@ -326,6 +837,7 @@ mod docs {
     use itertools::Itertools;
 
     const TABLE_EDITOR: [(Resource, AccessRole); 1] = [(Resource::Table, AccessRole::Editor)];
+    const TABLE_VIEWER: [(Resource, AccessRole); 1] = [(Resource::Table, AccessRole::Viewer)];
 
     fn entries<'a, R: OperationOutput>(
         op: TransformOperation<'a>,
@@ -341,6 +853,7 @@ mod docs {
             INVALID_TYPE,
             ENUMERATION_VALUE_MISSING,
             INVALID_FIELD_ID,
+            RELATION_ENTRY_NOT_FOUND,
         ]
         .into_iter()
         .map(|v| format!("<field_id>: {v}"))
@@ -358,12 +871,105 @@ mod docs {
         .required_access(TABLE_EDITOR)
     }
 
+    pub fn update_entries(op: TransformOperation) -> TransformOperation {
+        let errors = [ENTRY_NOT_FOUND, DUPLICATE_ENTRY_ID]
+            .into_iter()
+            .map(|v| format!("<entry_id>: {v}"))
+            .chain(
+                [
+                    IS_REQUIRED,
+                    INVALID_TYPE,
+                    ENUMERATION_VALUE_MISSING,
+                    INVALID_FIELD_ID,
+                    RELATION_ENTRY_NOT_FOUND,
+                ]
+                .into_iter()
+                .map(|v| format!("<entry_id>: <field_id>: {v}")),
+            )
+            .chain(
+                [NO_PARENT_TABLE, PARENT_ID_NOT_FOUND]
+                    .into_iter()
+                    .map(|v| format!("<entry_id>: {v}")),
+            )
+            .join("\n\n");
+
+        entries::<Json<Vec<Entry>>>(
+            op,
+            "update_entries",
+            "Update many entries of a table in a single transaction. \
+            Nothing is updated if any of the entries is invalid.",
+        )
+        .response_description::<400, String>(NO_DATA_IN_REQUEST_BODY)
+        .response_description::<404, ()>("Table not found")
+        .response_description::<422, String>(&errors)
+        .required_access(TABLE_EDITOR)
+    }
+
+    pub fn delete_entries(op: TransformOperation) -> TransformOperation {
+        let errors = [format!("<entry_id>: {ENTRY_NOT_FOUND}")]
+            .into_iter()
+            .chain(
+                [
+                    INVALID_FIELD_ID,
+                    INVALID_OPERATOR,
+                    INVALID_DAYS,
+                    INVALID_TYPE,
+                ]
+                .into_iter()
+                .map(|v| format!("<field_id>: {v}")),
+            )
+            .join("\n\n");
+
+        entries::<Json<Vec<Id>>>(
+            op,
+            "delete_entries",
+            "Move many entries of a table to the trash in a single transaction, \
+            either by ID or by matching all the filters. Returns the IDs of the deleted entries.",
+        )
+        .response_description::<400, String>(NO_DATA_IN_REQUEST_BODY)
+        .response_description::<404, ()>("Table not found")
+        .response_description::<409, String>(&format!("<entry_id>: {ENTRY_REFERENCED}"))
+        .response_description::<422, String>(&errors)
+        .required_access(TABLE_EDITOR)
+    }
+
+    pub fn query_entries(op: TransformOperation) -> TransformOperation {
+        let errors = [
+            INVALID_FIELD_ID,
+            INVALID_OPERATOR,
+            INVALID_DAYS,
+            INVALID_TYPE,
+            INVALID_SORT,
+        ]
+        .into_iter()
+        .map(|v| format!("<field_id>: {v}"))
+        .chain([
+            NO_PARENT_TABLE.into(),
+            INVALID_PAGINATION.into(),
+            OFFSET_WITH_CURSOR.into(),
+            INVALID_CURSOR.into(),
+        ])
+        .join("\n\n");
+
+        entries::<Json<EntryPage>>(
+            op,
+            "query_entries",
+            "Get a page of entries in a table matching all the filters, \
+            along with the total number of matching entries. \
+            Pagination uses either an offset or the cursor returned by the previous page.",
+        )
+        .response_description::<404, ()>("Table not found")
+        .response_description::<422, String>(&errors)
+        .required_access(TABLE_VIEWER)
+    }
+
     pub fn update_entry(op: TransformOperation) -> TransformOperation {
         let errors = [
             IS_REQUIRED,
             INVALID_TYPE,
             ENUMERATION_VALUE_MISSING,
             INVALID_FIELD_ID,
+            RELATION_ENTRY_NOT_FOUND,
             NO_PARENT_TABLE,
             PARENT_ID_NOT_FOUND,
         ]
@@ -379,23 +985,31 @@ mod docs {
     }
 
     pub fn delete_entry(op: TransformOperation) -> TransformOperation {
-        entries::<()>(op, "delete_entry", "Delete an entry from a table.")
-            .response_description::<404, ()>("Table not found\n\nEntry not found")
-            .required_access(TABLE_EDITOR)
+        entries::<()>(
+            op,
+            "delete_entry",
+            "Move an entry to the trash. Entries referencing it through relation fields \
+            are unlinked or deleted depending on the field options.",
+        )
+        .response_description::<404, ()>("Table not found\n\nEntry not found")
+        .response_description::<409, String>(ENTRY_REFERENCED)
+        .required_access(TABLE_EDITOR)
     }
 }
 
 #[cfg(test)]
 #[cfg_attr(coverage_nightly, coverage(off))]
 mod test {
+    use super::{DUPLICATE_ENTRY_ID, ENTRY_NOT_FOUND, INVALID_TYPE, RELATION_ENTRY_NOT_FOUND};
     use crate::{
         Id, db,
         model::{
             Cell,
             access::{AccessRole, Resource},
             data::{
-                CreateEntries, CreateField, CreateTable, FieldIdentifier, FieldKind, FieldMetadata,
-                TableIdentifier, UpdateEntry,
+                CreateEntries, CreateField, CreateTable, DeleteEntries, EntryFilter,
+                FieldIdentifier, FieldKind, FieldMetadata, FilterOperator, FormulaKind,
+                RelationDeleteRule, TableIdentifier, UpdateEntries, UpdateEntriesItem, UpdateEntry,
             },
         },
         test_util,
@@ -846,6 +1460,261 @@ mod test {
         Ok(())
     }
 
+    #[sqlx::test]
+    async fn update_entries(db: PgPool) -> anyhow::Result<()> {
+        let mut server = test_util::server(db.clone()).await;
+        let table_id = db::create_table(
+            &db,
+            CreateTable {
+                name: "test".into(),
+                description: "".into(),
+                parent_id: None,
+            },
+        )
+        .await?
+        .table_id;
+        let field = FieldMetadata::from_field(
+            db::create_field(
+                &db,
+                table_id,
+                CreateField {
+                    name: "abc".into(),
+                    field_kind: FieldKind::Integer {
+                        is_required: true,
+                        range_start: None,
+                        range_end: None,
+                    },
+                },
+            )
+            .await?,
+        );
+        let field_id = field.field_id;
+        let entry_ids = db::create_entries(
+            &db,
+            table_id,
+            None,
+            vec![field],
+            vec![vec![Cell::Integer(1)], vec![Cell::Integer(2)]],
+        )
+        .await?
+        .into_iter()
+        .map(|entry| entry.entry_id)
+        .collect_vec();
+
+        let path = format!("/api/tables/{table_id}/entries");
+        let update_entries = |values: [(Id, Value); 2]| UpdateEntries {
+            entries: values
+                .into_iter()
+                .map(|(entry_id, value)| UpdateEntriesItem {
+                    entry_id,
+                    parent_id: None,
+                    cells: HashMap::from_iter([(field_id, value)]),
+                })
+                .collect(),
+        };
+        let valid = update_entries([(entry_ids[0], json!(10)), (entry_ids[1], json!(20))]);
+
+        server
+            .patch(&path)
+            .json(&valid)
+            .await
+            .assert_status_unauthorized();
+
+        let user = db::create_user(&db, "test".into(), "".into(), false).await?;
+        test_util::login_session(&mut server, &user).await;
+        test_util::test_access_control(
+            &db,
+            Resource::Table,
+            table_id,
+            user.user_id,
+            AccessRole::Editor,
+            async || server.patch(&path).json(&valid).await,
+        )
+        .await;
+
+        server
+            .patch(&path)
+            .json(&UpdateEntries { entries: vec![] })
+            .await
+            .assert_status_bad_request();
+
+        let response = server
+            .patch(&path)
+            .json(&update_entries([
+                (entry_ids[0], json!("a")),
+                (1000, json!(30)),
+            ]))
+            .await;
+        response.assert_status_unprocessable_entity();
+        assert!(response.text().ends_with(&format!(
+            "{}: {field_id}: {INVALID_TYPE}, 1000: {ENTRY_NOT_FOUND}",
+            entry_ids[0]
+        )));
+        let response = server
+            .patch(&path)
+            .json(&update_entries([
+                (entry_ids[0], json!(30)),
+                (entry_ids[0], json!(40)),
+            ]))
+            .await;
+        response.assert_status_unprocessable_entity();
+        assert!(response.text().ends_with(&format!(
+            "{0}: {DUPLICATE_ENTRY_ID}, {0}: {DUPLICATE_ENTRY_ID}",
+            entry_ids[0]
+        )));
+
+        let response = server.patch(&path).json(&valid).await;
+        response.assert_status_ok();
+        let values = response
+            .json::<Vec<Value>>()
+            .into_iter()
+            .map(|entry| entry["cells"][field_id.to_string()].clone())
+            .collect_vec();
+        assert_eq!(values, [json!(10), json!(20)]);
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn delete_entries(db: PgPool) -> anyhow::Result<()> {
+        let mut server = test_util::server(db.clone()).await;
+        let table_id = db::create_table(
+            &db,
+            CreateTable {
+                name: "test".into(),
+                description: "".into(),
+                parent_id: None,
+            },
+        )
+        .await?
+        .table_id;
+        let field = FieldMetadata::from_field(
+            db::create_field(
+                &db,
+                table_id,
+                CreateField {
+                    name: "abc".into(),
+                    field_kind: FieldKind::Checkbox,
+                },
+            )
+            .await?,
+        );
+        let field_id = field.field_id;
+        let create_entries = async |values: Vec<bool>| {
+            db::create_entries(
+                &db,
+                table_id,
+                None,
+                vec![field.clone()],
+                values.into_iter().map(|v| vec![Cell::Boolean(v)]).collect(),
+            )
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.entry_id)
+            .collect_vec()
+        };
+
+        let path = format!("/api/tables/{table_id}/entries");
+        server
+            .delete(&path)
+            .json(&DeleteEntries::Ids {
+                entry_ids: vec![1000],
+            })
+            .await
+            .assert_status_unauthorized();
+
+        let user = db::create_user(&db, "test".into(), "".into(), false).await?;
+        test_util::login_session(&mut server, &user).await;
+        test_util::test_access_control(
+            &db,
+            Resource::Table,
+            table_id,
+            user.user_id,
+            AccessRole::Editor,
+            async || {
+                let entry_ids = create_entries(vec![true]).await;
+                server
+                    .delete(&path)
+                    .json(&DeleteEntries::Ids { entry_ids })
+                    .await
+            },
+        )
+        .await;
+
+        for request in [
+            DeleteEntries::Ids { entry_ids: vec![] },
+            DeleteEntries::Filters { filters: vec![] },
+        ] {
+            server
+                .delete(&path)
+                .json(&request)
+                .await
+                .assert_status_bad_request();
+        }
+
+        let entry_ids = create_entries(vec![true, false, true, false]).await;
+        let response = server
+            .delete(&path)
+            .json(&DeleteEntries::Ids {
+                entry_ids: vec![entry_ids[0], 1000],
+            })
+            .await;
+        response.assert_status_unprocessable_entity();
+        assert!(
+            response
+                .text()
+                .ends_with(&format!("1000: {ENTRY_NOT_FOUND}"))
+        );
+        let response = server
+            .delete(&path)
+            .json(&DeleteEntries::Filters {
+                filters: vec![EntryFilter {
+                    field_id,
+                    operator: FilterOperator::Equals { value: json!(1) },
+                }],
+            })
+            .await;
+        response.assert_status_unprocessable_entity();
+        assert!(
+            response
+                .text()
+                .ends_with(&format!("{field_id}: {INVALID_TYPE}"))
+        );
+
+        let response = server
+            .delete(&path)
+            .json(&DeleteEntries::Ids {
+                entry_ids: vec![entry_ids[0]],
+            })
+            .await;
+        response.assert_status_ok();
+        assert_eq!(response.json::<Vec<Id>>(), [entry_ids[0]]);
+
+        let response = server
+            .delete(&path)
+            .json(&DeleteEntries::Filters {
+                filters: vec![EntryFilter {
+                    field_id,
+                    operator: FilterOperator::Equals {
+                        value: json!(false),
+                    },
+                }],
+            })
+            .await;
+        response.assert_status_ok();
+        assert_eq!(response.json::<Vec<Id>>(), [entry_ids[1], entry_ids[3]]);
+
+        let remaining = db::get_existing_entry_ids(&db, table_id, entry_ids.clone()).await?;
+        assert_eq!(remaining, [entry_ids[2]]);
+        let trashed = db::get_trash(&db, user.user_id)
+            .await?
+            .into_iter()
+            .filter(|item| entry_ids.contains(&item.resource_id))
+            .count();
+        assert_eq!(trashed, 3);
+        Ok(())
+    }
+
     #[sqlx::test]
     async fn check_parent_id(db: PgPool) -> anyhow::Result<()> {
         let parent_table_id = db::create_table(
@@ -1044,6 +1913,298 @@ mod test {
         super::convert_cells(err_values, &fields).unwrap_err();
     }
 
+    #[sqlx::test]
+    async fn query_entries(db: PgPool) -> anyhow::Result<()> {
+        let mut server = test_util::server(db.clone()).await;
+        let table_id = db::create_table(
+            &db,
+            CreateTable {
+                name: "test".into(),
+                description: "".into(),
+                parent_id: None,
+            },
+        )
+        .await?
+        .table_id;
+        let field = FieldMetadata::from_field(
+            db::create_field(
+                &db,
+                table_id,
+                CreateField {
+                    name: "abc".into(),
+                    field_kind: FieldKind::Integer {
+                        is_required: false,
+                        range_start: None,
+                        range_end: None,
+                    },
+                },
+            )
+            .await?,
+        );
+        let field_id = field.field_id;
+        let entry_ids = db::create_entries(
+            &db,
+            table_id,
+            None,
+            vec![field],
+            (0..5).map(|i| vec![Cell::Integer(i)]).collect(),
+        )
+        .await?
+        .into_iter()
+        .map(|e| e.entry_id)
+        .collect_vec();
+
+        let path = format!("/api/tables/{table_id}/entries/query");
+        let query = json!({
+            "filters": [{ "field_id": field_id, "operator": "Range", "start": 1, "end": null }],
+            "sort": [{ "field_id": field_id, "direction": "Descending" }],
+            "limit": 3,
+        });
+
+        server
+            .post(&path)
+            .json(&query)
+            .await
+            .assert_status_unauthorized();
+
+        let user = db::create_user(&db, "test".into(), "".into(), false).await?;
+        test_util::login_session(&mut server, &user).await;
+        test_util::test_access_control(
+            &db,
+            Resource::Table,
+            table_id,
+            user.user_id,
+            AccessRole::Viewer,
+            async || server.post(&path).json(&query).await,
+        )
+        .await;
+
+        server
+            .post("/api/tables/1000/entries/query")
+            .json(&query)
+            .await
+            .assert_status_not_found();
+
+        for query_wrong in [
+            json!({ "filters": [{ "field_id": 1000, "operator": "IsNull" }] }),
+            json!({ "sort": [{ "field_id": 1000 }] }),
+            json!({ "filters": [{ "field_id": field_id, "operator": "Contains", "value": "1" }] }),
+            json!({ "filters": [{ "field_id": field_id, "operator": "Equals", "value": "1" }] }),
+            json!({ "filters": [{ "field_id": field_id, "operator": "Equals", "value": null }] }),
+            json!({ "limit": -1 }),
+            json!({ "offset": -1 }),
+            json!({ "offset": 1, "cursor": "" }),
+            json!({ "cursor": "abc" }),
+            json!({ "parent_id": 1 }),
+        ] {
+            server
+                .post(&path)
+                .json(&query_wrong)
+                .await
+                .assert_status_unprocessable_entity();
+        }
+
+        let response = server.post(&path).json(&query).await;
+        response.assert_status_ok();
+        let page: Value = response.json();
+        assert_eq!(page["total_count"], json!(4));
+        let page_entry_ids = page["entries"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| e["entry_id"].as_i64().unwrap() as Id)
+            .collect_vec();
+        assert_eq!(
+            page_entry_ids,
+            vec![entry_ids[4], entry_ids[3], entry_ids[2]]
+        );
+
+        let mut query = query;
+        query["cursor"] = page["next_cursor"].clone();
+        let response = server.post(&path).json(&query).await;
+        response.assert_status_ok();
+        let page: Value = response.json();
+        assert_eq!(page["total_count"], json!(4));
+        assert_eq!(page["next_cursor"], Value::Null);
+        let page_entry_ids = page["entries"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| e["entry_id"].as_i64().unwrap() as Id)
+            .collect_vec();
+        assert_eq!(page_entry_ids, vec![entry_ids[1]]);
+
+        query["sort"] = json!([]);
+        server
+            .post(&path)
+            .json(&query)
+            .await
+            .assert_status_unprocessable_entity();
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn relations(db: PgPool) -> anyhow::Result<()> {
+        let mut server = test_util::server(db.clone()).await;
+        let user = db::create_user(&db, "test".into(), "".into(), false).await?;
+        test_util::login_session(&mut server, &user).await;
+
+        let mut table_ids = Vec::new();
+        for name in ["target", "source"] {
+            let table_id = db::create_table(
+                &db,
+                CreateTable {
+                    name: name.into(),
+                    description: "".into(),
+                    parent_id: None,
+                },
+            )
+            .await?
+            .table_id;
+            db::create_access(
+                &db,
+                Resource::Table,
+                table_id,
+                user.user_id,
+                AccessRole::Owner,
+            )
+            .await?;
+            table_ids.push(table_id);
+        }
+        let [target_table_id, table_id] = table_ids.try_into().unwrap();
+
+        let target_entry_ids =
+            db::create_entries(&db, target_table_id, None, vec![], vec![vec![]; 2])
+                .await?
+                .into_iter()
+                .map(|entry| entry.entry_id)
+                .collect_vec();
+
+        let mut field_ids = Vec::new();
+        for multiple in [false, true] {
+            let field_id = db::create_field(
+                &db,
+                table_id,
+                CreateField {
+                    name: multiple.to_string(),
+                    field_kind: FieldKind::Relation {
+                        is_required: false,
+                        target_table_id,
+                        multiple,
+                        on_delete: RelationDeleteRule::Restrict,
+                    },
+                },
+            )
+            .await?
+            .field_id;
+            field_ids.push(field_id);
+        }
+
+        let path = format!("/api/tables/{table_id}/entries");
+        for entry_ids in [(1000, vec![]), (target_entry_ids[0], vec![1000])] {
+            server
+                .post(&path)
+                .json(&CreateEntries {
+                    parent_id: None,
+                    entries: vec![HashMap::from_iter([
+                        (field_ids[0], json!(entry_ids.0)),
+                        (field_ids[1], json!(entry_ids.1)),
+                    ])],
+                })
+                .await
+                .assert_status_unprocessable_entity();
+        }
+
+        // IDs of imported cells are not truncated to the ID of another entry.
+        let fields = db::get_fields_metadata(&db, table_id).await?;
+        let entry_id = target_entry_ids[0] as i64 + (1 << 32);
+        assert_eq!(
+            super::relation_errors(&db, &fields, &[vec![Cell::Integer(entry_id), Cell::Null]])
+                .await?,
+            [format!("{}: {RELATION_ENTRY_NOT_FOUND}", field_ids[0])]
+        );
+
+        let response = server
+            .post(&path)
+            .json(&CreateEntries {
+                parent_id: None,
+                entries: vec![HashMap::from_iter([
+                    (field_ids[0], json!(target_entry_ids[0])),
+                    (field_ids[1], json!(target_entry_ids)),
+                ])],
+            })
+            .await;
+        response.assert_status_ok();
+        let entries: Value = response.json();
+        assert_eq!(
+            entries[0]["cells"][field_ids[0].to_string()],
+            json!(target_entry_ids[0])
+        );
+        assert_eq!(
+            entries[0]["cells"][field_ids[1].to_string()],
+            json!(target_entry_ids)
+        );
+        let entry_id = entries[0]["entry_id"].as_i64().unwrap();
+
+        for target_entry_id in &target_entry_ids {
+            server
+                .delete(&format!(
+                    "/api/tables/{target_table_id}/entries/{target_entry_id}"
+                ))
+                .await
+                .assert_status_conflict();
+        }
+
+        server
+            .delete(&format!("{path}/{entry_id}"))
+            .await
+            .assert_status_ok();
+        server
+            .delete(&format!(
+                "/api/tables/{target_table_id}/entries/{}",
+                target_entry_ids[0]
+            ))
+            .await
+            .assert_status_ok();
+
+        Ok(())
+    }
+
+    #[test]
+    fn filter_value_to_cell() {
+        let integer = FieldKind::Integer {
+            is_required: true,
+            range_start: Some(0),
+            range_end: Some(1),
+        };
+        assert_eq!(
+            super::filter_value_to_cell(json!(5), &integer),
+            Ok(Cell::Integer(5))
+        );
+        assert!(super::filter_value_to_cell(Value::Null, &integer).is_err());
+        assert!(super::filter_value_to_cell(json!("5"), &integer).is_err());
+        assert_eq!(
+            super::filter_value_to_cell(
+                json!("1.5"),
+                &FieldKind::Money {
+                    is_required: false,
+                    range_start: None,
+                    range_end: None,
+                }
+            ),
+            Ok(Cell::Decimal(Decimal::from_str("1.5").unwrap()))
+        );
+        assert_eq!(
+            super::filter_value_to_cell(json!(true), &FieldKind::Checkbox),
+            Ok(Cell::Boolean(true))
+        );
+        assert_eq!(
+            super::filter_value_to_cell(json!("abc"), &FieldKind::WebLink { is_required: true }),
+            Ok(Cell::String("abc".into()))
+        );
+    }
+
     #[test]
     fn json_to_cell() {
         fn test_is_required<F>(get_field_kind: F)
@@ -1057,6 +2218,7 @@ mod test {
             );
         }
 
+        #[allow(clippy::too_many_arguments)]
         fn test_numeric<T, S, F, C>(
             ok_value: T,
             low_value: T,
@@ -1222,6 +2384,37 @@ mod test {
         assert!(
             super::json_to_cell(serde_json::to_value("0").unwrap(), &enumeration_field).is_err()
         );
+
+        let relation = |is_required, multiple| FieldKind::Relation {
+            is_required,
+            target_table_id: 1,
+            multiple,
+            on_delete: RelationDeleteRule::Restrict,
+        };
+        test_is_required(|is_required| relation(is_required, false));
+        assert_eq!(
+            super::json_to_cell(json!(5), &relation(true, false)),
+            Ok(Cell::Integer(5))
+        );
+        assert!(super::json_to_cell(json!(i64::MAX), &relation(true, false)).is_err());
+        assert!(super::json_to_cell(json!([5]), &relation(true, false)).is_err());
+        assert_eq!(
+            super::json_to_cell(json!([3, 1, 3]), &relation(true, true)),
+            Ok(Cell::EntryIds(vec![1, 3]))
+        );
+        assert_eq!(
+            super::json_to_cell(Value::Null, &relation(false, true)),
+            Ok(Cell::EntryIds(vec![]))
+        );
+        for value in [Value::Null, json!([]), json!(["1"]), json!(1)] {
+            assert!(super::json_to_cell(value, &relation(true, true)).is_err());
+        }
+
+        let formula = FieldKind::Formula {
+            expression: "1".into(),
+            result_kind: FormulaKind::Integer,
+        };
+        assert_eq!(super::json_to_cell(json!(5), &formula), Ok(Cell::Null));
     }
 
     #[test]
@@ -1,7 +1,7 @@
 //! Routes for managing table fields.
 
 use crate::{
-    AppState,
+    AppState, Id,
     api::NO_DATA_IN_REQUEST_BODY,
     auth::AppAuthSession,
     db,
@@ -9,7 +9,8 @@ use crate::{
     model::{
         access::{AccessRole, AccessRoleCheck, Resource},
         data::{
-            CreateField, Field, FieldKind, SelectField, SelectTable, SetFieldOrder, UpdateField,
+            CreateAuditLog, CreateField, Field, FieldKind, RelationDeleteRule, SelectField,
+            SelectTable, SetFieldOrder, UpdateField, compile_formula, formula_dependencies,
         },
     },
 };
@@ -26,13 +27,21 @@ use axum::{
 };
 use axum_login::AuthSession;
 use itertools::Itertools;
-use std::collections::HashSet;
+use sqlx::{Acquire, PgExecutor, Postgres};
+use std::{collections::HashSet, mem::discriminant};
 
 const INVALID_RANGE: &str = "Range start bound is greater than end bound";
 const ENUMERATION_INVALID_DEFAULT: &str = "Enumeration field default value does not exist";
 const FIELD_ID_NOT_FOUND: &str = "Field ID not found";
 const FIELD_ID_MISSING: &str = "Field ID missing";
 const INVALID_ORDERING: &str = "Ordering number does not follow the sequence";
+const RELATION_TARGET_NOT_FOUND: &str = "Relation target table not found";
+const RELATION_CHANGED: &str =
+    "Relation fields cannot be converted or change their target table and multiplicity";
+const RELATION_REQUIRED_UNLINK: &str =
+    "Required relations to one entry cannot unlink the deleted target entries";
+const INVALID_FORMULA: &str = "Invalid formula";
+const FIELD_USED_BY_FORMULA: &str = "Field is used by a formula field";
 
 pub fn router() -> ApiRouter<AppState> {
     ApiRouter::new().nest(
@@ -65,8 +74,16 @@ async fn create_field(
         .check(AccessRole::Owner)?;
 
     validate_field_kind(&mut create_field.field_kind)?;
+    check_relation(tx.as_mut(), user_id, &create_field.field_kind, None).await?;
+    check_formula(tx.as_mut(), table_id, None, &create_field.field_kind).await?;
 
     let field = db::create_field(tx.as_mut(), table_id, create_field).await?;
+    db::create_audit_logs(
+        tx.as_mut(),
+        Some(user_id),
+        vec![CreateAuditLog::field(None, Some(&field))],
+    )
+    .await?;
 
     tx.commit().await?;
     Ok(Json(field))
@@ -90,8 +107,37 @@ async fn update_field(
     };
 
     validate_field_kind(&mut update_field.field_kind)?;
+    let old_field = db::get_fields(tx.as_mut(), table_id)
+        .await?
+        .into_iter()
+        .find(|field| field.field_id == field_id)
+        .ok_or(ApiError::NotFound)?;
+    let old_field_kind = &old_field.field_kind.0;
+    check_relation(
+        tx.as_mut(),
+        user_id,
+        &update_field.field_kind,
+        Some(old_field_kind),
+    )
+    .await?;
+    check_formula(
+        tx.as_mut(),
+        table_id,
+        Some(field_id),
+        &update_field.field_kind,
+    )
+    .await?;
+    if discriminant(&update_field.field_kind) != discriminant(old_field_kind) {
+        check_formula_dependents(tx.as_mut(), table_id, field_id).await?;
+    }
 
     let field = db::update_field(tx.as_mut(), field_id, update_field).await?;
+    db::create_audit_logs(
+        tx.as_mut(),
+        Some(user_id),
+        vec![CreateAuditLog::field(Some(&old_field), Some(&field))],
+    )
+    .await?;
 
     tx.commit().await?;
     Ok(Json(field))
@@ -113,7 +159,20 @@ async fn delete_field(
         return Err(ApiError::NotFound);
     };
 
-    db::delete_field(tx.as_mut(), field_id).await?;
+    check_formula_dependents(tx.as_mut(), table_id, field_id).await?;
+
+    let old_field = db::get_fields(tx.as_mut(), table_id)
+        .await?
+        .into_iter()
+        .find(|field| field.field_id == field_id)
+        .ok_or(ApiError::NotFound)?;
+    db::trash_field(tx.as_mut(), user_id, field_id).await?;
+    db::create_audit_logs(
+        tx.as_mut(),
+        Some(user_id),
+        vec![CreateAuditLog::field(Some(&old_field), None)],
+    )
+    .await?;
 
     tx.commit().await?;
     Ok(())
@@ -181,7 +240,19 @@ async fn set_field_order(
         return Err(ApiError::UnprocessableEntity(error_messages.join(", ")));
     }
 
+    let old_fields = db::get_fields(tx.as_mut(), table_id).await?;
     db::set_field_order(tx.as_mut(), order).await?;
+    let logs = db::get_fields(tx.as_mut(), table_id)
+        .await?
+        .iter()
+        .filter_map(|field| {
+            let old_field = old_fields
+                .iter()
+                .find(|old_field| old_field.field_id == field.field_id)?;
+            (old_field != field).then(|| CreateAuditLog::field(Some(old_field), Some(field)))
+        })
+        .collect();
+    db::create_audit_logs(tx.as_mut(), Some(user_id), logs).await?;
 
     tx.commit().await?;
     Ok(())
@@ -219,18 +290,125 @@ fn validate_field_kind(field_kind: &mut FieldKind) -> ApiResult<()> {
             values,
             default_value,
             ..
-        } => {
-            if !values.contains_key(default_value) {
-                return Err(ApiError::UnprocessableEntity(
-                    ENUMERATION_INVALID_DEFAULT.into(),
-                ));
-            }
+        } if !values.contains_key(default_value) => {
+            return Err(ApiError::UnprocessableEntity(
+                ENUMERATION_INVALID_DEFAULT.into(),
+            ));
         }
         _ => {}
     };
     Ok(())
 }
 
+/// Check that the target table of a relation field exists and is visible to the user,
+/// and that an existing relation field is not converted or retargeted.
+/// Return `422 Unprocessable Entity` on failure.
+async fn check_relation(
+    conn: impl Acquire<'_, Database = Postgres>,
+    user_id: Id,
+    field_kind: &FieldKind,
+    old_field_kind: Option<&FieldKind>,
+) -> ApiResult<()> {
+    let mut tx = conn.begin().await?;
+
+    let is_changed = match (field_kind, old_field_kind) {
+        (
+            FieldKind::Relation {
+                target_table_id,
+                multiple,
+                ..
+            },
+            Some(FieldKind::Relation {
+                target_table_id: old_target_table_id,
+                multiple: old_multiple,
+                ..
+            }),
+        ) => target_table_id != old_target_table_id || multiple != old_multiple,
+        (FieldKind::Relation { .. }, Some(_)) | (_, Some(FieldKind::Relation { .. })) => true,
+        _ => false,
+    };
+    if is_changed {
+        return Err(ApiError::UnprocessableEntity(RELATION_CHANGED.into()));
+    }
+
+    if let FieldKind::Relation {
+        is_required: true,
+        multiple: false,
+        on_delete: RelationDeleteRule::Unlink,
+        ..
+    } = field_kind
+    {
+        return Err(ApiError::UnprocessableEntity(
+            RELATION_REQUIRED_UNLINK.into(),
+        ));
+    }
+
+    if let FieldKind::Relation {
+        target_table_id, ..
+    } = field_kind
+        && db::get_access_role(tx.as_mut(), Resource::Table, *target_table_id, user_id)
+            .await?
+            .is_none()
+    {
+        return Err(ApiError::UnprocessableEntity(
+            RELATION_TARGET_NOT_FOUND.into(),
+        ));
+    }
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Check that the expression of a formula field compiles against the other fields of the table.
+/// Return `422 Unprocessable Entity` on failure.
+async fn check_formula(
+    executor: impl PgExecutor<'_>,
+    table_id: Id,
+    field_id: Option<Id>,
+    field_kind: &FieldKind,
+) -> ApiResult<()> {
+    let FieldKind::Formula {
+        expression,
+        result_kind,
+    } = field_kind
+    else {
+        return Ok(());
+    };
+
+    let fields = db::get_fields_metadata(executor, table_id)
+        .await?
+        .into_iter()
+        .filter(|field| Some(field.field_id) != field_id)
+        .collect_vec();
+
+    compile_formula(expression, *result_kind, &fields)
+        .map_err(|error| ApiError::UnprocessableEntity(format!("{INVALID_FORMULA}: {error}")))?;
+    Ok(())
+}
+
+/// Check that no formula field of the table references this field.
+/// Return `409 Conflict` on failure.
+async fn check_formula_dependents(
+    executor: impl PgExecutor<'_>,
+    table_id: Id,
+    field_id: Id,
+) -> ApiResult<()> {
+    let is_used = db::get_fields_metadata(executor, table_id)
+        .await?
+        .into_iter()
+        .any(|field| match field.field_kind.0 {
+            FieldKind::Formula { expression, .. } => {
+                field.field_id != field_id && formula_dependencies(&expression).contains(&field_id)
+            }
+            _ => false,
+        });
+
+    if is_used {
+        return Err(ApiError::Conflict(FIELD_USED_BY_FORMULA.into()));
+    }
+    Ok(())
+}
+
 /// Validates the range definition of a field. Return `422 Unprocessable Entity` on failure.
 fn validate_range<T>(range_start: Option<T>, range_end: Option<T>) -> ApiResult<()>
 where
@@ -251,7 +429,11 @@ mod docs {
     use crate::{
         api::{
             NO_DATA_IN_REQUEST_BODY,
-            data::fields::{FIELD_ID_NOT_FOUND, INVALID_ORDERING, INVALID_RANGE},
+            data::fields::{
+                ENUMERATION_INVALID_DEFAULT, FIELD_ID_NOT_FOUND, FIELD_USED_BY_FORMULA,
+                INVALID_FORMULA, INVALID_ORDERING, INVALID_RANGE, RELATION_CHANGED,
+                RELATION_REQUIRED_UNLINK, RELATION_TARGET_NOT_FOUND,
+            },
         },
         docs::{FIELDS_TAG, TransformOperationExt, template},
         model::{
@@ -285,13 +467,33 @@ mod docs {
 
     pub fn create_field(op: TransformOperation) -> TransformOperation {
         fields::<Json<Field>>(op, "create_field", "Create a field in a table.")
-            .response_description::<422, String>(INVALID_RANGE)
+            .response_description::<422, String>(
+                &[
+                    INVALID_RANGE,
+                    ENUMERATION_INVALID_DEFAULT,
+                    RELATION_TARGET_NOT_FOUND,
+                    RELATION_REQUIRED_UNLINK,
+                    &format!("{INVALID_FORMULA}: <error>"),
+                ]
+                .join("\n\n"),
+            )
             .required_access(TABLE_OWNER)
     }
 
     pub fn update_field(op: TransformOperation) -> TransformOperation {
         select_fields::<Json<Field>>(op, "update_field", "Update a field's metadata in a table.")
-            .response_description::<422, String>(INVALID_RANGE)
+            .response_description::<422, String>(
+                &[
+                    INVALID_RANGE,
+                    ENUMERATION_INVALID_DEFAULT,
+                    RELATION_TARGET_NOT_FOUND,
+                    RELATION_CHANGED,
+                    RELATION_REQUIRED_UNLINK,
+                    &format!("{INVALID_FORMULA}: <error>"),
+                ]
+                .join("\n\n"),
+            )
+            .response_description::<409, String>(FIELD_USED_BY_FORMULA)
             .required_access(TABLE_OWNER)
     }
 
@@ -299,8 +501,10 @@ mod docs {
         select_fields::<()>(
             op,
             "delete_field",
-            "Delete a field and all cells in its respective column in the table.",
+            "Move a field to the trash along with all cells in its respective column in the table. \
+            Axes using the field are removed from their charts until it is restored.",
         )
+        .response_description::<409, String>(FIELD_USED_BY_FORMULA)
         .required_access(TABLE_OWNER)
     }
     pub fn get_fields(op: TransformOperation) -> TransformOperation {
@@ -336,7 +540,10 @@ mod test {
         db,
         model::{
             access::{AccessRole, Resource},
-            data::{CreateField, CreateTable, Field, FieldKind, SetFieldOrder, UpdateField},
+            data::{
+                AuditAction, AuditResource, CreateField, CreateTable, Field, FieldKind,
+                FormulaKind, RelationDeleteRule, SetFieldOrder, UpdateField,
+            },
         },
         test_util,
     };
@@ -412,6 +619,73 @@ mod test {
             .json(&invalid_range)
             .await
             .assert_status_unprocessable_entity();
+
+        let other_table_id = db::create_table(
+            &db,
+            CreateTable {
+                name: "Other".into(),
+                description: "".into(),
+                parent_id: None,
+            },
+        )
+        .await?
+        .table_id;
+        let relation = |target_table_id| CreateField {
+            name: "relation".into(),
+            field_kind: FieldKind::Relation {
+                is_required: false,
+                target_table_id,
+                multiple: true,
+                on_delete: RelationDeleteRule::Restrict,
+            },
+        };
+        for target_table_id in [other_table_id, 1000] {
+            server
+                .post(&path)
+                .json(&relation(target_table_id))
+                .await
+                .assert_status_unprocessable_entity();
+        }
+        let required_unlink = CreateField {
+            name: "relation".into(),
+            field_kind: FieldKind::Relation {
+                is_required: true,
+                target_table_id: table_id,
+                multiple: false,
+                on_delete: RelationDeleteRule::Unlink,
+            },
+        };
+        let response = server.post(&path).json(&required_unlink).await;
+        response.assert_status_unprocessable_entity();
+        assert!(response.text().ends_with(super::RELATION_REQUIRED_UNLINK));
+        server
+            .post(&path)
+            .json(&relation(table_id))
+            .await
+            .assert_status_ok();
+
+        let formula = |expression: String| CreateField {
+            name: "formula".into(),
+            field_kind: FieldKind::Formula {
+                expression,
+                result_kind: FormulaKind::Text,
+            },
+        };
+        for expression in ["{1000}".into(), format!("{{{}}} +", field_1.field_id)] {
+            server
+                .post(&path)
+                .json(&formula(expression))
+                .await
+                .assert_status_unprocessable_entity();
+        }
+        server
+            .post(&path)
+            .json(&formula(format!(
+                r#"IF({{{}}}, "yes", "no")"#,
+                field_1.field_id
+            )))
+            .await
+            .assert_status_ok();
         Ok(())
     }
 
@@ -502,6 +776,55 @@ mod test {
             .json(&create_field)
             .await
             .assert_status_unprocessable_entity();
+
+        let relation = |multiple| UpdateField {
+            name: "relation".into(),
+            field_kind: FieldKind::Relation {
+                is_required: false,
+                target_table_id: table_id,
+                multiple,
+                on_delete: RelationDeleteRule::Restrict,
+            },
+        };
+        server
+            .patch(&path)
+            .json(&relation(false))
+            .await
+            .assert_status_unprocessable_entity();
+        let relation_field_id = db::create_field(
+            &db,
+            table_id,
+            CreateField {
+                name: relation(false).name,
+                field_kind: relation(false).field_kind,
+            },
+        )
+        .await?
+        .field_id;
+        let relation_path = format!("/api/tables/{table_id}/fields/{relation_field_id}");
+        server
+            .patch(&relation_path)
+            .json(&relation(true))
+            .await
+            .assert_status_unprocessable_entity();
+        server
+            .patch(&relation_path)
+            .json(&update_field)
+            .await
+            .assert_status_unprocessable_entity();
+
+        let formula = UpdateField {
+            name: "formula".into(),
+            field_kind: FieldKind::Formula {
+                expression: format!("UPPER({{{field_id}}})"),
+                result_kind: FormulaKind::Text,
+            },
+        };
+        server
+            .patch(&path)
+            .json(&formula)
+            .await
+            .assert_status_unprocessable_entity();
         Ok(())
     }
 
@@ -566,6 +889,33 @@ mod test {
             server.delete(&path_wrong).await.assert_status_not_found();
         }
 
+        let formula_field_id = db::create_field(
+            &db,
+            table_id,
+            CreateField {
+                name: "formula".into(),
+                field_kind: FieldKind::Formula {
+                    expression: format!("NOT {{{field_id}}}"),
+                    result_kind: FormulaKind::Checkbox,
+                },
+            },
+        )
+        .await?
+        .field_id;
+        server.delete(&path).await.assert_status_conflict();
+        server
+            .patch(&path)
+            .json(&UpdateField {
+                name: "abc".into(),
+                field_kind: FieldKind::Text { is_required: false },
+            })
+            .await
+            .assert_status_conflict();
+        server
+            .delete(&format!("/api/tables/{table_id}/fields/{formula_field_id}"))
+            .await
+            .assert_status_ok();
+
         server.delete(&path).await.assert_status_ok();
         let not_exists: bool = sqlx::query_scalar(
             r#"SELECT NOT EXISTS (SELECT 1 FROM meta_field WHERE field_id = $1)"#,
@@ -725,6 +1075,11 @@ mod test {
                 .get(&f.field_id)
                 .is_some_and(|ordering| f.ordering == *ordering)
         }));
+        let page = db::get_table_activity(&db, table_id, 10, None).await?;
+        assert_eq!(page.logs.len(), fields.len());
+        assert!(page.logs.iter().all(|log| {
+            log.resource == AuditResource::Field && log.action == AuditAction::Update
+        }));
 
         let wrong_ordering = set_field_order
             .0
@@ -756,6 +1111,7 @@ mod test {
         Ok(())
     }
 
+    #[allow(clippy::type_complexity)]
     fn validate_range_data<T>(lower: T, higher: T) -> Vec<((Option<T>, Option<T>), bool)>
     where
         T: PartialOrd + Copy,
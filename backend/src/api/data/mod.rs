@@ -3,9 +3,12 @@
 //! Users must have the appropriate access role for any operation.
 //! Otherwise, `403 Forbidden` or `404 Not Found` is returned.
 
-mod entries;
+mod audit;
+pub(super) mod entries;
 mod fields;
 mod tables;
+mod trash;
+mod views;
 
 use crate::AppState;
 use aide::axum::ApiRouter;
@@ -15,4 +18,7 @@ pub fn router() -> ApiRouter<AppState> {
         .merge(tables::router())
         .merge(fields::router())
         .merge(entries::router())
+        .merge(audit::router())
+        .merge(trash::router())
+        .merge(views::router())
 }
@@ -0,0 +1,301 @@
+//! Routes for restoring deleted tables, fields and entries from the trash.
+//!
+//! Deleted items stay in the trash until they are purged after the retention period.
+
+use crate::{
+    AppState,
+    auth::AppAuthSession,
+    db,
+    error::{ApiError, ApiResult},
+    model::data::{AuditResource, CreateAuditLog, FieldKind, SelectTrashItem, TrashItem},
+};
+use aide::{
+    NoApi,
+    axum::{
+        ApiRouter,
+        routing::{get_with, post_with},
+    },
+};
+use axum::{
+    Json,
+    extract::{Path, State},
+};
+use axum_login::AuthSession;
+
+const PARENT_IN_TRASH: &str = "The parent table is in the trash";
+const TARGET_TABLE_DELETED: &str = "The table referenced by the relation was deleted";
+const PARENT_ENTRY_DELETED: &str = "The parent entry was deleted";
+
+pub fn router() -> ApiRouter<AppState> {
+    ApiRouter::new()
+        .api_route("/trash", get_with(get_trash, docs::get_trash))
+        .api_route(
+            "/trash/{trash_id}/restore",
+            post_with(restore_trash_item, docs::restore_trash_item),
+        )
+}
+
+async fn get_trash(
+    NoApi(AuthSession { user, .. }): AppAuthSession,
+    State(AppState { db, .. }): State<AppState>,
+) -> ApiResult<Json<Vec<TrashItem>>> {
+    let user_id = user.ok_or(ApiError::Unauthorized)?.user_id;
+
+    let trash = db::get_trash(&db, user_id).await?;
+
+    Ok(Json(trash))
+}
+
+async fn restore_trash_item(
+    NoApi(AuthSession { user, .. }): AppAuthSession,
+    State(AppState { db, .. }): State<AppState>,
+    Path(SelectTrashItem { trash_id }): Path<SelectTrashItem>,
+) -> ApiResult<()> {
+    let user_id = user.ok_or(ApiError::Unauthorized)?.user_id;
+    let mut tx = db.begin().await?;
+
+    let item = db::get_trash_item(tx.as_mut(), user_id, trash_id)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    match item.resource {
+        AuditResource::Table => {
+            if let Some(parent_id) = db::get_table_parent_id(tx.as_mut(), item.table_id).await?
+                && db::table_in_trash(tx.as_mut(), parent_id).await?
+            {
+                return Err(ApiError::Conflict(PARENT_IN_TRASH.into()));
+            }
+        }
+        AuditResource::Field => {
+            if let Ok(FieldKind::Relation {
+                target_table_id, ..
+            }) = serde_json::from_value(item.value.0["field_kind"].clone())
+                && db::get_table(tx.as_mut(), target_table_id).await.is_err()
+            {
+                return Err(ApiError::Conflict(TARGET_TABLE_DELETED.into()));
+            }
+        }
+        AuditResource::Entry => {}
+    }
+
+    db::restore_trash_item(tx.as_mut(), user_id, trash_id)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(e) if e.is_foreign_key_violation() => {
+                ApiError::Conflict(PARENT_ENTRY_DELETED.into())
+            }
+            e => e.into(),
+        })?;
+
+    let log = match item.resource {
+        AuditResource::Table => {
+            let table = db::get_table(tx.as_mut(), item.table_id).await?;
+            CreateAuditLog::table(None, Some(&table))
+        }
+        AuditResource::Field => {
+            let field = db::get_fields(tx.as_mut(), item.table_id)
+                .await?
+                .into_iter()
+                .find(|field| field.field_id == item.resource_id)
+                .ok_or(ApiError::NotFound)?;
+            CreateAuditLog::field(None, Some(&field))
+        }
+        AuditResource::Entry => {
+            let fields = db::get_fields_metadata(tx.as_mut(), item.table_id).await?;
+            let entry =
+                db::get_entry(tx.as_mut(), item.table_id, item.resource_id, &fields).await?;
+            CreateAuditLog::entry(item.table_id, None, Some(&entry))
+        }
+    };
+    db::create_audit_logs(tx.as_mut(), Some(user_id), vec![log]).await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod docs {
+    use crate::{
+        api::data::trash::{PARENT_ENTRY_DELETED, PARENT_IN_TRASH, TARGET_TABLE_DELETED},
+        docs::{TRASH_TAG, TransformOperationExt, template},
+        model::data::TrashItem,
+    };
+    use aide::{OperationOutput, transform::TransformOperation};
+    use axum::Json;
+
+    fn trash<'a, R: OperationOutput>(
+        op: TransformOperation<'a>,
+        summary: &'a str,
+        description: &'a str,
+    ) -> TransformOperation<'a> {
+        template::<R>(op, summary, description, true, TRASH_TAG)
+    }
+
+    pub fn get_trash(op: TransformOperation) -> TransformOperation {
+        trash::<Json<Vec<TrashItem>>>(
+            op,
+            "get_trash",
+            "Get the deleted tables, fields and entries the user can restore, most recently deleted first. Table owners can restore anything in their tables and editors can restore entries.",
+        )
+    }
+
+    pub fn restore_trash_item(op: TransformOperation) -> TransformOperation {
+        trash::<()>(
+            op,
+            "restore_trash_item",
+            "Restore a deleted table, field or entry and rebuild the charts depending on it. Child tables deleted along with a table are restored with it.",
+        )
+        .response_description::<404, ()>("Trash item not found")
+        .response_description::<409, String>(
+            &[PARENT_IN_TRASH, TARGET_TABLE_DELETED, PARENT_ENTRY_DELETED].join("\n\n"),
+        )
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod test {
+    use crate::{
+        db,
+        model::{
+            access::{AccessRole, Resource},
+            data::{
+                AuditAction, AuditLog, AuditResource, CreateEntries, CreateField, CreateTable,
+                FieldKind, Table, TrashItem,
+            },
+        },
+        test_util,
+    };
+    use itertools::Itertools;
+    use serde_json::json;
+    use sqlx::PgPool;
+    use std::collections::HashMap;
+
+    #[sqlx::test]
+    async fn trash(db: PgPool) -> anyhow::Result<()> {
+        let mut server = test_util::server(db.clone()).await;
+        server.get("/api/trash").await.assert_status_unauthorized();
+        server
+            .post("/api/trash/1/restore")
+            .await
+            .assert_status_unauthorized();
+
+        let user = db::create_user(&db, "test".into(), "".into(), false).await?;
+        test_util::login_session(&mut server, &user).await;
+
+        let table: Table = server
+            .post("/api/tables")
+            .json(&CreateTable {
+                name: "test".into(),
+                description: "".into(),
+                parent_id: None,
+            })
+            .await
+            .json();
+        let table_id = table.table_id;
+        let field_id = server
+            .post(&format!("/api/tables/{table_id}/fields"))
+            .json(&CreateField {
+                name: "test".into(),
+                field_kind: FieldKind::Text { is_required: false },
+            })
+            .await
+            .json::<serde_json::Value>()["field_id"]
+            .as_i64()
+            .unwrap() as i32;
+        let entry_path = format!("/api/tables/{table_id}/entries");
+        let entry_id = server
+            .post(&entry_path)
+            .json(&CreateEntries {
+                parent_id: None,
+                entries: vec![HashMap::from_iter([(field_id, json!("a"))])],
+            })
+            .await
+            .json::<serde_json::Value>()[0]["entry_id"]
+            .as_i64()
+            .unwrap() as i32;
+
+        server
+            .delete(&format!("{entry_path}/{entry_id}"))
+            .await
+            .assert_status_ok();
+        server
+            .delete(&format!("/api/tables/{table_id}/fields/{field_id}"))
+            .await
+            .assert_status_ok();
+
+        let trash: Vec<TrashItem> = server.get("/api/trash").await.json();
+        assert_eq!(
+            trash
+                .iter()
+                .map(|item| (item.resource, item.resource_id))
+                .collect_vec(),
+            [
+                (AuditResource::Field, field_id),
+                (AuditResource::Entry, entry_id)
+            ]
+        );
+        assert_eq!(trash[0].name.as_deref(), Some("test"));
+        assert_eq!(trash[1].value.0, json!({ field_id.to_string(): "a" }));
+
+        for item in &trash {
+            server
+                .post(&format!("/api/trash/{}/restore", item.trash_id))
+                .await
+                .assert_status_ok();
+        }
+        server
+            .post(&format!("/api/trash/{}/restore", trash[0].trash_id))
+            .await
+            .assert_status_not_found();
+        assert!(db::entry_exists(&db, table_id, entry_id).await?);
+        let history: Vec<AuditLog> = server
+            .get(&format!("{entry_path}/{entry_id}/history"))
+            .await
+            .json();
+        assert_eq!(
+            history.iter().map(|log| log.action).collect_vec(),
+            [
+                AuditAction::Create,
+                AuditAction::Delete,
+                AuditAction::Create
+            ]
+        );
+        assert_eq!(
+            history[2].new_value.as_ref().map(|v| &v.0),
+            Some(&json!({ field_id.to_string(): "a" }))
+        );
+
+        let table_path = format!("/api/tables/{table_id}");
+        server.delete(&table_path).await.assert_status_ok();
+        server
+            .get(&format!("{table_path}/fields"))
+            .await
+            .assert_status_not_found();
+        let trash: Vec<TrashItem> = server.get("/api/trash").await.json();
+        assert_eq!(trash.len(), 1);
+        assert_eq!(trash[0].resource, AuditResource::Table);
+
+        let other_user = db::create_user(&db, "other".into(), "".into(), false).await?;
+        db::create_access(
+            &db,
+            Resource::Table,
+            table_id,
+            other_user.user_id,
+            AccessRole::Editor,
+        )
+        .await?;
+        test_util::login_session(&mut server, &other_user).await;
+        let trash_path = format!("/api/trash/{}/restore", trash[0].trash_id);
+        server.post(&trash_path).await.assert_status_not_found();
+        assert_eq!(server.get("/api/trash").await.json::<Vec<TrashItem>>(), []);
+
+        test_util::login_session(&mut server, &user).await;
+        server.post(&trash_path).await.assert_status_ok();
+        server
+            .get(&format!("{table_path}/fields"))
+            .await
+            .assert_status_ok();
+        Ok(())
+    }
+}
@@ -0,0 +1,758 @@
+//! Routes for managing saved views of a table.
+//!
+//! Shared views are managed by the editors of the table and visible to all its users.
+//! Personal views are only visible to the user who created them.
+
+use crate::{
+    AppState, Id,
+    api::data::entries::{
+        INVALID_FIELD_ID, INVALID_SORT, NO_PARENT_TABLE, convert_filters, convert_query,
+    },
+    auth::AppAuthSession,
+    db,
+    error::{ApiError, ApiResult},
+    model::{
+        access::{AccessRole, AccessRoleCheck, Resource},
+        data::{
+            CreateTableView, EntryFilter, EntryPage, EntryQuery, EntrySort, FieldMetadata,
+            QueryEntries, SelectTable, SelectTableView, SortDirection, TableView, UpdateTableView,
+        },
+    },
+};
+use aide::{
+    NoApi,
+    axum::{
+        ApiRouter,
+        routing::{patch_with, post_with},
+    },
+};
+use axum::{
+    Json,
+    extract::{Path, State},
+};
+use axum_login::AuthSession;
+use std::collections::HashMap;
+
+const INVALID_COLUMN_WIDTH: &str = "Column width must be positive";
+const INVALID_GROUP_FIELD: &str = "Cannot group by a relation to many entries";
+
+pub fn router() -> ApiRouter<AppState> {
+    ApiRouter::new().nest(
+        "/tables/{table_id}/views",
+        ApiRouter::new()
+            .api_route(
+                "/",
+                post_with(create_table_view, docs::create_table_view)
+                    .get_with(get_table_views, docs::get_table_views),
+            )
+            .api_route(
+                "/{view_id}",
+                patch_with(update_table_view, docs::update_table_view)
+                    .delete_with(delete_table_view, docs::delete_table_view),
+            )
+            .api_route(
+                "/{view_id}/query",
+                post_with(query_table_view, docs::query_table_view),
+            ),
+    )
+}
+
+async fn create_table_view(
+    NoApi(AuthSession { user, .. }): AppAuthSession,
+    State(AppState { db, .. }): State<AppState>,
+    Path(SelectTable { table_id }): Path<SelectTable>,
+    Json(create_view): Json<CreateTableView>,
+) -> ApiResult<Json<TableView>> {
+    let user_id = user.ok_or(ApiError::Unauthorized)?.user_id;
+    let mut tx = db.begin().await?;
+
+    db::get_access_role(tx.as_mut(), Resource::Table, table_id, user_id)
+        .await?
+        .check(if create_view.is_shared {
+            AccessRole::Editor
+        } else {
+            AccessRole::Viewer
+        })?;
+
+    let fields = db::get_fields_metadata(tx.as_mut(), table_id).await?;
+    validate_table_view(
+        &create_view.filters,
+        &create_view.sort,
+        &create_view.hidden_field_ids,
+        &create_view.column_widths,
+        create_view.group_field_id,
+        &fields,
+    )
+    .map_err(|error_messages| ApiError::UnprocessableEntity(error_messages.join(", ")))?;
+
+    let view = db::create_table_view(tx.as_mut(), table_id, user_id, create_view).await?;
+
+    tx.commit().await?;
+    Ok(Json(view))
+}
+
+async fn update_table_view(
+    NoApi(AuthSession { user, .. }): AppAuthSession,
+    State(AppState { db, .. }): State<AppState>,
+    Path(SelectTableView { table_id, view_id }): Path<SelectTableView>,
+    Json(update_view): Json<UpdateTableView>,
+) -> ApiResult<Json<TableView>> {
+    let user_id = user.ok_or(ApiError::Unauthorized)?.user_id;
+    let mut tx = db.begin().await?;
+
+    let access_role = db::get_access_role(tx.as_mut(), Resource::Table, table_id, user_id).await?;
+    access_role.check(AccessRole::Viewer)?;
+    let view = db::get_table_view(tx.as_mut(), table_id, view_id, user_id)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    if view.is_shared || update_view.is_shared {
+        access_role.check(AccessRole::Editor)?;
+    }
+
+    let fields = db::get_fields_metadata(tx.as_mut(), table_id).await?;
+    validate_table_view(
+        &update_view.filters,
+        &update_view.sort,
+        &update_view.hidden_field_ids,
+        &update_view.column_widths,
+        update_view.group_field_id,
+        &fields,
+    )
+    .map_err(|error_messages| ApiError::UnprocessableEntity(error_messages.join(", ")))?;
+
+    let view = db::update_table_view(tx.as_mut(), view_id, update_view).await?;
+
+    tx.commit().await?;
+    Ok(Json(view))
+}
+
+async fn delete_table_view(
+    NoApi(AuthSession { user, .. }): AppAuthSession,
+    State(AppState { db, .. }): State<AppState>,
+    Path(SelectTableView { table_id, view_id }): Path<SelectTableView>,
+) -> ApiResult<()> {
+    let user_id = user.ok_or(ApiError::Unauthorized)?.user_id;
+    let mut tx = db.begin().await?;
+
+    let access_role = db::get_access_role(tx.as_mut(), Resource::Table, table_id, user_id).await?;
+    access_role.check(AccessRole::Viewer)?;
+    let view = db::get_table_view(tx.as_mut(), table_id, view_id, user_id)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    if view.is_shared {
+        access_role.check(AccessRole::Editor)?;
+    }
+
+    db::delete_table_view(tx.as_mut(), view_id).await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+async fn get_table_views(
+    NoApi(AuthSession { user, .. }): AppAuthSession,
+    State(AppState { db, .. }): State<AppState>,
+    Path(SelectTable { table_id }): Path<SelectTable>,
+) -> ApiResult<Json<Vec<TableView>>> {
+    let user_id = user.ok_or(ApiError::Unauthorized)?.user_id;
+
+    db::get_access_role(&db, Resource::Table, table_id, user_id)
+        .await?
+        .check(AccessRole::Viewer)?;
+
+    let views = db::get_table_views(&db, table_id, user_id).await?;
+
+    Ok(Json(views))
+}
+
+async fn query_table_view(
+    NoApi(AuthSession { user, .. }): AppAuthSession,
+    State(AppState { db, .. }): State<AppState>,
+    Path(SelectTableView { table_id, view_id }): Path<SelectTableView>,
+    Json(query): Json<QueryEntries>,
+) -> ApiResult<Json<EntryPage>> {
+    let user_id = user.ok_or(ApiError::Unauthorized)?.user_id;
+
+    db::get_access_role(&db, Resource::Table, table_id, user_id)
+        .await?
+        .check(AccessRole::Viewer)?;
+    let view = db::get_table_view(&db, table_id, view_id, user_id)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    if query.parent_id.is_some() && db::get_table_parent_id(&db, table_id).await?.is_none() {
+        return Err(ApiError::UnprocessableEntity(NO_PARENT_TABLE.into()));
+    }
+
+    let fields = db::get_fields_metadata(&db, table_id).await?;
+    let query = convert_view_query(&view, query, &fields)?;
+
+    let mut page = db::query_entries(&db, table_id, fields, query).await?;
+    for entry in &mut page.entries {
+        entry
+            .cells
+            .retain(|field_id, _| !view.hidden_field_ids.contains(field_id));
+    }
+
+    Ok(Json(page))
+}
+
+/// Validate the filters, sorts, hidden fields, column widths and group field of a view
+/// against the fields of the table.
+/// Return the messages of every violated rule, prefixed by the field ID.
+fn validate_table_view(
+    filters: &[EntryFilter],
+    sort: &[EntrySort],
+    hidden_field_ids: &[Id],
+    column_widths: &HashMap<Id, i32>,
+    group_field_id: Option<Id>,
+    fields: &[FieldMetadata],
+) -> Result<(), Vec<String>> {
+    let field_kinds: HashMap<_, _> = fields
+        .iter()
+        .map(|field| (field.field_id, &field.field_kind))
+        .collect();
+    let mut error_messages = Vec::new();
+
+    convert_filters(filters.to_vec(), fields, &mut error_messages);
+
+    let sort_field_ids = sort
+        .iter()
+        .map(|sort| (sort.field_id, INVALID_SORT))
+        .chain(group_field_id.map(|field_id| (field_id, INVALID_GROUP_FIELD)));
+    for (field_id, message) in sort_field_ids {
+        match field_kinds.get(&field_id) {
+            None => error_messages.push(format!("{field_id}: {INVALID_FIELD_ID}")),
+            Some(field_kind) if field_kind.is_multiple_relation() => {
+                error_messages.push(format!("{field_id}: {message}"));
+            }
+            Some(_) => {}
+        }
+    }
+
+    for field_id in hidden_field_ids.iter().chain(column_widths.keys()) {
+        if !field_kinds.contains_key(field_id) {
+            error_messages.push(format!("{field_id}: {INVALID_FIELD_ID}"));
+        }
+    }
+    for (field_id, _) in column_widths.iter().filter(|(_, width)| **width <= 0) {
+        error_messages.push(format!("{field_id}: {INVALID_COLUMN_WIDTH}"));
+    }
+
+    if error_messages.is_empty() {
+        Ok(())
+    } else {
+        Err(error_messages)
+    }
+}
+
+/// Validate the query request and apply the view on top of it.
+/// Entries are grouped by the view's group field first, then sorted by the view's sorts
+/// and finally by the request's sorts. The view's filters are combined with the request's.
+/// Filters and sorts of the view on fields deleted since it was saved are skipped.
+fn convert_view_query(
+    view: &TableView,
+    query: QueryEntries,
+    fields: &[FieldMetadata],
+) -> ApiResult<EntryQuery> {
+    let is_sortable = |sort: &EntrySort| {
+        fields.iter().any(|field| {
+            field.field_id == sort.field_id && !field.field_kind.is_multiple_relation()
+        })
+    };
+    let sort = view
+        .group_field_id
+        .map(|field_id| EntrySort {
+            field_id,
+            direction: SortDirection::Ascending,
+        })
+        .into_iter()
+        .chain(view.sort.iter().cloned())
+        .filter(is_sortable)
+        .chain(query.sort.clone())
+        .collect();
+
+    let mut entry_query = convert_query(QueryEntries { sort, ..query }, fields)?;
+    entry_query.filters = convert_filters(view.filters.to_vec(), fields, &mut Vec::new())
+        .into_iter()
+        .chain(entry_query.filters)
+        .collect();
+
+    Ok(entry_query)
+}
+
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod docs {
+    use crate::{
+        api::data::{
+            entries::{
+                INVALID_CURSOR, INVALID_DAYS, INVALID_FIELD_ID, INVALID_OPERATOR,
+                INVALID_PAGINATION, INVALID_SORT, INVALID_TYPE, NO_PARENT_TABLE,
+                OFFSET_WITH_CURSOR,
+            },
+            views::{INVALID_COLUMN_WIDTH, INVALID_GROUP_FIELD},
+        },
+        docs::{TransformOperationExt, VIEWS_TAG, template},
+        model::{
+            access::{AccessRole, Resource},
+            data::{EntryPage, TableView},
+        },
+    };
+    use aide::{OperationOutput, transform::TransformOperation};
+    use axum::Json;
+    use itertools::Itertools;
+
+    const TABLE_VIEWER: [(Resource, AccessRole); 1] = [(Resource::Table, AccessRole::Viewer)];
+
+    fn views<'a, R: OperationOutput>(
+        op: TransformOperation<'a>,
+        summary: &'a str,
+        description: &'a str,
+    ) -> TransformOperation<'a> {
+        template::<R>(op, summary, description, true, VIEWS_TAG)
+    }
+
+    fn view_errors() -> String {
+        [
+            INVALID_FIELD_ID,
+            INVALID_OPERATOR,
+            INVALID_DAYS,
+            INVALID_TYPE,
+            INVALID_SORT,
+            INVALID_GROUP_FIELD,
+            INVALID_COLUMN_WIDTH,
+        ]
+        .into_iter()
+        .map(|v| format!("<field_id> : {v}"))
+        .join("\n\n")
+    }
+
+    pub fn create_table_view(op: TransformOperation) -> TransformOperation {
+        let errors = view_errors();
+
+        views::<Json<TableView>>(
+            op,
+            "create_table_view",
+            "Save a view of the table with its filters, sorts, hidden fields, \
+            column widths and grouping. \
+            Shared views are visible to all users of the table and require the editor role.",
+        )
+        .response_description::<404, ()>("Table not found")
+        .response_description::<422, String>(&errors)
+        .required_access(TABLE_VIEWER)
+    }
+
+    pub fn update_table_view(op: TransformOperation) -> TransformOperation {
+        let errors = view_errors();
+
+        views::<Json<TableView>>(
+            op,
+            "update_table_view",
+            "Update a view of the table. Shared views require the editor role.",
+        )
+        .response_description::<404, ()>("Table not found\n\nView not found")
+        .response_description::<422, String>(&errors)
+        .required_access(TABLE_VIEWER)
+    }
+
+    pub fn delete_table_view(op: TransformOperation) -> TransformOperation {
+        views::<()>(
+            op,
+            "delete_table_view",
+            "Delete a view of the table. Shared views require the editor role.",
+        )
+        .response_description::<404, ()>("Table not found\n\nView not found")
+        .required_access(TABLE_VIEWER)
+    }
+
+    pub fn get_table_views(op: TransformOperation) -> TransformOperation {
+        views::<Json<Vec<TableView>>>(
+            op,
+            "get_table_views",
+            "Get the shared views of the table and the personal views of the user.",
+        )
+        .response_description::<404, ()>("Table not found")
+        .required_access(TABLE_VIEWER)
+    }
+
+    pub fn query_table_view(op: TransformOperation) -> TransformOperation {
+        let errors = [
+            INVALID_FIELD_ID,
+            INVALID_OPERATOR,
+            INVALID_DAYS,
+            INVALID_TYPE,
+            INVALID_SORT,
+        ]
+        .into_iter()
+        .map(|v| format!("<field_id>: {v}"))
+        .chain([
+            NO_PARENT_TABLE.into(),
+            INVALID_PAGINATION.into(),
+            OFFSET_WITH_CURSOR.into(),
+            INVALID_CURSOR.into(),
+        ])
+        .join("\n\n");
+
+        views::<Json<EntryPage>>(
+            op,
+            "query_table_view",
+            "Get a page of entries through a view of the table. \
+            The entries match the filters of both the view and the request, \
+            are grouped by the view's group field and sorted by the view's sorts \
+            before the request's sorts. Hidden fields are left out of the cells.",
+        )
+        .response_description::<404, ()>("Table not found\n\nView not found")
+        .response_description::<422, String>(&errors)
+        .required_access(TABLE_VIEWER)
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod test {
+    use super::{INVALID_COLUMN_WIDTH, INVALID_GROUP_FIELD};
+    use crate::{
+        Id,
+        api::data::entries::{INVALID_FIELD_ID, INVALID_SORT},
+        db,
+        model::{
+            Cell,
+            access::{AccessRole, Resource},
+            data::{
+                CreateField, CreateTable, EntryFilter, EntrySort, FieldKind, FieldMetadata,
+                FilterOperator, RelationDeleteRule, TableView,
+            },
+        },
+        test_util,
+    };
+    use itertools::Itertools;
+    use serde_json::{Value, json};
+    use sqlx::{PgPool, types::Json};
+    use std::collections::HashMap;
+
+    #[sqlx::test]
+    async fn table_views(db: PgPool) -> anyhow::Result<()> {
+        let mut server = test_util::server(db.clone()).await;
+        let table_id = db::create_table(
+            &db,
+            CreateTable {
+                name: "test".into(),
+                description: "".into(),
+                parent_id: None,
+            },
+        )
+        .await?
+        .table_id;
+        let field_id = db::create_field(
+            &db,
+            table_id,
+            CreateField {
+                name: "abc".into(),
+                field_kind: FieldKind::Integer {
+                    is_required: false,
+                    range_start: None,
+                    range_end: None,
+                },
+            },
+        )
+        .await?
+        .field_id;
+
+        let path = format!("/api/tables/{table_id}/views");
+        let personal_view = json!({ "name": "A", "sort": [{ "field_id": field_id }] });
+        let shared_view = json!({ "name": "B", "is_shared": true });
+
+        server
+            .post(&path)
+            .json(&personal_view)
+            .await
+            .assert_status_unauthorized();
+
+        let user = db::create_user(&db, "test".into(), "".into(), false).await?;
+        let other_user = db::create_user(&db, "other".into(), "".into(), false).await?;
+        test_util::login_session(&mut server, &user).await;
+        test_util::test_access_control(
+            &db,
+            Resource::Table,
+            table_id,
+            user.user_id,
+            AccessRole::Viewer,
+            async || server.post(&path).json(&personal_view).await,
+        )
+        .await;
+        test_util::test_access_control(
+            &db,
+            Resource::Table,
+            table_id,
+            user.user_id,
+            AccessRole::Editor,
+            async || server.post(&path).json(&shared_view).await,
+        )
+        .await;
+
+        for view_wrong in [
+            json!({ "name": "C", "filters": [{ "field_id": 1000, "operator": "IsNull" }] }),
+            json!({ "name": "C", "sort": [{ "field_id": 1000 }] }),
+            json!({ "name": "C", "hidden_field_ids": [1000] }),
+            json!({ "name": "C", "column_widths": { field_id.to_string(): 0 } }),
+            json!({ "name": "C", "group_field_id": 1000 }),
+        ] {
+            server
+                .post(&path)
+                .json(&view_wrong)
+                .await
+                .assert_status_unprocessable_entity();
+        }
+
+        let views: Vec<TableView> = server.get(&path).await.json();
+        assert_eq!(views.len(), 5);
+
+        let other_view = db::create_table_view(
+            &db,
+            table_id,
+            other_user.user_id,
+            serde_json::from_value(json!({ "name": "D" }))?,
+        )
+        .await?;
+        let views: Vec<TableView> = server.get(&path).await.json();
+        assert_eq!(views.len(), 5);
+        server
+            .patch(&format!("{path}/{}", other_view.view_id))
+            .json(&personal_view)
+            .await
+            .assert_status_not_found();
+        server
+            .delete(&format!("{path}/{}", other_view.view_id))
+            .await
+            .assert_status_not_found();
+
+        let view_id = views.iter().find(|view| view.is_shared).unwrap().view_id;
+        test_util::test_access_control(
+            &db,
+            Resource::Table,
+            table_id,
+            user.user_id,
+            AccessRole::Editor,
+            async || {
+                server
+                    .patch(&format!("{path}/{view_id}"))
+                    .json(&shared_view)
+                    .await
+            },
+        )
+        .await;
+
+        db::delete_many_access(&db, Resource::Table, table_id, [user.user_id]).await?;
+        db::create_access(
+            &db,
+            Resource::Table,
+            table_id,
+            user.user_id,
+            AccessRole::Viewer,
+        )
+        .await?;
+        server
+            .delete(&format!("{path}/{view_id}"))
+            .await
+            .assert_status_forbidden();
+        db::delete_many_access(&db, Resource::Table, table_id, [user.user_id]).await?;
+        db::create_access(
+            &db,
+            Resource::Table,
+            table_id,
+            user.user_id,
+            AccessRole::Editor,
+        )
+        .await?;
+        server
+            .delete(&format!("{path}/{view_id}"))
+            .await
+            .assert_status_ok();
+        server
+            .delete(&format!("{path}/{view_id}"))
+            .await
+            .assert_status_not_found();
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn query_table_view(db: PgPool) -> anyhow::Result<()> {
+        let mut server = test_util::server(db.clone()).await;
+        let table_id = db::create_table(
+            &db,
+            CreateTable {
+                name: "test".into(),
+                description: "".into(),
+                parent_id: None,
+            },
+        )
+        .await?
+        .table_id;
+        let fields = ["group", "value"]
+            .into_iter()
+            .map(|name| CreateField {
+                name: name.into(),
+                field_kind: FieldKind::Integer {
+                    is_required: false,
+                    range_start: None,
+                    range_end: None,
+                },
+            })
+            .collect_vec();
+        let mut field_metadata = Vec::new();
+        for field in fields {
+            field_metadata.push(FieldMetadata::from_field(
+                db::create_field(&db, table_id, field).await?,
+            ));
+        }
+        let (group_field_id, value_field_id) =
+            (field_metadata[0].field_id, field_metadata[1].field_id);
+        let entry_ids = db::create_entries(
+            &db,
+            table_id,
+            None,
+            field_metadata,
+            (0..6)
+                .map(|i| vec![Cell::Integer(i % 2), Cell::Integer(i)])
+                .collect(),
+        )
+        .await?
+        .into_iter()
+        .map(|e| e.entry_id)
+        .collect_vec();
+
+        let user = db::create_user(&db, "test".into(), "".into(), false).await?;
+        let view = db::create_table_view(
+            &db,
+            table_id,
+            user.user_id,
+            serde_json::from_value(json!({
+                "name": "A",
+                "filters": [{ "field_id": value_field_id, "operator": "Range", "start": 1 }],
+                "sort": [{ "field_id": value_field_id, "direction": "Descending" }],
+                "hidden_field_ids": [value_field_id],
+                "group_field_id": group_field_id,
+            }))?,
+        )
+        .await?;
+
+        let path = format!("/api/tables/{table_id}/views/{}/query", view.view_id);
+        let query = json!({ "limit": 4 });
+
+        server
+            .post(&path)
+            .json(&query)
+            .await
+            .assert_status_unauthorized();
+
+        test_util::login_session(&mut server, &user).await;
+        test_util::test_access_control(
+            &db,
+            Resource::Table,
+            table_id,
+            user.user_id,
+            AccessRole::Viewer,
+            async || server.post(&path).json(&query).await,
+        )
+        .await;
+
+        server
+            .post(&format!("/api/tables/{table_id}/views/1000/query"))
+            .json(&query)
+            .await
+            .assert_status_not_found();
+        server
+            .post(&path)
+            .json(&json!({ "sort": [{ "field_id": 1000 }] }))
+            .await
+            .assert_status_unprocessable_entity();
+
+        let response = server.post(&path).json(&query).await;
+        response.assert_status_ok();
+        let page: Value = response.json();
+        assert_eq!(page["total_count"], json!(5));
+        let entries = page["entries"].as_array().unwrap();
+        let page_entry_ids = entries
+            .iter()
+            .map(|e| e["entry_id"].as_i64().unwrap() as Id)
+            .collect_vec();
+        assert_eq!(
+            page_entry_ids,
+            [entry_ids[4], entry_ids[2], entry_ids[5], entry_ids[3]]
+        );
+        assert!(
+            entries
+                .iter()
+                .all(|e| e["cells"].get(value_field_id.to_string()).is_none())
+        );
+
+        let response = server
+            .post(&path)
+            .json(&json!({ "limit": 4, "cursor": page["next_cursor"] }))
+            .await;
+        response.assert_status_ok();
+        let page: Value = response.json();
+        assert_eq!(page["entries"][0]["entry_id"], json!(entry_ids[1]));
+
+        Ok(())
+    }
+
+    #[test]
+    fn validate_table_view() {
+        let relation = FieldMetadata {
+            field_id: 1,
+            field_kind: Json(FieldKind::Relation {
+                is_required: false,
+                target_table_id: 1,
+                multiple: true,
+                on_delete: RelationDeleteRule::Restrict,
+            }),
+        };
+        let checkbox = FieldMetadata {
+            field_id: 2,
+            field_kind: Json(FieldKind::Checkbox),
+        };
+        let fields = [relation, checkbox];
+
+        assert!(
+            super::validate_table_view(
+                &[EntryFilter {
+                    field_id: 2,
+                    operator: FilterOperator::IsNull,
+                }],
+                &[EntrySort {
+                    field_id: 2,
+                    direction: Default::default(),
+                }],
+                &[1],
+                &HashMap::from([(2, 100)]),
+                Some(2),
+                &fields,
+            )
+            .is_ok()
+        );
+
+        let error_messages = super::validate_table_view(
+            &[],
+            &[EntrySort {
+                field_id: 1,
+                direction: Default::default(),
+            }],
+            &[3],
+            &HashMap::from([(2, -1)]),
+            Some(1),
+            &fields,
+        )
+        .unwrap_err();
+        assert_eq!(
+            error_messages,
+            [
+                format!("1: {INVALID_SORT}"),
+                format!("1: {INVALID_GROUP_FIELD}"),
+                format!("3: {INVALID_FIELD_ID}"),
+                format!("2: {INVALID_COLUMN_WIDTH}"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,405 @@
+//! Routes for managing user groups and their members.
+//!
+//! Any user can create a group and becomes its owner.
+//! Groups are managed by their owners and by admins.
+
+use crate::{
+    AppState, Id,
+    auth::AppAuthSession,
+    db,
+    error::{ApiError, ApiResult},
+    model::{
+        groups::{
+            CreateGroupMember, CreateUserGroup, SelectGroupMember, SelectUserGroup,
+            UpdateGroupMember, UpdateUserGroup, UserGroup,
+        },
+        users::User,
+    },
+};
+use aide::{
+    NoApi,
+    axum::{
+        ApiRouter,
+        routing::{patch_with, post_with},
+    },
+};
+use axum::{
+    Json,
+    extract::{Path, State},
+};
+use axum_login::AuthSession;
+use sqlx::PgConnection;
+
+const GROUP_NAME_IS_TAKEN: &str = "Group name is taken";
+const USERNAME_NOT_FOUND: &str = "Username not found";
+const USER_IS_ALREADY_A_MEMBER: &str = "User is already a member";
+
+pub fn router() -> ApiRouter<AppState> {
+    ApiRouter::new().nest(
+        "/groups",
+        ApiRouter::new()
+            .api_route(
+                "/",
+                post_with(create_user_group, docs::create_user_group)
+                    .get_with(get_user_groups, docs::get_user_groups),
+            )
+            .api_route(
+                "/{group_id}",
+                patch_with(update_user_group, docs::update_user_group)
+                    .delete_with(delete_user_group, docs::delete_user_group),
+            )
+            .api_route(
+                "/{group_id}/members",
+                post_with(create_group_member, docs::create_group_member),
+            )
+            .api_route(
+                "/{group_id}/members/{user_id}",
+                patch_with(update_group_member, docs::update_group_member)
+                    .delete_with(delete_group_member, docs::delete_group_member),
+            ),
+    )
+}
+
+async fn create_user_group(
+    NoApi(AuthSession { user, .. }): AppAuthSession,
+    State(AppState { db, .. }): State<AppState>,
+    Json(create_group): Json<CreateUserGroup>,
+) -> ApiResult<Json<UserGroup>> {
+    let user_id = user.ok_or(ApiError::Unauthorized)?.user_id;
+    let mut tx = db.begin().await?;
+
+    if db::get_user_group_id(tx.as_mut(), &create_group.name)
+        .await?
+        .is_some()
+    {
+        return Err(ApiError::Conflict(GROUP_NAME_IS_TAKEN.into()));
+    }
+
+    let group = db::create_user_group(tx.as_mut(), user_id, create_group).await?;
+
+    tx.commit().await?;
+    Ok(Json(group))
+}
+
+async fn update_user_group(
+    NoApi(AuthSession { user, .. }): AppAuthSession,
+    State(AppState { db, .. }): State<AppState>,
+    Path(SelectUserGroup { group_id }): Path<SelectUserGroup>,
+    Json(update_group): Json<UpdateUserGroup>,
+) -> ApiResult<Json<UserGroup>> {
+    let user = user.ok_or(ApiError::Unauthorized)?;
+    let mut tx = db.begin().await?;
+
+    check_group_manager(tx.as_mut(), &user, group_id).await?;
+    if db::get_user_group_id(tx.as_mut(), &update_group.name)
+        .await?
+        .is_some_and(|id| id != group_id)
+    {
+        return Err(ApiError::Conflict(GROUP_NAME_IS_TAKEN.into()));
+    }
+
+    let group = db::update_user_group(tx.as_mut(), group_id, update_group).await?;
+
+    tx.commit().await?;
+    Ok(Json(group))
+}
+
+async fn delete_user_group(
+    NoApi(AuthSession { user, .. }): AppAuthSession,
+    State(AppState { db, .. }): State<AppState>,
+    Path(SelectUserGroup { group_id }): Path<SelectUserGroup>,
+) -> ApiResult<()> {
+    let user = user.ok_or(ApiError::Unauthorized)?;
+    let mut tx = db.begin().await?;
+
+    check_group_manager(tx.as_mut(), &user, group_id).await?;
+    db::delete_user_group(tx.as_mut(), group_id).await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+async fn get_user_groups(
+    NoApi(AuthSession { user, .. }): AppAuthSession,
+    State(AppState { db, .. }): State<AppState>,
+) -> ApiResult<Json<Vec<UserGroup>>> {
+    let user = user.ok_or(ApiError::Unauthorized)?;
+
+    let groups = db::get_user_groups(&db, (!user.is_admin).then_some(user.user_id)).await?;
+
+    Ok(Json(groups))
+}
+
+async fn create_group_member(
+    NoApi(AuthSession { user, .. }): AppAuthSession,
+    State(AppState { db, .. }): State<AppState>,
+    Path(SelectUserGroup { group_id }): Path<SelectUserGroup>,
+    Json(CreateGroupMember { username, is_owner }): Json<CreateGroupMember>,
+) -> ApiResult<Json<UserGroup>> {
+    let user = user.ok_or(ApiError::Unauthorized)?;
+    let mut tx = db.begin().await?;
+
+    check_group_manager(tx.as_mut(), &user, group_id).await?;
+    let member_id = db::get_user_by_username(tx.as_mut(), username)
+        .await?
+        .ok_or(ApiError::UnprocessableEntity(USERNAME_NOT_FOUND.into()))?
+        .user_id;
+    if db::get_group_member_is_owner(tx.as_mut(), group_id, member_id)
+        .await?
+        .is_some()
+    {
+        return Err(ApiError::Conflict(USER_IS_ALREADY_A_MEMBER.into()));
+    }
+
+    db::create_group_member(tx.as_mut(), group_id, member_id, is_owner).await?;
+    let group = db::get_user_group(tx.as_mut(), group_id).await?;
+
+    tx.commit().await?;
+    Ok(Json(group))
+}
+
+async fn update_group_member(
+    NoApi(AuthSession { user, .. }): AppAuthSession,
+    State(AppState { db, .. }): State<AppState>,
+    Path(SelectGroupMember { group_id, user_id }): Path<SelectGroupMember>,
+    Json(UpdateGroupMember { is_owner }): Json<UpdateGroupMember>,
+) -> ApiResult<Json<UserGroup>> {
+    let user = user.ok_or(ApiError::Unauthorized)?;
+    let mut tx = db.begin().await?;
+
+    check_group_manager(tx.as_mut(), &user, group_id).await?;
+    check_group_member(tx.as_mut(), group_id, user_id).await?;
+
+    db::update_group_member(tx.as_mut(), group_id, user_id, is_owner).await?;
+    let group = db::get_user_group(tx.as_mut(), group_id).await?;
+
+    tx.commit().await?;
+    Ok(Json(group))
+}
+
+async fn delete_group_member(
+    NoApi(AuthSession { user, .. }): AppAuthSession,
+    State(AppState { db, .. }): State<AppState>,
+    Path(SelectGroupMember { group_id, user_id }): Path<SelectGroupMember>,
+) -> ApiResult<()> {
+    let user = user.ok_or(ApiError::Unauthorized)?;
+    let mut tx = db.begin().await?;
+
+    check_group_manager(tx.as_mut(), &user, group_id).await?;
+    check_group_member(tx.as_mut(), group_id, user_id).await?;
+
+    db::delete_group_member(tx.as_mut(), group_id, user_id).await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Check that the user is an admin or an owner of the group.
+/// Groups the user is not a member of are not found unless the user is an admin.
+async fn check_group_manager(conn: &mut PgConnection, user: &User, group_id: Id) -> ApiResult<()> {
+    if !db::user_group_exists(&mut *conn, group_id).await? {
+        return Err(ApiError::NotFound);
+    }
+    if user.is_admin {
+        return Ok(());
+    }
+    match db::get_group_member_is_owner(conn, group_id, user.user_id).await? {
+        Some(true) => Ok(()),
+        Some(false) => Err(ApiError::Forbidden),
+        None => Err(ApiError::NotFound),
+    }
+}
+
+/// Check that the user is a member of the group.
+async fn check_group_member(conn: &mut PgConnection, group_id: Id, user_id: Id) -> ApiResult<()> {
+    if db::get_group_member_is_owner(conn, group_id, user_id)
+        .await?
+        .is_none()
+    {
+        return Err(ApiError::NotFound);
+    }
+    Ok(())
+}
+
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod docs {
+    use crate::{
+        api::groups::{GROUP_NAME_IS_TAKEN, USER_IS_ALREADY_A_MEMBER, USERNAME_NOT_FOUND},
+        docs::{GROUPS_TAG, TransformOperationExt, template},
+        model::groups::UserGroup,
+    };
+    use aide::{OperationOutput, transform::TransformOperation};
+    use axum::Json;
+
+    fn groups<'a, R: OperationOutput>(
+        op: TransformOperation<'a>,
+        summary: &'a str,
+        description: &'a str,
+    ) -> TransformOperation<'a> {
+        template::<R>(op, summary, description, true, GROUPS_TAG)
+    }
+
+    fn manage_groups<'a, R: OperationOutput>(
+        op: TransformOperation<'a>,
+        summary: &'a str,
+        description: &'a str,
+    ) -> TransformOperation<'a> {
+        groups::<R>(op, summary, description)
+            .response_description::<403, ()>("User is not an owner of the group or an admin")
+    }
+
+    pub fn create_user_group(op: TransformOperation) -> TransformOperation {
+        groups::<Json<UserGroup>>(
+            op,
+            "create_user_group",
+            "Create a user group with the user as its owner.",
+        )
+        .response_description::<409, String>(GROUP_NAME_IS_TAKEN)
+    }
+
+    pub fn update_user_group(op: TransformOperation) -> TransformOperation {
+        manage_groups::<Json<UserGroup>>(op, "update_user_group", "Rename a user group.")
+            .response_description::<404, ()>("Group not found")
+            .response_description::<409, String>(GROUP_NAME_IS_TAKEN)
+    }
+
+    pub fn delete_user_group(op: TransformOperation) -> TransformOperation {
+        manage_groups::<()>(
+            op,
+            "delete_user_group",
+            "Delete a user group and the access granted to it.",
+        )
+        .response_description::<404, ()>("Group not found")
+    }
+
+    pub fn get_user_groups(op: TransformOperation) -> TransformOperation {
+        groups::<Json<Vec<UserGroup>>>(
+            op,
+            "get_user_groups",
+            "Get the groups the user is a member of, or all the groups for admins.",
+        )
+    }
+
+    pub fn create_group_member(op: TransformOperation) -> TransformOperation {
+        manage_groups::<Json<UserGroup>>(op, "create_group_member", "Add a user to the group.")
+            .response_description::<404, ()>("Group not found")
+            .response_description::<409, String>(USER_IS_ALREADY_A_MEMBER)
+            .response_description::<422, String>(USERNAME_NOT_FOUND)
+    }
+
+    pub fn update_group_member(op: TransformOperation) -> TransformOperation {
+        manage_groups::<Json<UserGroup>>(
+            op,
+            "update_group_member",
+            "Change whether a member owns the group.",
+        )
+        .response_description::<404, ()>("Group not found\n\nMember not found")
+    }
+
+    pub fn delete_group_member(op: TransformOperation) -> TransformOperation {
+        manage_groups::<()>(op, "delete_group_member", "Remove a user from the group.")
+            .response_description::<404, ()>("Group not found\n\nMember not found")
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod test {
+    use crate::{db, model::groups::UserGroup, test_util};
+    use serde_json::json;
+    use sqlx::PgPool;
+
+    #[sqlx::test]
+    async fn user_groups(db: PgPool) -> anyhow::Result<()> {
+        let mut server = test_util::server(db.clone()).await;
+        server
+            .post("/api/groups")
+            .json(&json!({ "name": "team" }))
+            .await
+            .assert_status_unauthorized();
+
+        let owner = db::create_user(&db, "owner".into(), "".into(), false).await?;
+        let member = db::create_user(&db, "member".into(), "".into(), false).await?;
+        let admin = db::create_user(&db, "admin".into(), "".into(), true).await?;
+        test_util::login_session(&mut server, &owner).await;
+
+        let group: UserGroup = server
+            .post("/api/groups")
+            .json(&json!({ "name": "team" }))
+            .await
+            .json();
+        server
+            .post("/api/groups")
+            .json(&json!({ "name": "Team" }))
+            .await
+            .assert_status_conflict();
+        let path = format!("/api/groups/{}", group.group_id);
+
+        server
+            .post(&format!("{path}/members"))
+            .json(&json!({ "username": "nobody" }))
+            .await
+            .assert_status_unprocessable_entity();
+        let group: UserGroup = server
+            .post(&format!("{path}/members"))
+            .json(&json!({ "username": "member" }))
+            .await
+            .json();
+        assert_eq!(group.members.len(), 2);
+        server
+            .post(&format!("{path}/members"))
+            .json(&json!({ "username": "member" }))
+            .await
+            .assert_status_conflict();
+        server
+            .patch(&format!("{path}/members/{}", admin.user_id))
+            .json(&json!({ "is_owner": true }))
+            .await
+            .assert_status_not_found();
+
+        test_util::login_session(&mut server, &member).await;
+        let groups: Vec<UserGroup> = server.get("/api/groups").await.json();
+        assert_eq!(groups, vec![group]);
+        server
+            .patch(&path)
+            .json(&json!({ "name": "squad" }))
+            .await
+            .assert_status_forbidden();
+
+        test_util::login_session(&mut server, &owner).await;
+        server
+            .patch(&format!("{path}/members/{}", member.user_id))
+            .json(&json!({ "is_owner": true }))
+            .await
+            .assert_status_ok();
+        test_util::login_session(&mut server, &member).await;
+        let group: UserGroup = server
+            .patch(&path)
+            .json(&json!({ "name": "squad" }))
+            .await
+            .json();
+        assert_eq!(group.name, "squad");
+        server
+            .delete(&format!("{path}/members/{}", owner.user_id))
+            .await
+            .assert_status_ok();
+
+        test_util::login_session(&mut server, &owner).await;
+        assert!(
+            server
+                .get("/api/groups")
+                .await
+                .json::<Vec<UserGroup>>()
+                .is_empty()
+        );
+        server.delete(&path).await.assert_status_not_found();
+
+        test_util::login_session(&mut server, &admin).await;
+        let groups: Vec<UserGroup> = server.get("/api/groups").await.json();
+        assert_eq!(groups.len(), 1);
+        server.delete(&path).await.assert_status_ok();
+        server.delete(&path).await.assert_status_not_found();
+
+        Ok(())
+    }
+}
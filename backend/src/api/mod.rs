@@ -1,18 +1,24 @@
 //! This modules defines the API's routes and implements their handlers.
 //! 
-//! All requests other than those to `/api/login` require that the user is authenticated.
-//! Otherwise, `401 Unauthorized` is returned.
+//! All requests other than those to `/api/login` and the share links of `/api/public`
+//! require that the user is authenticated. Otherwise, `401 Unauthorized` is returned.
 //! 
 //! Instructions to view the OpenAPI documentation is in `README.md`.
 
 mod access;
 mod data;
+mod groups;
+mod public;
+mod shares;
+mod tokens;
 mod users;
 mod viz;
 
 use crate::AppState;
 use aide::axum::ApiRouter;
 
+pub use public::SHARE_PASSWORD_HEADER;
+
 const NO_DATA_IN_REQUEST_BODY: &str = "No data in request body";
 
 pub fn router() -> ApiRouter<AppState> {
@@ -20,8 +26,12 @@ pub fn router() -> ApiRouter<AppState> {
         "/api",
         ApiRouter::new()
             .merge(users::router())
+            .merge(tokens::router())
+            .merge(shares::router())
+            .merge(groups::router())
             .merge(data::router())
             .merge(viz::router())
-            .merge(access::router()),
+            .merge(access::router())
+            .merge(public::router()),
     )
 }
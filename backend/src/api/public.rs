@@ -0,0 +1,321 @@
+//! Public routes serving the resources of share links.
+//!
+//! These routes do not require authentication. They only give read-only access to the
+//! table or dashboard of the share link in the path and never expose any other endpoint.
+//! Password-protected links require the password in the `X-Share-Password` header.
+//! Otherwise, `401 Unauthorized` is returned.
+
+use crate::{
+    AppState, Id,
+    api::viz::charts::query_chart_data,
+    db,
+    error::{ApiError, ApiResult, IntoAnyhow},
+    model::{
+        access::Resource,
+        shares::{SelectSharedChart, SelectSharedResource, SharedDashboard, SharedResource},
+        viz::{ChartData, ChartDataQuery},
+    },
+};
+use aide::axum::{ApiRouter, routing::get_with};
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::HeaderMap,
+};
+use password_auth::verify_password;
+use sqlx::PgPool;
+use tokio::task;
+
+/// Header containing the password of a password-protected share link.
+pub const SHARE_PASSWORD_HEADER: &str = "x-share-password";
+
+pub fn router() -> ApiRouter<AppState> {
+    ApiRouter::new().nest(
+        "/public/{secret}",
+        ApiRouter::new()
+            .api_route(
+                "/",
+                get_with(get_shared_resource, docs::get_shared_resource),
+            )
+            .api_route(
+                "/charts/{chart_id}/data",
+                get_with(get_shared_chart_data, docs::get_shared_chart_data),
+            ),
+    )
+}
+
+async fn get_shared_resource(
+    State(AppState { db, .. }): State<AppState>,
+    Path(SelectSharedResource { secret }): Path<SelectSharedResource>,
+    headers: HeaderMap,
+) -> ApiResult<Json<SharedResource>> {
+    let shared_resource = match use_share_link(&db, &secret, &headers).await? {
+        (Resource::Table, table_id) => {
+            SharedResource::Table(db::get_table_data(&db, table_id).await?)
+        }
+        (Resource::Dashboard, dashboard_id) => SharedResource::Dashboard(SharedDashboard {
+            dashboard: db::get_dashboard(&db, dashboard_id).await?,
+            charts: db::get_charts(&db, dashboard_id).await?,
+            controls: db::get_controls(&db, dashboard_id).await?,
+        }),
+    };
+
+    Ok(Json(shared_resource))
+}
+
+async fn get_shared_chart_data(
+    State(AppState { db, .. }): State<AppState>,
+    Path(SelectSharedChart { secret, chart_id }): Path<SelectSharedChart>,
+    Query(ChartDataQuery { controls }): Query<ChartDataQuery>,
+    headers: HeaderMap,
+) -> ApiResult<Json<ChartData>> {
+    let (Resource::Dashboard, dashboard_id) = use_share_link(&db, &secret, &headers).await? else {
+        return Err(ApiError::NotFound);
+    };
+
+    let chart_data = query_chart_data(&db, dashboard_id, chart_id, controls).await?;
+
+    Ok(Json(chart_data))
+}
+
+/// Get the resource shared by the link with this secret.
+/// The link must not be expired, its password must be in the headers if it has one,
+/// and its creator must still be able to view the resource.
+async fn use_share_link(
+    db: &PgPool,
+    secret: &str,
+    headers: &HeaderMap,
+) -> ApiResult<(Resource, Id)> {
+    let link = db::use_share_link(db, secret)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    if let Some(password_hash) = link.password_hash {
+        let password = headers
+            .get(SHARE_PASSWORD_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_owned)
+            .ok_or(ApiError::Unauthorized)?;
+        task::spawn_blocking(move || verify_password(password, &password_hash))
+            .await
+            .anyhow()?
+            .map_err(|_| ApiError::Unauthorized)?;
+    }
+
+    let (resource, resource_id) = link
+        .table_id
+        .map(|table_id| (Resource::Table, table_id))
+        .or(link
+            .dashboard_id
+            .map(|dashboard_id| (Resource::Dashboard, dashboard_id)))
+        .ok_or(ApiError::NotFound)?;
+    if db::get_access_role(db, resource, resource_id, link.user_id)
+        .await?
+        .is_none()
+    {
+        return Err(ApiError::NotFound);
+    }
+
+    Ok((resource, resource_id))
+}
+
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod docs {
+    use crate::{
+        docs::{PUBLIC_TAG, TransformOperationExt, template},
+        model::{shares::SharedResource, viz::ChartData},
+    };
+    use aide::{OperationOutput, transform::TransformOperation};
+    use axum::Json;
+
+    fn public<'a, R: OperationOutput>(
+        op: TransformOperation<'a>,
+        summary: &'a str,
+        description: &'a str,
+    ) -> TransformOperation<'a> {
+        template::<R>(op, summary, description, false, PUBLIC_TAG).response_description::<401, ()>(
+            "Share link has a password that is missing or wrong in the X-Share-Password header",
+        )
+    }
+
+    pub fn get_shared_resource(op: TransformOperation) -> TransformOperation {
+        public::<Json<SharedResource>>(
+            op,
+            "get_shared_resource",
+            "Get the table data or the dashboard shared by a share link, without authentication. \
+            A shared table includes its fields, entries and child tables. \
+            A shared dashboard includes its charts and filter controls.",
+        )
+        .response_description::<404, ()>("Share link not found or expired")
+    }
+
+    pub fn get_shared_chart_data(op: TransformOperation) -> TransformOperation {
+        public::<Json<ChartData>>(
+            op,
+            "get_shared_chart_data",
+            "Get the data of a chart of the dashboard shared by a share link, \
+            without authentication. The dashboard controls are applied as for get_chart_data.",
+        )
+        .response_description::<404, ()>("Share link not found or expired\n\nChart not found")
+        .response_description::<422, String>("<control_id>: <control error>")
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod test {
+    use super::SHARE_PASSWORD_HEADER;
+    use crate::{
+        db,
+        model::{
+            access::{AccessRole, Resource},
+            data::CreateTable,
+            shares::CreatedShareLink,
+            viz::{ChartKind, CreateChart, CreateDashboard},
+        },
+        test_util,
+    };
+    use serde_json::{Value, json};
+    use sqlx::PgPool;
+
+    #[sqlx::test]
+    async fn shared_resources(db: PgPool) -> anyhow::Result<()> {
+        let mut server = test_util::server(db.clone()).await;
+        let user = db::create_user(&db, "test".into(), "".into(), false).await?;
+        let table_id = db::create_table(
+            &db,
+            CreateTable {
+                name: "A".into(),
+                description: "".into(),
+                parent_id: None,
+            },
+        )
+        .await?
+        .table_id;
+        let mut dashboard_ids = Vec::new();
+        for name in ["A", "B"] {
+            let dashboard_id = db::create_dashboard(
+                &db,
+                CreateDashboard {
+                    name: name.into(),
+                    description: "".into(),
+                },
+            )
+            .await?
+            .dashboard_id;
+            db::create_access(
+                &db,
+                Resource::Dashboard,
+                dashboard_id,
+                user.user_id,
+                AccessRole::Owner,
+            )
+            .await?;
+            dashboard_ids.push(dashboard_id);
+        }
+        let mut chart_ids = Vec::new();
+        for &dashboard_id in &dashboard_ids {
+            let chart_id = db::create_chart(
+                &db,
+                dashboard_id,
+                CreateChart {
+                    table_id,
+                    name: "A".into(),
+                    chart_kind: ChartKind::Table,
+                    top_n: None,
+                },
+            )
+            .await?
+            .chart_id;
+            chart_ids.push(chart_id);
+        }
+        db::create_access(
+            &db,
+            Resource::Table,
+            table_id,
+            user.user_id,
+            AccessRole::Owner,
+        )
+        .await?;
+
+        test_util::login_session(&mut server, &user).await;
+        let table_link: CreatedShareLink = server
+            .post("/api/shares")
+            .json(&json!({ "table_id": table_id }))
+            .await
+            .json();
+        let dashboard_link: CreatedShareLink = server
+            .post("/api/shares")
+            .json(&json!({ "dashboard_id": dashboard_ids[0], "password": "secret" }))
+            .await
+            .json();
+        server.clear_cookies();
+        server.do_not_save_cookies();
+
+        let response = server
+            .get(&format!("/api/public/{}", table_link.secret))
+            .await;
+        response.assert_status_ok();
+        let shared: Value = response.json();
+        assert_eq!(shared["resource"], "Table");
+        assert_eq!(shared["table"]["table_id"], table_id);
+        server
+            .get(&format!(
+                "/api/public/{}/charts/{}/data",
+                table_link.secret, chart_ids[0]
+            ))
+            .await
+            .assert_status_not_found();
+        server
+            .get("/api/public/shr_wrong")
+            .await
+            .assert_status_not_found();
+
+        let path = format!("/api/public/{}", dashboard_link.secret);
+        server.get(&path).await.assert_status_unauthorized();
+        server
+            .get(&path)
+            .add_header(SHARE_PASSWORD_HEADER, "wrong")
+            .await
+            .assert_status_unauthorized();
+        let response = server
+            .get(&path)
+            .add_header(SHARE_PASSWORD_HEADER, "secret")
+            .await;
+        response.assert_status_ok();
+        let shared: Value = response.json();
+        assert_eq!(shared["resource"], "Dashboard");
+        assert_eq!(shared["dashboard"]["dashboard_id"], dashboard_ids[0]);
+        assert_eq!(shared["charts"][0]["chart_id"], chart_ids[0]);
+
+        server
+            .get(&format!("{path}/charts/{}/data", chart_ids[0]))
+            .add_header(SHARE_PASSWORD_HEADER, "secret")
+            .await
+            .assert_status_ok();
+        server
+            .get(&format!("{path}/charts/{}/data", chart_ids[1]))
+            .add_header(SHARE_PASSWORD_HEADER, "secret")
+            .await
+            .assert_status_not_found();
+        server
+            .get(&format!("/api/dashboards/{}/charts", dashboard_ids[0]))
+            .add_header(SHARE_PASSWORD_HEADER, "secret")
+            .await
+            .assert_status_unauthorized();
+
+        db::delete_many_access(&db, Resource::Table, table_id, [user.user_id]).await?;
+        server
+            .get(&format!("/api/public/{}", table_link.secret))
+            .await
+            .assert_status_not_found();
+
+        db::delete_share_link(&db, dashboard_link.link.share_id).await?;
+        server
+            .get(&path)
+            .add_header(SHARE_PASSWORD_HEADER, "secret")
+            .await
+            .assert_status_not_found();
+        Ok(())
+    }
+}
@@ -0,0 +1,318 @@
+//! Routes for managing the public share links of the user.
+//!
+//! Share links give anonymous read-only access to a single table or dashboard.
+//! The shared resources are served by the separate route tree in [super::public].
+
+use crate::{
+    AppState,
+    auth::AppAuthSession,
+    db,
+    error::{ApiError, ApiResult, IntoAnyhow},
+    model::{
+        access::{AccessRole, AccessRoleCheck, Resource},
+        shares::{CreateShareLink, CreatedShareLink, SelectShareLink, ShareLink},
+    },
+};
+use aide::{
+    NoApi,
+    axum::{
+        ApiRouter,
+        routing::{delete_with, post_with},
+    },
+};
+use axum::{
+    Json,
+    extract::{Path, State},
+};
+use axum_login::AuthSession;
+use chrono::Utc;
+use password_auth::generate_hash;
+use tokio::task;
+
+const INVALID_RESOURCE: &str = "Exactly one of a table or a dashboard must be shared";
+const INVALID_EXPIRY: &str = "Expiry must be in the future";
+const INVALID_PASSWORD: &str = "Password must not be empty";
+
+pub fn router() -> ApiRouter<AppState> {
+    ApiRouter::new().nest(
+        "/shares",
+        ApiRouter::new()
+            .api_route(
+                "/",
+                post_with(create_share_link, docs::create_share_link)
+                    .get_with(get_share_links, docs::get_share_links),
+            )
+            .api_route(
+                "/{share_id}",
+                delete_with(delete_share_link, docs::delete_share_link),
+            ),
+    )
+}
+
+async fn create_share_link(
+    NoApi(AuthSession { user, .. }): AppAuthSession,
+    State(AppState { db, .. }): State<AppState>,
+    Json(CreateShareLink {
+        table_id,
+        dashboard_id,
+        password,
+        expires_at,
+    }): Json<CreateShareLink>,
+) -> ApiResult<Json<CreatedShareLink>> {
+    let user_id = user.ok_or(ApiError::Unauthorized)?.user_id;
+    let mut tx = db.begin().await?;
+
+    let mut error_messages = Vec::new();
+    let resource = match (table_id, dashboard_id) {
+        (Some(table_id), None) => Some((Resource::Table, table_id)),
+        (None, Some(dashboard_id)) => Some((Resource::Dashboard, dashboard_id)),
+        _ => {
+            error_messages.push(INVALID_RESOURCE);
+            None
+        }
+    };
+    if expires_at.is_some_and(|expires_at| expires_at <= Utc::now()) {
+        error_messages.push(INVALID_EXPIRY);
+    }
+    if password
+        .as_ref()
+        .is_some_and(|password| password.is_empty())
+    {
+        error_messages.push(INVALID_PASSWORD);
+    }
+    let Some((resource, resource_id)) = resource.filter(|_| error_messages.is_empty()) else {
+        return Err(ApiError::UnprocessableEntity(error_messages.join(", ")));
+    };
+
+    db::get_access_role(tx.as_mut(), resource, resource_id, user_id)
+        .await?
+        .check(AccessRole::Owner)?;
+
+    let password_hash = match password {
+        Some(password) => Some(
+            task::spawn_blocking(|| generate_hash(password))
+                .await
+                .anyhow()?,
+        ),
+        None => None,
+    };
+    let link = db::create_share_link(
+        tx.as_mut(),
+        user_id,
+        table_id,
+        dashboard_id,
+        password_hash,
+        expires_at,
+    )
+    .await?;
+
+    tx.commit().await?;
+    Ok(Json(link))
+}
+
+async fn get_share_links(
+    NoApi(AuthSession { user, .. }): AppAuthSession,
+    State(AppState { db, .. }): State<AppState>,
+) -> ApiResult<Json<Vec<ShareLink>>> {
+    let user_id = user.ok_or(ApiError::Unauthorized)?.user_id;
+
+    Ok(Json(db::get_share_links(&db, user_id).await?))
+}
+
+async fn delete_share_link(
+    NoApi(AuthSession { user, .. }): AppAuthSession,
+    State(AppState { db, .. }): State<AppState>,
+    Path(SelectShareLink { share_id }): Path<SelectShareLink>,
+) -> ApiResult<()> {
+    let user_id = user.ok_or(ApiError::Unauthorized)?.user_id;
+    let mut tx = db.begin().await?;
+
+    if !db::share_link_exists(tx.as_mut(), user_id, share_id).await? {
+        return Err(ApiError::NotFound);
+    }
+    db::delete_share_link(tx.as_mut(), share_id).await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod docs {
+    use crate::{
+        api::shares::{INVALID_EXPIRY, INVALID_PASSWORD, INVALID_RESOURCE},
+        docs::{SHARES_TAG, TransformOperationExt, template},
+        model::{
+            access::{AccessRole, Resource},
+            shares::{CreatedShareLink, ShareLink},
+        },
+    };
+    use aide::{OperationOutput, transform::TransformOperation};
+    use axum::Json;
+
+    fn shares<'a, R: OperationOutput>(
+        op: TransformOperation<'a>,
+        summary: &'a str,
+        description: &'a str,
+    ) -> TransformOperation<'a> {
+        template::<R>(op, summary, description, true, SHARES_TAG)
+    }
+
+    pub fn create_share_link(op: TransformOperation) -> TransformOperation {
+        shares::<Json<CreatedShareLink>>(
+            op,
+            "create_share_link",
+            "Create a public read-only link to a table or a dashboard, \
+            optionally expiring and protected by a password. \
+            The secret of the link is only returned in this response. \
+            The link stops working when the creator can no longer view the resource.",
+        )
+        .response_description::<404, ()>("Table not found\n\nDashboard not found")
+        .response_description::<422, String>(&format!(
+            "{INVALID_RESOURCE}\n\n{INVALID_EXPIRY}\n\n{INVALID_PASSWORD}"
+        ))
+        .required_access([
+            (Resource::Table, AccessRole::Owner),
+            (Resource::Dashboard, AccessRole::Owner),
+        ])
+    }
+
+    pub fn get_share_links(op: TransformOperation) -> TransformOperation {
+        shares::<Json<Vec<ShareLink>>>(
+            op,
+            "get_share_links",
+            "Get all the share links created by the user, without their secrets.",
+        )
+    }
+
+    pub fn delete_share_link(op: TransformOperation) -> TransformOperation {
+        shares::<()>(
+            op,
+            "delete_share_link",
+            "Revoke a share link of the user. The link stops working immediately.",
+        )
+        .response_description::<404, ()>("Share link not found")
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod test {
+    use crate::{
+        db,
+        model::{
+            access::{AccessRole, Resource},
+            data::CreateTable,
+            shares::{CreatedShareLink, ShareLink},
+            viz::CreateDashboard,
+        },
+        test_util,
+    };
+    use chrono::{TimeDelta, Utc};
+    use serde_json::json;
+    use sqlx::PgPool;
+
+    #[sqlx::test]
+    async fn share_links(db: PgPool) -> anyhow::Result<()> {
+        let mut server = test_util::server(db.clone()).await;
+        let table_id = db::create_table(
+            &db,
+            CreateTable {
+                name: "A".into(),
+                description: "".into(),
+                parent_id: None,
+            },
+        )
+        .await?
+        .table_id;
+        let dashboard_id = db::create_dashboard(
+            &db,
+            CreateDashboard {
+                name: "A".into(),
+                description: "".into(),
+            },
+        )
+        .await?
+        .dashboard_id;
+
+        server
+            .post("/api/shares")
+            .json(&json!({ "table_id": table_id }))
+            .await
+            .assert_status_unauthorized();
+
+        let user = db::create_user(&db, "test".into(), "".into(), false).await?;
+        test_util::login_session(&mut server, &user).await;
+        test_util::test_access_control(
+            &db,
+            Resource::Table,
+            table_id,
+            user.user_id,
+            AccessRole::Owner,
+            async || {
+                server
+                    .post("/api/shares")
+                    .json(&json!({ "table_id": table_id }))
+                    .await
+            },
+        )
+        .await;
+        db::create_access(
+            &db,
+            Resource::Dashboard,
+            dashboard_id,
+            user.user_id,
+            AccessRole::Owner,
+        )
+        .await?;
+
+        for link_wrong in [
+            json!({}),
+            json!({ "table_id": table_id, "dashboard_id": dashboard_id }),
+            json!({ "table_id": table_id, "expires_at": Utc::now() - TimeDelta::days(1) }),
+            json!({ "table_id": table_id, "password": "" }),
+        ] {
+            server
+                .post("/api/shares")
+                .json(&link_wrong)
+                .await
+                .assert_status_unprocessable_entity();
+        }
+        server
+            .post("/api/shares")
+            .json(&json!({ "dashboard_id": 1000 }))
+            .await
+            .assert_status_not_found();
+
+        let response = server
+            .post("/api/shares")
+            .json(&json!({
+                "dashboard_id": dashboard_id,
+                "password": "secret",
+                "expires_at": Utc::now() + TimeDelta::days(1),
+            }))
+            .await;
+        response.assert_status_ok();
+        let dashboard_link: CreatedShareLink = response.json();
+        assert_eq!(dashboard_link.link.dashboard_id, Some(dashboard_id));
+        assert!(dashboard_link.link.has_password);
+
+        let links: Vec<ShareLink> = server.get("/api/shares").await.json();
+        assert_eq!(links.len(), 2);
+        assert_eq!(links[1], dashboard_link.link);
+        assert_eq!(links[0].table_id, Some(table_id));
+
+        let other_user = db::create_user(&db, "other".into(), "".into(), false).await?;
+        test_util::login_session(&mut server, &other_user).await;
+        let path = format!("/api/shares/{}", dashboard_link.link.share_id);
+        server.delete(&path).await.assert_status_not_found();
+        let links: Vec<ShareLink> = server.get("/api/shares").await.json();
+        assert!(links.is_empty());
+
+        test_util::login_session(&mut server, &user).await;
+        server.delete(&path).await.assert_status_ok();
+        server.delete(&path).await.assert_status_not_found();
+        let links: Vec<ShareLink> = server.get("/api/shares").await.json();
+        assert_eq!(links.len(), 1);
+        Ok(())
+    }
+}
@@ -0,0 +1,496 @@
+//! Routes for managing the personal API tokens of the user.
+//!
+//! API tokens authenticate requests through the `Authorization: Bearer` header
+//! instead of the session cookie. See [crate::auth] for how their scopes are enforced.
+
+use crate::{
+    AppState,
+    auth::AppAuthSession,
+    db,
+    error::{ApiError, ApiResult},
+    model::{
+        access::Resource,
+        tokens::{ApiToken, CreateApiToken, CreatedApiToken, SelectApiToken},
+    },
+};
+use aide::{
+    NoApi,
+    axum::{
+        ApiRouter,
+        routing::{delete_with, post_with},
+    },
+};
+use axum::{
+    Json,
+    extract::{Path, State},
+};
+use axum_login::AuthSession;
+use chrono::Utc;
+
+const INVALID_EXPIRY: &str = "Expiry must be in the future";
+const TABLE_NOT_FOUND: &str = "Table not found";
+const DASHBOARD_NOT_FOUND: &str = "Dashboard not found";
+
+pub fn router() -> ApiRouter<AppState> {
+    ApiRouter::new().nest(
+        "/tokens",
+        ApiRouter::new()
+            .api_route(
+                "/",
+                post_with(create_api_token, docs::create_api_token)
+                    .get_with(get_api_tokens, docs::get_api_tokens),
+            )
+            .api_route(
+                "/{token_id}",
+                delete_with(delete_api_token, docs::delete_api_token),
+            ),
+    )
+}
+
+async fn create_api_token(
+    NoApi(AuthSession { user, .. }): AppAuthSession,
+    State(AppState { db, .. }): State<AppState>,
+    Json(create_token): Json<CreateApiToken>,
+) -> ApiResult<Json<CreatedApiToken>> {
+    let user_id = user.ok_or(ApiError::Unauthorized)?.user_id;
+    let mut tx = db.begin().await?;
+
+    let mut error_messages = Vec::new();
+    if create_token
+        .expires_at
+        .is_some_and(|expires_at| expires_at <= Utc::now())
+    {
+        error_messages.push(INVALID_EXPIRY.to_string());
+    }
+    for (resource, resource_ids, message) in [
+        (Resource::Table, &create_token.table_ids, TABLE_NOT_FOUND),
+        (
+            Resource::Dashboard,
+            &create_token.dashboard_ids,
+            DASHBOARD_NOT_FOUND,
+        ),
+    ] {
+        for &resource_id in resource_ids.iter().flatten() {
+            if db::get_access_role(tx.as_mut(), resource, resource_id, user_id)
+                .await?
+                .is_none()
+            {
+                error_messages.push(format!("{resource_id}: {message}"));
+            }
+        }
+    }
+    if !error_messages.is_empty() {
+        return Err(ApiError::UnprocessableEntity(error_messages.join(", ")));
+    }
+
+    let token = db::create_api_token(tx.as_mut(), user_id, create_token).await?;
+
+    tx.commit().await?;
+    Ok(Json(token))
+}
+
+async fn get_api_tokens(
+    NoApi(AuthSession { user, .. }): AppAuthSession,
+    State(AppState { db, .. }): State<AppState>,
+) -> ApiResult<Json<Vec<ApiToken>>> {
+    let user_id = user.ok_or(ApiError::Unauthorized)?.user_id;
+
+    Ok(Json(db::get_api_tokens(&db, user_id).await?))
+}
+
+async fn delete_api_token(
+    NoApi(AuthSession { user, .. }): AppAuthSession,
+    State(AppState { db, .. }): State<AppState>,
+    Path(SelectApiToken { token_id }): Path<SelectApiToken>,
+) -> ApiResult<()> {
+    let user_id = user.ok_or(ApiError::Unauthorized)?.user_id;
+    let mut tx = db.begin().await?;
+
+    if !db::api_token_exists(tx.as_mut(), user_id, token_id).await? {
+        return Err(ApiError::NotFound);
+    }
+    db::delete_api_token(tx.as_mut(), token_id).await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod docs {
+    use crate::{
+        api::tokens::{DASHBOARD_NOT_FOUND, INVALID_EXPIRY, TABLE_NOT_FOUND},
+        docs::{TOKENS_TAG, TransformOperationExt, template},
+        model::tokens::{ApiToken, CreatedApiToken},
+    };
+    use aide::{OperationOutput, transform::TransformOperation};
+    use axum::Json;
+
+    fn tokens<'a, R: OperationOutput>(
+        op: TransformOperation<'a>,
+        summary: &'a str,
+        description: &'a str,
+    ) -> TransformOperation<'a> {
+        template::<R>(op, summary, description, true, TOKENS_TAG)
+            .response_description::<403, ()>("Request is authenticated by an API token")
+    }
+
+    pub fn create_api_token(op: TransformOperation) -> TransformOperation {
+        let errors = format!(
+            "{INVALID_EXPIRY}\n\n<table_id>: {TABLE_NOT_FOUND}\n\n\
+            <dashboard_id>: {DASHBOARD_NOT_FOUND}"
+        );
+
+        tokens::<Json<CreatedApiToken>>(
+            op,
+            "create_api_token",
+            "Create a personal API token for the user, \
+            optionally limited to reading data and to some tables and dashboards. \
+            A token limited to some resources can only reach the routes of these resources \
+            and only lists these resources. \
+            A token limited to some tables can only use the charts reading these tables. \
+            Tokens cannot manage tokens, users, groups or shares. \
+            The secret of the token is only returned in this response.",
+        )
+        .response_description::<422, String>(&errors)
+    }
+
+    pub fn get_api_tokens(op: TransformOperation) -> TransformOperation {
+        tokens::<Json<Vec<ApiToken>>>(
+            op,
+            "get_api_tokens",
+            "Get the personal API tokens of the user, without their secrets.",
+        )
+    }
+
+    pub fn delete_api_token(op: TransformOperation) -> TransformOperation {
+        tokens::<()>(
+            op,
+            "delete_api_token",
+            "Revoke a personal API token of the user.",
+        )
+        .response_description::<404, ()>("Token not found")
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod test {
+    use crate::{
+        db,
+        model::{
+            access::{AccessRole, Resource},
+            data::{CreateTable, GetTable},
+            tokens::{ApiToken, CreatedApiToken},
+            viz::{ChartKind, CreateChart, CreateDashboard},
+        },
+        test_util,
+    };
+    use axum::http::{HeaderName, HeaderValue, header::AUTHORIZATION};
+    use chrono::{TimeDelta, Utc};
+    use itertools::Itertools;
+    use serde_json::json;
+    use sqlx::PgPool;
+
+    fn bearer(token: &CreatedApiToken) -> (HeaderName, HeaderValue) {
+        (
+            AUTHORIZATION,
+            HeaderValue::from_str(&format!("Bearer {}", token.secret)).unwrap(),
+        )
+    }
+
+    #[sqlx::test]
+    async fn api_tokens(db: PgPool) -> anyhow::Result<()> {
+        let mut server = test_util::server(db.clone()).await;
+        let create_table = |name: &str| CreateTable {
+            name: name.into(),
+            description: "".into(),
+            parent_id: None,
+        };
+        let table_id = db::create_table(&db, create_table("A")).await?.table_id;
+        let other_table_id = db::create_table(&db, create_table("B")).await?.table_id;
+
+        server
+            .post("/api/tokens")
+            .json(&json!({ "name": "ETL", "permission": "ReadOnly" }))
+            .await
+            .assert_status_unauthorized();
+
+        let user = db::create_user(&db, "test".into(), "".into(), false).await?;
+        for table_id in [table_id, other_table_id] {
+            db::create_access(
+                &db,
+                Resource::Table,
+                table_id,
+                user.user_id,
+                AccessRole::Owner,
+            )
+            .await?;
+        }
+        test_util::login_session(&mut server, &user).await;
+
+        for token_wrong in [
+            json!({
+                "name": "ETL",
+                "permission": "ReadOnly",
+                "expires_at": Utc::now() - TimeDelta::days(1),
+            }),
+            json!({ "name": "ETL", "permission": "ReadOnly", "table_ids": [1000] }),
+            json!({ "name": "ETL", "permission": "ReadOnly", "dashboard_ids": [1000] }),
+        ] {
+            server
+                .post("/api/tokens")
+                .json(&token_wrong)
+                .await
+                .assert_status_unprocessable_entity();
+        }
+
+        let read_token: CreatedApiToken = server
+            .post("/api/tokens")
+            .json(&json!({ "name": "ETL", "permission": "ReadOnly", "table_ids": [table_id] }))
+            .await
+            .json();
+        let write_token: CreatedApiToken = server
+            .post("/api/tokens")
+            .json(&json!({
+                "name": "Sync",
+                "permission": "ReadWrite",
+                "expires_at": Utc::now() + TimeDelta::days(1),
+            }))
+            .await
+            .json();
+        let tokens: Vec<ApiToken> = server.get("/api/tokens").await.json();
+        assert_eq!(
+            tokens,
+            vec![read_token.token.clone(), write_token.token.clone()]
+        );
+
+        server.clear_cookies();
+        server.do_not_save_cookies();
+        let (name, value) = bearer(&read_token);
+        server
+            .get(&format!("/api/tables/{table_id}/data"))
+            .add_header(name.clone(), value.clone())
+            .await
+            .assert_status_ok();
+        server
+            .post(&format!("/api/tables/{table_id}/entries/query"))
+            .add_header(name.clone(), value.clone())
+            .json(&json!({}))
+            .await
+            .assert_status_ok();
+        server
+            .patch(&format!("/api/tables/{table_id}"))
+            .add_header(name.clone(), value.clone())
+            .json(&json!({ "name": "C", "description": "" }))
+            .await
+            .assert_status_forbidden();
+        server
+            .get(&format!("/api/tables/{other_table_id}/data"))
+            .add_header(name.clone(), value.clone())
+            .await
+            .assert_status_forbidden();
+        server
+            .get("/api/tokens")
+            .add_header(name.clone(), value.clone())
+            .await
+            .assert_status_forbidden();
+        let tokens: Vec<ApiToken> = db::get_api_tokens(&db, user.user_id).await?;
+        assert!(tokens[0].last_used_at.is_some());
+
+        let (write_name, write_value) = bearer(&write_token);
+        server
+            .patch(&format!("/api/tables/{other_table_id}"))
+            .add_header(write_name, write_value)
+            .json(&json!({ "name": "C", "description": "" }))
+            .await
+            .assert_status_ok();
+
+        test_util::login_session(&mut server, &user).await;
+        server
+            .delete(&format!("/api/tokens/{}", read_token.token.token_id))
+            .await
+            .assert_status_ok();
+        server
+            .delete(&format!("/api/tokens/{}", read_token.token.token_id))
+            .await
+            .assert_status_not_found();
+
+        server.clear_cookies();
+        server.do_not_save_cookies();
+        server
+            .get(&format!("/api/tables/{table_id}/data"))
+            .add_header(name, value)
+            .await
+            .assert_status_unauthorized();
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn scoped_api_tokens(db: PgPool) -> anyhow::Result<()> {
+        let mut server = test_util::server(db.clone()).await;
+        let user = db::create_user(&db, "test".into(), "".into(), true).await?;
+        let mut table_ids = Vec::new();
+        for name in ["A", "B"] {
+            let table_id = db::create_table(
+                &db,
+                CreateTable {
+                    name: name.into(),
+                    description: "".into(),
+                    parent_id: None,
+                },
+            )
+            .await?
+            .table_id;
+            db::create_access(
+                &db,
+                Resource::Table,
+                table_id,
+                user.user_id,
+                AccessRole::Owner,
+            )
+            .await?;
+            table_ids.push(table_id);
+        }
+        let dashboard_id = db::create_dashboard(
+            &db,
+            CreateDashboard {
+                name: "A".into(),
+                description: "".into(),
+            },
+        )
+        .await?
+        .dashboard_id;
+        db::create_access(
+            &db,
+            Resource::Dashboard,
+            dashboard_id,
+            user.user_id,
+            AccessRole::Owner,
+        )
+        .await?;
+        let mut chart_ids = Vec::new();
+        for &table_id in &table_ids {
+            let chart_id = db::create_chart(
+                &db,
+                dashboard_id,
+                CreateChart {
+                    table_id,
+                    name: "A".into(),
+                    chart_kind: ChartKind::Table,
+                    top_n: None,
+                },
+            )
+            .await?
+            .chart_id;
+            chart_ids.push(chart_id);
+        }
+
+        test_util::login_session(&mut server, &user).await;
+        let token: CreatedApiToken = server
+            .post("/api/tokens")
+            .json(&json!({
+                "name": "ETL",
+                "permission": "ReadWrite",
+                "table_ids": [table_ids[0]],
+            }))
+            .await
+            .json();
+        let admin_token: CreatedApiToken = server
+            .post("/api/tokens")
+            .json(&json!({ "name": "Admin", "permission": "ReadWrite" }))
+            .await
+            .json();
+        server.clear_cookies();
+        server.do_not_save_cookies();
+
+        // Tokens cannot manage users, even without scopes.
+        let (admin_name, admin_value) = bearer(&admin_token);
+        server
+            .patch(&format!("/api/users/{}", user.user_id))
+            .add_header(admin_name.clone(), admin_value.clone())
+            .json(&json!({ "password": "reset" }))
+            .await
+            .assert_status_forbidden();
+        server
+            .get("/api/groups")
+            .add_header(admin_name, admin_value)
+            .await
+            .assert_status_forbidden();
+
+        let (name, value) = bearer(&token);
+
+        for path in [
+            format!("/api/users/{}", user.user_id),
+            "/api/groups".to_string(),
+            format!("/api/Table/{}/access", table_ids[0]),
+        ] {
+            server
+                .get(&path)
+                .add_header(name.clone(), value.clone())
+                .await
+                .assert_status_forbidden();
+        }
+        server
+            .patch(&format!("/api/users/{}", user.user_id))
+            .add_header(name.clone(), value.clone())
+            .json(&json!({ "password": "reset" }))
+            .await
+            .assert_status_forbidden();
+
+        let tables: Vec<GetTable> = server
+            .get("/api/tables")
+            .add_header(name.clone(), value.clone())
+            .await
+            .json();
+        assert_eq!(
+            tables
+                .iter()
+                .map(|table| table.table.table_id)
+                .collect_vec(),
+            [table_ids[0]]
+        );
+
+        let charts_path = format!("/api/dashboards/{dashboard_id}/charts");
+        for (table_id, allowed) in [(table_ids[0], true), (table_ids[1], false)] {
+            let response = server
+                .post(&charts_path)
+                .add_header(name.clone(), value.clone())
+                .json(&json!({ "table_id": table_id, "name": "B", "chart_kind": "Table" }))
+                .await;
+            if allowed {
+                response.assert_status_ok();
+            } else {
+                response.assert_status_forbidden();
+            }
+        }
+        server
+            .get(&format!("{charts_path}/{}/data", chart_ids[0]))
+            .add_header(name.clone(), value.clone())
+            .await
+            .assert_status_ok();
+        server
+            .get(&format!("{charts_path}/{}/data", chart_ids[1]))
+            .add_header(name.clone(), value.clone())
+            .await
+            .assert_status_forbidden();
+        server
+            .put(&format!("{charts_path}/{}/axes", chart_ids[0]))
+            .add_header(name.clone(), value.clone())
+            .json(&json!([{
+                "field_id": 1000,
+                "table_path": [table_ids[1]],
+                "axis_kind": "X",
+                "aggregate": null,
+            }]))
+            .await
+            .assert_status_forbidden();
+        server
+            .put(&format!("{charts_path}/{}/axes", chart_ids[0]))
+            .add_header(name.clone(), value.clone())
+            .text(" ".repeat(3 * 1024 * 1024))
+            .await
+            .assert_status_bad_request();
+        Ok(())
+    }
+}
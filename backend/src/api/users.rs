@@ -5,13 +5,19 @@
 //! front-end and received in the back-end to confirm identity.
 //! 
 //! Session cookies are signed using a private key.
+//!
+//! Users can also log in through an OpenID Connect identity provider if one is configured.
+//! These users are created on their first login.
 
 use crate::{
     AppState,
     auth::AppAuthSession,
     db,
     error::{ApiError, ApiResult, IntoAnyhow},
-    model::users::{CreateUser, Credentials, SelectUser, UpdateUser, UserResponse},
+    model::users::{
+        AuthCredentials, CreateUser, Credentials, OidcCallback, OidcLogin, OidcRequest, SelectUser,
+        UpdateUser, UserResponse,
+    },
 };
 use aide::{
     NoApi,
@@ -31,10 +37,19 @@ use tokio::task;
 const INVALID_CREDENTIALS: &str = "Invalid credentials";
 const ALREADY_LOGGED_IN: &str = "Already logged in";
 const USERNAME_IS_TAKEN: &str = "Username is taken";
+const INVALID_OIDC_STATE: &str = "Invalid login state";
+
+/// Session key of the pending OpenID Connect authorization request.
+const OIDC_REQUEST_KEY: &str = "oidc_request";
 
 pub fn router() -> ApiRouter<AppState> {
     ApiRouter::new()
         .api_route("/login", post_with(login, docs::login))
+        .api_route("/login/oidc", get_with(login_oidc, docs::login_oidc))
+        .api_route(
+            "/login/oidc/callback",
+            post_with(login_oidc_callback, docs::login_oidc_callback),
+        )
         .api_route("/logout", get_with(logout, docs::logout))
         .api_route("/user", get_with(get_auth_user, docs::get_auth_user))
         .nest(
@@ -61,7 +76,58 @@ async fn login(
         return Err(ApiError::BadRequest(ALREADY_LOGGED_IN.into()));
     }
     let user = session
-        .authenticate(creds)
+        .authenticate(creds.into())
+        .await
+        .anyhow()?
+        .ok_or(ApiError::UnprocessableEntity(INVALID_CREDENTIALS.into()))?;
+
+    session.login(&user).await.anyhow()?;
+
+    Ok(Json(UserResponse {
+        user_id: user.user_id,
+        username: user.username,
+        is_admin: user.is_admin,
+    }))
+}
+
+async fn login_oidc(session: AppAuthSession) -> ApiResult<Json<OidcLogin>> {
+    if session.user.is_some() {
+        return Err(ApiError::BadRequest(ALREADY_LOGGED_IN.into()));
+    }
+    let oidc = session.backend.oidc().ok_or(ApiError::NotFound)?;
+
+    let (authorization_url, request) = oidc.authorize().await?;
+    session
+        .session
+        .insert(OIDC_REQUEST_KEY, request)
+        .await
+        .anyhow()?;
+
+    Ok(Json(OidcLogin {
+        authorization_url: authorization_url.into(),
+    }))
+}
+
+async fn login_oidc_callback(
+    mut session: AppAuthSession,
+    Form(OidcCallback { code, state }): Form<OidcCallback>,
+) -> ApiResult<Json<UserResponse>> {
+    if session.user.is_some() {
+        return Err(ApiError::BadRequest(ALREADY_LOGGED_IN.into()));
+    }
+    if session.backend.oidc().is_none() {
+        return Err(ApiError::NotFound);
+    }
+    let request = session
+        .session
+        .remove::<OidcRequest>(OIDC_REQUEST_KEY)
+        .await
+        .anyhow()?
+        .filter(|request| request.state == state)
+        .ok_or(ApiError::BadRequest(INVALID_OIDC_STATE.into()))?;
+
+    let user = session
+        .authenticate(AuthCredentials::Oidc { code, request })
         .await
         .anyhow()?
         .ok_or(ApiError::UnprocessableEntity(INVALID_CREDENTIALS.into()))?;
@@ -203,9 +269,9 @@ async fn get_all_users(
 #[cfg_attr(coverage_nightly, coverage(off))]
 mod docs {
     use crate::{
-        api::users::INVALID_CREDENTIALS,
+        api::users::{INVALID_CREDENTIALS, INVALID_OIDC_STATE},
         docs::{AUTHENTICATION_TAG, TransformOperationExt, USERS_TAG, template},
-        model::users::UserResponse,
+        model::users::{OidcLogin, UserResponse},
     };
     use aide::{OperationOutput, transform::TransformOperation};
     use axum::Json;
@@ -231,6 +297,38 @@ mod docs {
         .response_description::<422, String>(INVALID_CREDENTIALS)
     }
 
+    pub fn login_oidc(op: TransformOperation) -> TransformOperation {
+        template::<Json<OidcLogin>>(
+            op,
+            "login_oidc",
+            "Start a login through the OpenID Connect identity provider. \
+            The user is redirected to the returned URL, \
+            then back to the front-end which calls `login_oidc_callback`.",
+            false,
+            AUTHENTICATION_TAG,
+        )
+        .response_description::<400, ()>("User is already authenticated")
+        .response_description::<404, ()>("Single sign-on is not configured")
+    }
+
+    pub fn login_oidc_callback(op: TransformOperation) -> TransformOperation {
+        template::<Json<UserResponse>>(
+            op,
+            "login_oidc_callback",
+            "Login the user from the authorization code of the identity provider. \
+            The user is created on their first login \
+            and is an admin if they are in the configured admin group.",
+            false,
+            AUTHENTICATION_TAG,
+        )
+        .response_description::<400, ()>(&format!(
+            "User is already authenticated\n\n{INVALID_OIDC_STATE}"
+        ))
+        .response_description::<404, ()>("Single sign-on is not configured")
+        .response_description::<409, ()>("Username is taken")
+        .response_description::<422, String>(INVALID_CREDENTIALS)
+    }
+
     pub fn logout(op: TransformOperation) -> TransformOperation {
         template::<()>(
             op,
@@ -293,12 +391,17 @@ mod docs {
 mod test {
     use crate::{
         db,
-        model::users::{CreateUser, Credentials, SelectUser, UpdateUser, UserResponse},
+        model::users::{
+            CreateUser, Credentials, OidcCallback, OidcLogin, SelectUser, UpdateUser, UserResponse,
+        },
         test_util,
     };
+    use axum_test::TestServer;
     use password_auth::generate_hash;
     use serde_json::json;
     use sqlx::PgPool;
+    use std::collections::HashMap;
+    use url::Url;
 
     #[sqlx::test]
     async fn login(db: PgPool) -> anyhow::Result<()> {
@@ -354,6 +457,119 @@ mod test {
         Ok(())
     }
 
+    #[sqlx::test]
+    async fn login_oidc(db: PgPool) -> anyhow::Result<()> {
+        let server = test_util::server(db.clone()).await;
+        server
+            .get("/api/login/oidc")
+            .await
+            .assert_status_not_found();
+
+        let oidc = test_util::mock_oidc_provider("jane", &["staff", "admins"]).await;
+        let mut server = test_util::oidc_server(db.clone(), Some(oidc)).await;
+        server.save_cookies();
+
+        let callback = |code: &str, state: &str| OidcCallback {
+            code: code.into(),
+            state: state.into(),
+        };
+        let start_login = async |server: &TestServer| {
+            let OidcLogin { authorization_url } = server.get("/api/login/oidc").await.json();
+            let params: HashMap<_, _> = Url::parse(&authorization_url)
+                .unwrap()
+                .query_pairs()
+                .into_owned()
+                .collect();
+            assert!(params.contains_key("code_challenge"));
+            (params["nonce"].clone(), params["state"].clone())
+        };
+
+        server
+            .post("/api/login/oidc/callback")
+            .form(&callback("", ""))
+            .await
+            .assert_status_bad_request();
+
+        let (nonce, state) = start_login(&server).await;
+        server
+            .post("/api/login/oidc/callback")
+            .form(&callback(&nonce, "wrong"))
+            .await
+            .assert_status_bad_request();
+
+        let (_, state_2) = start_login(&server).await;
+        assert_ne!(state, state_2);
+        server
+            .post("/api/login/oidc/callback")
+            .form(&callback("invalid", &state_2))
+            .await
+            .assert_status_unprocessable_entity();
+
+        let (_, state) = start_login(&server).await;
+        server
+            .post("/api/login/oidc/callback")
+            .form(&callback("wrong nonce", &state))
+            .await
+            .assert_status_unprocessable_entity();
+
+        let (nonce, state) = start_login(&server).await;
+        let user: UserResponse = server
+            .post("/api/login/oidc/callback")
+            .form(&callback(&nonce, &state))
+            .await
+            .json();
+        assert_eq!(user.username, "jane");
+        assert!(user.is_admin);
+        server.get("/api/user").await.assert_json(&user);
+        server.get("/api/logout").await.assert_status_ok();
+
+        db::update_user(&db, user.user_id, None, None, Some(false)).await?;
+        let (nonce, state) = start_login(&server).await;
+        let user_2: UserResponse = server
+            .post("/api/login/oidc/callback")
+            .form(&callback(&nonce, &state))
+            .await
+            .json();
+        assert_eq!(user_2, user);
+        assert_eq!(db::get_all_users(&db).await?, vec![user.clone()]);
+        let password_hash = db::get_user_by_id(&db, user.user_id)
+            .await?
+            .unwrap()
+            .password_hash;
+        assert!(!password_hash.is_empty());
+
+        server
+            .post("/api/login")
+            .form(&Credentials {
+                username: "jane".into(),
+                password: "".into(),
+            })
+            .await
+            .assert_status_bad_request();
+        server.get("/api/logout").await.assert_status_ok();
+        server
+            .post("/api/login")
+            .form(&Credentials {
+                username: "jane".into(),
+                password: "".into(),
+            })
+            .await
+            .assert_status_unprocessable_entity();
+
+        let oidc = test_util::mock_oidc_provider("jane", &[]).await;
+        let mut server = test_util::oidc_server(db.clone(), Some(oidc)).await;
+        server.save_cookies();
+        let (nonce, state) = start_login(&server).await;
+        let user: UserResponse = server
+            .post("/api/login/oidc/callback")
+            .form(&callback(&nonce, &state))
+            .await
+            .json();
+        assert!(!user.is_admin);
+
+        Ok(())
+    }
+
     #[sqlx::test]
     async fn logout(db: PgPool) -> anyhow::Result<()> {
         let mut server = test_util::server(db.clone()).await;
@@ -1,7 +1,7 @@
 //! Route for managing chart axes.
 
 use crate::{
-    AppState,
+    AppState, Id,
     api::NO_DATA_IN_REQUEST_BODY,
     auth::AppAuthSession,
     db::{self},
@@ -9,7 +9,7 @@ use crate::{
     model::{
         access::{AccessRole, AccessRoleCheck, Resource},
         data::FieldKind,
-        viz::{Aggregate, Axis, SelectChart, SetAxes},
+        viz::{Aggregate, Axis, AxisKind, ChartKind, CreateAxis, SelectChart, SetAxes, TimeBucket},
     },
 };
 use aide::{
@@ -25,7 +25,26 @@ use itertools::Itertools;
 use std::collections::HashMap;
 
 const FIELD_NOT_FOUND: &str = "Field not found";
+const AXIS_AGGREGATE_REQUIRED: &str =
+    "Axis aggregate is required for the values of this chart kind when other axes are grouped";
+const INVALID_AXIS_BINS: &str = "Axis bins must be positive and are only valid and required \
+    for the X axis of histograms on numeric fields without aggregate";
+const INVALID_AXIS_COUNT: &str = "Number of axes of this kind is invalid for this chart kind";
 const INVALID_AXIS_AGGREGATE: &str = "Axis aggregate is invalid for this field";
+const INVALID_AXIS_BUCKET: &str = "Axis bucket is only valid for DateTime fields";
+const INVALID_TIME_ZONE: &str = "Axis bucket time zone is invalid";
+const INVALID_AXIS_FIELD: &str = "Axis field cannot be a relation to many entries";
+const INVALID_AXIS_ORDER_FIELD: &str =
+    "Axis order field must be a DateTime field and is only valid for First and Last";
+const INVALID_AXIS_PERCENTILE: &str =
+    "Axis percentile must be between 0 and 100 and is only valid for Percentile";
+const INVALID_SIZE_AXIS: &str = "Size axis must be numeric or counted";
+const INVALID_TABLE_PATH: &str = "Axis table path must join parent and child tables \
+    from the chart table to the table of the field";
+const INVALID_CHILD_JOINS: &str =
+    "Axis table paths cannot join child tables of more than one line of descent";
+const INVALID_JOINED_AGGREGATE: &str = "Axis aggregate must be on the deepest joined child table, \
+    whose rows are not repeated by the joins";
 
 pub fn router() -> ApiRouter<AppState> {
     ApiRouter::new().nest(
@@ -59,30 +78,58 @@ async fn set_axes(
 
     let table_id = db::get_chart_table_id(tx.as_mut(), chart_id).await?;
 
-    let field_kinds: HashMap<_, _> = db::get_fields_metadata(tx.as_mut(), table_id)
-        .await?
+    let mut table_parent_ids = HashMap::from([(
+        table_id,
+        db::get_table_parent_id(tx.as_mut(), table_id).await?,
+    )]);
+    for path_table_id in axes
+        .iter()
+        .flat_map(|axis| axis.table_path.iter().copied())
+        .unique()
+        .collect_vec()
+    {
+        db::get_access_role(tx.as_mut(), Resource::Table, path_table_id, user_id)
+            .await?
+            .check(AccessRole::Viewer)?;
+        let parent_id = db::get_table_parent_id(tx.as_mut(), path_table_id).await?;
+        table_parent_ids.insert(path_table_id, parent_id);
+    }
+
+    let fields = db::get_axes_fields(tx.as_mut(), table_id, &axes).await?;
+    let field_table_ids: HashMap<_, _> = fields
+        .iter()
+        .map(|field| (field.field_id, field.table_id))
+        .collect();
+    let field_kinds: HashMap<_, _> = fields
         .into_iter()
         .map(|field| (field.field_id, field.field_kind.0))
         .collect();
 
-    let axes = axes
-        .into_iter()
-        .map(|axis| {
-            let field_kind =
-                &field_kinds
-                    .get(&axis.field_id)
-                    .ok_or(ApiError::UnprocessableEntity(format!(
-                        "{}: {FIELD_NOT_FOUND}",
-                        axis.field_id,
-                    )))?;
-            if let Some(aggregate) = &axis.aggregate {
-                validate_axis(aggregate, field_kind).map_err(|message| {
-                    ApiError::UnprocessableEntity(format!("{}: {message}", axis.field_id,))
-                })?;
-            }
-            ApiResult::Ok(axis)
-        })
-        .try_collect()?;
+    let chart_kind = db::get_chart_kind(tx.as_mut(), chart_id).await?;
+    let mut error_messages = validate_chart_axes(&chart_kind, &axes, &field_kinds)
+        .err()
+        .unwrap_or_default();
+    let mut table_path_messages = Vec::new();
+    for axis in &axes {
+        if let Err(message) =
+            validate_table_path(axis, table_id, &table_parent_ids, &field_table_ids)
+        {
+            table_path_messages.push(format!("{}: {message}", axis.field_id));
+        }
+        if let Some(bucket) = &axis.bucket
+            && !db::time_zone_exists(tx.as_mut(), &bucket.time_zone).await?
+        {
+            error_messages.push(format!("{}: {INVALID_TIME_ZONE}", axis.field_id));
+        }
+    }
+    if table_path_messages.is_empty() {
+        table_path_messages =
+            validate_joined_aggregates(&axes, table_id, &table_parent_ids, &field_table_ids);
+    }
+    error_messages.extend(table_path_messages);
+    if !error_messages.is_empty() {
+        return Err(ApiError::UnprocessableEntity(error_messages.join(", ")));
+    }
 
     let axes = db::set_axes(tx.as_mut(), chart_id, table_id, axes).await?;
 
@@ -90,16 +137,30 @@ async fn set_axes(
     Ok(Json(axes))
 }
 
-/// Validate that the axis aggregate and field_kind are compatible
-fn validate_axis(aggregate: &Aggregate, field_kind: &FieldKind) -> Result<(), &'static str> {
+/// Validate that the axis aggregate and bucket are compatible with field_kind
+fn validate_axis(
+    aggregate: Option<&Aggregate>,
+    bucket: Option<&TimeBucket>,
+    field_kind: &FieldKind,
+) -> Result<(), &'static str> {
+    if bucket.is_some() && !matches!(field_kind, FieldKind::DateTime { .. }) {
+        return Err(INVALID_AXIS_BUCKET);
+    }
+    let Some(aggregate) = aggregate else {
+        return Ok(());
+    };
     match (aggregate, field_kind) {
-        (Aggregate::Count, _)
+        (Aggregate::Count | Aggregate::CountDistinct | Aggregate::First | Aggregate::Last, _)
         | (
             Aggregate::Sum,
             FieldKind::Integer { .. } | FieldKind::Float { .. } | FieldKind::Money { .. },
         )
         | (
-            Aggregate::Average,
+            Aggregate::Average
+            | Aggregate::Median
+            | Aggregate::Percentile
+            | Aggregate::StdDev
+            | Aggregate::Variance,
             FieldKind::Integer { .. }
             | FieldKind::Float { .. }
             | FieldKind::Money { .. }
@@ -118,12 +179,241 @@ fn validate_axis(aggregate: &Aggregate, field_kind: &FieldKind) -> Result<(), &'
     }
 }
 
+/// Validate these axes against their fields and the chart kind.
+/// Return the messages of every violated rule, prefixed by the field ID of the axis
+/// or by the axis kind for the number of axes.
+pub(super) fn validate_chart_axes(
+    chart_kind: &ChartKind,
+    axes: &[CreateAxis],
+    field_kinds: &HashMap<Id, FieldKind>,
+) -> Result<(), Vec<String>> {
+    let mut error_messages = Vec::new();
+
+    for axis_kind in [
+        AxisKind::X,
+        AxisKind::Y,
+        AxisKind::Color,
+        AxisKind::Size,
+        AxisKind::Tooltip,
+        AxisKind::Label,
+        AxisKind::Detail,
+    ] {
+        let count = axes.iter().filter(|a| a.axis_kind == axis_kind).count();
+        if !chart_kind.axis_count(axis_kind).contains(&count) {
+            error_messages.push(format!("{axis_kind:?}: {INVALID_AXIS_COUNT}"));
+        }
+    }
+
+    let value_axis_kind = chart_kind.value_axis_kind();
+    let is_grouped = axes
+        .iter()
+        .any(|a| a.aggregate.is_none() && Some(a.axis_kind) != value_axis_kind);
+    for axis in axes {
+        let Some(field_kind) = field_kinds.get(&axis.field_id) else {
+            error_messages.push(format!("{}: {FIELD_NOT_FOUND}", axis.field_id));
+            continue;
+        };
+        let mut messages = Vec::new();
+        if field_kind.is_multiple_relation() {
+            messages.push(INVALID_AXIS_FIELD);
+        }
+        let field_kind = field_kind.value_kind();
+        messages.extend(
+            validate_axis(axis.aggregate.as_ref(), axis.bucket.as_ref(), &field_kind).err(),
+        );
+        messages.extend(validate_axis_options(axis, field_kinds).err());
+        messages.extend(validate_chart_axis(
+            chart_kind,
+            axis,
+            &field_kind,
+            is_grouped,
+        ));
+        error_messages.extend(
+            messages
+                .into_iter()
+                .map(|message| format!("{}: {message}", axis.field_id)),
+        );
+    }
+
+    if error_messages.is_empty() {
+        Ok(())
+    } else {
+        Err(error_messages)
+    }
+}
+
+/// Validate the rules of the chart kind for a single axis.
+/// The values of a chart must be aggregated when other axes are grouped, and all the axes
+/// of a `Kpi` must be aggregated.
+fn validate_chart_axis(
+    chart_kind: &ChartKind,
+    axis: &CreateAxis,
+    field_kind: &FieldKind,
+    is_grouped: bool,
+) -> Vec<&'static str> {
+    let mut messages = Vec::new();
+
+    let is_numeric = matches!(
+        field_kind,
+        FieldKind::Integer { .. }
+            | FieldKind::Float { .. }
+            | FieldKind::Money { .. }
+            | FieldKind::Progress { .. }
+    );
+    let is_histogram_x = *chart_kind == ChartKind::Histogram && axis.axis_kind == AxisKind::X;
+    match axis.bins {
+        Some(1..) if is_histogram_x && is_numeric && axis.aggregate.is_none() => {}
+        None if !is_histogram_x => {}
+        _ => messages.push(INVALID_AXIS_BINS),
+    }
+
+    let is_value = chart_kind.value_axis_kind() == Some(axis.axis_kind);
+    if axis.aggregate.is_none() && (*chart_kind == ChartKind::Kpi || is_value && is_grouped) {
+        messages.push(AXIS_AGGREGATE_REQUIRED);
+    }
+
+    let is_count = matches!(
+        axis.aggregate,
+        Some(Aggregate::Count | Aggregate::CountDistinct)
+    );
+    if axis.axis_kind == AxisKind::Size && !is_numeric && !is_count {
+        messages.push(INVALID_SIZE_AXIS);
+    }
+
+    messages
+}
+
+/// Validate that the table path of the axis joins parent and child tables from the chart table,
+/// without repeating a table, up to the table of the axis field and of its order field.
+fn validate_table_path(
+    axis: &CreateAxis,
+    table_id: Id,
+    table_parent_ids: &HashMap<Id, Option<Id>>,
+    field_table_ids: &HashMap<Id, Id>,
+) -> Result<(), &'static str> {
+    let mut previous_id = table_id;
+    for (i, &next_id) in axis.table_path.iter().enumerate() {
+        let is_child = table_parent_ids.get(&next_id) == Some(&Some(previous_id));
+        let is_parent = table_parent_ids.get(&previous_id) == Some(&Some(next_id));
+        let is_repeated = next_id == table_id || axis.table_path[..i].contains(&next_id);
+        if is_repeated || !(is_child || is_parent) {
+            return Err(INVALID_TABLE_PATH);
+        }
+        previous_id = next_id;
+    }
+
+    let is_in_last_table = |field_id| {
+        field_table_ids
+            .get(&field_id)
+            .is_none_or(|field_table_id| *field_table_id == previous_id)
+    };
+    if !is_in_last_table(axis.field_id) || !axis.order_field_id.is_none_or(is_in_last_table) {
+        return Err(INVALID_TABLE_PATH);
+    }
+
+    Ok(())
+}
+
+/// Validate that joining child tables does not repeat the rows aggregated by the axes.
+/// A table joined from its parent table repeats the rows of the other tables for each of
+/// its entries, so the tables joined from their parent must be in a single line of descent
+/// and the aggregated axes must be on the deepest of them.
+/// Return the messages of every violated rule, prefixed by the field ID of the axis.
+fn validate_joined_aggregates(
+    axes: &[CreateAxis],
+    table_id: Id,
+    table_parent_ids: &HashMap<Id, Option<Id>>,
+    field_table_ids: &HashMap<Id, Id>,
+) -> Vec<String> {
+    let mut child_ids = Vec::new();
+    for axis in axes {
+        let mut previous_id = table_id;
+        for &next_id in &axis.table_path {
+            if table_parent_ids.get(&next_id) == Some(&Some(previous_id)) {
+                child_ids.push((next_id, axis.field_id));
+            }
+            previous_id = next_id;
+        }
+    }
+    if child_ids.is_empty() {
+        return Vec::new();
+    }
+
+    let is_ancestor = |ancestor_id: Id, mut table_id: Id| {
+        while table_id != ancestor_id {
+            match table_parent_ids.get(&table_id) {
+                Some(Some(parent_id)) => table_id = *parent_id,
+                _ => return false,
+            }
+        }
+        true
+    };
+    let Some(deepest_id) = child_ids
+        .iter()
+        .map(|(child_id, _)| *child_id)
+        .find(|&child_id| {
+            child_ids
+                .iter()
+                .all(|(other_id, _)| is_ancestor(*other_id, child_id))
+        })
+    else {
+        return child_ids
+            .into_iter()
+            .map(|(_, field_id)| field_id)
+            .unique()
+            .map(|field_id| format!("{field_id}: {INVALID_CHILD_JOINS}"))
+            .collect();
+    };
+
+    axes.iter()
+        .filter(|axis| {
+            axis.aggregate.is_some()
+                && field_table_ids
+                    .get(&axis.field_id)
+                    .is_some_and(|field_table_id| *field_table_id != deepest_id)
+        })
+        .map(|axis| format!("{}: {INVALID_JOINED_AGGREGATE}", axis.field_id))
+        .collect()
+}
+
+/// Validate the parameters of the axis aggregate.
+/// `First` and `Last` must be ordered by a `DateTime` field of the same table.
+fn validate_axis_options(
+    axis: &CreateAxis,
+    field_kinds: &HashMap<Id, FieldKind>,
+) -> Result<(), &'static str> {
+    let is_percentile = axis.aggregate == Some(Aggregate::Percentile);
+    match axis.percentile {
+        Some(0..=100) if is_percentile => {}
+        None if !is_percentile => {}
+        _ => return Err(INVALID_AXIS_PERCENTILE),
+    }
+
+    let is_ordered = matches!(axis.aggregate, Some(Aggregate::First | Aggregate::Last));
+    match axis
+        .order_field_id
+        .map(|field_id| field_kinds.get(&field_id))
+    {
+        Some(Some(field_kind))
+            if is_ordered && matches!(*field_kind.value_kind(), FieldKind::DateTime { .. }) => {}
+        None if !is_ordered => {}
+        _ => return Err(INVALID_AXIS_ORDER_FIELD),
+    }
+
+    Ok(())
+}
+
 #[cfg_attr(coverage_nightly, coverage(off))]
 mod docs {
     use crate::{
         api::{
             NO_DATA_IN_REQUEST_BODY,
-            viz::axes::{FIELD_NOT_FOUND, INVALID_AXIS_AGGREGATE},
+            viz::axes::{
+                AXIS_AGGREGATE_REQUIRED, FIELD_NOT_FOUND, INVALID_AXIS_AGGREGATE,
+                INVALID_AXIS_BINS, INVALID_AXIS_BUCKET, INVALID_AXIS_COUNT, INVALID_AXIS_FIELD,
+                INVALID_AXIS_ORDER_FIELD, INVALID_AXIS_PERCENTILE, INVALID_CHILD_JOINS,
+                INVALID_JOINED_AGGREGATE, INVALID_SIZE_AXIS, INVALID_TABLE_PATH, INVALID_TIME_ZONE,
+            },
         },
         docs::{AXES_TAG, TransformOperationExt, template},
         model::{
@@ -135,8 +425,10 @@ mod docs {
     use axum::Json;
     use itertools::Itertools;
 
-    const DASHBOARD_EDITOR: [(Resource, AccessRole); 1] =
-        [(Resource::Dashboard, AccessRole::Editor)];
+    const DASHBOARD_EDITOR_TABLE_VIEWER: [(Resource, AccessRole); 2] = [
+        (Resource::Dashboard, AccessRole::Editor),
+        (Resource::Table, AccessRole::Viewer),
+    ];
 
     fn axes<'a, R: OperationOutput>(
         op: TransformOperation<'a>,
@@ -147,20 +439,47 @@ mod docs {
     }
 
     pub fn set_axes(op: TransformOperation) -> TransformOperation {
-        let errors = [FIELD_NOT_FOUND, INVALID_AXIS_AGGREGATE]
-            .into_iter()
-            .map(|v| format!("<field_id> : {v}"))
-            .join("\n\n");
+        let errors = [
+            AXIS_AGGREGATE_REQUIRED,
+            FIELD_NOT_FOUND,
+            INVALID_AXIS_AGGREGATE,
+            INVALID_AXIS_BINS,
+            INVALID_AXIS_BUCKET,
+            INVALID_AXIS_FIELD,
+            INVALID_AXIS_ORDER_FIELD,
+            INVALID_AXIS_PERCENTILE,
+            INVALID_SIZE_AXIS,
+            INVALID_TABLE_PATH,
+            INVALID_CHILD_JOINS,
+            INVALID_JOINED_AGGREGATE,
+            INVALID_TIME_ZONE,
+        ]
+        .into_iter()
+        .map(|v| format!("<field_id> : {v}"))
+        .chain([format!("<axis_kind> : {INVALID_AXIS_COUNT}")])
+        .join("\n\n");
 
         axes::<Json<Vec<Axis>>>(
             op,
             "set_axes",
-            "Set all the axes of the specified chart and rebuild the dynamic view.",
+            "Set all the axes of the specified chart and rebuild the dynamic view. \
+            The number of axes of each kind must fit the chart kind, \
+            the values must be aggregated when other axes are grouped \
+            and the size must be numeric. \
+            Every violated rule is listed in the error. \
+            The timestamps of DateTime axes can be truncated to time buckets in a time zone \
+            and the X axis of a histogram is split into equal-width bins. \
+            Axes can select fields of the ancestor and descendant tables of the chart table \
+            by joining the tables of their table path along their parent. \
+            The joined child tables must be in a single line of descent \
+            and only the fields of the deepest one can be aggregated.",
         )
         .response_description::<40, String>(NO_DATA_IN_REQUEST_BODY)
-        .response_description::<404, ()>("Dashboard not found\n\nChart not found")
+        .response_description::<404, ()>(
+            "Dashboard not found\n\nChart not found\n\nTable not found",
+        )
         .response_description::<422, String>(&errors)
-        .required_access(DASHBOARD_EDITOR)
+        .required_access(DASHBOARD_EDITOR_TABLE_VIEWER)
     }
 }
 
@@ -173,14 +492,14 @@ mod test {
     use sqlx::PgPool;
 
     use crate::{
-        db,
+        Id, db,
         model::{
             Cell,
             access::{AccessRole, Resource},
-            data::{CreateField, CreateTable, FieldKind, FieldMetadata},
+            data::{CreateField, CreateTable, FieldKind, FieldMetadata, RelationDeleteRule},
             viz::{
                 Aggregate, Axis, AxisKind, ChartKind, CreateAxis, CreateChart, CreateDashboard,
-                SetAxes,
+                SetAxes, TimeBucket, TimeUnit,
             },
         },
         test_util,
@@ -259,6 +578,7 @@ mod test {
                 table_id,
                 name: "Test".into(),
                 chart_kind: ChartKind::Bar,
+                top_n: None,
             },
         )
         .await?
@@ -266,11 +586,31 @@ mod test {
 
         let path = format!("/api/dashboards/{dashboard_id}/charts/{chart_id}/axes");
 
-        let set_axes = SetAxes(vec![CreateAxis {
-            field_id: text_field.field_id,
-            axis_kind: AxisKind::X,
-            aggregate: None,
-        }]);
+        let count_axis = CreateAxis {
+            field_id: integer_field.field_id,
+            table_path: Vec::new(),
+            axis_kind: AxisKind::Y,
+            aggregate: Some(Aggregate::Count),
+            percentile: None,
+            order_field_id: None,
+            sort: None,
+            bins: None,
+            bucket: None,
+        };
+        let set_axes = SetAxes(vec![
+            CreateAxis {
+                field_id: text_field.field_id,
+                table_path: Vec::new(),
+                axis_kind: AxisKind::X,
+                aggregate: None,
+                percentile: None,
+                order_field_id: None,
+                sort: None,
+                bins: None,
+                bucket: None,
+            },
+            count_axis.clone(),
+        ]);
 
         server
             .put(&path)
@@ -303,13 +643,25 @@ mod test {
 
         let create_group_axis = CreateAxis {
             field_id: text_field.field_id,
+            table_path: Vec::new(),
             axis_kind: AxisKind::X,
             aggregate: None,
+            percentile: None,
+            order_field_id: None,
+            sort: None,
+            bins: None,
+            bucket: None,
         };
         let create_max_axis = CreateAxis {
             field_id: integer_field.field_id,
+            table_path: Vec::new(),
             axis_kind: AxisKind::Y,
             aggregate: Some(Aggregate::Max),
+            percentile: None,
+            order_field_id: None,
+            sort: None,
+            bins: None,
+            bucket: None,
         };
         let set_axes = SetAxes(vec![create_group_axis.clone(), create_max_axis.clone()]);
 
@@ -322,8 +674,8 @@ mod test {
             .collect_tuple()
             .unwrap();
         for (create_axis, axis_1) in [
-            (create_group_axis, group_axis_1),
-            (create_max_axis, max_axis_1),
+            (create_group_axis.clone(), group_axis_1),
+            (create_max_axis.clone(), max_axis_1),
         ] {
             assert_eq!(create_axis.field_id, axis_1.field_id);
             assert_eq!(create_axis.axis_kind, axis_1.axis_kind);
@@ -344,8 +696,14 @@ mod test {
 
         let wrong_field_id = SetAxes(vec![CreateAxis {
             field_id: 1000,
+            table_path: Vec::new(),
             axis_kind: AxisKind::X,
             aggregate: None,
+            percentile: None,
+            order_field_id: None,
+            sort: None,
+            bins: None,
+            bucket: None,
         }]);
         server
             .put(&path)
@@ -355,8 +713,14 @@ mod test {
 
         let invalid_aggregate = SetAxes(vec![CreateAxis {
             field_id: text_field.field_id,
+            table_path: Vec::new(),
             axis_kind: AxisKind::X,
             aggregate: Some(Aggregate::Average),
+            percentile: None,
+            order_field_id: None,
+            sort: None,
+            bins: None,
+            bucket: None,
         }]);
         server
             .put(&path)
@@ -364,6 +728,163 @@ mod test {
             .await
             .assert_status_unprocessable_entity();
 
+        let invalid_percentile = SetAxes(vec![CreateAxis {
+            field_id: integer_field.field_id,
+            table_path: Vec::new(),
+            axis_kind: AxisKind::Y,
+            aggregate: Some(Aggregate::Percentile),
+            percentile: Some(101),
+            order_field_id: None,
+            sort: None,
+            bins: None,
+            bucket: None,
+        }]);
+        server
+            .put(&path)
+            .json(&invalid_percentile)
+            .await
+            .assert_status_unprocessable_entity();
+
+        let invalid_order_field = SetAxes(vec![CreateAxis {
+            field_id: integer_field.field_id,
+            table_path: Vec::new(),
+            axis_kind: AxisKind::Y,
+            aggregate: Some(Aggregate::Last),
+            percentile: None,
+            order_field_id: Some(text_field.field_id),
+            sort: None,
+            bins: None,
+            bucket: None,
+        }]);
+        server
+            .put(&path)
+            .json(&invalid_order_field)
+            .await
+            .assert_status_unprocessable_entity();
+
+        let invalid_bucket = SetAxes(vec![CreateAxis {
+            field_id: text_field.field_id,
+            table_path: Vec::new(),
+            axis_kind: AxisKind::X,
+            aggregate: None,
+            percentile: None,
+            order_field_id: None,
+            sort: None,
+            bins: None,
+            bucket: Some(TimeBucket {
+                unit: TimeUnit::Month,
+                time_zone: "UTC".into(),
+            }),
+        }]);
+        server
+            .put(&path)
+            .json(&invalid_bucket)
+            .await
+            .assert_status_unprocessable_entity();
+
+        let date_time_field_id = db::create_field(
+            &db,
+            table_id,
+            CreateField {
+                name: "DateTime".into(),
+                field_kind: FieldKind::DateTime {
+                    is_required: false,
+                    range_start: None,
+                    range_end: None,
+                },
+            },
+        )
+        .await?
+        .field_id;
+        let invalid_time_zone = SetAxes(vec![
+            CreateAxis {
+                field_id: date_time_field_id,
+                table_path: Vec::new(),
+                axis_kind: AxisKind::X,
+                aggregate: None,
+                percentile: None,
+                order_field_id: None,
+                sort: None,
+                bins: None,
+                bucket: Some(TimeBucket {
+                    unit: TimeUnit::Month,
+                    time_zone: "Nowhere/Invalid".into(),
+                }),
+            },
+            count_axis.clone(),
+        ]);
+        server
+            .put(&path)
+            .json(&invalid_time_zone)
+            .await
+            .assert_status_unprocessable_entity();
+
+        let relation_field_id = db::create_field(
+            &db,
+            table_id,
+            CreateField {
+                name: "Relation".into(),
+                field_kind: FieldKind::Relation {
+                    is_required: false,
+                    target_table_id: table_id,
+                    multiple: true,
+                    on_delete: RelationDeleteRule::Unlink,
+                },
+            },
+        )
+        .await?
+        .field_id;
+        let multiple_relation = SetAxes(vec![CreateAxis {
+            field_id: relation_field_id,
+            table_path: Vec::new(),
+            axis_kind: AxisKind::X,
+            aggregate: Some(Aggregate::Count),
+            percentile: None,
+            order_field_id: None,
+            sort: None,
+            bins: None,
+            bucket: None,
+        }]);
+        server
+            .put(&path)
+            .json(&multiple_relation)
+            .await
+            .assert_status_unprocessable_entity();
+
+        let missing_table = SetAxes(vec![
+            create_group_axis.clone(),
+            CreateAxis {
+                table_path: vec![1000],
+                ..create_max_axis.clone()
+            },
+        ]);
+        server
+            .put(&path)
+            .json(&missing_table)
+            .await
+            .assert_status_not_found();
+
+        let ungrouped_values = SetAxes(vec![
+            create_group_axis.clone(),
+            CreateAxis {
+                aggregate: None,
+                ..create_max_axis.clone()
+            },
+            CreateAxis {
+                axis_kind: AxisKind::Size,
+                ..create_max_axis.clone()
+            },
+        ]);
+        let response = server.put(&path).json(&ungrouped_values).await;
+        response.assert_status_unprocessable_entity();
+        let error_message = response.text();
+        assert!(error_message.contains("Size"));
+        assert!(error_message.contains(&format!(
+            "{}: {}",
+            integer_field.field_id,
+            super::AXIS_AGGREGATE_REQUIRED
+        )));
+
         Ok(())
     }
 
@@ -459,10 +980,385 @@ mod test {
                 [&web_link, &checkbox, &enumeration].iter(),
                 false,
             ),
+            (
+                Aggregate::CountDistinct,
+                [
+                    &text,
+                    &integer,
+                    &float,
+                    &money,
+                    &date_time,
+                    &progress,
+                    &web_link,
+                    &checkbox,
+                    &enumeration,
+                ]
+                .iter(),
+                true,
+            ),
+            (
+                Aggregate::First,
+                [
+                    &text,
+                    &integer,
+                    &float,
+                    &money,
+                    &date_time,
+                    &progress,
+                    &web_link,
+                    &checkbox,
+                    &enumeration,
+                ]
+                .iter(),
+                true,
+            ),
+            (
+                Aggregate::Last,
+                [
+                    &text,
+                    &integer,
+                    &float,
+                    &money,
+                    &date_time,
+                    &progress,
+                    &web_link,
+                    &checkbox,
+                    &enumeration,
+                ]
+                .iter(),
+                true,
+            ),
+            (
+                Aggregate::Median,
+                [&integer, &float, &money, &progress].iter(),
+                true,
+            ),
+            (
+                Aggregate::Median,
+                [&text, &date_time, &web_link, &checkbox, &enumeration].iter(),
+                false,
+            ),
+            (
+                Aggregate::Percentile,
+                [&integer, &float, &money, &progress].iter(),
+                true,
+            ),
+            (
+                Aggregate::Percentile,
+                [&text, &date_time, &web_link, &checkbox, &enumeration].iter(),
+                false,
+            ),
+            (
+                Aggregate::StdDev,
+                [&integer, &float, &money, &progress].iter(),
+                true,
+            ),
+            (
+                Aggregate::StdDev,
+                [&text, &date_time, &web_link, &checkbox, &enumeration].iter(),
+                false,
+            ),
+            (
+                Aggregate::Variance,
+                [&integer, &float, &money, &progress].iter(),
+                true,
+            ),
+            (
+                Aggregate::Variance,
+                [&text, &date_time, &web_link, &checkbox, &enumeration].iter(),
+                false,
+            ),
         ] {
             for field_kind in field_kinds {
-                assert_eq!(super::validate_axis(&aggregate, field_kind).is_ok(), is_ok);
+                assert_eq!(
+                    super::validate_axis(Some(&aggregate), None, field_kind).is_ok(),
+                    is_ok
+                );
             }
         }
+
+        let bucket = TimeBucket {
+            unit: TimeUnit::Day,
+            time_zone: "UTC".into(),
+        };
+        assert!(super::validate_axis(None, Some(&bucket), &date_time).is_ok());
+        assert!(super::validate_axis(Some(&Aggregate::Count), Some(&bucket), &date_time).is_ok());
+        for field_kind in [
+            &text,
+            &integer,
+            &float,
+            &money,
+            &progress,
+            &web_link,
+            &checkbox,
+            &enumeration,
+        ] {
+            assert!(super::validate_axis(None, Some(&bucket), field_kind).is_err());
+        }
+    }
+
+    #[test]
+    fn validate_axis_options() {
+        let date_time = FieldKind::DateTime {
+            is_required: true,
+            range_start: None,
+            range_end: None,
+        };
+        let integer = FieldKind::Integer {
+            is_required: true,
+            range_start: None,
+            range_end: None,
+        };
+        let field_kinds = HashMap::from([(1, integer), (2, date_time)]);
+        for (aggregate, percentile, order_field_id, is_ok) in [
+            (None, None, None, true),
+            (Some(Aggregate::Sum), None, None, true),
+            (Some(Aggregate::Percentile), Some(0), None, true),
+            (Some(Aggregate::Percentile), Some(95), None, true),
+            (Some(Aggregate::Percentile), Some(100), None, true),
+            (Some(Aggregate::Percentile), None, None, false),
+            (Some(Aggregate::Percentile), Some(-1), None, false),
+            (Some(Aggregate::Percentile), Some(101), None, false),
+            (Some(Aggregate::Median), Some(50), None, false),
+            (None, Some(50), None, false),
+            (Some(Aggregate::First), None, Some(2), true),
+            (Some(Aggregate::Last), None, Some(2), true),
+            (Some(Aggregate::First), None, None, false),
+            (Some(Aggregate::First), None, Some(1), false),
+            (Some(Aggregate::Last), None, Some(1000), false),
+            (Some(Aggregate::Max), None, Some(2), false),
+            (None, None, Some(2), false),
+        ] {
+            let axis = CreateAxis {
+                field_id: 1,
+                table_path: Vec::new(),
+                axis_kind: AxisKind::Y,
+                aggregate,
+                percentile,
+                order_field_id,
+                sort: None,
+                bins: None,
+                bucket: None,
+            };
+            assert_eq!(
+                super::validate_axis_options(&axis, &field_kinds).is_ok(),
+                is_ok
+            );
+        }
+    }
+
+    #[test]
+    fn validate_chart_axes() {
+        let text = FieldKind::Text { is_required: true };
+        let integer = FieldKind::Integer {
+            is_required: true,
+            range_start: None,
+            range_end: None,
+        };
+        let field_kinds = HashMap::from([(1, text), (2, integer)]);
+        let axis = |field_id, axis_kind, aggregate, bins| CreateAxis {
+            field_id,
+            table_path: Vec::new(),
+            axis_kind,
+            aggregate,
+            percentile: None,
+            order_field_id: None,
+            sort: None,
+            bins,
+            bucket: None,
+        };
+        let x = axis(1, AxisKind::X, None, None);
+        let y = axis(2, AxisKind::Y, Some(Aggregate::Sum), None);
+        let color = axis(2, AxisKind::Color, Some(Aggregate::Count), None);
+        let size = axis(2, AxisKind::Size, Some(Aggregate::Max), None);
+        let bins = axis(2, AxisKind::X, None, Some(10));
+        for (chart_kind, axes, is_ok) in [
+            (ChartKind::Table, vec![&size], true),
+            (ChartKind::Bar, vec![&x, &y], true),
+            (ChartKind::StackedBar, vec![&x, &y, &y, &color], true),
+            (ChartKind::Line, vec![&y], false),
+            (ChartKind::Area, vec![&x, &x, &y], false),
+            (ChartKind::Bar, vec![&x, &y, &size], false),
+            (ChartKind::Pie, vec![&x, &y], true),
+            (ChartKind::Donut, vec![&x, &y, &y], false),
+            (ChartKind::Scatter, vec![&x, &y, &color, &size], true),
+            (ChartKind::Scatter, vec![&x, &y, &size, &size], false),
+            (ChartKind::Histogram, vec![&bins, &y], true),
+            (ChartKind::Histogram, vec![&x, &y], false),
+            (
+                ChartKind::Histogram,
+                vec![&axis(1, AxisKind::X, None, Some(10)), &y],
+                false,
+            ),
+            (
+                ChartKind::Histogram,
+                vec![&axis(2, AxisKind::X, None, Some(0)), &y],
+                false,
+            ),
+            (
+                ChartKind::Histogram,
+                vec![&axis(2, AxisKind::X, Some(Aggregate::Max), Some(10)), &y],
+                false,
+            ),
+            (ChartKind::Bar, vec![&bins, &y], false),
+            (ChartKind::Heatmap, vec![&x, &y, &color], true),
+            (ChartKind::Heatmap, vec![&x, &y], false),
+            (ChartKind::Kpi, vec![&y], true),
+            (
+                ChartKind::Kpi,
+                vec![&axis(2, AxisKind::Y, None, None)],
+                false,
+            ),
+            (ChartKind::Kpi, vec![&x, &y], false),
+            (
+                ChartKind::Line,
+                vec![&x, &axis(2, AxisKind::Y, None, None)],
+                false,
+            ),
+            (
+                ChartKind::Line,
+                vec![
+                    &axis(1, AxisKind::X, Some(Aggregate::Max), None),
+                    &axis(2, AxisKind::Y, None, None),
+                ],
+                true,
+            ),
+            (
+                ChartKind::Heatmap,
+                vec![&x, &y, &axis(2, AxisKind::Color, None, None)],
+                false,
+            ),
+            (
+                ChartKind::Scatter,
+                vec![&x, &y, &axis(2, AxisKind::Size, None, None)],
+                true,
+            ),
+            (
+                ChartKind::Scatter,
+                vec![&x, &y, &axis(1, AxisKind::Size, None, None)],
+                false,
+            ),
+            (
+                ChartKind::Scatter,
+                vec![
+                    &x,
+                    &y,
+                    &axis(1, AxisKind::Size, Some(Aggregate::CountDistinct), None),
+                ],
+                true,
+            ),
+        ] {
+            let axes = axes.into_iter().cloned().collect_vec();
+            assert_eq!(
+                super::validate_chart_axes(&chart_kind, &axes, &field_kinds).is_ok(),
+                is_ok,
+                "{chart_kind:?}"
+            );
+        }
+
+        let axes = [
+            axis(1, AxisKind::X, None, None),
+            axis(1, AxisKind::X, None, None),
+            axis(1, AxisKind::Y, Some(Aggregate::Sum), None),
+            axis(1, AxisKind::Size, None, None),
+            axis(1000, AxisKind::Label, None, None),
+        ];
+        let error_messages =
+            super::validate_chart_axes(&ChartKind::Line, &axes, &field_kinds).unwrap_err();
+        assert_eq!(
+            error_messages,
+            [
+                format!("X: {}", super::INVALID_AXIS_COUNT),
+                format!("Size: {}", super::INVALID_AXIS_COUNT),
+                format!("1: {}", super::INVALID_AXIS_AGGREGATE),
+                format!("1: {}", super::INVALID_SIZE_AXIS),
+                format!("1000: {}", super::FIELD_NOT_FOUND),
+            ]
+        );
+    }
+
+    #[test]
+    fn validate_table_path() {
+        // Table 2 is the child of table 1 and the parent of table 3. Table 4 is the child of 1.
+        let table_parent_ids = HashMap::from([(1, None), (2, Some(1)), (3, Some(2)), (4, Some(1))]);
+        let field_table_ids = HashMap::from([(10, 1), (20, 2), (30, 3), (40, 4)]);
+        let axis = |field_id, table_path: &[Id], order_field_id| CreateAxis {
+            field_id,
+            table_path: table_path.to_vec(),
+            axis_kind: AxisKind::Y,
+            aggregate: None,
+            percentile: None,
+            order_field_id,
+            sort: None,
+            bins: None,
+            bucket: None,
+        };
+        for (table_id, axis, is_ok) in [
+            (2, axis(20, &[], None), true),
+            (2, axis(10, &[1], None), true),
+            (2, axis(30, &[3], None), true),
+            (1, axis(30, &[2, 3], Some(30)), true),
+            (3, axis(10, &[2, 1], None), true),
+            (2, axis(40, &[1, 4], None), true),
+            (2, axis(10, &[], None), false),
+            (2, axis(30, &[1], None), false),
+            (1, axis(30, &[3], None), false),
+            (2, axis(20, &[1, 2], None), false),
+            (2, axis(10, &[1, 4, 1], None), false),
+            (1, axis(30, &[2, 3], Some(20)), false),
+            (1, axis(20, &[1000], None), false),
+        ] {
+            assert_eq!(
+                super::validate_table_path(&axis, table_id, &table_parent_ids, &field_table_ids)
+                    .is_ok(),
+                is_ok,
+                "{table_id} {axis:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn validate_joined_aggregates() {
+        // Table 2 is the child of table 1 and the parent of table 3. Table 4 is the child of 1.
+        let table_parent_ids = HashMap::from([(1, None), (2, Some(1)), (3, Some(2)), (4, Some(1))]);
+        let field_table_ids = HashMap::from([(10, 1), (20, 2), (30, 3), (40, 4)]);
+        let axis = |field_id, table_path: &[Id], aggregate| CreateAxis {
+            field_id,
+            table_path: table_path.to_vec(),
+            axis_kind: AxisKind::Y,
+            aggregate,
+            percentile: None,
+            order_field_id: None,
+            sort: None,
+            bins: None,
+            bucket: None,
+        };
+        let count = Some(Aggregate::Count);
+        let sum = Some(Aggregate::Sum);
+        for (table_id, axes, error_count) in [
+            (1, vec![axis(10, &[], None), axis(20, &[2], sum)], 0),
+            (1, vec![axis(10, &[], None), axis(30, &[2, 3], sum)], 0),
+            (1, vec![axis(20, &[2], None), axis(30, &[2, 3], sum)], 0),
+            (2, vec![axis(10, &[1], count), axis(20, &[], sum)], 0),
+            (3, vec![axis(10, &[2, 1], None), axis(30, &[], count)], 0),
+            // The entries of table 1 are repeated for each joined entry of table 2.
+            (1, vec![axis(10, &[], count), axis(20, &[2], sum)], 1),
+            (1, vec![axis(20, &[2], sum), axis(30, &[2, 3], sum)], 1),
+            (2, vec![axis(10, &[1], count), axis(30, &[3], sum)], 1),
+            (1, vec![axis(20, &[2], None), axis(40, &[4], None)], 2),
+            (2, vec![axis(30, &[3], None), axis(40, &[1, 4], sum)], 2),
+        ] {
+            assert_eq!(
+                super::validate_joined_aggregates(
+                    &axes,
+                    table_id,
+                    &table_parent_ids,
+                    &field_table_ids
+                )
+                .len(),
+                error_count,
+                "{table_id} {axes:?}"
+            );
+        }
     }
 }
@@ -1,13 +1,17 @@
 //! Routes for managing dashboard charts.
 
 use crate::{
-    AppState,
+    AppState, Id,
+    api::viz::{axes::validate_chart_axes, controls::convert_control_filters},
     auth::AppAuthSession,
     db,
     error::{ApiError, ApiResult},
     model::{
         access::{AccessRole, AccessRoleCheck, Resource},
-        viz::{Chart, ChartData, CreateChart, SelectChart, SelectDashboard, UpdateChart},
+        viz::{
+            Chart, ChartData, ChartDataQuery, ControlFilter, CreateChart, SelectChart,
+            SelectDashboard, UpdateChart,
+        },
     },
 };
 use aide::{
@@ -19,9 +23,13 @@ use aide::{
 };
 use axum::{
     Json,
-    extract::{Path, State},
+    extract::{Path, Query, State},
 };
 use axum_login::AuthSession;
+use sqlx::PgPool;
+use std::collections::HashMap;
+
+const INVALID_TOP_N: &str = "Top N must be positive";
 
 pub fn router() -> ApiRouter<AppState> {
     ApiRouter::new().nest(
@@ -59,6 +67,10 @@ async fn create_chart(
         .await?
         .check(AccessRole::Viewer)?;
 
+    if create_chart.top_n.is_some_and(|top_n| top_n < 1) {
+        return Err(ApiError::UnprocessableEntity(INVALID_TOP_N.into()));
+    }
+
     let chart = db::create_chart(tx.as_mut(), dashboard_id, create_chart).await?;
 
     tx.commit().await?;
@@ -84,6 +96,25 @@ async fn update_chart(
         return Err(ApiError::NotFound);
     };
 
+    if update_chart.top_n.is_some_and(|top_n| top_n < 1) {
+        return Err(ApiError::UnprocessableEntity(INVALID_TOP_N.into()));
+    }
+
+    if db::get_chart_kind(tx.as_mut(), chart_id).await? != update_chart.chart_kind {
+        let axes = db::get_chart_axes(tx.as_mut(), chart_id).await?;
+        if !axes.is_empty() {
+            let table_id = db::get_chart_table_id(tx.as_mut(), chart_id).await?;
+            let field_kinds: HashMap<_, _> = db::get_axes_fields(tx.as_mut(), table_id, &axes)
+                .await?
+                .into_iter()
+                .map(|field| (field.field_id, field.field_kind.0))
+                .collect();
+            validate_chart_axes(&update_chart.chart_kind, &axes, &field_kinds).map_err(
+                |error_messages| ApiError::UnprocessableEntity(error_messages.join(", ")),
+            )?;
+        }
+    }
+
     let chart = db::update_chart(tx.as_mut(), chart_id, update_chart).await?;
 
     tx.commit().await?;
@@ -136,24 +167,54 @@ async fn get_chart_data(
         dashboard_id,
         chart_id,
     }): Path<SelectChart>,
+    Query(ChartDataQuery { controls }): Query<ChartDataQuery>,
 ) -> ApiResult<Json<ChartData>> {
     let user_id = user.ok_or(ApiError::Unauthorized)?.user_id;
 
     db::get_access_role(&db, Resource::Dashboard, dashboard_id, user_id)
         .await?
         .check(AccessRole::Viewer)?;
-    if !db::chart_exists(&db, dashboard_id, chart_id).await? {
+
+    let chart_data = query_chart_data(&db, dashboard_id, chart_id, controls).await?;
+
+    Ok(Json(chart_data))
+}
+
+/// Get the data of a chart of this dashboard with the values of the dashboard controls applied.
+/// Access to the dashboard must already be checked.
+pub(in crate::api) async fn query_chart_data(
+    db: &PgPool,
+    dashboard_id: Id,
+    chart_id: Id,
+    controls: Vec<ControlFilter>,
+) -> ApiResult<ChartData> {
+    if !db::chart_exists(db, dashboard_id, chart_id).await? {
         return Err(ApiError::NotFound);
     };
 
-    let chart_data = db::get_chart_data(&db, chart_id).await?;
+    let filters = if controls.is_empty() {
+        Vec::new()
+    } else {
+        let dashboard_controls = db::get_controls(db, dashboard_id).await?;
+        let table_id = db::get_chart_table_id(db, chart_id).await?;
+        let fields = db::get_fields_metadata(db, table_id).await?;
+        convert_control_filters(controls, &dashboard_controls, chart_id, &fields)
+            .map_err(|error_messages| ApiError::UnprocessableEntity(error_messages.join(", ")))?
+    };
 
-    Ok(Json(chart_data))
+    Ok(db::get_chart_data(db, chart_id, filters).await?)
 }
 
 #[cfg_attr(coverage_nightly, coverage(off))]
 mod docs {
     use crate::{
+        api::{
+            data::entries::INVALID_TYPE,
+            viz::{
+                charts::INVALID_TOP_N,
+                controls::{CONTROL_NOT_FOUND, INVALID_CONTROL_OPERATOR},
+            },
+        },
         docs::{CHARTS_TAG, TransformOperationExt, template},
         model::{
             access::{AccessRole, Resource},
@@ -162,6 +223,7 @@ mod docs {
     };
     use aide::{OperationOutput, transform::TransformOperation};
     use axum::Json;
+    use itertools::Itertools;
 
     const DASHBOARD_EDITOR_TABLE_VIEWER: [(Resource, AccessRole); 2] = [
         (Resource::Dashboard, AccessRole::Editor),
@@ -183,13 +245,23 @@ mod docs {
     pub fn create_chart(op: TransformOperation) -> TransformOperation {
         charts::<Json<Chart>>(op, "create_chart", "Create a blank chart.")
             .response_description::<404, ()>("Dashboard not found\n\nTable not found")
+            .response_description::<422, String>(INVALID_TOP_N)
             .required_access(DASHBOARD_EDITOR_TABLE_VIEWER)
     }
 
     pub fn update_chart(op: TransformOperation) -> TransformOperation {
-        charts::<Json<Chart>>(op, "update_chart", "Update a chart's metadata.")
-            .response_description::<404, ()>("Dashboard not found\n\nChart not found")
-            .required_access(DASHBOARD_EDITOR)
+        charts::<Json<Chart>>(
+            op,
+            "update_chart",
+            "Update a chart's metadata and rebuild the dynamic view. \
+            When the chart kind changes, the existing axes must be valid for the new chart kind \
+            and every violated rule is listed in the error, as for set_axes.",
+        )
+        .response_description::<404, ()>("Dashboard not found\n\nChart not found")
+        .response_description::<422, String>(&format!(
+            "{INVALID_TOP_N}\n\n<field_id or axis_kind> : <axis error>"
+        ))
+        .required_access(DASHBOARD_EDITOR)
     }
 
     pub fn delete_chart(op: TransformOperation) -> TransformOperation {
@@ -199,19 +271,34 @@ mod docs {
     }
 
     pub fn get_charts(op: TransformOperation) -> TransformOperation {
-        charts::<Json<Vec<Chart>>>(op, "get_charts", "Get all charts for this dashboard.")
-            .response_description::<404, ()>("Dashboard not found")
-            .required_access(DASHBOARD_VIEWER)
+        charts::<Json<Vec<Chart>>>(
+            op,
+            "get_charts",
+            "Get all charts for this dashboard, including their layout on the dashboard grid.",
+        )
+        .response_description::<404, ()>("Dashboard not found")
+        .required_access(DASHBOARD_VIEWER)
     }
 
     pub fn get_chart_data(op: TransformOperation) -> TransformOperation {
+        let errors = [CONTROL_NOT_FOUND, INVALID_CONTROL_OPERATOR, INVALID_TYPE]
+            .into_iter()
+            .map(|v| format!("<control_id>: {v}"))
+            .join("\n\n");
+
         charts::<Json<ChartData>>(
             op,
             "get_chart_data",
             "Get the chart's metadata, axes metadata, and data points.
-            Used for building and displaying the chart.",
+            Used for building and displaying the chart.
+            The data points are sorted by the sorted axes. If the chart has a top N, \
+            the groups beyond the first N are aggregated into the other data point. \
+            Without sorted axes, the top N groups are the ones with the largest aggregated axes. \
+            The values of the dashboard controls are applied on top of the chart filters \
+            to the fields bound to the chart.",
         )
         .response_description::<404, ()>("Dashboard not found\n\nChart not found")
+        .response_description::<422, String>(&errors)
         .required_access(DASHBOARD_VIEWER)
     }
 }
@@ -265,6 +352,7 @@ mod test {
             table_id,
             name: "Test".into(),
             chart_kind: ChartKind::Bar,
+            top_n: None,
         };
         server
             .post(&path)
@@ -314,6 +402,7 @@ mod test {
             table_id,
             name: "abcdef".into(),
             chart_kind: ChartKind::Bar,
+            top_n: None,
         };
         let response = server.post(&path).json(&create_chart).await;
         response.assert_status_ok();
@@ -358,6 +447,7 @@ mod test {
                 table_id,
                 name: "abc".into(),
                 chart_kind: ChartKind::Bar,
+                top_n: None,
             },
         )
         .await?
@@ -367,6 +457,7 @@ mod test {
         let update_chart = UpdateChart {
             name: "def".into(),
             chart_kind: ChartKind::Table,
+            top_n: None,
         };
         server
             .patch(&path)
@@ -397,20 +488,89 @@ mod test {
                 .assert_status_not_found();
         }
 
+        let invalid_top_n = UpdateChart {
+            name: "ghj".into(),
+            chart_kind: ChartKind::Line,
+            top_n: Some(0),
+        };
+        server
+            .patch(&path)
+            .json(&invalid_top_n)
+            .await
+            .assert_status_unprocessable_entity();
+
         let update_chart = UpdateChart {
             name: "ghj".into(),
             chart_kind: ChartKind::Line,
+            top_n: Some(5),
         };
         let response = server.patch(&path).json(&update_chart).await;
         response.assert_status_ok();
         let chart_1: Chart = response.json();
         assert_eq!(chart_1.name, update_chart.name);
         assert_eq!(chart_1.chart_kind, update_chart.chart_kind);
+        assert_eq!(chart_1.top_n, update_chart.top_n);
         let chart_2: Chart = sqlx::query_as(r#"SELECT * FROM chart WHERE chart_id = $1"#)
             .bind(chart_1.chart_id)
             .fetch_one(&db)
             .await?;
         assert_eq!(chart_1, chart_2);
+
+        let text_field_id = db::create_field(
+            &db,
+            table_id,
+            CreateField {
+                name: "Text".into(),
+                field_kind: FieldKind::Text { is_required: true },
+            },
+        )
+        .await?
+        .field_id;
+        let axis = |field_id, axis_kind, aggregate| CreateAxis {
+            field_id,
+            table_path: Vec::new(),
+            axis_kind,
+            aggregate,
+            percentile: None,
+            order_field_id: None,
+            sort: None,
+            bins: None,
+            bucket: None,
+        };
+        db::set_axes(
+            &db,
+            chart_id,
+            table_id,
+            vec![
+                axis(text_field_id, AxisKind::X, None),
+                axis(text_field_id, AxisKind::Y, Some(Aggregate::Count)),
+            ],
+        )
+        .await?;
+
+        let invalid_chart_kind = UpdateChart {
+            name: "ghj".into(),
+            chart_kind: ChartKind::Kpi,
+            top_n: None,
+        };
+        let response = server.patch(&path).json(&invalid_chart_kind).await;
+        response.assert_status_unprocessable_entity();
+        let error_message = response.text();
+        assert!(error_message.contains("X: "));
+        assert!(error_message.contains(&format!("{text_field_id}: ")));
+        let chart_kind: ChartKind = db::get_chart_kind(&db, chart_id).await?;
+        assert_eq!(chart_kind, ChartKind::Line);
+
+        let valid_chart_kind = UpdateChart {
+            name: "ghj".into(),
+            chart_kind: ChartKind::Area,
+            top_n: None,
+        };
+        server
+            .patch(&path)
+            .json(&valid_chart_kind)
+            .await
+            .assert_status_ok();
         Ok(())
     }
 
@@ -443,6 +603,7 @@ mod test {
                 table_id,
                 name: "abc".into(),
                 chart_kind: ChartKind::Bar,
+                top_n: None,
             },
         )
         .await?
@@ -467,6 +628,7 @@ mod test {
                         table_id,
                         name: "abc".into(),
                         chart_kind: ChartKind::Bar,
+                        top_n: None,
                     },
                 )
                 .await
@@ -534,6 +696,7 @@ mod test {
                         table_id,
                         name,
                         chart_kind,
+                        top_n: None,
                     },
                 )
                 .await?,
@@ -622,6 +785,7 @@ mod test {
                 table_id,
                 name: "Test".into(),
                 chart_kind: ChartKind::Bar,
+                top_n: None,
             },
         )
         .await?;
@@ -632,13 +796,25 @@ mod test {
             vec![
                 CreateAxis {
                     field_id: field.field_id,
+                    table_path: Vec::new(),
                     axis_kind: AxisKind::X,
                     aggregate: None,
+                    percentile: None,
+                    order_field_id: None,
+                    sort: None,
+                    bins: None,
+                    bucket: None,
                 },
                 CreateAxis {
                     field_id: field.field_id,
+                    table_path: Vec::new(),
                     axis_kind: AxisKind::X,
                     aggregate: Some(Aggregate::Count),
+                    percentile: None,
+                    order_field_id: None,
+                    sort: None,
+                    bins: None,
+                    bucket: None,
                 },
             ],
         )
@@ -0,0 +1,857 @@
+//! Routes for managing dashboard filter controls.
+
+use crate::{
+    AppState, Id,
+    api::data::entries::{INVALID_TYPE, check_days},
+    auth::AppAuthSession,
+    db,
+    error::{ApiError, ApiResult},
+    model::{
+        Cell,
+        access::{AccessRole, AccessRoleCheck, Resource},
+        data::{EntryFilter, FieldKind, FieldMetadata, FilterOperator},
+        viz::{
+            ControlBinding, ControlFilter, ControlKind, CreateControl, DashboardControl,
+            SelectControl, SelectDashboard, UpdateControl,
+        },
+    },
+};
+use aide::{
+    NoApi,
+    axum::{
+        ApiRouter,
+        routing::{patch_with, post_with},
+    },
+};
+use axum::{
+    Json,
+    extract::{Path, State},
+};
+use axum_login::AuthSession;
+use itertools::Itertools;
+use serde_json::Value;
+use sqlx::{Acquire, Postgres};
+use std::collections::HashMap;
+
+const CHART_NOT_FOUND: &str = "Chart not found";
+const DUPLICATE_CHART_BINDING: &str = "Chart is bound more than once";
+const FIELD_NOT_FOUND: &str = "Field is not a field of the chart's table";
+const INVALID_CONTROL_FIELD: &str = "Field kind is not supported by the control kind";
+pub(super) const CONTROL_NOT_FOUND: &str = "Control not found";
+pub(super) const INVALID_CONTROL_OPERATOR: &str =
+    "Filter operator is not supported by the control kind";
+
+pub fn router() -> ApiRouter<AppState> {
+    ApiRouter::new().nest(
+        "/dashboards/{dashboard_id}/controls",
+        ApiRouter::new()
+            .api_route(
+                "/",
+                post_with(create_control, docs::create_control)
+                    .get_with(get_controls, docs::get_controls),
+            )
+            .api_route(
+                "/{control_id}",
+                patch_with(update_control, docs::update_control)
+                    .delete_with(delete_control, docs::delete_control),
+            ),
+    )
+}
+
+async fn create_control(
+    NoApi(AuthSession { user, .. }): AppAuthSession,
+    State(AppState { db, .. }): State<AppState>,
+    Path(SelectDashboard { dashboard_id }): Path<SelectDashboard>,
+    Json(create_control): Json<CreateControl>,
+) -> ApiResult<Json<DashboardControl>> {
+    let user_id = user.ok_or(ApiError::Unauthorized)?.user_id;
+    let mut tx = db.begin().await?;
+
+    db::get_access_role(tx.as_mut(), Resource::Dashboard, dashboard_id, user_id)
+        .await?
+        .check(AccessRole::Editor)?;
+
+    let chart_fields = get_chart_fields(tx.as_mut(), dashboard_id).await?;
+    validate_bindings(
+        create_control.control_kind,
+        &create_control.bindings,
+        &chart_fields,
+    )
+    .map_err(|error_messages| ApiError::UnprocessableEntity(error_messages.join(", ")))?;
+
+    let control = db::create_control(tx.as_mut(), dashboard_id, create_control).await?;
+
+    tx.commit().await?;
+    Ok(Json(control))
+}
+
+async fn update_control(
+    NoApi(AuthSession { user, .. }): AppAuthSession,
+    State(AppState { db, .. }): State<AppState>,
+    Path(SelectControl {
+        dashboard_id,
+        control_id,
+    }): Path<SelectControl>,
+    Json(update_control): Json<UpdateControl>,
+) -> ApiResult<Json<DashboardControl>> {
+    let user_id = user.ok_or(ApiError::Unauthorized)?.user_id;
+    let mut tx = db.begin().await?;
+
+    db::get_access_role(tx.as_mut(), Resource::Dashboard, dashboard_id, user_id)
+        .await?
+        .check(AccessRole::Editor)?;
+    if !db::control_exists(tx.as_mut(), dashboard_id, control_id).await? {
+        return Err(ApiError::NotFound);
+    }
+
+    let chart_fields = get_chart_fields(tx.as_mut(), dashboard_id).await?;
+    validate_bindings(
+        update_control.control_kind,
+        &update_control.bindings,
+        &chart_fields,
+    )
+    .map_err(|error_messages| ApiError::UnprocessableEntity(error_messages.join(", ")))?;
+
+    let control = db::update_control(tx.as_mut(), control_id, update_control).await?;
+
+    tx.commit().await?;
+    Ok(Json(control))
+}
+
+async fn delete_control(
+    NoApi(AuthSession { user, .. }): AppAuthSession,
+    State(AppState { db, .. }): State<AppState>,
+    Path(SelectControl {
+        dashboard_id,
+        control_id,
+    }): Path<SelectControl>,
+) -> ApiResult<()> {
+    let user_id = user.ok_or(ApiError::Unauthorized)?.user_id;
+    let mut tx = db.begin().await?;
+
+    db::get_access_role(tx.as_mut(), Resource::Dashboard, dashboard_id, user_id)
+        .await?
+        .check(AccessRole::Editor)?;
+    if !db::control_exists(tx.as_mut(), dashboard_id, control_id).await? {
+        return Err(ApiError::NotFound);
+    }
+
+    db::delete_control(tx.as_mut(), control_id).await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+async fn get_controls(
+    NoApi(AuthSession { user, .. }): AppAuthSession,
+    State(AppState { db, .. }): State<AppState>,
+    Path(SelectDashboard { dashboard_id }): Path<SelectDashboard>,
+) -> ApiResult<Json<Vec<DashboardControl>>> {
+    let user_id = user.ok_or(ApiError::Unauthorized)?.user_id;
+
+    db::get_access_role(&db, Resource::Dashboard, dashboard_id, user_id)
+        .await?
+        .check(AccessRole::Viewer)?;
+
+    let controls = db::get_controls(&db, dashboard_id).await?;
+
+    Ok(Json(controls))
+}
+
+/// Get the fields of the table of each chart of the dashboard.
+async fn get_chart_fields(
+    conn: impl Acquire<'_, Database = Postgres>,
+    dashboard_id: Id,
+) -> ApiResult<HashMap<Id, Vec<FieldMetadata>>> {
+    let mut tx = conn.begin().await?;
+
+    let mut chart_fields = HashMap::new();
+    for chart in db::get_charts(tx.as_mut(), dashboard_id).await? {
+        let fields = db::get_fields_metadata(tx.as_mut(), chart.table_id).await?;
+        chart_fields.insert(chart.chart_id, fields);
+    }
+
+    tx.commit().await?;
+    Ok(chart_fields)
+}
+
+/// Validate the bindings of a control against the fields of the table of each chart,
+/// since the charts of a dashboard can come from different tables.
+/// Return the messages of every violated rule, prefixed by the chart ID.
+fn validate_bindings(
+    control_kind: ControlKind,
+    bindings: &[ControlBinding],
+    chart_fields: &HashMap<Id, Vec<FieldMetadata>>,
+) -> Result<(), Vec<String>> {
+    let mut error_messages = Vec::new();
+
+    for (index, binding) in bindings.iter().enumerate() {
+        let message = if bindings[..index]
+            .iter()
+            .any(|other| other.chart_id == binding.chart_id)
+        {
+            Some(DUPLICATE_CHART_BINDING)
+        } else if let Some(fields) = chart_fields.get(&binding.chart_id) {
+            match fields
+                .iter()
+                .find(|field| field.field_id == binding.field_id)
+            {
+                None => Some(FIELD_NOT_FOUND),
+                Some(field) if !control_kind.supports_field(&field.field_kind) => {
+                    Some(INVALID_CONTROL_FIELD)
+                }
+                Some(_) => None,
+            }
+        } else {
+            Some(CHART_NOT_FOUND)
+        };
+        if let Some(message) = message {
+            error_messages.push(format!("{}: {message}", binding.chart_id));
+        }
+    }
+
+    if error_messages.is_empty() {
+        Ok(())
+    } else {
+        Err(error_messages)
+    }
+}
+
+/// Convert the values of the dashboard controls into filters on the fields
+/// bound to this chart. Values of controls not bound to the chart are ignored.
+/// Enumeration values are matched by name, and names missing from the field are skipped.
+/// Return the messages of every violated rule, prefixed by the control ID.
+pub(super) fn convert_control_filters(
+    control_filters: Vec<ControlFilter>,
+    controls: &[DashboardControl],
+    chart_id: Id,
+    fields: &[FieldMetadata],
+) -> Result<Vec<EntryFilter<Cell>>, Vec<String>> {
+    let mut error_messages = Vec::new();
+    let mut filters = Vec::new();
+
+    for ControlFilter {
+        control_id,
+        operator,
+    } in control_filters
+    {
+        let Some(control) = controls
+            .iter()
+            .find(|control| control.control_id == control_id)
+        else {
+            error_messages.push(format!("{control_id}: {CONTROL_NOT_FOUND}"));
+            continue;
+        };
+        if !control.control_kind.supports_operator(&operator) {
+            error_messages.push(format!("{control_id}: {INVALID_CONTROL_OPERATOR}"));
+            continue;
+        }
+        if let Err(message) = check_days(&operator) {
+            error_messages.push(format!("{control_id}: {message}"));
+            continue;
+        }
+        let Some(field) = control
+            .bindings
+            .iter()
+            .find(|binding| binding.chart_id == chart_id)
+            .and_then(|binding| {
+                fields
+                    .iter()
+                    .find(|field| field.field_id == binding.field_id)
+            })
+        else {
+            continue;
+        };
+
+        let field_kind = &*field.field_kind.value_kind();
+        let operator = match (operator, field_kind) {
+            (FilterOperator::In { values }, FieldKind::Enumeration { values: names, .. }) => {
+                enumeration_values(values, names).map(|values| FilterOperator::In { values })
+            }
+            (operator, _) => Ok(operator),
+        }
+        .and_then(|operator| {
+            operator.try_map(|value| Cell::from_filter_value(value, field_kind).ok_or(INVALID_TYPE))
+        });
+        match operator {
+            Ok(operator) => filters.push(EntryFilter {
+                field_id: field.field_id,
+                operator,
+            }),
+            Err(message) => error_messages.push(format!("{control_id}: {message}")),
+        }
+    }
+
+    if error_messages.is_empty() {
+        Ok(filters)
+    } else {
+        Err(error_messages)
+    }
+}
+
+/// Replace the enumeration value names by their IDs, skipping the names missing from the field.
+fn enumeration_values(
+    values: Vec<Value>,
+    names: &HashMap<i64, String>,
+) -> Result<Vec<Value>, &'static str> {
+    values
+        .into_iter()
+        .filter_map(|value| match value {
+            Value::String(name) => names
+                .iter()
+                .find(|(_, other)| **other == name)
+                .map(|(id, _)| Ok(Value::from(*id))),
+            _ => Some(Err(INVALID_TYPE)),
+        })
+        .try_collect()
+}
+
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod docs {
+    use crate::{
+        api::viz::controls::{
+            CHART_NOT_FOUND, DUPLICATE_CHART_BINDING, FIELD_NOT_FOUND, INVALID_CONTROL_FIELD,
+        },
+        docs::{CONTROLS_TAG, TransformOperationExt, template},
+        model::{
+            access::{AccessRole, Resource},
+            viz::DashboardControl,
+        },
+    };
+    use aide::{OperationOutput, transform::TransformOperation};
+    use axum::Json;
+    use itertools::Itertools;
+
+    const DASHBOARD_EDITOR: [(Resource, AccessRole); 1] =
+        [(Resource::Dashboard, AccessRole::Editor)];
+    const DASHBOARD_VIEWER: [(Resource, AccessRole); 1] =
+        [(Resource::Dashboard, AccessRole::Viewer)];
+
+    fn controls<'a, R: OperationOutput>(
+        op: TransformOperation<'a>,
+        summary: &'a str,
+        description: &'a str,
+    ) -> TransformOperation<'a> {
+        template::<R>(op, summary, description, true, CONTROLS_TAG)
+    }
+
+    fn binding_errors() -> String {
+        [
+            CHART_NOT_FOUND,
+            DUPLICATE_CHART_BINDING,
+            FIELD_NOT_FOUND,
+            INVALID_CONTROL_FIELD,
+        ]
+        .into_iter()
+        .map(|v| format!("<chart_id>: {v}"))
+        .join("\n\n")
+    }
+
+    pub fn create_control(op: TransformOperation) -> TransformOperation {
+        let errors = binding_errors();
+
+        controls::<Json<DashboardControl>>(
+            op,
+            "create_control",
+            "Create a filter control on the dashboard, bound to a field of the table \
+            of each chart it filters. \
+            Its values are given when getting the data of the charts.",
+        )
+        .response_description::<404, ()>("Dashboard not found")
+        .response_description::<422, String>(&errors)
+        .required_access(DASHBOARD_EDITOR)
+    }
+
+    pub fn update_control(op: TransformOperation) -> TransformOperation {
+        let errors = binding_errors();
+
+        controls::<Json<DashboardControl>>(
+            op,
+            "update_control",
+            "Update a filter control and replace its bindings.",
+        )
+        .response_description::<404, ()>("Dashboard not found\n\nControl not found")
+        .response_description::<422, String>(&errors)
+        .required_access(DASHBOARD_EDITOR)
+    }
+
+    pub fn delete_control(op: TransformOperation) -> TransformOperation {
+        controls::<()>(op, "delete_control", "Delete a filter control.")
+            .response_description::<404, ()>("Dashboard not found\n\nControl not found")
+            .required_access(DASHBOARD_EDITOR)
+    }
+
+    pub fn get_controls(op: TransformOperation) -> TransformOperation {
+        controls::<Json<Vec<DashboardControl>>>(
+            op,
+            "get_controls",
+            "Get all the filter controls of the dashboard.",
+        )
+        .response_description::<404, ()>("Dashboard not found")
+        .required_access(DASHBOARD_VIEWER)
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod test {
+    use super::{
+        CHART_NOT_FOUND, CONTROL_NOT_FOUND, DUPLICATE_CHART_BINDING, FIELD_NOT_FOUND,
+        INVALID_CONTROL_FIELD, INVALID_CONTROL_OPERATOR,
+    };
+    use crate::{
+        Id,
+        api::data::entries::{INVALID_DAYS, INVALID_TYPE},
+        db,
+        model::{
+            Cell,
+            access::{AccessRole, Resource},
+            data::{
+                CreateField, CreateTable, EntryFilter, FieldKind, FieldMetadata, FilterOperator,
+            },
+            viz::{
+                Aggregate, AxisKind, ChartKind, ControlBinding, ControlFilter, ControlKind,
+                CreateAxis, CreateChart, CreateControl, CreateDashboard, DashboardControl,
+            },
+        },
+        test_util,
+    };
+    use chrono::{DateTime, Utc};
+    use serde_json::{Value, json};
+    use sqlx::{PgPool, types::Json};
+    use std::collections::HashMap;
+
+    #[sqlx::test]
+    async fn controls(db: PgPool) -> anyhow::Result<()> {
+        let mut server = test_util::server(db.clone()).await;
+        let dashboard_id = db::create_dashboard(
+            &db,
+            CreateDashboard {
+                name: "Test".into(),
+                description: "".into(),
+            },
+        )
+        .await?
+        .dashboard_id;
+        let table_id = db::create_table(
+            &db,
+            CreateTable {
+                name: "Test".into(),
+                description: "".into(),
+                parent_id: None,
+            },
+        )
+        .await?
+        .table_id;
+        let field_id = db::create_field(
+            &db,
+            table_id,
+            CreateField {
+                name: "Date".into(),
+                field_kind: FieldKind::DateTime {
+                    is_required: false,
+                    range_start: None,
+                    range_end: None,
+                },
+            },
+        )
+        .await?
+        .field_id;
+        let chart_id = db::create_chart(
+            &db,
+            dashboard_id,
+            CreateChart {
+                table_id,
+                name: "Test".into(),
+                chart_kind: ChartKind::Bar,
+                top_n: None,
+            },
+        )
+        .await?
+        .chart_id;
+
+        let path = format!("/api/dashboards/{dashboard_id}/controls");
+        let create_control = json!({
+            "name": "Period",
+            "control_kind": "DateRange",
+            "bindings": [{ "chart_id": chart_id, "field_id": field_id }],
+        });
+
+        server
+            .post(&path)
+            .json(&create_control)
+            .await
+            .assert_status_unauthorized();
+
+        let user = db::create_user(&db, "test".into(), "".into(), false).await?;
+        test_util::login_session(&mut server, &user).await;
+        test_util::test_access_control(
+            &db,
+            Resource::Dashboard,
+            dashboard_id,
+            user.user_id,
+            AccessRole::Editor,
+            async || server.post(&path).json(&create_control).await,
+        )
+        .await;
+        test_util::test_access_control(
+            &db,
+            Resource::Dashboard,
+            dashboard_id,
+            user.user_id,
+            AccessRole::Viewer,
+            async || server.get(&path).await,
+        )
+        .await;
+
+        server
+            .post(&path)
+            .json(&json!({
+                "name": "Amount",
+                "control_kind": "NumberRange",
+                "bindings": [{ "chart_id": chart_id, "field_id": field_id }],
+            }))
+            .await
+            .assert_status_unprocessable_entity();
+
+        let controls: Vec<DashboardControl> = server.get(&path).await.json();
+        assert_eq!(controls.len(), 2);
+        assert_eq!(
+            controls[0].bindings.0,
+            [ControlBinding { chart_id, field_id }]
+        );
+
+        let control_path = format!("{path}/{}", controls[0].control_id);
+        let update_control = json!({ "name": "Date", "control_kind": "DateRange" });
+        test_util::test_access_control(
+            &db,
+            Resource::Dashboard,
+            dashboard_id,
+            user.user_id,
+            AccessRole::Editor,
+            async || server.patch(&control_path).json(&update_control).await,
+        )
+        .await;
+        server
+            .patch(&format!("{path}/1000"))
+            .json(&update_control)
+            .await
+            .assert_status_not_found();
+        let control: DashboardControl = server
+            .patch(&control_path)
+            .json(&update_control)
+            .await
+            .json();
+        assert_eq!(control.name, "Date");
+        assert!(control.bindings.is_empty());
+
+        server.delete(&control_path).await.assert_status_ok();
+        server.delete(&control_path).await.assert_status_not_found();
+        let controls: Vec<DashboardControl> = server.get(&path).await.json();
+        assert_eq!(controls.len(), 1);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn get_chart_data_controls(db: PgPool) -> anyhow::Result<()> {
+        let mut server = test_util::server(db.clone()).await;
+        let dashboard_id = db::create_dashboard(
+            &db,
+            CreateDashboard {
+                name: "Test".into(),
+                description: "".into(),
+            },
+        )
+        .await?
+        .dashboard_id;
+        let table_id = db::create_table(
+            &db,
+            CreateTable {
+                name: "Test".into(),
+                description: "".into(),
+                parent_id: None,
+            },
+        )
+        .await?
+        .table_id;
+        let field = db::create_field(
+            &db,
+            table_id,
+            CreateField {
+                name: "Amount".into(),
+                field_kind: FieldKind::Integer {
+                    is_required: false,
+                    range_start: None,
+                    range_end: None,
+                },
+            },
+        )
+        .await?;
+        db::create_entries(
+            &db,
+            table_id,
+            None,
+            vec![FieldMetadata::from_field(field.clone())],
+            (1..=5).map(|i| vec![Cell::Integer(i)]).collect(),
+        )
+        .await?;
+        let chart_id = db::create_chart(
+            &db,
+            dashboard_id,
+            CreateChart {
+                table_id,
+                name: "Test".into(),
+                chart_kind: ChartKind::Kpi,
+                top_n: None,
+            },
+        )
+        .await?
+        .chart_id;
+        let axis_id = db::set_axes(
+            &db,
+            chart_id,
+            table_id,
+            vec![CreateAxis {
+                field_id: field.field_id,
+                table_path: Vec::new(),
+                axis_kind: AxisKind::Y,
+                aggregate: Some(Aggregate::Count),
+                percentile: None,
+                order_field_id: None,
+                sort: None,
+                bins: None,
+                bucket: None,
+            }],
+        )
+        .await?[0]
+            .axis_id;
+        let control_id = db::create_control(
+            &db,
+            dashboard_id,
+            CreateControl {
+                name: "Amount".into(),
+                control_kind: ControlKind::NumberRange,
+                bindings: vec![ControlBinding {
+                    chart_id,
+                    field_id: field.field_id,
+                }],
+            },
+        )
+        .await?
+        .control_id;
+
+        let user = db::create_user(&db, "test".into(), "".into(), false).await?;
+        test_util::login_session(&mut server, &user).await;
+        db::create_access(
+            &db,
+            Resource::Dashboard,
+            dashboard_id,
+            user.user_id,
+            AccessRole::Viewer,
+        )
+        .await?;
+
+        let path = format!("/api/dashboards/{dashboard_id}/charts/{chart_id}/data");
+        let get_count = async |controls: Value| {
+            let response = server
+                .get(&path)
+                .add_query_param("controls", controls.to_string())
+                .await;
+            response.assert_status_ok();
+            let chart_data: Value = response.json();
+            chart_data["cells"][0][axis_id.to_string()].clone()
+        };
+
+        assert_eq!(get_count(json!([])).await, json!(5));
+        assert_eq!(
+            get_count(
+                json!([{ "control_id": control_id, "operator": "Range", "start": 2, "end": 4 }])
+            )
+            .await,
+            json!(3)
+        );
+
+        for controls in [
+            json!([{ "control_id": 1000, "operator": "Range", "start": 2 }]),
+            json!([{ "control_id": control_id, "operator": "IsNull" }]),
+            json!([{ "control_id": control_id, "operator": "Range", "start": "a" }]),
+        ] {
+            server
+                .get(&path)
+                .add_query_param("controls", controls.to_string())
+                .await
+                .assert_status_unprocessable_entity();
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn validate_bindings() {
+        let date_time = FieldMetadata {
+            field_id: 1,
+            field_kind: Json(FieldKind::DateTime {
+                is_required: false,
+                range_start: None,
+                range_end: None,
+            }),
+        };
+        let checkbox = FieldMetadata {
+            field_id: 2,
+            field_kind: Json(FieldKind::Checkbox),
+        };
+        let chart_fields = HashMap::from([(1, vec![date_time, checkbox])]);
+        let binding = |chart_id, field_id| ControlBinding { chart_id, field_id };
+
+        assert!(
+            super::validate_bindings(ControlKind::DateRange, &[binding(1, 1)], &chart_fields)
+                .is_ok()
+        );
+        assert_eq!(
+            super::validate_bindings(
+                ControlKind::DateRange,
+                &[binding(1, 2), binding(1, 1), binding(2, 1), binding(1, 3)],
+                &chart_fields,
+            )
+            .unwrap_err(),
+            [
+                format!("1: {INVALID_CONTROL_FIELD}"),
+                format!("1: {DUPLICATE_CHART_BINDING}"),
+                format!("2: {CHART_NOT_FOUND}"),
+                format!("1: {DUPLICATE_CHART_BINDING}"),
+            ]
+        );
+        assert_eq!(
+            super::validate_bindings(ControlKind::DateRange, &[binding(1, 3)], &chart_fields)
+                .unwrap_err(),
+            [format!("1: {FIELD_NOT_FOUND}")]
+        );
+    }
+
+    #[test]
+    fn convert_control_filters() {
+        let enumeration = FieldMetadata {
+            field_id: 1,
+            field_kind: Json(FieldKind::Enumeration {
+                is_required: false,
+                values: HashMap::from([(10, "Open".into()), (11, "Closed".into())]),
+                default_value: 10,
+            }),
+        };
+        let date_time = FieldMetadata {
+            field_id: 2,
+            field_kind: Json(FieldKind::DateTime {
+                is_required: false,
+                range_start: None,
+                range_end: None,
+            }),
+        };
+        let control = |control_id: Id, control_kind, field_id| DashboardControl {
+            control_id,
+            dashboard_id: 1,
+            name: "".into(),
+            control_kind,
+            bindings: Json(vec![ControlBinding {
+                chart_id: 1,
+                field_id,
+            }]),
+            created_at: Utc::now(),
+            updated_at: None,
+        };
+        let controls = [
+            control(1, ControlKind::Enumeration, 1),
+            control(2, ControlKind::DateRange, 2),
+        ];
+        let fields = [enumeration, date_time];
+        let start: DateTime<Utc> = "2024-01-01T00:00:00Z".parse().unwrap();
+
+        let filters = super::convert_control_filters(
+            vec![
+                ControlFilter {
+                    control_id: 1,
+                    operator: FilterOperator::In {
+                        values: vec![json!("Closed"), json!("Missing")],
+                    },
+                },
+                ControlFilter {
+                    control_id: 2,
+                    operator: FilterOperator::Range {
+                        start: Some(json!(start)),
+                        end: None,
+                    },
+                },
+            ],
+            &controls,
+            1,
+            &fields,
+        )
+        .unwrap();
+        assert_eq!(
+            filters,
+            [
+                EntryFilter {
+                    field_id: 1,
+                    operator: FilterOperator::In {
+                        values: vec![Cell::Integer(11)],
+                    },
+                },
+                EntryFilter {
+                    field_id: 2,
+                    operator: FilterOperator::Range {
+                        start: Some(Cell::DateTime(start)),
+                        end: None,
+                    },
+                },
+            ]
+        );
+
+        let filters = super::convert_control_filters(
+            vec![ControlFilter {
+                control_id: 2,
+                operator: FilterOperator::WithinLastDays { days: 7 },
+            }],
+            &controls,
+            2,
+            &fields,
+        )
+        .unwrap();
+        assert!(filters.is_empty());
+
+        let error_messages = super::convert_control_filters(
+            vec![
+                ControlFilter {
+                    control_id: 3,
+                    operator: FilterOperator::IsNull,
+                },
+                ControlFilter {
+                    control_id: 2,
+                    operator: FilterOperator::In { values: Vec::new() },
+                },
+                ControlFilter {
+                    control_id: 1,
+                    operator: FilterOperator::In {
+                        values: vec![json!(10)],
+                    },
+                },
+                ControlFilter {
+                    control_id: 2,
+                    operator: FilterOperator::WithinLastDays { days: 100_000 },
+                },
+            ],
+            &controls,
+            1,
+            &fields,
+        )
+        .unwrap_err();
+        assert_eq!(
+            error_messages,
+            [
+                format!("3: {CONTROL_NOT_FOUND}"),
+                format!("2: {INVALID_CONTROL_OPERATOR}"),
+                format!("1: {INVALID_TYPE}"),
+                format!("2: {INVALID_DAYS}"),
+            ]
+        );
+    }
+}
@@ -1,27 +1,40 @@
 //! Routes for managing dashboards.
 
 use crate::{
-    AppState,
-    auth::AppAuthSession,
+    AppState, Id,
+    auth::{AppApiToken, AppAuthSession},
     db,
     error::{ApiError, ApiResult},
     model::{
         access::{AccessRole, AccessRoleCheck, Resource},
-        viz::{CreateDashboard, Dashboard, GetDashboard, SelectDashboard, UpdateDashboard},
+        tokens::ApiToken,
+        viz::{
+            Chart, CreateDashboard, Dashboard, GetDashboard, SelectDashboard, SetLayout,
+            UpdateDashboard,
+        },
     },
 };
 use aide::{
     NoApi,
     axum::{
         ApiRouter,
-        routing::{patch_with, post_with},
+        routing::{patch_with, post_with, put_with},
     },
 };
 use axum::{
-    Json,
+    Extension, Json,
     extract::{Path, State},
 };
 use axum_login::AuthSession;
+use itertools::Itertools;
+use std::collections::HashSet;
+
+const CHART_NOT_FOUND: &str = "Chart not found";
+const DUPLICATE_CHART_LAYOUT: &str = "Chart layout is set more than once";
+const INVALID_CHART_LAYOUT: &str = "Chart layout must have a positive size and fit in the grid";
+const INVALID_TABS: &str = "Tab names must be unique and not empty";
+const OVERLAPPING_CHART_LAYOUT: &str = "Chart layout overlaps another chart of the same tab";
+const TAB_NOT_FOUND: &str = "Chart tab is not a tab of the dashboard";
 
 pub fn router() -> ApiRouter<AppState> {
     ApiRouter::new().nest(
@@ -36,6 +49,10 @@ pub fn router() -> ApiRouter<AppState> {
                 "/{dashboard_id}",
                 patch_with(update_dashboard, docs::update_dashboard)
                     .delete_with(delete_dashboard, docs::delete_dashboard),
+            )
+            .api_route(
+                "/{dashboard_id}/layout",
+                put_with(set_dashboard_layout, docs::set_dashboard_layout),
             ),
     )
 }
@@ -99,30 +116,127 @@ async fn delete_dashboard(
     Ok(())
 }
 
+async fn set_dashboard_layout(
+    NoApi(AuthSession { user, .. }): AppAuthSession,
+    State(AppState { db, .. }): State<AppState>,
+    Path(SelectDashboard { dashboard_id }): Path<SelectDashboard>,
+    Json(set_layout): Json<SetLayout>,
+) -> ApiResult<Json<Vec<Chart>>> {
+    let user_id = user.ok_or(ApiError::Unauthorized)?.user_id;
+    let mut tx = db.begin().await?;
+
+    db::get_access_role(tx.as_mut(), Resource::Dashboard, dashboard_id, user_id)
+        .await?
+        .check(AccessRole::Editor)?;
+
+    let chart_ids: HashSet<_> = db::get_charts(tx.as_mut(), dashboard_id)
+        .await?
+        .into_iter()
+        .map(|chart| chart.chart_id)
+        .collect();
+    validate_layout(&set_layout, &chart_ids)
+        .map_err(|error_messages| ApiError::UnprocessableEntity(error_messages.join(", ")))?;
+
+    let charts = db::set_dashboard_layout(tx.as_mut(), dashboard_id, set_layout).await?;
+
+    tx.commit().await?;
+    Ok(Json(charts))
+}
+
 async fn get_dashboards(
     NoApi(AuthSession { user, .. }): AppAuthSession,
+    NoApi(token): AppApiToken,
     State(AppState { db, .. }): State<AppState>,
 ) -> ApiResult<Json<Vec<GetDashboard>>> {
     let user_id = user.ok_or(ApiError::Unauthorized)?.user_id;
 
-    let dashboards = db::get_dashboards_for_user(&db, user_id).await?;
+    let mut dashboards = db::get_dashboards_for_user(&db, user_id).await?;
+    if let Some(Extension(ApiToken {
+        dashboard_ids: Some(dashboard_ids),
+        ..
+    })) = token
+    {
+        dashboards.retain(|dashboard| dashboard_ids.contains(&dashboard.dashboard.dashboard_id));
+    }
 
     Ok(Json(dashboards))
 }
 
+/// Validate the tabs and the chart layouts against the charts of the dashboard.
+/// Return the messages of every violated rule, prefixed by the chart ID if relevant.
+fn validate_layout(
+    SetLayout { tabs, charts }: &SetLayout,
+    chart_ids: &HashSet<Id>,
+) -> Result<(), Vec<String>> {
+    let mut error_messages = Vec::new();
+
+    if !tabs.iter().all_unique() || tabs.iter().any(|tab| tab.trim().is_empty()) {
+        error_messages.push(INVALID_TABS.to_string());
+    }
+
+    for (index, chart) in charts.iter().enumerate() {
+        let mut messages = Vec::new();
+        if !chart_ids.contains(&chart.chart_id) {
+            messages.push(CHART_NOT_FOUND);
+        }
+        if charts[..index]
+            .iter()
+            .any(|other| other.chart_id == chart.chart_id)
+        {
+            messages.push(DUPLICATE_CHART_LAYOUT);
+        }
+        if !chart.layout.is_in_grid() {
+            messages.push(INVALID_CHART_LAYOUT);
+        }
+        if chart
+            .layout
+            .tab
+            .as_ref()
+            .is_some_and(|tab| !tabs.contains(tab))
+        {
+            messages.push(TAB_NOT_FOUND);
+        }
+        if charts
+            .iter()
+            .enumerate()
+            .any(|(i, other)| i != index && chart.layout.overlaps(&other.layout))
+        {
+            messages.push(OVERLAPPING_CHART_LAYOUT);
+        }
+        error_messages.extend(
+            messages
+                .into_iter()
+                .map(|message| format!("{}: {message}", chart.chart_id)),
+        );
+    }
+
+    if error_messages.is_empty() {
+        Ok(())
+    } else {
+        Err(error_messages)
+    }
+}
+
 #[cfg_attr(coverage_nightly, coverage(off))]
 mod docs {
     use crate::{
+        api::viz::dashboards::{
+            CHART_NOT_FOUND, DUPLICATE_CHART_LAYOUT, INVALID_CHART_LAYOUT, INVALID_TABS,
+            OVERLAPPING_CHART_LAYOUT, TAB_NOT_FOUND,
+        },
         docs::{DASHBOARDS_TAG, TransformOperationExt, template},
         model::{
             access::{AccessRole, Resource},
-            viz::Dashboard,
+            viz::{Chart, Dashboard},
         },
     };
     use aide::{OperationOutput, transform::TransformOperation};
     use axum::Json;
+    use itertools::Itertools;
 
     const DASHBOARD_OWNER: [(Resource, AccessRole); 1] = [(Resource::Dashboard, AccessRole::Owner)];
+    const DASHBOARD_EDITOR: [(Resource, AccessRole); 1] =
+        [(Resource::Dashboard, AccessRole::Editor)];
 
     fn dashboards<'a, R: OperationOutput>(
         op: TransformOperation<'a>,
@@ -152,6 +266,32 @@ mod docs {
         .required_access(DASHBOARD_OWNER)
     }
 
+    pub fn set_dashboard_layout(op: TransformOperation) -> TransformOperation {
+        let errors = [
+            CHART_NOT_FOUND,
+            DUPLICATE_CHART_LAYOUT,
+            INVALID_CHART_LAYOUT,
+            OVERLAPPING_CHART_LAYOUT,
+            TAB_NOT_FOUND,
+        ]
+        .into_iter()
+        .map(|v| format!("<chart_id> : {v}"))
+        .chain([INVALID_TABS.to_string()])
+        .join("\n\n");
+
+        dashboards::<Json<Vec<Chart>>>(
+            op,
+            "set_dashboard_layout",
+            "Set the tabs of the dashboard and the position and size of its charts \
+            on a grid of 12 columns and 1000 rows. \
+            Charts of the same tab cannot overlap and the charts missing from the request \
+            are removed from the grid. Return all the charts of the dashboard.",
+        )
+        .response_description::<404, ()>("Dashboard not found")
+        .response_description::<422, String>(&errors)
+        .required_access(DASHBOARD_EDITOR)
+    }
+
     pub fn get_dashboards(op: TransformOperation) -> TransformOperation {
         dashboards::<Json<Vec<Dashboard>>>(
             op,
@@ -168,12 +308,17 @@ mod test {
         db,
         model::{
             access::{AccessRole, Resource},
-            viz::{CreateDashboard, Dashboard, GetDashboard, UpdateDashboard},
+            data::CreateTable,
+            viz::{
+                Chart, ChartKind, ChartLayout, CreateChart, CreateDashboard, Dashboard,
+                GetDashboard, SetChartLayout, SetLayout, UpdateDashboard,
+            },
         },
         test_util,
     };
     use anyhow::Ok;
     use sqlx::PgPool;
+    use std::collections::HashSet;
 
     #[sqlx::test]
     async fn create_dashboard(db: PgPool) -> anyhow::Result<()> {
@@ -281,6 +426,220 @@ mod test {
         Ok(())
     }
 
+    #[sqlx::test]
+    async fn set_dashboard_layout(db: PgPool) -> anyhow::Result<()> {
+        let mut server = test_util::server(db.clone()).await;
+        let dashboard_id = db::create_dashboard(
+            &db,
+            CreateDashboard {
+                name: "A".into(),
+                description: "B".into(),
+            },
+        )
+        .await?
+        .dashboard_id;
+        let table_id = db::create_table(
+            &db,
+            CreateTable {
+                name: "Test".into(),
+                description: "".into(),
+                parent_id: None,
+            },
+        )
+        .await?
+        .table_id;
+        let mut chart_ids = Vec::new();
+        for name in ["C", "D"] {
+            let chart = db::create_chart(
+                &db,
+                dashboard_id,
+                CreateChart {
+                    table_id,
+                    name: name.into(),
+                    chart_kind: ChartKind::Bar,
+                    top_n: None,
+                },
+            )
+            .await?;
+            chart_ids.push(chart.chart_id);
+        }
+        let path = format!("/api/dashboards/{dashboard_id}/layout");
+
+        let layout = |x, y, tab: Option<&str>| ChartLayout {
+            x,
+            y,
+            width: 6,
+            height: 4,
+            tab: tab.map(Into::into),
+        };
+        let set_layout = SetLayout {
+            tabs: vec!["Overview".into(), "Details".into()],
+            charts: vec![
+                SetChartLayout {
+                    chart_id: chart_ids[0],
+                    layout: layout(0, 0, Some("Overview")),
+                },
+                SetChartLayout {
+                    chart_id: chart_ids[1],
+                    layout: layout(0, 0, Some("Details")),
+                },
+            ],
+        };
+
+        server
+            .put(&path)
+            .json(&set_layout)
+            .await
+            .assert_status_unauthorized();
+
+        let user = db::create_user(&db, "test".into(), "".into(), false).await?;
+        test_util::login_session(&mut server, &user).await;
+        test_util::test_access_control(
+            &db,
+            Resource::Dashboard,
+            dashboard_id,
+            user.user_id,
+            AccessRole::Editor,
+            async || server.put(&path).json(&set_layout).await,
+        )
+        .await;
+
+        server
+            .put("/api/dashboards/1000/layout")
+            .json(&set_layout)
+            .await
+            .assert_status_not_found();
+
+        let response = server.put(&path).json(&set_layout).await;
+        response.assert_status_ok();
+        let charts_1: Vec<Chart> = response.json();
+        for SetChartLayout { chart_id, layout } in &set_layout.charts {
+            let chart = charts_1.iter().find(|c| c.chart_id == *chart_id).unwrap();
+            assert_eq!(chart.layout.as_ref().map(|l| &l.0), Some(layout));
+        }
+        let charts_2: Vec<Chart> = server
+            .get(&format!("/api/dashboards/{dashboard_id}/charts"))
+            .await
+            .json();
+        test_util::assert_eq_vec(charts_1, charts_2, |c| c.chart_id);
+        let dashboard: Dashboard =
+            sqlx::query_as(r#"SELECT * FROM dashboard WHERE dashboard_id = $1"#)
+                .bind(dashboard_id)
+                .fetch_one(&db)
+                .await?;
+        assert_eq!(dashboard.tabs, set_layout.tabs);
+
+        let invalid_layout = SetLayout {
+            tabs: vec!["Overview".into()],
+            charts: vec![
+                SetChartLayout {
+                    chart_id: chart_ids[0],
+                    layout: layout(0, 0, Some("Overview")),
+                },
+                SetChartLayout {
+                    chart_id: chart_ids[1],
+                    layout: layout(3, 2, Some("Overview")),
+                },
+                SetChartLayout {
+                    chart_id: 1000,
+                    layout: layout(8, 0, Some("Details")),
+                },
+            ],
+        };
+        let response = server.put(&path).json(&invalid_layout).await;
+        response.assert_status_unprocessable_entity();
+        let error_message = response.text();
+        for message in [
+            format!("{}: {}", chart_ids[0], super::OVERLAPPING_CHART_LAYOUT),
+            format!("{}: {}", chart_ids[1], super::OVERLAPPING_CHART_LAYOUT),
+            format!("1000: {}", super::CHART_NOT_FOUND),
+            format!("1000: {}", super::INVALID_CHART_LAYOUT),
+            format!("1000: {}", super::TAB_NOT_FOUND),
+        ] {
+            assert!(error_message.contains(&message), "{message}");
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn validate_layout() {
+        let chart_ids = HashSet::from([1, 2]);
+        let layout = |chart_id, x, y, width, height, tab: Option<&str>| SetChartLayout {
+            chart_id,
+            layout: ChartLayout {
+                x,
+                y,
+                width,
+                height,
+                tab: tab.map(Into::into),
+            },
+        };
+        let tabs = vec!["A".to_string(), "B".to_string()];
+        for (tabs, charts, is_ok) in [
+            (vec![], vec![], true),
+            (tabs.clone(), vec![], true),
+            (vec!["A".into(), "A".into()], vec![], false),
+            (vec![" ".into()], vec![], false),
+            (
+                vec![],
+                vec![layout(1, 0, 0, 6, 2, None), layout(2, 6, 0, 6, 2, None)],
+                true,
+            ),
+            (
+                vec![],
+                vec![layout(1, 0, 0, 6, 2, None), layout(2, 0, 2, 12, 2, None)],
+                true,
+            ),
+            (
+                vec![],
+                vec![layout(1, 0, 0, 6, 2, None), layout(2, 5, 1, 6, 2, None)],
+                false,
+            ),
+            (
+                tabs.clone(),
+                vec![
+                    layout(1, 0, 0, 6, 2, Some("A")),
+                    layout(2, 0, 0, 6, 2, Some("B")),
+                ],
+                true,
+            ),
+            (tabs.clone(), vec![layout(1, 0, 0, 6, 2, Some("C"))], false),
+            (vec![], vec![layout(3, 0, 0, 6, 2, None)], false),
+            (
+                vec![],
+                vec![layout(1, 0, 0, 6, 2, None), layout(1, 6, 0, 6, 2, None)],
+                false,
+            ),
+            (vec![], vec![layout(1, 7, 0, 6, 2, None)], false),
+            (vec![], vec![layout(1, -1, 0, 6, 2, None)], false),
+            (vec![], vec![layout(1, 0, -1, 6, 2, None)], false),
+            (vec![], vec![layout(1, 0, 0, 0, 2, None)], false),
+            (vec![], vec![layout(1, 0, 0, 6, 0, None)], false),
+            (vec![], vec![layout(1, 0, 994, 6, 6, None)], true),
+            (vec![], vec![layout(1, 0, 995, 6, 6, None)], false),
+            (vec![], vec![layout(1, i32::MAX, 0, 6, 2, None)], false),
+            (vec![], vec![layout(1, 0, 0, i32::MAX, 2, None)], false),
+            (vec![], vec![layout(1, 0, i32::MAX, 6, 2, None)], false),
+            (vec![], vec![layout(1, 0, 0, 6, i32::MAX, None)], false),
+            (
+                vec![],
+                vec![
+                    layout(1, i32::MAX, i32::MAX, i32::MAX, i32::MAX, None),
+                    layout(2, 0, 0, 6, 2, None),
+                ],
+                false,
+            ),
+        ] {
+            let set_layout = SetLayout { tabs, charts };
+            assert_eq!(
+                super::validate_layout(&set_layout, &chart_ids).is_ok(),
+                is_ok,
+                "{set_layout:?}"
+            );
+        }
+    }
+
     #[sqlx::test]
     async fn delete_dashboard(db: PgPool) -> anyhow::Result<()> {
         let mut server = test_util::server(db.clone()).await;
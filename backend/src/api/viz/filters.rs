@@ -0,0 +1,463 @@
+//! Routes for managing chart filters.
+
+use crate::{
+    AppState,
+    api::data::entries::convert_filters,
+    auth::AppAuthSession,
+    db,
+    error::{ApiError, ApiResult},
+    model::{
+        access::{AccessRole, AccessRoleCheck, Resource},
+        viz::{ChartFilter, SelectChart, SetChartFilters},
+    },
+};
+use aide::{
+    NoApi,
+    axum::{ApiRouter, routing::put_with},
+};
+use axum::{
+    Json,
+    extract::{Path, State},
+};
+use axum_login::AuthSession;
+
+pub fn router() -> ApiRouter<AppState> {
+    ApiRouter::new().nest(
+        "/dashboards/{dashboard_id}/charts/{chart_id}/filters",
+        ApiRouter::new().api_route(
+            "/",
+            put_with(set_chart_filters, docs::set_chart_filters)
+                .get_with(get_chart_filters, docs::get_chart_filters),
+        ),
+    )
+}
+
+async fn set_chart_filters(
+    NoApi(AuthSession { user, .. }): AppAuthSession,
+    State(AppState { db, .. }): State<AppState>,
+    Path(SelectChart {
+        dashboard_id,
+        chart_id,
+    }): Path<SelectChart>,
+    Json(SetChartFilters(filters)): Json<SetChartFilters>,
+) -> ApiResult<Json<Vec<ChartFilter>>> {
+    let user_id = user.ok_or(ApiError::Unauthorized)?.user_id;
+    let mut tx = db.begin().await?;
+
+    db::get_access_role(tx.as_mut(), Resource::Dashboard, dashboard_id, user_id)
+        .await?
+        .check(AccessRole::Editor)?;
+    if !db::chart_exists(tx.as_mut(), dashboard_id, chart_id).await? {
+        return Err(ApiError::NotFound);
+    };
+
+    let table_id = db::get_chart_table_id(tx.as_mut(), chart_id).await?;
+    let fields = db::get_fields_metadata(tx.as_mut(), table_id).await?;
+
+    let mut error_messages = Vec::new();
+    convert_filters(filters.clone(), &fields, &mut error_messages);
+    if !error_messages.is_empty() {
+        return Err(ApiError::UnprocessableEntity(error_messages.join(", ")));
+    }
+
+    let filters = db::set_chart_filters(tx.as_mut(), chart_id, filters).await?;
+
+    tx.commit().await?;
+    Ok(Json(filters))
+}
+
+async fn get_chart_filters(
+    NoApi(AuthSession { user, .. }): AppAuthSession,
+    State(AppState { db, .. }): State<AppState>,
+    Path(SelectChart {
+        dashboard_id,
+        chart_id,
+    }): Path<SelectChart>,
+) -> ApiResult<Json<Vec<ChartFilter>>> {
+    let user_id = user.ok_or(ApiError::Unauthorized)?.user_id;
+
+    db::get_access_role(&db, Resource::Dashboard, dashboard_id, user_id)
+        .await?
+        .check(AccessRole::Viewer)?;
+    if !db::chart_exists(&db, dashboard_id, chart_id).await? {
+        return Err(ApiError::NotFound);
+    };
+
+    Ok(Json(db::get_chart_filters(&db, chart_id).await?))
+}
+
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod docs {
+    use crate::{
+        api::data::entries::{INVALID_DAYS, INVALID_FIELD_ID, INVALID_OPERATOR, INVALID_TYPE},
+        docs::{FILTERS_TAG, TransformOperationExt, template},
+        model::{
+            access::{AccessRole, Resource},
+            viz::ChartFilter,
+        },
+    };
+    use aide::{OperationOutput, transform::TransformOperation};
+    use axum::Json;
+    use itertools::Itertools;
+
+    const DASHBOARD_EDITOR: [(Resource, AccessRole); 1] =
+        [(Resource::Dashboard, AccessRole::Editor)];
+    const DASHBOARD_VIEWER: [(Resource, AccessRole); 1] =
+        [(Resource::Dashboard, AccessRole::Viewer)];
+
+    fn filters<'a, R: OperationOutput>(
+        op: TransformOperation<'a>,
+        summary: &'a str,
+        description: &'a str,
+    ) -> TransformOperation<'a> {
+        template::<R>(op, summary, description, true, FILTERS_TAG)
+            .response_description::<404, ()>("Dashboard not found\n\nChart not found")
+    }
+
+    pub fn set_chart_filters(op: TransformOperation) -> TransformOperation {
+        let errors = [
+            INVALID_FIELD_ID,
+            INVALID_OPERATOR,
+            INVALID_DAYS,
+            INVALID_TYPE,
+        ]
+        .into_iter()
+        .map(|v| format!("<field_id>: {v}"))
+        .join("\n\n");
+
+        filters::<Json<Vec<ChartFilter>>>(
+            op,
+            "set_chart_filters",
+            "Set all the filters of the specified chart and rebuild the dynamic view. \
+            Only the rows matching all the filters are aggregated by the chart.",
+        )
+        .response_description::<422, String>(&errors)
+        .required_access(DASHBOARD_EDITOR)
+    }
+
+    pub fn get_chart_filters(op: TransformOperation) -> TransformOperation {
+        filters::<Json<Vec<ChartFilter>>>(
+            op,
+            "get_chart_filters",
+            "Get all the filters of the specified chart.",
+        )
+        .required_access(DASHBOARD_VIEWER)
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod test {
+    use crate::{
+        db,
+        model::{
+            Cell,
+            access::{AccessRole, Resource},
+            data::{
+                CreateField, CreateTable, EntryFilter, FieldKind, FieldMetadata, FilterOperator,
+            },
+            viz::{
+                Aggregate, AxisKind, ChartFilter, ChartIdentifier, ChartKind, CreateAxis,
+                CreateChart, CreateDashboard, SetChartFilters,
+            },
+        },
+        test_util,
+    };
+    use chrono::{TimeDelta, Utc};
+    use serde_json::json;
+    use sqlx::PgPool;
+    use std::collections::HashMap;
+
+    #[sqlx::test]
+    async fn set_chart_filters(db: PgPool) -> anyhow::Result<()> {
+        let mut server = test_util::server(db.clone()).await;
+        let dashboard_id = db::create_dashboard(
+            &db,
+            CreateDashboard {
+                name: "Test".into(),
+                description: "".into(),
+            },
+        )
+        .await?
+        .dashboard_id;
+        let table_id = db::create_table(
+            &db,
+            CreateTable {
+                name: "Test".into(),
+                description: "".into(),
+                parent_id: None,
+            },
+        )
+        .await?
+        .table_id;
+
+        let status_field = FieldMetadata::from_field(
+            db::create_field(
+                &db,
+                table_id,
+                CreateField {
+                    name: "Status".into(),
+                    field_kind: FieldKind::Enumeration {
+                        is_required: true,
+                        values: HashMap::from_iter([
+                            (0, "Scheduled".into()),
+                            (1, "Completed".into()),
+                        ]),
+                        default_value: 0,
+                    },
+                },
+            )
+            .await?,
+        );
+        let due_date_field = FieldMetadata::from_field(
+            db::create_field(
+                &db,
+                table_id,
+                CreateField {
+                    name: "Due Date".into(),
+                    field_kind: FieldKind::DateTime {
+                        is_required: true,
+                        range_start: None,
+                        range_end: None,
+                    },
+                },
+            )
+            .await?,
+        );
+        let now = Utc::now();
+        db::create_entries(
+            &db,
+            table_id,
+            None,
+            vec![status_field.clone(), due_date_field.clone()],
+            [(0, 2), (1, 5), (1, 40), (1, 60), (0, 90)]
+                .map(|(status, days)| {
+                    vec![
+                        Cell::Integer(status),
+                        Cell::DateTime(now - TimeDelta::days(days)),
+                    ]
+                })
+                .into(),
+        )
+        .await?;
+        let chart_id = db::create_chart(
+            &db,
+            dashboard_id,
+            CreateChart {
+                table_id,
+                name: "Test".into(),
+                chart_kind: ChartKind::Bar,
+                top_n: None,
+            },
+        )
+        .await?
+        .chart_id;
+        db::set_axes(
+            &db,
+            chart_id,
+            table_id,
+            vec![CreateAxis {
+                field_id: status_field.field_id,
+                table_path: Vec::new(),
+                axis_kind: AxisKind::Y,
+                aggregate: Some(Aggregate::Count),
+                percentile: None,
+                order_field_id: None,
+                sort: None,
+                bins: None,
+                bucket: None,
+            }],
+        )
+        .await?;
+
+        let path = format!("/api/dashboards/{dashboard_id}/charts/{chart_id}/filters");
+        let set_filters = SetChartFilters(vec![
+            EntryFilter {
+                field_id: status_field.field_id,
+                operator: FilterOperator::Equals { value: json!(1) },
+            },
+            EntryFilter {
+                field_id: due_date_field.field_id,
+                operator: FilterOperator::WithinLastDays { days: 30 },
+            },
+        ]);
+
+        server
+            .put(&path)
+            .json(&set_filters)
+            .await
+            .assert_status_unauthorized();
+
+        let user = db::create_user(&db, "test".into(), "".into(), false).await?;
+        test_util::login_session(&mut server, &user).await;
+        test_util::test_access_control(
+            &db,
+            Resource::Dashboard,
+            dashboard_id,
+            user.user_id,
+            AccessRole::Editor,
+            async || server.put(&path).json(&set_filters).await,
+        )
+        .await;
+
+        for path_wrong in [
+            format!("/api/dashboards/1000/charts/{chart_id}/filters"),
+            format!("/api/dashboards/{dashboard_id}/charts/1000/filters"),
+        ] {
+            server
+                .put(&path_wrong)
+                .json(&set_filters)
+                .await
+                .assert_status_not_found();
+        }
+        server
+            .put(&path)
+            .json(&SetChartFilters(vec![EntryFilter {
+                field_id: due_date_field.field_id,
+                operator: FilterOperator::WithinLastDays { days: 100_000 },
+            }]))
+            .await
+            .assert_status_unprocessable_entity();
+
+        let response = server.put(&path).json(&set_filters).await;
+        response.assert_status_ok();
+        let filters_1: Vec<ChartFilter> = response.json();
+        assert_eq!(filters_1.len(), 2);
+        for (filter, set_filter) in filters_1.iter().zip(&set_filters.0) {
+            assert_eq!(filter.field_id, set_filter.field_id);
+            assert_eq!(filter.operator.0, set_filter.operator);
+        }
+
+        let response = server.get(&path).await;
+        response.assert_status_ok();
+        let filters_2: Vec<ChartFilter> = response.json();
+        assert_eq!(filters_1, filters_2);
+
+        // Only the completed entry due in the last 30 days is counted.
+        let chart_ident = ChartIdentifier::new(chart_id, "data_view");
+        // The view is replaced between queries, so the statement is not cached.
+        let count_query = format!(r#"SELECT * FROM {chart_ident}"#);
+        let count = |db: PgPool| {
+            let count_query = count_query.clone();
+            async move {
+                sqlx::query_scalar::<_, i64>(&count_query)
+                    .persistent(false)
+                    .fetch_one(&db)
+                    .await
+            }
+        };
+        assert_eq!(count(db.clone()).await?, 1);
+
+        // The filters are kept when the axes are replaced.
+        db::set_axes(
+            &db,
+            chart_id,
+            table_id,
+            vec![CreateAxis {
+                field_id: due_date_field.field_id,
+                table_path: Vec::new(),
+                axis_kind: AxisKind::Y,
+                aggregate: Some(Aggregate::Count),
+                percentile: None,
+                order_field_id: None,
+                sort: None,
+                bins: None,
+                bucket: None,
+            }],
+        )
+        .await?;
+        assert_eq!(count(db.clone()).await?, 1);
+
+        let response = server.put(&path).json(&SetChartFilters(Vec::new())).await;
+        response.assert_status_ok();
+        assert_eq!(count(db.clone()).await?, 5);
+
+        for invalid_filter in [
+            EntryFilter {
+                field_id: 1000,
+                operator: FilterOperator::IsNull,
+            },
+            EntryFilter {
+                field_id: status_field.field_id,
+                operator: FilterOperator::WithinLastDays { days: 30 },
+            },
+            EntryFilter {
+                field_id: due_date_field.field_id,
+                operator: FilterOperator::Equals { value: json!(1) },
+            },
+        ] {
+            server
+                .put(&path)
+                .json(&SetChartFilters(vec![invalid_filter]))
+                .await
+                .assert_status_unprocessable_entity();
+        }
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn get_chart_filters(db: PgPool) -> anyhow::Result<()> {
+        let mut server = test_util::server(db.clone()).await;
+        let dashboard_id = db::create_dashboard(
+            &db,
+            CreateDashboard {
+                name: "Test".into(),
+                description: "".into(),
+            },
+        )
+        .await?
+        .dashboard_id;
+        let table_id = db::create_table(
+            &db,
+            CreateTable {
+                name: "Test".into(),
+                description: "".into(),
+                parent_id: None,
+            },
+        )
+        .await?
+        .table_id;
+        let chart_id = db::create_chart(
+            &db,
+            dashboard_id,
+            CreateChart {
+                table_id,
+                name: "Test".into(),
+                chart_kind: ChartKind::Table,
+                top_n: None,
+            },
+        )
+        .await?
+        .chart_id;
+
+        let path = format!("/api/dashboards/{dashboard_id}/charts/{chart_id}/filters");
+        server.get(&path).await.assert_status_unauthorized();
+
+        let user = db::create_user(&db, "test".into(), "".into(), false).await?;
+        test_util::login_session(&mut server, &user).await;
+        test_util::test_access_control(
+            &db,
+            Resource::Dashboard,
+            dashboard_id,
+            user.user_id,
+            AccessRole::Viewer,
+            async || server.get(&path).await,
+        )
+        .await;
+
+        server
+            .get(&format!(
+                "/api/dashboards/{dashboard_id}/charts/1000/filters"
+            ))
+            .await
+            .assert_status_not_found();
+
+        let response = server.get(&path).await;
+        response.assert_status_ok();
+        let filters: Vec<ChartFilter> = response.json();
+        assert!(filters.is_empty());
+
+        Ok(())
+    }
+}
@@ -1,11 +1,13 @@
-//! Routes for managing user dashboards, charts, and axes.
+//! Routes for managing user dashboards, charts, axes, filters, and controls.
 //!
 //! Users must have the appropriate access role for any operation.
 //! Otherwise, `403 Forbidden` or `404 Not Found` is returned.
 
 mod axes;
-mod charts;
+pub(super) mod charts;
+mod controls;
 mod dashboards;
+mod filters;
 
 use aide::axum::ApiRouter;
 
@@ -16,4 +18,6 @@ pub fn router() -> ApiRouter<AppState> {
         .merge(dashboards::router())
         .merge(charts::router())
         .merge(axes::router())
+        .merge(filters::router())
+        .merge(controls::router())
 }
@@ -1,20 +1,34 @@
 //! This module contains configuration for user authentication.
 
+mod oidc;
+
+pub use oidc::*;
+
 use crate::{
-    AppState, db,
-    error::{ApiError, IntoAnyhow},
-    model::users::{Credentials, User},
+    AppState, Id, db,
+    error::{ApiError, ApiResult, IntoAnyhow},
+    model::{
+        tokens::{ApiToken, TokenPermission},
+        users::{AuthCredentials, Credentials, User},
+        viz::{CreateChart, SetAxes},
+    },
 };
 use aide::NoApi;
 use anyhow::anyhow;
 use axum::{
-    Router,
-    http::{HeaderValue, header},
+    Extension, Router,
+    body::Body,
+    extract::Request,
+    http::{HeaderValue, Method, header},
+    middleware::{self, Next},
     response::Response,
 };
 use axum_login::{AuthManagerLayerBuilder, AuthSession, AuthnBackend, UserId};
+use chrono::TimeDelta;
 use password_auth::{generate_hash, verify_password};
+use rand::Rng;
 use sqlx::{Acquire, PgPool, Postgres};
+use std::sync::Arc;
 use tokio::task;
 use tower::ServiceBuilder;
 use tower_sessions::{
@@ -23,33 +37,108 @@ use tower_sessions::{
 };
 use tower_sessions_sqlx_store::PostgresStore;
 
+const USERNAME_IS_TAKEN: &str = "Username is taken";
+const INVALID_BODY: &str = "Request body could not be read";
+
+/// Largest request body buffered to check the tables of an API token, as the default body limit.
+const MAX_BODY_SIZE: usize = 2 * 1024 * 1024;
+
 /// The backend type for [axum_login::AuthSession].
 #[derive(Debug, Clone)]
 pub struct AuthBackend {
     db: PgPool,
+    oidc: Option<Arc<OidcClient>>,
 }
 
 impl AuthBackend {
     pub fn new(db: PgPool) -> Self {
-        Self { db }
+        Self { db, oidc: None }
+    }
+
+    /// Enable single sign-on through the OpenID Connect provider.
+    pub fn with_oidc(self, oidc: Option<OidcConfig>) -> Self {
+        Self {
+            oidc: oidc.map(|config| Arc::new(OidcClient::new(config))),
+            ..self
+        }
+    }
+
+    /// The OpenID Connect client if single sign-on is enabled.
+    pub fn oidc(&self) -> Option<&OidcClient> {
+        self.oidc.as_deref()
+    }
+
+    /// Get the user authenticated by the OpenID Connect provider.
+    /// The user is created on their first login and their admin role
+    /// follows the admin group if one is configured.
+    async fn authenticate_oidc(
+        &self,
+        OidcIdentity {
+            subject,
+            username,
+            is_admin,
+        }: OidcIdentity,
+    ) -> Result<User, ApiError> {
+        let mut tx = self.db.begin().await?;
+
+        let user = match db::get_user_by_oidc_subject(tx.as_mut(), &subject).await? {
+            Some(user) if is_admin.is_none_or(|is_admin| is_admin == user.is_admin) => user,
+            Some(user) => db::update_user(tx.as_mut(), user.user_id, None, None, is_admin).await?,
+            None => {
+                if db::user_exists_by_username(tx.as_mut(), username.clone()).await? {
+                    return Err(ApiError::Conflict(USERNAME_IS_TAKEN.into()));
+                }
+                // The random password is never known, so it cannot be used to log in.
+                let password_hash =
+                    task::spawn_blocking(|| generate_hash(rand::rng().random::<[u8; 32]>()))
+                        .await
+                        .anyhow()?;
+                db::create_oidc_user(
+                    tx.as_mut(),
+                    subject,
+                    username,
+                    password_hash,
+                    is_admin.unwrap_or(false),
+                )
+                .await?
+            }
+        };
+
+        tx.commit().await?;
+        Ok(user)
     }
 }
 
 impl AuthnBackend for AuthBackend {
     type User = User;
-    type Credentials = Credentials;
+    type Credentials = AuthCredentials;
     type Error = ApiError;
 
     async fn authenticate(
         &self,
         creds: Self::Credentials,
     ) -> Result<Option<Self::User>, Self::Error> {
-        let user = db::get_user_by_username(&self.db, creds.username).await?;
-        task::spawn_blocking(|| {
-            Ok(user.filter(|user| verify_password(creds.password, &user.password_hash).is_ok()))
-        })
-        .await
-        .anyhow()?
+        match creds {
+            AuthCredentials::Password(creds) => {
+                let user = db::get_user_by_username(&self.db, creds.username).await?;
+                task::spawn_blocking(|| {
+                    Ok(user.filter(|user| {
+                        verify_password(creds.password, &user.password_hash).is_ok()
+                    }))
+                })
+                .await
+                .anyhow()?
+            }
+            AuthCredentials::Oidc { code, request } => {
+                let Some(oidc) = &self.oidc else {
+                    return Ok(None);
+                };
+                match oidc.authenticate(code, request).await? {
+                    Some(identity) => Ok(Some(self.authenticate_oidc(identity).await?)),
+                    None => Ok(None),
+                }
+            }
+        }
     }
 
     async fn get_user(&self, user_id: &UserId<Self>) -> Result<Option<Self::User>, Self::Error> {
@@ -59,11 +148,16 @@ impl AuthnBackend for AuthBackend {
 
 pub type AppAuthSession = NoApi<AuthSession<AuthBackend>>;
 
+/// The personal API token authenticating the request, if any.
+pub type AppApiToken = NoApi<Option<Extension<ApiToken>>>;
+
 /// Initialize authentication for the router.
 pub async fn init(
     router: Router<AppState>,
     db: PgPool,
     session_key: Key,
+    trash_retention: TimeDelta,
+    oidc: Option<OidcConfig>,
 ) -> anyhow::Result<Router<AppState>> {
     let session_store = PostgresStore::new(db.clone());
     session_store.migrate().await?;
@@ -74,6 +168,12 @@ pub async fn init(
             .continuously_delete_expired(tokio::time::Duration::from_secs(60)),
     );
 
+    let _purge_task = tokio::task::spawn(continuously_purge_trash(
+        db.clone(),
+        trash_retention,
+        tokio::time::Duration::from_secs(60 * 60),
+    ));
+
     let session_layer = SessionManagerLayer::new(session_store)
         .with_secure(true)
         .with_same_site(SameSite::None)
@@ -84,16 +184,149 @@ pub async fn init(
     //
     // This combines the session layer with our backend to establish the auth
     // service which will provide the auth session as a request extension.
-    let backend = AuthBackend::new(db);
+    let backend = AuthBackend::new(db).with_oidc(oidc);
     let auth_layer = AuthManagerLayerBuilder::new(backend.clone(), session_layer).build();
 
     let service = ServiceBuilder::new()
         .map_response(set_partitioned_cookie)
-        .layer(auth_layer);
+        .layer(auth_layer)
+        .layer(middleware::from_fn(bearer_auth));
 
     Ok(router.layer(service))
 }
 
+/// Authenticates the user of the request with the personal API token
+/// in the `Authorization: Bearer` header, if there is one.
+/// Requests outside of the scopes of the token are forbidden.
+async fn bearer_auth(mut request: Request, next: Next) -> ApiResult<Response> {
+    let Some(secret) = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::to_owned)
+    else {
+        return Ok(next.run(request).await);
+    };
+    let Some(auth_session) = request
+        .extensions_mut()
+        .get_mut::<AuthSession<AuthBackend>>()
+    else {
+        return Ok(next.run(request).await);
+    };
+
+    let token = db::use_api_token(&auth_session.backend.db, &secret)
+        .await?
+        .ok_or(ApiError::Unauthorized)?;
+    let user = db::get_user_by_id(&auth_session.backend.db, token.user_id)
+        .await?
+        .ok_or(ApiError::Unauthorized)?;
+    auth_session.user = Some(user);
+    let db = auth_session.backend.db.clone();
+
+    if !token_allows(&token, request.method(), request.uri().path()) {
+        return Err(ApiError::Forbidden);
+    }
+    let mut request = match &token.table_ids {
+        Some(table_ids) => check_token_chart_tables(&db, table_ids, request).await?,
+        None => request,
+    };
+    request.extensions_mut().insert(token);
+
+    Ok(next.run(request).await)
+}
+
+/// Return true if the request is within the permission and scopes of the API token.
+/// Tokens never reach the routes managing tokens, users, groups and shares.
+fn token_allows(token: &ApiToken, method: &Method, path: &str) -> bool {
+    let segments = path.trim_matches('/').split('/').collect::<Vec<_>>();
+
+    let is_read =
+        method == Method::GET || (method == Method::POST && segments.last() == Some(&"query"));
+    if token.permission == TokenPermission::ReadOnly && !is_read {
+        return false;
+    }
+
+    let in_scope = |ids: &Option<Vec<Id>>, id: &str| match ids {
+        None => true,
+        Some(ids) => id.parse().is_ok_and(|id| ids.contains(&id)),
+    };
+
+    // Tokens limited to some resources may only reach the routes of these resources.
+    let is_scoped = token.table_ids.is_some() || token.dashboard_ids.is_some();
+    match segments.as_slice() {
+        [
+            "api",
+            "tokens" | "users" | "groups" | "shares" | "login" | "logout",
+            ..,
+        ] => false,
+        ["api", "user"] | ["api", "public", ..] => true,
+        ["api", "tables"] => method == Method::GET || token.table_ids.is_none(),
+        ["api", "dashboards"] => method == Method::GET || token.dashboard_ids.is_none(),
+        ["api", "tables", id, ..] => in_scope(&token.table_ids, id),
+        ["api", "dashboards", id, ..] => in_scope(&token.dashboard_ids, id),
+        _ => !is_scoped,
+    }
+}
+
+/// Check that the charts read or written by the request only use the tables
+/// the API token is limited to, through the chart table or the table paths of the axes.
+/// The body of the request is buffered to read the tables of new charts and axes,
+/// up to the default body limit.
+async fn check_token_chart_tables(
+    db: &PgPool,
+    table_ids: &[Id],
+    request: Request,
+) -> ApiResult<Request> {
+    let path = request.uri().path().to_owned();
+    let segments = path.trim_matches('/').split('/').collect::<Vec<_>>();
+    let chart_id = match segments.as_slice() {
+        ["api", "dashboards", _, "charts"] if request.method() == Method::POST => None,
+        ["api", "dashboards", _, "charts", chart_id, ..] => match chart_id.parse::<Id>() {
+            Ok(chart_id) => Some(chart_id),
+            Err(_) => return Ok(request),
+        },
+        _ => return Ok(request),
+    };
+
+    let (parts, body) = request.into_parts();
+    let bytes = axum::body::to_bytes(body, MAX_BODY_SIZE)
+        .await
+        .map_err(|_| ApiError::BadRequest(INVALID_BODY.into()))?;
+
+    let mut chart_table_ids = match chart_id {
+        Some(chart_id) => db::get_chart_table_ids(db, chart_id).await?,
+        None => serde_json::from_slice::<CreateChart>(&bytes)
+            .map(|create_chart| vec![create_chart.table_id])
+            .unwrap_or_default(),
+    };
+    if parts.method == Method::PUT
+        && segments.last() == Some(&"axes")
+        && let Ok(SetAxes(axes)) = serde_json::from_slice(&bytes)
+    {
+        chart_table_ids.extend(axes.into_iter().flat_map(|axis| axis.table_path));
+    }
+    if !chart_table_ids
+        .iter()
+        .all(|table_id| table_ids.contains(table_id))
+    {
+        return Err(ApiError::Forbidden);
+    }
+
+    Ok(Request::from_parts(parts, Body::from(bytes)))
+}
+
+/// Permanently delete the trashed items older than the retention period at every interval.
+async fn continuously_purge_trash(db: PgPool, retention: TimeDelta, period: tokio::time::Duration) {
+    let mut interval = tokio::time::interval(period);
+    loop {
+        interval.tick().await;
+        if let Err(e) = db::purge_trash(&db, retention).await {
+            tracing::error!("failed to purge the trash: {e}");
+        }
+    }
+}
+
 /// Sets up an admin user if there are no admin users in the system.
 pub async fn set_admin_user(
     conn: impl Acquire<'_, Database = Postgres>,
@@ -149,13 +382,21 @@ fn set_partitioned_cookie(mut res: Response) -> Response {
 #[cfg(test)]
 #[cfg_attr(coverage_nightly, coverage(off))]
 mod test {
-    use crate::{auth::AuthBackend, db, model::users::Credentials};
+    use crate::{
+        auth::AuthBackend,
+        db,
+        model::{
+            tokens::{ApiToken, TokenPermission},
+            users::Credentials,
+        },
+    };
     use anyhow::{Ok, Result};
     use axum::{
         body::{Body, Bytes},
-        http::Response,
+        http::{Method, Response},
     };
     use axum_login::AuthnBackend;
+    use chrono::Utc;
     use password_auth::{generate_hash, verify_password};
     use reqwest::header::SET_COOKIE;
     use sqlx::PgPool;
@@ -177,7 +418,7 @@ mod test {
             password: creds.password.clone(),
         };
 
-        let result = auth_backend.authenticate(creds.clone()).await?;
+        let result = auth_backend.authenticate(creds.clone().into()).await?;
         assert!(result.is_none());
 
         let user_1 = db::create_user(
@@ -188,14 +429,21 @@ mod test {
         )
         .await?;
 
-        let user_2 = auth_backend.authenticate(creds.clone()).await?.unwrap();
+        let user_2 = auth_backend
+            .authenticate(creds.clone().into())
+            .await?
+            .unwrap();
         verify_password(creds.password, &user_2.password_hash)?;
         assert_eq!(creds.username, user_2.username);
         assert_eq!(user_1.is_admin, user_2.is_admin);
 
-        let result = auth_backend.authenticate(creds_wrong_password).await?;
+        let result = auth_backend
+            .authenticate(creds_wrong_password.into())
+            .await?;
         assert!(result.is_none());
-        let result = auth_backend.authenticate(creds_wrong_username).await?;
+        let result = auth_backend
+            .authenticate(creds_wrong_username.into())
+            .await?;
         assert!(result.is_none());
 
         Ok(())
@@ -235,6 +483,69 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn token_allows() {
+        let token = ApiToken {
+            token_id: 1,
+            user_id: 1,
+            name: "".into(),
+            permission: TokenPermission::ReadOnly,
+            table_ids: Some(vec![1]),
+            dashboard_ids: None,
+            expires_at: None,
+            last_used_at: None,
+            created_at: Utc::now(),
+        };
+
+        for (method, path, allowed) in [
+            (Method::GET, "/api/tables", true),
+            (Method::POST, "/api/tables", false),
+            (Method::GET, "/api/tables/1/fields", true),
+            (Method::POST, "/api/tables/1/entries/query", true),
+            (Method::POST, "/api/tables/1/entries", false),
+            (Method::GET, "/api/tables/2/fields", false),
+            (Method::GET, "/api/Table/1/access", false),
+            (Method::GET, "/api/dashboards/5/charts", true),
+            (Method::GET, "/api/trash", false),
+            (Method::GET, "/api/shares", false),
+            (Method::GET, "/api/tokens", false),
+            (Method::GET, "/api/users", false),
+            (Method::GET, "/api/groups", false),
+            (Method::GET, "/api/user", true),
+            (Method::GET, "/api/public/shr_secret", true),
+        ] {
+            assert_eq!(
+                crate::auth::token_allows(&token, &method, path),
+                allowed,
+                "{method} {path}"
+            );
+        }
+
+        let token = ApiToken {
+            permission: TokenPermission::ReadWrite,
+            table_ids: None,
+            ..token
+        };
+        assert!(crate::auth::token_allows(
+            &token,
+            &Method::POST,
+            "/api/tables"
+        ));
+        assert!(crate::auth::token_allows(
+            &token,
+            &Method::DELETE,
+            "/api/trash/1"
+        ));
+        assert!(!crate::auth::token_allows(
+            &token,
+            &Method::POST,
+            "/api/logout"
+        ));
+        for path in ["/api/users/1", "/api/groups/1", "/api/shares/1"] {
+            assert!(!crate::auth::token_allows(&token, &Method::PATCH, path));
+        }
+    }
+
     #[test]
     fn set_partitioned_cookie() {
         let res: Response<Body> = Response::builder()
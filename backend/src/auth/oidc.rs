@@ -0,0 +1,272 @@
+//! OpenID Connect client for single sign-on through an identity provider,
+//! using the authorization code flow with PKCE.
+
+use crate::model::users::OidcRequest;
+use anyhow::anyhow;
+use base64::{Engine, prelude::BASE64_URL_SAFE_NO_PAD};
+use jsonwebtoken::{
+    Algorithm, DecodingKey, Validation,
+    jwk::{AlgorithmParameters, Jwk, JwkSet},
+};
+use rand::Rng;
+use serde::Deserialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::{collections::HashMap, str::FromStr};
+use tokio::sync::{OnceCell, RwLock};
+use url::Url;
+
+/// Algorithms accepted for the signature of ID tokens.
+const SIGNING_ALGORITHMS: [Algorithm; 3] = [Algorithm::RS256, Algorithm::ES256, Algorithm::EdDSA];
+
+/// OpenID Connect configuration.
+#[derive(Clone, Deserialize)]
+pub struct OidcConfig {
+    /// Issuer URL of the identity provider, used to discover its endpoints.
+    pub issuer_url: String,
+    pub client_id: String,
+    pub client_secret: String,
+    /// URL the identity provider redirects the user to after logging in.
+    pub redirect_url: String,
+    /// Claim of the ID token containing the groups of the user.
+    #[serde(default = "default_groups_claim")]
+    pub groups_claim: String,
+    /// Members of this group are admins and other users are not.
+    /// The admin role of the users is left unchanged if not set.
+    pub admin_group: Option<String>,
+}
+
+impl std::fmt::Debug for OidcConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OidcConfig")
+            .field("issuer_url", &self.issuer_url)
+            .field("client_id", &self.client_id)
+            .field("client_secret", &"[redacted]")
+            .field("redirect_url", &self.redirect_url)
+            .field("groups_claim", &self.groups_claim)
+            .field("admin_group", &self.admin_group)
+            .finish()
+    }
+}
+
+fn default_groups_claim() -> String {
+    "groups".into()
+}
+
+/// Endpoints of the identity provider from its discovery document.
+#[derive(Debug, Deserialize)]
+struct ProviderMetadata {
+    issuer: String,
+    authorization_endpoint: String,
+    token_endpoint: String,
+    jwks_uri: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    id_token: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct IdTokenClaims {
+    sub: String,
+    nonce: Option<String>,
+    preferred_username: Option<String>,
+    email: Option<String>,
+    #[serde(flatten)]
+    other: HashMap<String, Value>,
+}
+
+/// Identity of a user authenticated by the identity provider.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OidcIdentity {
+    pub subject: String,
+    pub username: String,
+    /// Whether the user is in the admin group, if one is configured.
+    pub is_admin: Option<bool>,
+}
+
+/// OpenID Connect client. The provider metadata is discovered on first use
+/// and the signing keys are cached until a token is signed by an unknown key.
+#[derive(Debug)]
+pub struct OidcClient {
+    config: OidcConfig,
+    http: reqwest::Client,
+    metadata: OnceCell<ProviderMetadata>,
+    jwks: RwLock<Option<JwkSet>>,
+}
+
+impl OidcClient {
+    pub fn new(config: OidcConfig) -> Self {
+        Self {
+            config,
+            http: reqwest::Client::new(),
+            metadata: OnceCell::new(),
+            jwks: RwLock::new(None),
+        }
+    }
+
+    async fn metadata(&self) -> anyhow::Result<&ProviderMetadata> {
+        self.metadata
+            .get_or_try_init(async || {
+                let url = format!(
+                    "{}/.well-known/openid-configuration",
+                    self.config.issuer_url.trim_end_matches('/')
+                );
+                Ok(self
+                    .http
+                    .get(url)
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .json()
+                    .await?)
+            })
+            .await
+    }
+
+    /// Find the signing key with this ID, fetching the keys again if it is not cached
+    /// as the identity provider may have rotated them.
+    async fn signing_key(&self, jwks_uri: &str, kid: &str) -> anyhow::Result<Option<Jwk>> {
+        if let Some(jwk) = self
+            .jwks
+            .read()
+            .await
+            .as_ref()
+            .and_then(|jwks| jwks.find(kid))
+        {
+            return Ok(Some(jwk.clone()));
+        }
+
+        let jwks: JwkSet = self
+            .http
+            .get(jwks_uri)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let jwk = jwks.find(kid).cloned();
+        *self.jwks.write().await = Some(jwks);
+        Ok(jwk)
+    }
+
+    /// Start an authorization request.
+    /// Returns the URL of the identity provider and the request to keep until the callback.
+    pub async fn authorize(&self) -> anyhow::Result<(Url, OidcRequest)> {
+        let metadata = self.metadata().await?;
+        let request = OidcRequest {
+            state: random_string(),
+            nonce: random_string(),
+            code_verifier: random_string(),
+        };
+        let code_challenge =
+            BASE64_URL_SAFE_NO_PAD.encode(Sha256::digest(request.code_verifier.as_bytes()));
+
+        let url = Url::parse_with_params(
+            &metadata.authorization_endpoint,
+            [
+                ("response_type", "code"),
+                ("client_id", &self.config.client_id),
+                ("redirect_uri", &self.config.redirect_url),
+                ("scope", "openid profile email"),
+                ("state", &request.state),
+                ("nonce", &request.nonce),
+                ("code_challenge", &code_challenge),
+                ("code_challenge_method", "S256"),
+            ],
+        )?;
+
+        Ok((url, request))
+    }
+
+    /// Exchange the authorization code for an ID token and validate it.
+    /// Returns `None` if the code or the ID token is rejected.
+    pub async fn authenticate(
+        &self,
+        code: String,
+        request: OidcRequest,
+    ) -> anyhow::Result<Option<OidcIdentity>> {
+        let metadata = self.metadata().await?;
+
+        let response = self
+            .http
+            .post(&metadata.token_endpoint)
+            .form(&[
+                ("grant_type", "authorization_code"),
+                ("code", &code),
+                ("redirect_uri", &self.config.redirect_url),
+                ("client_id", &self.config.client_id),
+                ("client_secret", &self.config.client_secret),
+                ("code_verifier", &request.code_verifier),
+            ])
+            .send()
+            .await?;
+        if response.status().is_client_error() {
+            return Ok(None);
+        }
+        let TokenResponse { id_token } = response.error_for_status()?.json().await?;
+
+        // Tokens must name their signing key, which pins the algorithm instead of the token.
+        let Ok(jsonwebtoken::Header { kid: Some(kid), .. }) =
+            jsonwebtoken::decode_header(&id_token)
+        else {
+            return Ok(None);
+        };
+        let jwk = self
+            .signing_key(&metadata.jwks_uri, &kid)
+            .await?
+            .ok_or(anyhow!("signing key of the ID token not found"))?;
+        let Some(algorithm) = jwk
+            .common
+            .key_algorithm
+            .and_then(|algorithm| Algorithm::from_str(&algorithm.to_string()).ok())
+            .or(match &jwk.algorithm {
+                AlgorithmParameters::RSA(_) => Some(Algorithm::RS256),
+                AlgorithmParameters::EllipticCurve(_) => Some(Algorithm::ES256),
+                AlgorithmParameters::OctetKeyPair(_) => Some(Algorithm::EdDSA),
+                _ => None,
+            })
+            .filter(|algorithm| SIGNING_ALGORITHMS.contains(algorithm))
+        else {
+            return Ok(None);
+        };
+
+        let mut validation = Validation::new(algorithm);
+        validation.set_issuer(&[&metadata.issuer]);
+        validation.set_audience(&[&self.config.client_id]);
+        let Ok(token) = jsonwebtoken::decode::<IdTokenClaims>(
+            &id_token,
+            &DecodingKey::from_jwk(&jwk)?,
+            &validation,
+        ) else {
+            return Ok(None);
+        };
+        let claims = token.claims;
+        if claims.nonce.as_ref() != Some(&request.nonce) {
+            return Ok(None);
+        }
+
+        let is_admin = self.config.admin_group.as_ref().map(|admin_group| {
+            match claims.other.get(&self.config.groups_claim) {
+                Some(Value::Array(groups)) => groups.iter().any(|group| group == admin_group),
+                Some(Value::String(group)) => group == admin_group,
+                _ => false,
+            }
+        });
+
+        Ok(Some(OidcIdentity {
+            username: claims
+                .preferred_username
+                .or(claims.email)
+                .unwrap_or_else(|| claims.sub.clone()),
+            subject: claims.sub,
+            is_admin,
+        }))
+    }
+}
+
+/// Random URL-safe string with 256 bits of entropy.
+fn random_string() -> String {
+    BASE64_URL_SAFE_NO_PAD.encode(rand::rng().random::<[u8; 32]>())
+}
@@ -1,8 +1,8 @@
-/// Database functions for managing resource access.
+//! Database functions for managing resource access.
 
 use crate::{
     Id,
-    model::access::{AccessRole, GetAccess, Resource},
+    model::access::{AccessRole, GetAccess, Grantee, Resource},
 };
 use sqlx::{Acquire, PgExecutor, Postgres, QueryBuilder};
 
@@ -88,57 +88,231 @@ pub async fn delete_many_access(
     Ok(())
 }
 
-/// Get all users and their access roles for a resource.
+/// Grant a user group an access role to a resource.
+pub async fn create_group_access(
+    executor: impl PgExecutor<'_>,
+    resource: Resource,
+    resource_id: Id,
+    group_id: Id,
+    access_role: AccessRole,
+) -> sqlx::Result<()> {
+    let tablename = resource.group_access_tablename();
+    sqlx::query(&format!(
+        r#"
+            INSERT INTO {tablename} (group_id, resource_id, access_role)
+            VALUES ($1, $2, $3)
+        "#
+    ))
+    .bind(group_id)
+    .bind(resource_id)
+    .bind(access_role)
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+/// Update the access role of the user groups.
+pub async fn update_many_group_access(
+    executor: impl PgExecutor<'_>,
+    resource: Resource,
+    resource_id: Id,
+    group_access_roles: impl IntoIterator<Item = (Id, AccessRole)>,
+) -> sqlx::Result<()> {
+    let tablename = resource.group_access_tablename();
+    QueryBuilder::new(format!(
+        r#"
+            UPDATE {tablename} AS t
+            SET access_role = v.access_role
+            FROM (
+        "#
+    ))
+    .push_values(
+        group_access_roles,
+        |mut builder, (group_id, access_role)| {
+            builder.push_bind(group_id).push_bind(access_role);
+        },
+    )
+    .push(
+        r#"
+            ) AS v(group_id, access_role)
+            WHERE t.group_id = v.group_id
+            AND t.resource_id = 
+        "#,
+    )
+    .push_bind(resource_id)
+    .build()
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+/// Revoke access to that resource from the user groups.
+pub async fn delete_many_group_access(
+    executor: impl PgExecutor<'_>,
+    resource: Resource,
+    resource_id: Id,
+    group_ids: impl IntoIterator<Item = Id>,
+) -> sqlx::Result<()> {
+    let tablename = resource.group_access_tablename();
+    QueryBuilder::new(format!(r#"DELETE FROM {tablename} WHERE resource_id = "#))
+        .push_bind(resource_id)
+        .push(" AND group_id IN (")
+        .push_values(group_ids, |mut builder, group_id| {
+            builder.push_bind(group_id);
+        })
+        .push(")")
+        .build()
+        .execute(executor)
+        .await?;
+    Ok(())
+}
+
+/// Get all users and user groups and their access roles for a resource.
 pub async fn get_all_access(
     executor: impl PgExecutor<'_>,
     resource: Resource,
     resource_id: Id,
 ) -> sqlx::Result<Vec<GetAccess>> {
     let tablename = resource.access_tablename();
-    sqlx::query_as(&format!(
+    let group_tablename = resource.group_access_tablename();
+    let rows: Vec<(Option<String>, Option<String>, AccessRole)> = sqlx::query_as(&format!(
         r#"
-            SELECT username, access_role
+            SELECT username, NULL AS group_name, access_role
             FROM {tablename} AS a
             JOIN app_user AS u
             ON a.user_id = u.user_id
             WHERE resource_id = $1
-
+            UNION ALL
+            SELECT NULL, name, access_role
+            FROM {group_tablename} AS a
+            JOIN user_group AS g
+            ON a.group_id = g.group_id
+            WHERE resource_id = $1
         "#
     ))
     .bind(resource_id)
     .fetch_all(executor)
-    .await
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(username, group_name, access_role)| GetAccess {
+            grantee: match (username, group_name) {
+                (Some(username), _) => Grantee::User { username },
+                (None, group_name) => Grantee::Group {
+                    group_name: group_name.unwrap_or_default(),
+                },
+            },
+            access_role,
+        })
+        .collect())
 }
 
-/// Get the access role of the user if it exists for that resource.
-pub async fn get_access_role(
+/// Return true if the user was granted an access role to the resource,
+/// not counting the roles of their groups.
+pub async fn user_access_exists(
     executor: impl PgExecutor<'_>,
     resource: Resource,
     resource_id: Id,
     user_id: Id,
-) -> sqlx::Result<Option<AccessRole>> {
+) -> sqlx::Result<bool> {
     let tablename = resource.access_tablename();
-    sqlx::query_scalar::<_, AccessRole>(&format!(
+    sqlx::query_scalar(&format!(
         r#"
-            SELECT access_role
-            FROM {tablename}
-            WHERE user_id = $1 AND resource_id = $2
+            SELECT EXISTS (
+                SELECT 1
+                FROM {tablename}
+                WHERE user_id = $1 AND resource_id = $2
+            )
         "#
     ))
     .bind(user_id)
     .bind(resource_id)
-    .fetch_optional(executor)
+    .fetch_one(executor)
+    .await
+}
+
+/// Return true if the user group was granted an access role to the resource.
+pub async fn group_access_exists(
+    executor: impl PgExecutor<'_>,
+    resource: Resource,
+    resource_id: Id,
+    group_id: Id,
+) -> sqlx::Result<bool> {
+    let tablename = resource.group_access_tablename();
+    sqlx::query_scalar(&format!(
+        r#"
+            SELECT EXISTS (
+                SELECT 1
+                FROM {tablename}
+                WHERE group_id = $1 AND resource_id = $2
+            )
+        "#
+    ))
+    .bind(group_id)
+    .bind(resource_id)
+    .fetch_one(executor)
     .await
 }
 
+/// Get the access role of the user if it exists for that resource.
+///
+/// A table without an access role of its own inherits the role of its closest parent table
+/// that has one. Trashed tables break the inheritance.
+pub async fn get_access_role(
+    executor: impl PgExecutor<'_>,
+    resource: Resource,
+    resource_id: Id,
+    user_id: Id,
+) -> sqlx::Result<Option<AccessRole>> {
+    let viewname = resource.access_viewname();
+    let query = match resource {
+        Resource::Table => format!(
+            r#"
+                WITH RECURSIVE ancestor AS (
+                    SELECT table_id, parent_id, 0 AS depth
+                    FROM meta_table
+                    WHERE table_id = $2 AND deleted_at IS NULL
+                    UNION ALL
+                    SELECT t.table_id, t.parent_id, a.depth + 1
+                    FROM meta_table AS t
+                    JOIN ancestor AS a
+                    ON t.table_id = a.parent_id
+                    WHERE t.deleted_at IS NULL
+                )
+                SELECT v.access_role
+                FROM ancestor AS a
+                JOIN {viewname} AS v
+                ON v.resource_id = a.table_id
+                WHERE v.user_id = $1
+                ORDER BY a.depth
+                LIMIT 1
+            "#
+        ),
+        Resource::Dashboard => format!(
+            r#"
+                SELECT access_role
+                FROM {viewname}
+                WHERE user_id = $1 AND resource_id = $2
+            "#
+        ),
+    };
+    sqlx::query_scalar::<_, AccessRole>(&query)
+        .bind(user_id)
+        .bind(resource_id)
+        .fetch_optional(executor)
+        .await
+}
+
 #[cfg(test)]
 #[cfg_attr(coverage_nightly, coverage(off))]
 mod test {
     use crate::{
         db,
         model::{
-            access::{AccessRole, GetAccess, Resource},
+            access::{AccessRole, GetAccess, Grantee, Resource},
             data::CreateTable,
+            groups::CreateUserGroup,
             viz::CreateDashboard,
         },
         test_util,
@@ -316,6 +490,15 @@ mod test {
     async fn get_all_access(db: PgPool) -> anyhow::Result<()> {
         let user_1 = db::create_user(&db, "gary".into(), "".into(), false).await?;
         let user_2 = db::create_user(&db, "mary".into(), "".into(), false).await?;
+        let group_id = db::create_user_group(
+            &db,
+            user_1.user_id,
+            CreateUserGroup {
+                name: "team".into(),
+            },
+        )
+        .await?
+        .group_id;
 
         let table_id = db::create_table(
             &db,
@@ -352,18 +535,30 @@ mod test {
             .bind(AccessRole::Owner)
             .execute(&db)
             .await?;
+            super::create_group_access(&db, resource, resource_id, group_id, AccessRole::Editor)
+                .await?;
             let get_access_1 = vec![
                 GetAccess {
-                    username: user_1.username.clone(),
+                    grantee: Grantee::User {
+                        username: user_1.username.clone(),
+                    },
                     access_role: AccessRole::Viewer,
                 },
                 GetAccess {
-                    username: user_2.username.clone(),
+                    grantee: Grantee::User {
+                        username: user_2.username.clone(),
+                    },
                     access_role: AccessRole::Owner,
                 },
+                GetAccess {
+                    grantee: Grantee::Group {
+                        group_name: "team".into(),
+                    },
+                    access_role: AccessRole::Editor,
+                },
             ];
             let get_access_2 = super::get_all_access(&db, resource, resource_id).await?;
-            test_util::assert_eq_vec(get_access_1, get_access_2, |x| x.username.clone());
+            test_util::assert_eq_vec(get_access_1, get_access_2, |x| format!("{:?}", x.grantee));
         }
         Ok(())
     }
@@ -419,4 +614,166 @@ mod test {
         }
         Ok(())
     }
+
+    #[sqlx::test]
+    async fn get_access_role_with_groups(db: PgPool) -> anyhow::Result<()> {
+        let user_id = db::create_user(&db, "gary".into(), "".into(), false)
+            .await?
+            .user_id;
+        let owner_id = db::create_user(&db, "mary".into(), "".into(), false)
+            .await?
+            .user_id;
+        let mut group_ids = Vec::new();
+        for name in ["a", "b"] {
+            let group_id =
+                db::create_user_group(&db, owner_id, CreateUserGroup { name: name.into() })
+                    .await?
+                    .group_id;
+            db::create_group_member(&db, group_id, user_id, false).await?;
+            group_ids.push(group_id);
+        }
+
+        let table_id = db::create_table(
+            &db,
+            CreateTable {
+                parent_id: None,
+                name: "test".into(),
+                description: "".into(),
+            },
+        )
+        .await?
+        .table_id;
+        let dashboard_id = db::create_dashboard(
+            &db,
+            CreateDashboard {
+                name: "test".into(),
+                description: "".into(),
+            },
+        )
+        .await?
+        .dashboard_id;
+        for (resource, resource_id) in [
+            (Resource::Table, table_id),
+            (Resource::Dashboard, dashboard_id),
+        ] {
+            super::create_group_access(
+                &db,
+                resource,
+                resource_id,
+                group_ids[0],
+                AccessRole::Viewer,
+            )
+            .await?;
+            assert_eq!(
+                super::get_access_role(&db, resource, resource_id, user_id).await?,
+                Some(AccessRole::Viewer)
+            );
+            assert!(!super::user_access_exists(&db, resource, resource_id, user_id).await?);
+
+            super::create_group_access(
+                &db,
+                resource,
+                resource_id,
+                group_ids[1],
+                AccessRole::Editor,
+            )
+            .await?;
+            assert_eq!(
+                super::get_access_role(&db, resource, resource_id, user_id).await?,
+                Some(AccessRole::Editor)
+            );
+
+            super::create_access(&db, resource, resource_id, user_id, AccessRole::Owner).await?;
+            assert_eq!(
+                super::get_access_role(&db, resource, resource_id, user_id).await?,
+                Some(AccessRole::Owner)
+            );
+            super::delete_many_access(&db, resource, resource_id, [user_id]).await?;
+
+            super::update_many_group_access(
+                &db,
+                resource,
+                resource_id,
+                [(group_ids[1], AccessRole::Viewer)],
+            )
+            .await?;
+            assert_eq!(
+                super::get_access_role(&db, resource, resource_id, user_id).await?,
+                Some(AccessRole::Viewer)
+            );
+
+            super::delete_many_group_access(&db, resource, resource_id, group_ids.clone()).await?;
+            assert!(!super::group_access_exists(&db, resource, resource_id, group_ids[0]).await?);
+            assert_eq!(
+                super::get_access_role(&db, resource, resource_id, user_id).await?,
+                None
+            );
+        }
+
+        db::create_group_access(
+            &db,
+            Resource::Table,
+            table_id,
+            group_ids[0],
+            AccessRole::Editor,
+        )
+        .await?;
+        db::trash_table(&db, owner_id, table_id).await?;
+        assert_eq!(
+            super::get_access_role(&db, Resource::Table, table_id, user_id).await?,
+            None
+        );
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn get_access_role_inherited(db: PgPool) -> anyhow::Result<()> {
+        let user_id = db::create_user(&db, "gary".into(), "".into(), false)
+            .await?
+            .user_id;
+
+        let mut table_ids = Vec::new();
+        for name in ["parent", "child", "grandchild"] {
+            let table_id = db::create_table(
+                &db,
+                CreateTable {
+                    parent_id: table_ids.last().copied(),
+                    name: name.into(),
+                    description: "".into(),
+                },
+            )
+            .await?
+            .table_id;
+            table_ids.push(table_id);
+        }
+        let [parent_id, child_id, grandchild_id] = table_ids[..] else {
+            unreachable!()
+        };
+
+        super::create_access(&db, Resource::Table, parent_id, user_id, AccessRole::Editor).await?;
+        for table_id in [parent_id, child_id, grandchild_id] {
+            assert_eq!(
+                super::get_access_role(&db, Resource::Table, table_id, user_id).await?,
+                Some(AccessRole::Editor)
+            );
+        }
+
+        super::create_access(&db, Resource::Table, child_id, user_id, AccessRole::Viewer).await?;
+        assert_eq!(
+            super::get_access_role(&db, Resource::Table, child_id, user_id).await?,
+            Some(AccessRole::Viewer)
+        );
+        assert_eq!(
+            super::get_access_role(&db, Resource::Table, grandchild_id, user_id).await?,
+            Some(AccessRole::Viewer)
+        );
+
+        super::delete_many_access(&db, Resource::Table, child_id, [user_id]).await?;
+        db::trash_table(&db, user_id, child_id).await?;
+        assert_eq!(
+            super::get_access_role(&db, Resource::Table, grandchild_id, user_id).await?,
+            None
+        );
+        Ok(())
+    }
 }
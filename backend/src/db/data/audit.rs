@@ -0,0 +1,190 @@
+//! Database functions for the audit log of user tables.
+
+use crate::{
+    Id,
+    model::data::{AuditLog, AuditLogPage, AuditResource, CreateAuditLog},
+};
+use sqlx::{PgExecutor, QueryBuilder, types::Json};
+
+/// Record changes made by this user in the audit log.
+/// The user is missing for changes made by the system, such as purging the trash.
+pub async fn create_audit_logs(
+    executor: impl PgExecutor<'_>,
+    user_id: Option<Id>,
+    logs: Vec<CreateAuditLog>,
+) -> sqlx::Result<()> {
+    if logs.is_empty() {
+        return Ok(());
+    }
+
+    QueryBuilder::new(
+        r#"
+            INSERT INTO audit_log (
+                table_id,
+                resource,
+                resource_id,
+                action,
+                user_id,
+                old_value,
+                new_value
+            )
+        "#,
+    )
+    .push_values(logs, |mut builder, log| {
+        builder
+            .push_bind(log.table_id)
+            .push_bind(log.resource)
+            .push_bind(log.resource_id)
+            .push_bind(log.action)
+            .push_bind(user_id)
+            .push_bind(log.old_value.map(Json))
+            .push_bind(log.new_value.map(Json));
+    })
+    .build()
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// Get the changes made to this entry, oldest first.
+pub async fn get_entry_history(
+    executor: impl PgExecutor<'_>,
+    table_id: Id,
+    entry_id: Id,
+) -> sqlx::Result<Vec<AuditLog>> {
+    sqlx::query_as(
+        r#"
+            SELECT l.*, u.username
+            FROM audit_log AS l
+            LEFT JOIN app_user AS u
+            ON l.user_id = u.user_id
+            WHERE l.table_id = $1 AND l.resource = $2 AND l.resource_id = $3
+            ORDER BY l.audit_id
+        "#,
+    )
+    .bind(table_id)
+    .bind(AuditResource::Entry)
+    .bind(entry_id)
+    .fetch_all(executor)
+    .await
+}
+
+/// Get a page of the changes made to this table and its fields and entries, newest first.
+/// Only records older than the cursor are returned.
+pub async fn get_table_activity(
+    executor: impl PgExecutor<'_>,
+    table_id: Id,
+    limit: i64,
+    cursor: Option<Id>,
+) -> sqlx::Result<AuditLogPage> {
+    let logs: Vec<AuditLog> = sqlx::query_as(
+        r#"
+            SELECT l.*, u.username
+            FROM audit_log AS l
+            LEFT JOIN app_user AS u
+            ON l.user_id = u.user_id
+            WHERE l.table_id = $1 AND ($2::INT IS NULL OR l.audit_id < $2)
+            ORDER BY l.audit_id DESC
+            LIMIT $3
+        "#,
+    )
+    .bind(table_id)
+    .bind(cursor)
+    .bind(limit)
+    .fetch_all(executor)
+    .await?;
+
+    let next_cursor = logs
+        .last()
+        .filter(|_| limit > 0 && logs.len() as i64 == limit)
+        .map(|log| log.audit_id);
+
+    Ok(AuditLogPage { logs, next_cursor })
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod test {
+    use crate::{
+        db,
+        model::data::{
+            AuditAction, AuditResource, CreateAuditLog, CreateTable, Entry, Table, UpdateTable,
+        },
+    };
+    use chrono::Utc;
+    use itertools::Itertools;
+    use sqlx::PgPool;
+    use std::collections::HashMap;
+
+    #[sqlx::test]
+    async fn audit_logs(db: PgPool) -> anyhow::Result<()> {
+        let user = db::create_user(&db, "test".into(), "".into(), false).await?;
+        let table = db::create_table(
+            &db,
+            CreateTable {
+                parent_id: None,
+                name: "test".into(),
+                description: "".into(),
+            },
+        )
+        .await?;
+        let updated_table: Table = db::update_table(
+            &db,
+            table.table_id,
+            UpdateTable {
+                name: "updated".into(),
+                description: "".into(),
+            },
+        )
+        .await?;
+        let entry = Entry {
+            entry_id: 1,
+            parent_id: None,
+            created_at: Utc::now(),
+            updated_at: None,
+            cells: HashMap::new(),
+        };
+
+        super::create_audit_logs(&db, Some(user.user_id), Vec::new()).await?;
+        super::create_audit_logs(
+            &db,
+            Some(user.user_id),
+            vec![
+                CreateAuditLog::table(None, Some(&table)),
+                CreateAuditLog::table(Some(&table), Some(&updated_table)),
+                CreateAuditLog::entry(table.table_id, None, Some(&entry)),
+                CreateAuditLog::entry(table.table_id, Some(&entry), None),
+            ],
+        )
+        .await?;
+
+        let history = super::get_entry_history(&db, table.table_id, entry.entry_id).await?;
+        assert_eq!(
+            history.iter().map(|log| log.action).collect_vec(),
+            [AuditAction::Create, AuditAction::Delete]
+        );
+        assert!(
+            history
+                .iter()
+                .all(|log| log.resource == AuditResource::Entry
+                    && log.username.as_deref() == Some("test"))
+        );
+
+        let page = super::get_table_activity(&db, table.table_id, 3, None).await?;
+        assert_eq!(page.logs.len(), 3);
+        assert_eq!(page.logs[0].action, AuditAction::Delete);
+        assert_eq!(page.logs[2].resource, AuditResource::Table);
+        assert_eq!(page.next_cursor, Some(page.logs[2].audit_id));
+
+        let page = super::get_table_activity(&db, table.table_id, 3, page.next_cursor).await?;
+        assert_eq!(page.logs.len(), 1);
+        assert_eq!(page.logs[0].action, AuditAction::Create);
+        assert_eq!(page.next_cursor, None);
+
+        db::delete_user(&db, user.user_id).await?;
+        let page = super::get_table_activity(&db, table.table_id, 10, None).await?;
+        assert!(page.logs.iter().all(|log| log.user_id.is_none()));
+        Ok(())
+    }
+}
@@ -1,11 +1,17 @@
-/// Database functions for managing table entries.
+//! Database functions for managing table entries.
 
-use super::{entry_from_row, insert_columns, select_columns, update_columns};
+use super::{
+    entry_from_row, insert_columns, select_columns, set_relation_links, split_relation_cells,
+    split_relation_fields, update_columns,
+};
 use crate::{
-    Id,
+    Id, db,
     model::{
         Cell,
-        data::{Entry, FieldIdentifier, FieldMetadata, TableIdentifier},
+        data::{
+            Entry, EntryCursor, EntryFilter, EntryPage, EntryQuery, FieldIdentifier, FieldMetadata,
+            FilterOperator, SortDirection, TableIdentifier,
+        },
     },
 };
 use itertools::Itertools;
@@ -22,23 +28,35 @@ pub async fn create_entries(
     let mut tx = conn.begin().await?;
     let table_ident = TableIdentifier::new(table_id, "data_table");
 
-    let field_idents = fields
-        .iter()
-        .map(|field| FieldIdentifier::new(field.field_id))
-        .collect_vec();
+    let (field_idents, relation_field_ids) = split_relation_fields(&fields);
+    let (entries, relation_entries): (Vec<_>, Vec<_>) = entries
+        .into_iter()
+        .map(|entry| split_relation_cells(&fields, entry))
+        .unzip();
 
     let insert_columns = insert_columns(parent_id.is_some(), &field_idents);
-    let return_columns = select_columns(parent_id.is_some(), &field_idents);
-
-    let rows = QueryBuilder::new(format!(r#"INSERT INTO {table_ident} ({insert_columns})"#))
-        .push_values(entries, |mut builder, entry| {
-            for cell in entry {
-                cell.push_bind(&mut builder);
-            }
-            if let Some(parent_id) = parent_id {
-                builder.push_bind(parent_id);
-            }
-        })
+    let return_columns = select_columns(parent_id.is_some(), &fields);
+
+    let mut builder = QueryBuilder::new(format!(r#"INSERT INTO {table_ident} "#));
+    if insert_columns.is_empty() {
+        // Every entry is inserted with default values.
+        builder
+            .push("SELECT FROM generate_series(1, ")
+            .push_bind(entries.len() as i64)
+            .push(")");
+    } else {
+        builder
+            .push(format!("({insert_columns})"))
+            .push_values(entries, |mut builder, entry| {
+                for cell in entry {
+                    cell.push_bind(&mut builder);
+                }
+                if let Some(parent_id) = parent_id {
+                    builder.push_bind(parent_id);
+                }
+            });
+    }
+    let rows = builder
         .push(format!(
             r#"
                 RETURNING {return_columns}
@@ -48,11 +66,31 @@ pub async fn create_entries(
         .fetch_all(tx.as_mut())
         .await?;
 
-    let entries = rows
+    let mut entries = rows
         .into_iter()
         .map(|row| entry_from_row(row, &fields).unwrap())
         .collect_vec();
 
+    for (idx, field_id) in relation_field_ids.into_iter().enumerate() {
+        let links = entries
+            .iter_mut()
+            .zip(&relation_entries)
+            .map(|(entry, relation_cells)| {
+                let target_ids = relation_cells[idx]
+                    .iter()
+                    .copied()
+                    .sorted()
+                    .dedup()
+                    .collect_vec();
+                entry
+                    .cells
+                    .insert(field_id, Cell::EntryIds(target_ids.clone()));
+                (entry.entry_id, target_ids)
+            })
+            .collect_vec();
+        set_relation_links(tx.as_mut(), field_id, links).await?;
+    }
+
     tx.commit().await?;
 
     Ok(entries)
@@ -69,17 +107,20 @@ pub async fn update_entry(
 ) -> sqlx::Result<Entry> {
     let mut tx = conn.begin().await?;
 
-    let field_idents = fields
-        .iter()
-        .map(|field| FieldIdentifier::new(field.field_id))
-        .collect_vec();
+    let (field_idents, relation_field_ids) = split_relation_fields(&fields);
+    let (entry, relation_cells) = split_relation_cells(&fields, entry);
+
+    for (field_id, target_ids) in relation_field_ids.into_iter().zip(relation_cells) {
+        set_relation_links(tx.as_mut(), field_id, vec![(entry_id, target_ids)]).await?;
+    }
 
     let set_columns = update_columns(parent_id.is_some(), &field_idents, 2);
 
-    let return_columns = select_columns(parent_id.is_some(), &field_idents);
+    let return_columns = select_columns(parent_id.is_some(), &fields);
 
     let table_ident = TableIdentifier::new(table_id, "data_table");
 
+    // Touch the entry so it is returned even when there are no columns to set.
     let update_query = format!(
         r#"
             UPDATE {table_ident}
@@ -87,6 +128,11 @@ pub async fn update_entry(
             WHERE entry_id = $1
             RETURNING {return_columns}
         "#,
+        set_columns = if set_columns.is_empty() {
+            "entry_id = entry_id".into()
+        } else {
+            set_columns
+        }
     );
     let mut update_query = sqlx::query(&update_query).bind(entry_id);
 
@@ -125,6 +171,55 @@ pub async fn delete_entry(
     Ok(())
 }
 
+/// Get an entry of the actual SQL table.
+pub async fn get_entry(
+    conn: impl Acquire<'_, Database = Postgres>,
+    table_id: Id,
+    entry_id: Id,
+    fields: &[FieldMetadata],
+) -> sqlx::Result<Entry> {
+    let mut tx = conn.begin().await?;
+
+    let with_parent = db::get_table_parent_id(tx.as_mut(), table_id)
+        .await?
+        .is_some();
+    let select_columns = select_columns(with_parent, fields);
+    let table_ident = TableIdentifier::new(table_id, "data_table");
+    let row = sqlx::query(&format!(
+        r#"
+            SELECT {select_columns}
+            FROM {table_ident}
+            WHERE entry_id = $1
+        "#
+    ))
+    .bind(entry_id)
+    .fetch_one(tx.as_mut())
+    .await?;
+    let entry = entry_from_row(row, fields)?;
+
+    tx.commit().await?;
+    Ok(entry)
+}
+
+/// Return the IDs of the entries that exist in this table out of the given IDs.
+pub async fn get_existing_entry_ids(
+    executor: impl PgExecutor<'_>,
+    table_id: Id,
+    entry_ids: Vec<Id>,
+) -> sqlx::Result<Vec<Id>> {
+    let table_ident = TableIdentifier::new(table_id, "data_table");
+    sqlx::query_scalar(&format!(
+        r#"
+            SELECT entry_id
+            FROM {table_ident}
+            WHERE entry_id = ANY($1)
+        "#
+    ))
+    .bind(entry_ids)
+    .fetch_all(executor)
+    .await
+}
+
 /// Return true if the entry exists.
 pub async fn entry_exists(
     executor: impl PgExecutor<'_>,
@@ -146,15 +241,270 @@ pub async fn entry_exists(
     .await
 }
 
+/// Get the IDs of the entries matching all the filters.
+pub async fn get_filtered_entry_ids(
+    executor: impl PgExecutor<'_>,
+    table_id: Id,
+    filters: Vec<EntryFilter<Cell>>,
+) -> sqlx::Result<Vec<Id>> {
+    let table_ident = TableIdentifier::new(table_id, "data_table");
+    let mut builder =
+        QueryBuilder::new(format!(r#"SELECT entry_id FROM {table_ident} WHERE TRUE"#));
+    push_entry_filters(&mut builder, filters, false);
+    builder.push(" ORDER BY entry_id");
+    builder.build_query_scalar().fetch_all(executor).await
+}
+
+/// Get a page of entries matching the query along with the total number of matching entries.
+pub async fn query_entries(
+    conn: impl Acquire<'_, Database = Postgres>,
+    table_id: Id,
+    fields: Vec<FieldMetadata>,
+    EntryQuery {
+        parent_id,
+        filters,
+        sort,
+        limit,
+        offset,
+        cursor,
+    }: EntryQuery,
+) -> sqlx::Result<EntryPage> {
+    let mut tx = conn.begin().await?;
+    // The count and the page are read from the same snapshot.
+    sqlx::query("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ")
+        .execute(tx.as_mut())
+        .await?;
+
+    let table_ident = TableIdentifier::new(table_id, "data_table");
+    let with_parent = db::get_table_parent_id(tx.as_mut(), table_id)
+        .await?
+        .is_some();
+
+    let push_conditions = |builder: &mut QueryBuilder<'_, Postgres>| {
+        builder.push(" WHERE TRUE");
+        if let Some(parent_id) = parent_id {
+            builder.push(" AND parent_id = ").push_bind(parent_id);
+        }
+        push_entry_filters(builder, filters.clone(), false);
+    };
+
+    let mut count_query = QueryBuilder::new(format!(r#"SELECT COUNT(*) FROM {table_ident}"#));
+    push_conditions(&mut count_query);
+    let total_count: i64 = count_query
+        .build_query_scalar()
+        .fetch_one(tx.as_mut())
+        .await?;
+
+    let select_columns = select_columns(with_parent, &fields);
+
+    let mut select_query =
+        QueryBuilder::new(format!(r#"SELECT {select_columns} FROM {table_ident}"#));
+    push_conditions(&mut select_query);
+
+    let sort_columns = sort
+        .iter()
+        .map(|sort| (FieldIdentifier::new(sort.field_id), sort.direction))
+        .collect_vec();
+
+    if let Some(cursor) = cursor {
+        select_query.push(" AND ");
+        push_cursor_condition(&mut select_query, &sort_columns, cursor);
+    }
+
+    select_query.push(" ORDER BY ");
+    for (field_ident, direction) in &sort_columns {
+        select_query.push(format!("{field_ident} {}, ", direction.get_sql_direction()));
+    }
+    select_query.push("entry_id ASC");
+
+    if let Some(limit) = limit {
+        select_query.push(" LIMIT ").push_bind(limit);
+    }
+    if let Some(offset) = offset {
+        select_query.push(" OFFSET ").push_bind(offset);
+    }
+
+    let entries: Vec<Entry> = select_query
+        .build()
+        .fetch_all(tx.as_mut())
+        .await?
+        .into_iter()
+        .map(|row| entry_from_row(row, &fields))
+        .try_collect()?;
+
+    let next_cursor = limit
+        .filter(|limit| *limit > 0 && entries.len() as i64 == *limit)
+        .and_then(|_| entries.last())
+        .map(|entry| {
+            EntryCursor {
+                cells: sort
+                    .iter()
+                    .map(|sort| {
+                        entry
+                            .cells
+                            .get(&sort.field_id)
+                            .cloned()
+                            .unwrap_or(Cell::Null)
+                    })
+                    .collect(),
+                entry_id: entry.entry_id,
+            }
+            .encode()
+        });
+
+    tx.commit().await?;
+    Ok(EntryPage {
+        entries,
+        total_count,
+        next_cursor,
+    })
+}
+
+/// Push the SQL conditions of the filters into the query, each preceded by `AND`.
+/// Values are bound as parameters, or inlined as SQL literals for statements
+/// which cannot take parameters such as view definitions.
+pub(crate) fn push_entry_filters(
+    builder: &mut QueryBuilder<'_, Postgres>,
+    filters: Vec<EntryFilter<Cell>>,
+    inline_values: bool,
+) {
+    let push_value = |builder: &mut QueryBuilder<'_, Postgres>, value: Cell| {
+        if inline_values {
+            builder.push(value.to_sql_literal());
+        } else {
+            value.push_bind_query(builder);
+        }
+    };
+
+    for EntryFilter { field_id, operator } in filters {
+        let field_ident = FieldIdentifier::new(field_id);
+        builder.push(" AND ");
+        match operator {
+            FilterOperator::Contains { value } => {
+                let value = value.to_string();
+                let pattern = value
+                    .replace('\\', r"\\")
+                    .replace('%', r"\%")
+                    .replace('_', r"\_");
+                // Non-deterministic collations do not support pattern matching.
+                builder.push(format!(r#"{field_ident} COLLATE "default" ILIKE "#));
+                push_value(builder, Cell::String(format!("%{pattern}%")));
+            }
+            FilterOperator::Equals { value } => {
+                builder.push(format!("{field_ident} = "));
+                push_value(builder, value);
+            }
+            FilterOperator::Range { start, end } => {
+                builder.push("TRUE");
+                if let Some(start) = start {
+                    builder.push(format!(" AND {field_ident} >= "));
+                    push_value(builder, start);
+                }
+                if let Some(end) = end {
+                    builder.push(format!(" AND {field_ident} <= "));
+                    push_value(builder, end);
+                }
+            }
+            FilterOperator::In { values } => {
+                if values.is_empty() {
+                    builder.push("FALSE");
+                } else {
+                    builder.push(format!("{field_ident} IN ("));
+                    for (idx, value) in values.into_iter().enumerate() {
+                        if idx > 0 {
+                            builder.push(", ");
+                        }
+                        push_value(builder, value);
+                    }
+                    builder.push(")");
+                }
+            }
+            FilterOperator::IsNull => {
+                builder.push(format!("{field_ident} IS NULL"));
+            }
+            FilterOperator::IsNotNull => {
+                builder.push(format!("{field_ident} IS NOT NULL"));
+            }
+            FilterOperator::WithinLastDays { days } => {
+                builder.push(format!("{field_ident} BETWEEN now() - "));
+                push_value(builder, Cell::Integer(days.into()));
+                builder.push(" * INTERVAL '1 day' AND now()");
+            }
+        }
+    }
+}
+
+/// Push the SQL condition selecting the entries which come after the cursor
+/// given the sort order. `NULL` values are sorted as the greatest values.
+fn push_cursor_condition(
+    builder: &mut QueryBuilder<'_, Postgres>,
+    sort_columns: &[(FieldIdentifier, SortDirection)],
+    EntryCursor { cells, entry_id }: EntryCursor,
+) {
+    builder.push("(FALSE");
+    for (idx, ((field_ident, direction), cell)) in sort_columns.iter().zip(&cells).enumerate() {
+        builder.push(" OR (TRUE");
+        push_cursor_equal_columns(builder, &sort_columns[..idx], &cells[..idx]);
+        builder.push(" AND ");
+        match (direction, cell) {
+            (SortDirection::Ascending, Cell::Null) => {
+                builder.push("FALSE");
+            }
+            (SortDirection::Ascending, cell) => {
+                builder.push(format!("({field_ident} IS NULL OR {field_ident} > "));
+                cell.clone().push_bind_query(builder);
+                builder.push(")");
+            }
+            (SortDirection::Descending, Cell::Null) => {
+                builder.push(format!("{field_ident} IS NOT NULL"));
+            }
+            (SortDirection::Descending, cell) => {
+                builder.push(format!("{field_ident} < "));
+                cell.clone().push_bind_query(builder);
+            }
+        }
+        builder.push(")");
+    }
+    builder.push(" OR (TRUE");
+    push_cursor_equal_columns(builder, sort_columns, &cells);
+    builder
+        .push(" AND entry_id > ")
+        .push_bind(entry_id)
+        .push("))");
+}
+
+/// Push the SQL conditions that the columns are equal to the cursor cells.
+fn push_cursor_equal_columns(
+    builder: &mut QueryBuilder<'_, Postgres>,
+    sort_columns: &[(FieldIdentifier, SortDirection)],
+    cells: &[Cell],
+) {
+    for ((field_ident, _), cell) in sort_columns.iter().zip(cells) {
+        if let Cell::Null = cell {
+            builder.push(format!(" AND {field_ident} IS NULL"));
+        } else {
+            builder.push(format!(" AND {field_ident} = "));
+            cell.clone().push_bind_query(builder);
+        }
+    }
+}
+
 #[cfg(test)]
 #[cfg_attr(coverage_nightly, coverage(off))]
 mod test {
     use crate::{
+        Id,
         db::{
             self,
             data::{entry_from_row, select_columns},
         },
-        model::data::{CreateField, CreateTable, FieldIdentifier, FieldMetadata, TableIdentifier},
+        model::{
+            Cell,
+            data::{
+                CreateField, CreateTable, EntryCursor, EntryFilter, EntryQuery, EntrySort,
+                FieldKind, FieldMetadata, FilterOperator, SortDirection, TableIdentifier,
+            },
+        },
         test_util,
     };
     use itertools::Itertools;
@@ -211,11 +561,7 @@ mod test {
         assert!(entries_2.iter().all(|e| e.parent_id == parent_id));
 
         let table_ident = TableIdentifier::new(table_id, "data_table");
-        let field_idents = fields
-            .iter()
-            .map(|field| FieldIdentifier::new(field.field_id))
-            .collect_vec();
-        let select_columns = select_columns(parent_id.is_some(), &field_idents);
+        let select_columns = select_columns(parent_id.is_some(), &fields);
         let entries_3 = sqlx::query(&format!(r#"SELECT {select_columns} FROM {table_ident}"#))
             .fetch_all(&db)
             .await?
@@ -283,11 +629,7 @@ mod test {
         assert_eq!(entry_id, entry_2.entry_id);
         assert_eq!(parent_id, entry_2.parent_id);
 
-        let field_idents = fields
-            .iter()
-            .map(|field| FieldIdentifier::new(field.field_id))
-            .collect_vec();
-        let select_columns = select_columns(parent_id.is_some(), &field_idents);
+        let select_columns = select_columns(parent_id.is_some(), &fields);
         let entry_3 = entry_from_row(
             sqlx::query(&format!(
                 r#"SELECT {select_columns} FROM {table_ident} WHERE entry_id = $1"#
@@ -357,4 +699,262 @@ mod test {
         assert!(!exists);
         Ok(())
     }
+
+    #[sqlx::test]
+    async fn query_entries(db: PgPool) -> anyhow::Result<()> {
+        let table_id = db::create_table(
+            &db,
+            CreateTable {
+                parent_id: None,
+                name: "test".into(),
+                description: "".into(),
+            },
+        )
+        .await?
+        .table_id;
+
+        let mut fields: Vec<FieldMetadata> = Vec::new();
+        for (name, field_kind) in [
+            ("text", FieldKind::Text { is_required: false }),
+            (
+                "integer",
+                FieldKind::Integer {
+                    is_required: false,
+                    range_start: None,
+                    range_end: None,
+                },
+            ),
+            ("link", FieldKind::WebLink { is_required: false }),
+        ] {
+            let field = db::create_field(
+                &db,
+                table_id,
+                CreateField {
+                    name: name.into(),
+                    field_kind,
+                },
+            )
+            .await?;
+            fields.push(FieldMetadata {
+                field_id: field.field_id,
+                field_kind: field.field_kind,
+            });
+        }
+        let [text_id, integer_id, link_id] =
+            fields.iter().map(|f| f.field_id).collect_array().unwrap();
+
+        let cells = (0..10)
+            .map(|i| {
+                vec![
+                    Cell::String(format!("{}_{i}", if i % 2 == 0 { "Even" } else { "odd%" })),
+                    if i % 3 == 0 {
+                        Cell::Null
+                    } else {
+                        Cell::Integer(i % 4)
+                    },
+                    Cell::String(format!("HTTPS://example.com/{i}")),
+                ]
+            })
+            .collect_vec();
+        let entries = super::create_entries(&db, table_id, None, fields.clone(), cells).await?;
+        let entry_ids = entries.iter().map(|e| e.entry_id).collect_vec();
+
+        let filter_ids = async |filters: Vec<EntryFilter<Cell>>| -> anyhow::Result<Vec<_>> {
+            let page = super::query_entries(
+                &db,
+                table_id,
+                fields.clone(),
+                EntryQuery {
+                    filters,
+                    ..Default::default()
+                },
+            )
+            .await?;
+            assert_eq!(page.total_count, page.entries.len() as i64);
+            assert_eq!(page.next_cursor, None);
+            Ok(page.entries.into_iter().map(|e| e.entry_id).collect_vec())
+        };
+
+        assert_eq!(filter_ids(vec![]).await?, entry_ids);
+        assert_eq!(
+            filter_ids(vec![EntryFilter {
+                field_id: text_id,
+                operator: FilterOperator::Contains {
+                    value: Cell::String("EVEN".into())
+                },
+            }])
+            .await?,
+            entry_ids.iter().copied().step_by(2).collect_vec()
+        );
+        assert_eq!(
+            filter_ids(vec![EntryFilter {
+                field_id: text_id,
+                operator: FilterOperator::Contains {
+                    value: Cell::String("%_".into())
+                },
+            }])
+            .await?,
+            entry_ids.iter().copied().skip(1).step_by(2).collect_vec()
+        );
+        assert_eq!(
+            filter_ids(vec![EntryFilter {
+                field_id: link_id,
+                operator: FilterOperator::Contains {
+                    value: Cell::String("example.com/3".into())
+                },
+            }])
+            .await?,
+            vec![entry_ids[3]]
+        );
+        assert_eq!(
+            filter_ids(vec![EntryFilter {
+                field_id: link_id,
+                operator: FilterOperator::Equals {
+                    value: Cell::String("https://EXAMPLE.com/4".into())
+                },
+            }])
+            .await?,
+            vec![entry_ids[4]]
+        );
+        assert_eq!(
+            filter_ids(vec![
+                EntryFilter {
+                    field_id: integer_id,
+                    operator: FilterOperator::Range {
+                        start: Some(Cell::Integer(1)),
+                        end: Some(Cell::Integer(2)),
+                    },
+                },
+                EntryFilter {
+                    field_id: text_id,
+                    operator: FilterOperator::Contains {
+                        value: Cell::String("odd".into())
+                    },
+                },
+            ])
+            .await?,
+            vec![entry_ids[1], entry_ids[5]]
+        );
+        assert_eq!(
+            filter_ids(vec![EntryFilter {
+                field_id: integer_id,
+                operator: FilterOperator::In {
+                    values: vec![Cell::Integer(0), Cell::Integer(3)]
+                },
+            }])
+            .await?,
+            vec![entry_ids[4], entry_ids[7], entry_ids[8]]
+        );
+        assert_eq!(
+            filter_ids(vec![EntryFilter {
+                field_id: integer_id,
+                operator: FilterOperator::In { values: vec![] },
+            }])
+            .await?,
+            Vec::<Id>::new()
+        );
+        assert_eq!(
+            filter_ids(vec![EntryFilter {
+                field_id: integer_id,
+                operator: FilterOperator::IsNull,
+            }])
+            .await?,
+            vec![entry_ids[0], entry_ids[3], entry_ids[6], entry_ids[9]]
+        );
+        assert_eq!(
+            filter_ids(vec![EntryFilter {
+                field_id: integer_id,
+                operator: FilterOperator::IsNotNull,
+            }])
+            .await?
+            .len(),
+            6
+        );
+
+        for direction in [SortDirection::Ascending, SortDirection::Descending] {
+            let sort = vec![EntrySort {
+                field_id: integer_id,
+                direction,
+            }];
+            let all_entries = super::query_entries(
+                &db,
+                table_id,
+                fields.clone(),
+                EntryQuery {
+                    sort: sort.clone(),
+                    ..Default::default()
+                },
+            )
+            .await?
+            .entries;
+            let values = all_entries
+                .iter()
+                .map(|e| match e.cells[&integer_id] {
+                    Cell::Integer(v) => Some(v),
+                    _ => None,
+                })
+                .collect_vec();
+            let mut sorted_values = values.clone();
+            sorted_values.sort_by(|a, b| match (a, b) {
+                (None, None) => std::cmp::Ordering::Equal,
+                (None, Some(_)) => std::cmp::Ordering::Greater,
+                (Some(_), None) => std::cmp::Ordering::Less,
+                (Some(a), Some(b)) => a.cmp(b),
+            });
+            if direction == SortDirection::Descending {
+                sorted_values.reverse();
+            }
+            assert_eq!(values, sorted_values);
+
+            let mut offset_entries = Vec::new();
+            let mut cursor_entries = Vec::new();
+            let mut cursor: Option<EntryCursor> = None;
+            for offset in (0..10).step_by(3) {
+                let page = super::query_entries(
+                    &db,
+                    table_id,
+                    fields.clone(),
+                    EntryQuery {
+                        sort: sort.clone(),
+                        limit: Some(3),
+                        offset: Some(offset),
+                        ..Default::default()
+                    },
+                )
+                .await?;
+                assert_eq!(page.total_count, 10);
+                offset_entries.extend(page.entries);
+
+                let page = super::query_entries(
+                    &db,
+                    table_id,
+                    fields.clone(),
+                    EntryQuery {
+                        sort: sort.clone(),
+                        limit: Some(3),
+                        cursor: cursor.take(),
+                        ..Default::default()
+                    },
+                )
+                .await?;
+                assert_eq!(page.total_count, 10);
+                assert_eq!(page.next_cursor.is_some(), page.entries.len() == 3);
+                cursor = page.next_cursor.map(|c| {
+                    let (entry_id, values) = EntryCursor::decode(&c).unwrap();
+                    EntryCursor {
+                        cells: values
+                            .into_iter()
+                            .map(|v| v.as_i64().map_or(Cell::Null, Cell::Integer))
+                            .collect(),
+                        entry_id,
+                    }
+                });
+                cursor_entries.extend(page.entries);
+            }
+            assert_eq!(offset_entries, all_entries);
+            assert_eq!(cursor_entries, all_entries);
+        }
+
+        Ok(())
+    }
 }
@@ -5,8 +5,8 @@ use crate::{
     model::{
         Cell,
         data::{
-            CreateField, Field, FieldIdentifier, FieldKind, FieldMetadata, TableIdentifier,
-            UpdateField,
+            CreateField, Field, FieldIdentifier, FieldKind, FieldMetadata, RelationDeleteRule,
+            RelationIdentifier, TableIdentifier, UpdateField, compile_formula,
         },
         viz::CreateAxis,
     },
@@ -36,18 +36,7 @@ pub async fn create_field(
     .fetch_one(tx.as_mut())
     .await?;
 
-    let column_type = field_kind.get_sql_column();
-    let table_ident = TableIdentifier::new(table_id, "data_table");
-    let field_ident = FieldIdentifier::new(field.field_id);
-
-    sqlx::query(&format!(
-        r#"
-            ALTER TABLE {table_ident}
-            ADD COLUMN {field_ident} {column_type}
-        "#,
-    ))
-    .execute(tx.as_mut())
-    .await?;
+    add_field_storage(tx.as_mut(), table_id, field.field_id, &field_kind).await?;
 
     tx.commit().await?;
 
@@ -75,29 +64,290 @@ pub async fn create_fields(
             .fetch_all(tx.as_mut())
             .await?;
 
-    let add_column_statement = fields
-        .iter()
-        .map(|field| {
-            let column_type = field.field_kind.0.get_sql_column();
-            let field_ident = FieldIdentifier::new(field.field_id);
-            format!(r#"ADD COLUMN {field_ident} {column_type}"#)
-        })
-        .join(", ");
+    for field in &fields {
+        add_field_storage(tx.as_mut(), table_id, field.field_id, &field.field_kind).await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(fields)
+}
+
+/// Add the column of a field to the actual SQL table,
+/// or create the join table of a relation to many entries.
+/// The column of a formula field is generated from the other columns.
+async fn add_field_storage(
+    conn: impl Acquire<'_, Database = Postgres>,
+    table_id: Id,
+    field_id: Id,
+    field_kind: &FieldKind,
+) -> sqlx::Result<()> {
+    let mut tx = conn.begin().await?;
 
     let table_ident = TableIdentifier::new(table_id, "data_table");
+    let field_ident = FieldIdentifier::new(field_id);
 
-    sqlx::query(&format!(
-        r#"
-            ALTER TABLE {table_ident}
-            {add_column_statement}
-        "#,
-    ))
-    .execute(tx.as_mut())
+    match field_kind {
+        FieldKind::Relation {
+            target_table_id,
+            multiple: true,
+            on_delete,
+            ..
+        } => {
+            let relation_ident = RelationIdentifier::new(field_id);
+            let target_ident = TableIdentifier::new(*target_table_id, "data_table");
+            let on_delete_action = on_delete.get_sql_action(true);
+            sqlx::query(&format!(
+                r#"
+                    CREATE TABLE {relation_ident} (
+                        source_id INT NOT NULL REFERENCES {table_ident} (entry_id) ON DELETE CASCADE,
+                        target_id INT NOT NULL,
+                        PRIMARY KEY (source_id, target_id),
+                        CONSTRAINT target_fkey FOREIGN KEY (target_id)
+                            REFERENCES {target_ident} (entry_id) ON DELETE {on_delete_action}
+                    )
+                "#
+            ))
+            .execute(tx.as_mut())
+            .await?;
+
+            sqlx::query(&format!(r#"CREATE INDEX ON {relation_ident} (target_id)"#))
+                .execute(tx.as_mut())
+                .await?;
+
+            if *on_delete == RelationDeleteRule::Cascade {
+                trigger_relation_cascade_delete(tx.as_mut(), table_id, field_id, *target_table_id)
+                    .await?;
+            }
+        }
+        FieldKind::Relation {
+            target_table_id,
+            on_delete,
+            ..
+        } => {
+            let target_ident = TableIdentifier::new(*target_table_id, "data_table");
+            let constraint_ident = relation_constraint_ident(field_id);
+            let on_delete_action = on_delete.get_sql_action(false);
+            sqlx::query(&format!(
+                r#"
+                    ALTER TABLE {table_ident}
+                    ADD COLUMN {field_ident} INT
+                    CONSTRAINT {constraint_ident}
+                    REFERENCES {target_ident} (entry_id) ON DELETE {on_delete_action}
+                "#,
+            ))
+            .execute(tx.as_mut())
+            .await?;
+        }
+        FieldKind::Formula {
+            expression,
+            result_kind,
+        } => {
+            let fields = get_fields_metadata(tx.as_mut(), table_id).await?;
+            let generation_expr = compile_formula(expression, *result_kind, &fields)
+                .map_err(|error| sqlx::Error::InvalidArgument(error.to_string()))?;
+            let column_type = field_kind.get_sql_type();
+            sqlx::query(&format!(
+                r#"
+                    ALTER TABLE {table_ident}
+                    ADD COLUMN {field_ident} {column_type}
+                    GENERATED ALWAYS AS ({generation_expr}) STORED
+                "#,
+            ))
+            .execute(tx.as_mut())
+            .await?;
+        }
+        field_kind => {
+            let column_type = field_kind.get_sql_column();
+            sqlx::query(&format!(
+                r#"
+                    ALTER TABLE {table_ident}
+                    ADD COLUMN {field_ident} {column_type}
+                "#,
+            ))
+            .execute(tx.as_mut())
+            .await?;
+        }
+    }
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Change the rule applied when an entry referenced by this relation field is deleted.
+async fn set_relation_delete_rule(
+    conn: impl Acquire<'_, Database = Postgres>,
+    table_id: Id,
+    field_id: Id,
+    target_table_id: Id,
+    multiple: bool,
+    on_delete: RelationDeleteRule,
+) -> sqlx::Result<()> {
+    let mut tx = conn.begin().await?;
+
+    let target_ident = TableIdentifier::new(target_table_id, "data_table");
+    let on_delete_action = on_delete.get_sql_action(multiple);
+
+    if multiple {
+        let relation_ident = RelationIdentifier::new(field_id);
+        sqlx::query(&format!(
+            r#"
+                ALTER TABLE {relation_ident}
+                DROP CONSTRAINT IF EXISTS target_fkey,
+                ADD CONSTRAINT target_fkey FOREIGN KEY (target_id)
+                    REFERENCES {target_ident} (entry_id) ON DELETE {on_delete_action}
+            "#
+        ))
+        .execute(tx.as_mut())
+        .await?;
+
+        sqlx::query(&format!(
+            r#"DROP TRIGGER IF EXISTS relation_cascade_delete ON {relation_ident}"#
+        ))
+        .execute(tx.as_mut())
+        .await?;
+
+        if on_delete == RelationDeleteRule::Cascade {
+            trigger_relation_cascade_delete(tx.as_mut(), table_id, field_id, target_table_id)
+                .await?;
+        }
+    } else {
+        let table_ident = TableIdentifier::new(table_id, "data_table");
+        let field_ident = FieldIdentifier::new(field_id);
+        let constraint_ident = relation_constraint_ident(field_id);
+        sqlx::query(&format!(
+            r#"
+                ALTER TABLE {table_ident}
+                DROP CONSTRAINT IF EXISTS {constraint_ident},
+                ADD CONSTRAINT {constraint_ident} FOREIGN KEY ({field_ident})
+                    REFERENCES {target_ident} (entry_id) ON DELETE {on_delete_action}
+            "#
+        ))
+        .execute(tx.as_mut())
+        .await?;
+    }
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Drop the foreign key and the cascade trigger of this relation field,
+/// so that its kept values neither restrict nor follow the deletion of the target entries.
+pub(super) async fn disable_relation_constraint(
+    conn: impl Acquire<'_, Database = Postgres>,
+    table_id: Id,
+    field_id: Id,
+    multiple: bool,
+) -> sqlx::Result<()> {
+    let mut tx = conn.begin().await?;
+
+    if multiple {
+        let relation_ident = RelationIdentifier::new(field_id);
+        sqlx::query(&format!(
+            r#"ALTER TABLE {relation_ident} DROP CONSTRAINT IF EXISTS target_fkey"#
+        ))
+        .execute(tx.as_mut())
+        .await?;
+
+        sqlx::query(&format!(
+            r#"DROP TRIGGER IF EXISTS relation_cascade_delete ON {relation_ident}"#
+        ))
+        .execute(tx.as_mut())
+        .await?;
+    } else {
+        let table_ident = TableIdentifier::new(table_id, "data_table");
+        let constraint_ident = relation_constraint_ident(field_id);
+        sqlx::query(&format!(
+            r#"ALTER TABLE {table_ident} DROP CONSTRAINT IF EXISTS {constraint_ident}"#
+        ))
+        .execute(tx.as_mut())
+        .await?;
+    }
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Add back the foreign key and the cascade trigger of this relation field.
+/// References to the target entries deleted since the constraint was dropped are removed first.
+pub(super) async fn enable_relation_constraint(
+    conn: impl Acquire<'_, Database = Postgres>,
+    table_id: Id,
+    field_id: Id,
+    target_table_id: Id,
+    multiple: bool,
+    on_delete: RelationDeleteRule,
+) -> sqlx::Result<()> {
+    let mut tx = conn.begin().await?;
+
+    let target_ident = TableIdentifier::new(target_table_id, "data_table");
+    if multiple {
+        let relation_ident = RelationIdentifier::new(field_id);
+        sqlx::query(&format!(
+            r#"
+                DELETE FROM {relation_ident} AS r
+                WHERE NOT EXISTS (
+                    SELECT 1
+                    FROM {target_ident} AS t
+                    WHERE t.entry_id = r.target_id
+                )
+            "#
+        ))
+        .execute(tx.as_mut())
+        .await?;
+    } else {
+        let table_ident = TableIdentifier::new(table_id, "data_table");
+        let field_ident = FieldIdentifier::new(field_id);
+        sqlx::query(&format!(
+            r#"
+                UPDATE {table_ident} AS s
+                SET {field_ident} = NULL
+                WHERE {field_ident} IS NOT NULL
+                    AND NOT EXISTS (
+                        SELECT 1
+                        FROM {target_ident} AS t
+                        WHERE t.entry_id = s.{field_ident}
+                    )
+            "#
+        ))
+        .execute(tx.as_mut())
+        .await?;
+    }
+
+    set_relation_delete_rule(
+        tx.as_mut(),
+        table_id,
+        field_id,
+        target_table_id,
+        multiple,
+        on_delete,
+    )
     .await?;
 
     tx.commit().await?;
+    Ok(())
+}
 
-    Ok(fields)
+/// Create the trigger deleting the source entries of a relation to many entries
+/// when one of their target entries is deleted.
+async fn trigger_relation_cascade_delete(
+    executor: impl PgExecutor<'_>,
+    table_id: Id,
+    field_id: Id,
+    target_table_id: Id,
+) -> sqlx::Result<()> {
+    sqlx::query(r#"SELECT trigger_relation_cascade_delete($1, $2, $3)"#)
+        .bind(RelationIdentifier::new(field_id).to_string())
+        .bind(TableIdentifier::new(table_id, "data_table").to_string())
+        .bind(TableIdentifier::new(target_table_id, "data_table").to_string())
+        .execute(executor)
+        .await?;
+    Ok(())
+}
+
+/// Return the quoted name of the foreign key constraint of a relation column.
+fn relation_constraint_ident(field_id: Id) -> String {
+    format!(r#""{}_fkey""#, FieldIdentifier::new(field_id).unquote())
 }
 
 /// Update a field in this table and change the column in the actual SQL table.
@@ -137,6 +387,45 @@ pub async fn update_field(
 
     if discriminant(&field_kind) != discriminant(&old_field_kind) {
         field = convert_field_kind(tx.as_mut(), field, old_field_kind).await?;
+    } else if let (
+        FieldKind::Relation {
+            target_table_id,
+            multiple,
+            on_delete,
+            ..
+        },
+        FieldKind::Relation {
+            on_delete: old_on_delete,
+            ..
+        },
+    ) = (&field_kind, &old_field_kind)
+        && on_delete != old_on_delete
+    {
+        set_relation_delete_rule(
+            tx.as_mut(),
+            field.table_id,
+            field_id,
+            *target_table_id,
+            *multiple,
+            *on_delete,
+        )
+        .await?;
+    } else if let (
+        FieldKind::Formula {
+            expression,
+            result_kind,
+        },
+        FieldKind::Formula {
+            expression: old_expression,
+            result_kind: old_result_kind,
+        },
+    ) = (&field_kind, &old_field_kind)
+        && (expression != old_expression || result_kind != old_result_kind)
+    {
+        if result_kind != old_result_kind {
+            delete_field_axes(tx.as_mut(), field_id).await?;
+        }
+        replace_formula_column(tx.as_mut(), &field).await?;
     }
 
     tx.commit().await?;
@@ -144,6 +433,72 @@ pub async fn update_field(
     Ok(field)
 }
 
+/// Replace the generated column of a formula field after its expression changed.
+/// The SQL views of the charts using the column are rebuilt with the same axes.
+async fn replace_formula_column(
+    conn: impl Acquire<'_, Database = Postgres>,
+    field: &Field,
+) -> sqlx::Result<()> {
+    let mut tx = conn.begin().await?;
+
+    let affected_chart_ids: Vec<Id> = sqlx::query_scalar(
+        r#"
+            SELECT DISTINCT chart_id
+            FROM axis
+            WHERE field_id = $1 OR order_field_id = $1
+        "#,
+    )
+    .bind(field.field_id)
+    .fetch_all(tx.as_mut())
+    .await?;
+
+    let mut chart_axes = Vec::new();
+    for chart_id in affected_chart_ids {
+        let axes: Vec<CreateAxis> = sqlx::query_as(
+            r#"
+                SELECT *
+                FROM axis
+                WHERE chart_id = $1
+                ORDER BY axis_id
+            "#,
+        )
+        .bind(chart_id)
+        .fetch_all(tx.as_mut())
+        .await?;
+
+        let table_id = db::get_chart_table_id(tx.as_mut(), chart_id).await?;
+        db::set_axes(tx.as_mut(), chart_id, table_id, Vec::new()).await?;
+        chart_axes.push((chart_id, axes));
+    }
+
+    let table_ident = TableIdentifier::new(field.table_id, "data_table");
+    let field_ident = FieldIdentifier::new(field.field_id);
+    sqlx::query(&format!(
+        r#"
+            ALTER TABLE {table_ident}
+            DROP COLUMN {field_ident}
+        "#,
+    ))
+    .execute(tx.as_mut())
+    .await?;
+
+    add_field_storage(
+        tx.as_mut(),
+        field.table_id,
+        field.field_id,
+        &field.field_kind,
+    )
+    .await?;
+
+    for (chart_id, axes) in chart_axes {
+        let table_id = db::get_chart_table_id(tx.as_mut(), chart_id).await?;
+        db::set_axes(tx.as_mut(), chart_id, table_id, axes).await?;
+    }
+
+    tx.commit().await?;
+    Ok(())
+}
+
 /// Create a new field with all the cells converted to the new [FieldKind] and swap their ordering.
 /// Renames the old field to avoid conflict.
 async fn convert_field_kind(
@@ -269,70 +624,79 @@ pub async fn delete_field(
 
     delete_field_axes(tx.as_mut(), field_id).await?;
 
-    let table_id: Id = sqlx::query_scalar(
+    let (table_id, Json(field_kind)): (Id, Json<FieldKind>) = sqlx::query_as(
         r#"
             DELETE FROM meta_field
             WHERE field_id = $1
-            RETURNING table_id
+            RETURNING table_id, field_kind
         "#,
     )
     .bind(field_id)
     .fetch_one(tx.as_mut())
     .await?;
 
-    let table_ident = TableIdentifier::new(table_id, "data_table");
-    let field_ident = FieldIdentifier::new(field_id);
+    if field_kind.is_multiple_relation() {
+        let relation_ident = RelationIdentifier::new(field_id);
+        sqlx::query(&format!(r#"DROP TABLE {relation_ident}"#))
+            .execute(tx.as_mut())
+            .await?;
+    } else {
+        let table_ident = TableIdentifier::new(table_id, "data_table");
+        let field_ident = FieldIdentifier::new(field_id);
 
-    sqlx::query(&format!(
-        r#"
-            ALTER TABLE {table_ident}
-            DROP COLUMN {field_ident}
-        "#,
-    ))
-    .execute(tx.as_mut())
-    .await?;
+        sqlx::query(&format!(
+            r#"
+                ALTER TABLE {table_ident}
+                DROP COLUMN {field_ident}
+            "#,
+        ))
+        .execute(tx.as_mut())
+        .await?;
+    }
 
     tx.commit().await?;
 
     Ok(())
 }
 
-/// Delete all axes associated with this field and update the chart SQL views.
-async fn delete_field_axes(
+/// Delete all axes and chart filters associated with this field and update the chart SQL views.
+/// Axes ordered by this field are deleted as well.
+pub(super) async fn delete_field_axes(
     conn: impl Acquire<'_, Database = Postgres>,
     field_id: Id,
 ) -> sqlx::Result<()> {
     let mut tx = conn.begin().await?;
 
-    let table_id: Id = sqlx::query_scalar(
+    let affected_chart_ids: Vec<Id> = sqlx::query_scalar(
         r#"
-            SELECT table_id
-            FROM meta_field
+            SELECT chart_id
+            FROM axis
+            WHERE field_id = $1 OR order_field_id = $1
+            UNION
+            SELECT chart_id
+            FROM chart_filter
             WHERE field_id = $1
         "#,
     )
     .bind(field_id)
-    .fetch_one(tx.as_mut())
+    .fetch_all(tx.as_mut())
     .await?;
 
-    let affected_chart_ids: Vec<Id> = sqlx::query_scalar(
+    sqlx::query(
         r#"
-            SELECT DISTINCT c.chart_id
-            FROM chart AS c
-            JOIN axis AS a
-            ON c.chart_id = a.chart_id
-            WHERE field_id = $1
+            DELETE FROM axis
+            WHERE field_id = $1 OR order_field_id = $1
         "#,
     )
     .bind(field_id)
-    .fetch_all(tx.as_mut())
+    .execute(tx.as_mut())
     .await?;
 
     sqlx::query(
         r#"
-        DELETE FROM axis
-        WHERE axis_id = $1
-    "#,
+            DELETE FROM chart_filter
+            WHERE field_id = $1
+        "#,
     )
     .bind(field_id)
     .execute(tx.as_mut())
@@ -350,6 +714,7 @@ async fn delete_field_axes(
         .fetch_all(tx.as_mut())
         .await?;
 
+        let table_id = db::get_chart_table_id(tx.as_mut(), chart_id).await?;
         db::set_axes(tx.as_mut(), chart_id, table_id, axes).await?;
     }
     tx.commit().await?;
@@ -461,7 +826,8 @@ mod test {
         model::{
             Cell,
             data::{
-                CreateField, CreateTable, Field, FieldIdentifier, FieldKind, FieldMetadata,
+                CreateField, CreateTable, EntryFilter, Field, FieldIdentifier, FieldKind,
+                FieldMetadata, FilterOperator, FormulaKind, RelationDeleteRule, RelationIdentifier,
                 TableIdentifier, UpdateField,
             },
             viz::{
@@ -657,9 +1023,6 @@ mod test {
                 }
                 _ => new_value.to_string(),
             });
-            println!(
-                "{field_kind:?}: old_value: {old_value:?} new_value {new_value:?}"
-            );
 
             let table_id = db::create_table(
                 &db,
@@ -680,9 +1043,10 @@ mod test {
                 },
             )
             .await?;
-            test_util::test_insert_cell(&db, table_id, old_field_1.field_id, old_value.clone())
-                .await;
-            println!("OK");
+            assert!(
+                test_util::test_insert_cell(&db, table_id, old_field_1.field_id, old_value.clone())
+                    .await
+            );
             let new_field_1: Field = sqlx::query_as(
                 r#"UPDATE meta_field SET field_kind = $1 WHERE field_id = $2 RETURNING *"#,
             )
@@ -690,7 +1054,6 @@ mod test {
             .bind(old_field_1.field_id)
             .fetch_one(&db)
             .await?;
-            println!("OK");
             let new_field_2 =
                 super::convert_field_kind(&db, new_field_1.clone(), old_field_kind.clone()).await?;
             assert_eq!(new_field_1.name, new_field_2.name);
@@ -705,7 +1068,7 @@ mod test {
                 &field_ident.unquote(),
                 &field_kind,
             )?;
-            assert_eq!(new_value, actual_new_value);
+            assert_eq!(new_value, actual_new_value, "{field_kind:?}: {old_value:?}");
 
             let old_field_2: Field =
                 sqlx::query_as(r#"SELECT * FROM meta_field WHERE field_id = $1"#)
@@ -806,6 +1169,7 @@ mod test {
                 table_id,
                 name: "test".into(),
                 chart_kind: ChartKind::Bar,
+                top_n: None,
             },
         )
         .await?
@@ -816,8 +1180,14 @@ mod test {
             table_id,
             vec![CreateAxis {
                 field_id,
+                table_path: Vec::new(),
                 axis_kind: AxisKind::X,
                 aggregate: Some(Aggregate::Count),
+                percentile: None,
+                order_field_id: None,
+                sort: None,
+                bins: None,
+                bucket: None,
             }],
         )
         .await?
@@ -825,8 +1195,18 @@ mod test {
         .next()
         .unwrap()
         .axis_id;
+        db::set_chart_filters(
+            &db,
+            chart_id,
+            vec![EntryFilter {
+                field_id,
+                operator: FilterOperator::Equals { value: true.into() },
+            }],
+        )
+        .await?;
 
         super::delete_field_axes(&db, field_id).await?;
+        assert!(db::get_chart_filters(&db, chart_id).await?.is_empty());
         let not_exists: bool =
             sqlx::query_scalar(r#"SELECT NOT EXISTS (SELECT 1 FROM axis WHERE axis_id = $1)"#)
                 .bind(axis_id)
@@ -845,6 +1225,370 @@ mod test {
         Ok(())
     }
 
+    #[sqlx::test]
+    async fn relation_fields(db: PgPool) -> anyhow::Result<()> {
+        let create_table = async |name: &str| -> anyhow::Result<Id> {
+            Ok(db::create_table(
+                &db,
+                CreateTable {
+                    name: name.into(),
+                    description: "".into(),
+                    parent_id: None,
+                },
+            )
+            .await?
+            .table_id)
+        };
+        let target_table_id = create_table("target").await?;
+        let source_table_id = create_table("source").await?;
+
+        let target_ids = db::create_entries(&db, target_table_id, None, vec![], vec![vec![]; 6])
+            .await?
+            .into_iter()
+            .map(|entry| entry.entry_id)
+            .collect_vec();
+
+        let mut fields = Vec::new();
+        for (multiple, on_delete) in [
+            (false, RelationDeleteRule::Restrict),
+            (false, RelationDeleteRule::Unlink),
+            (true, RelationDeleteRule::Unlink),
+            (true, RelationDeleteRule::Cascade),
+        ] {
+            let field = db::create_field(
+                &db,
+                source_table_id,
+                CreateField {
+                    name: format!("{multiple} {on_delete:?}"),
+                    field_kind: FieldKind::Relation {
+                        is_required: false,
+                        target_table_id,
+                        multiple,
+                        on_delete,
+                    },
+                },
+            )
+            .await?;
+            fields.push(FieldMetadata::from_field(field));
+        }
+        let field_ids = fields.iter().map(|field| field.field_id).collect_vec();
+
+        let entries = db::create_entries(
+            &db,
+            source_table_id,
+            None,
+            fields.clone(),
+            vec![
+                vec![
+                    Cell::Integer(target_ids[0].into()),
+                    Cell::Integer(target_ids[1].into()),
+                    Cell::EntryIds(vec![target_ids[2], target_ids[1]]),
+                    Cell::EntryIds(vec![]),
+                ],
+                vec![
+                    Cell::Null,
+                    Cell::Null,
+                    Cell::EntryIds(vec![]),
+                    Cell::EntryIds(vec![target_ids[3]]),
+                ],
+            ],
+        )
+        .await?;
+        let [entry_id_1, entry_id_2] = entries.iter().map(|e| e.entry_id).collect_array().unwrap();
+        assert_eq!(
+            entries[0].cells[&field_ids[2]],
+            Cell::EntryIds(vec![target_ids[1], target_ids[2]])
+        );
+        let table_data = db::get_table_data(&db, source_table_id).await?;
+        assert_eq!(table_data.entries, entries);
+
+        // Restrict
+        assert!(
+            db::delete_entry(&db, target_table_id, target_ids[0])
+                .await
+                .is_err()
+        );
+
+        // Unlink
+        db::delete_entry(&db, target_table_id, target_ids[1]).await?;
+        let entry = db::get_table_data(&db, source_table_id)
+            .await?
+            .entries
+            .into_iter()
+            .find(|e| e.entry_id == entry_id_1)
+            .unwrap();
+        assert_eq!(entry.cells[&field_ids[1]], Cell::Null);
+        assert_eq!(
+            entry.cells[&field_ids[2]],
+            Cell::EntryIds(vec![target_ids[2]])
+        );
+
+        // Cascade
+        db::delete_entry(&db, target_table_id, target_ids[3]).await?;
+        assert!(!db::entry_exists(&db, source_table_id, entry_id_2).await?);
+        assert!(db::entry_exists(&db, source_table_id, entry_id_1).await?);
+
+        // Updating the links of an entry does not cascade.
+        db::update_entry(
+            &db,
+            source_table_id,
+            entry_id_1,
+            None,
+            vec![fields[3].clone()],
+            vec![Cell::EntryIds(vec![target_ids[4]])],
+        )
+        .await?;
+        let entry = db::update_entry(
+            &db,
+            source_table_id,
+            entry_id_1,
+            None,
+            vec![fields[3].clone()],
+            vec![Cell::EntryIds(vec![target_ids[5]])],
+        )
+        .await?;
+        assert_eq!(
+            entry.cells[&field_ids[3]],
+            Cell::EntryIds(vec![target_ids[5]])
+        );
+
+        // Change the delete rule
+        db::update_field(
+            &db,
+            field_ids[0],
+            UpdateField {
+                name: "restrict to unlink".into(),
+                field_kind: FieldKind::Relation {
+                    is_required: false,
+                    target_table_id,
+                    multiple: false,
+                    on_delete: RelationDeleteRule::Unlink,
+                },
+            },
+        )
+        .await?;
+        db::delete_entry(&db, target_table_id, target_ids[0]).await?;
+        db::update_field(
+            &db,
+            field_ids[3],
+            UpdateField {
+                name: "cascade to restrict".into(),
+                field_kind: FieldKind::Relation {
+                    is_required: false,
+                    target_table_id,
+                    multiple: true,
+                    on_delete: RelationDeleteRule::Restrict,
+                },
+            },
+        )
+        .await?;
+        assert!(
+            db::delete_entry(&db, target_table_id, target_ids[5])
+                .await
+                .is_err()
+        );
+
+        for field_id in &field_ids[2..] {
+            db::delete_field(&db, *field_id).await?;
+            let relation_exists: bool = sqlx::query_scalar(r#"SELECT to_regclass($1) IS NOT NULL"#)
+                .bind(RelationIdentifier::new(*field_id).to_string())
+                .fetch_one(&db)
+                .await?;
+            assert!(!relation_exists);
+        }
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn formula_fields(db: PgPool) -> anyhow::Result<()> {
+        let table_id = db::create_table(
+            &db,
+            CreateTable {
+                parent_id: None,
+                name: "test".into(),
+                description: "".into(),
+            },
+        )
+        .await?
+        .table_id;
+        let mut fields = Vec::new();
+        for field_kind in [
+            FieldKind::Text { is_required: false },
+            FieldKind::Integer {
+                is_required: false,
+                range_start: None,
+                range_end: None,
+            },
+            FieldKind::Money {
+                is_required: false,
+                range_start: None,
+                range_end: None,
+            },
+        ] {
+            let field = db::create_field(
+                &db,
+                table_id,
+                CreateField {
+                    name: "test".into(),
+                    field_kind,
+                },
+            )
+            .await?;
+            fields.push(FieldMetadata::from_field(field));
+        }
+        let [name_id, quantity_id, price_id] =
+            fields.iter().map(|f| f.field_id).collect_array().unwrap();
+
+        let total_kind = |expression: &str, result_kind| FieldKind::Formula {
+            expression: expression.into(),
+            result_kind,
+        };
+        let total = db::create_field(
+            &db,
+            table_id,
+            CreateField {
+                name: "total".into(),
+                field_kind: total_kind(
+                    &format!("{{{quantity_id}}} * {{{price_id}}}"),
+                    FormulaKind::Money,
+                ),
+            },
+        )
+        .await?;
+        let label = db::create_field(
+            &db,
+            table_id,
+            CreateField {
+                name: "label".into(),
+                field_kind: total_kind(
+                    &format!(r#"UPPER({{{name_id}}}) & "!""#),
+                    FormulaKind::Text,
+                ),
+            },
+        )
+        .await?;
+        fields.push(FieldMetadata::from_field(total.clone()));
+        fields.push(FieldMetadata::from_field(label.clone()));
+
+        let entry = db::create_entries(
+            &db,
+            table_id,
+            None,
+            fields.clone(),
+            vec![vec![
+                Cell::String("apple".into()),
+                Cell::Integer(3),
+                Cell::Decimal("1.50".parse()?),
+                Cell::Null,
+                Cell::Null,
+            ]],
+        )
+        .await?
+        .into_iter()
+        .next()
+        .unwrap();
+        assert_eq!(entry.cells[&total.field_id], Cell::Decimal("4.50".parse()?));
+        assert_eq!(entry.cells[&label.field_id], Cell::String("APPLE!".into()));
+
+        let entry = db::update_entry(
+            &db,
+            table_id,
+            entry.entry_id,
+            None,
+            fields.clone(),
+            vec![
+                Cell::String("pear".into()),
+                Cell::Integer(4),
+                Cell::Decimal("1.50".parse()?),
+                Cell::Null,
+                Cell::Null,
+            ],
+        )
+        .await?;
+        assert_eq!(entry.cells[&total.field_id], Cell::Decimal("6.00".parse()?));
+        assert_eq!(entry.cells[&label.field_id], Cell::String("PEAR!".into()));
+
+        // The chart using the formula is rebuilt when the expression changes.
+        let dashboard_id = db::create_dashboard(
+            &db,
+            CreateDashboard {
+                name: "test".into(),
+                description: "".into(),
+            },
+        )
+        .await?
+        .dashboard_id;
+        let chart_id = db::create_chart(
+            &db,
+            dashboard_id,
+            CreateChart {
+                table_id,
+                name: "test".into(),
+                chart_kind: ChartKind::Bar,
+                top_n: None,
+            },
+        )
+        .await?
+        .chart_id;
+        db::set_axes(
+            &db,
+            chart_id,
+            table_id,
+            vec![CreateAxis {
+                field_id: total.field_id,
+                table_path: Vec::new(),
+                axis_kind: AxisKind::Y,
+                aggregate: Some(Aggregate::Sum),
+                percentile: None,
+                order_field_id: None,
+                sort: None,
+                bins: None,
+                bucket: None,
+            }],
+        )
+        .await?;
+
+        db::update_field(
+            &db,
+            total.field_id,
+            UpdateField {
+                name: "total".into(),
+                field_kind: total_kind(
+                    &format!("{{{quantity_id}}} * {{{price_id}}} * 2"),
+                    FormulaKind::Money,
+                ),
+            },
+        )
+        .await?;
+        let chart_data = db::get_chart_data(&db, chart_id, Vec::new()).await?;
+        assert_eq!(
+            chart_data.cells[0][&chart_data.axes[0].axis.axis_id],
+            Cell::Decimal("12.00".parse()?)
+        );
+
+        // The axes are deleted when the result kind changes.
+        db::update_field(
+            &db,
+            total.field_id,
+            UpdateField {
+                name: "total".into(),
+                field_kind: total_kind(&format!("{{{quantity_id}}} * 2"), FormulaKind::Integer),
+            },
+        )
+        .await?;
+        assert!(db::get_chart_data(&db, chart_id, Vec::new()).await?.axes.is_empty());
+        let entry = db::get_table_data(&db, table_id)
+            .await?
+            .entries
+            .into_iter()
+            .next()
+            .unwrap();
+        assert_eq!(entry.cells[&total.field_id], Cell::Integer(8));
+
+        Ok(())
+    }
+
     #[sqlx::test]
     async fn get_fields(db: PgPool) -> anyhow::Result<()> {
         let table_id = db::create_table(
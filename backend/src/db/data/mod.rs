@@ -1,22 +1,44 @@
 //! Database functions for the data management features.
 
+mod audit;
 mod entries;
 mod fields;
 mod tables;
+mod trash;
+mod views;
 
-use crate::model::{
-    Cell,
-    data::{Entry, FieldIdentifier, FieldMetadata},
+use crate::{
+    Id,
+    model::{
+        Cell,
+        data::{Entry, FieldIdentifier, FieldMetadata, RelationIdentifier},
+    },
 };
-use itertools::Itertools;
-use sqlx::{Row, postgres::PgRow};
-pub use {entries::*, fields::*, tables::*};
+use itertools::{Either, Itertools};
+use sqlx::{Acquire, Postgres, Row, postgres::PgRow};
+pub use {audit::*, entries::*, fields::*, tables::*, trash::*, views::*};
 
 /// Return the columns of a the dynamic SQL table prepared for a "select" query.
-fn select_columns(with_parent: bool, field_idents: &[FieldIdentifier]) -> String {
-    field_idents
+/// Relations to many entries are aggregated from their join table.
+fn select_columns(with_parent: bool, fields: &[FieldMetadata]) -> String {
+    fields
         .iter()
-        .map(|x| x.to_string())
+        .map(|field| {
+            let field_ident = FieldIdentifier::new(field.field_id);
+            if field.field_kind.is_multiple_relation() {
+                let relation_ident = RelationIdentifier::new(field.field_id);
+                format!(
+                    r#"ARRAY(
+                        SELECT target_id
+                        FROM {relation_ident}
+                        WHERE source_id = entry_id
+                        ORDER BY target_id
+                    ) AS {field_ident}"#
+                )
+            } else {
+                field_ident.to_string()
+            }
+        })
         .chain(
             ["entry_id", "created_at", "updated_at"]
                 .into_iter()
@@ -46,6 +68,77 @@ fn update_columns(with_parent: bool, field_idents: &[FieldIdentifier], position:
         .join(", ")
 }
 
+/// Split the fields into the ones stored in a column of the dynamic SQL table
+/// and the relations to many entries stored in a join table.
+/// Formula fields are computed by the database and are skipped.
+fn split_relation_fields(fields: &[FieldMetadata]) -> (Vec<FieldIdentifier>, Vec<Id>) {
+    fields
+        .iter()
+        .filter(|field| !field.field_kind.is_formula())
+        .partition_map(|field| {
+            if field.field_kind.is_multiple_relation() {
+                Either::Right(field.field_id)
+            } else {
+                Either::Left(FieldIdentifier::new(field.field_id))
+            }
+        })
+}
+
+/// Split the cells of an entry the same way as [split_relation_fields].
+fn split_relation_cells(fields: &[FieldMetadata], cells: Vec<Cell>) -> (Vec<Cell>, Vec<Vec<Id>>) {
+    fields
+        .iter()
+        .zip(cells)
+        .filter(|(field, _)| !field.field_kind.is_formula())
+        .partition_map(|(field, cell)| match cell {
+            Cell::EntryIds(ids) if field.field_kind.is_multiple_relation() => Either::Right(ids),
+            _ if field.field_kind.is_multiple_relation() => Either::Right(Vec::new()),
+            cell => Either::Left(cell),
+        })
+}
+
+/// Replace the entries referenced by a relation to many entries for these source entries.
+async fn set_relation_links(
+    conn: impl Acquire<'_, Database = Postgres>,
+    field_id: Id,
+    links: Vec<(Id, Vec<Id>)>,
+) -> sqlx::Result<()> {
+    let mut tx = conn.begin().await?;
+    let relation_ident = RelationIdentifier::new(field_id);
+
+    let (source_ids, target_ids): (Vec<Id>, Vec<Id>) = links
+        .iter()
+        .flat_map(|(source_id, target_ids)| {
+            target_ids.iter().map(|target_id| (*source_id, *target_id))
+        })
+        .unzip();
+
+    sqlx::query(&format!(
+        r#"
+            DELETE FROM {relation_ident}
+            WHERE source_id = ANY($1)
+        "#
+    ))
+    .bind(links.iter().map(|(source_id, _)| *source_id).collect_vec())
+    .execute(tx.as_mut())
+    .await?;
+
+    sqlx::query(&format!(
+        r#"
+            INSERT INTO {relation_ident} (source_id, target_id)
+            SELECT * FROM UNNEST($1::int[], $2::int[])
+            ON CONFLICT DO NOTHING
+        "#
+    ))
+    .bind(source_ids)
+    .bind(target_ids)
+    .execute(tx.as_mut())
+    .await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
 /// Convert this [PgRow] into an [Entry].
 fn entry_from_row(row: PgRow, fields: &[FieldMetadata]) -> sqlx::Result<Entry> {
     Ok(Entry {
@@ -73,26 +166,93 @@ fn entry_from_row(row: PgRow, fields: &[FieldMetadata]) -> sqlx::Result<Entry> {
 #[cfg(test)]
 #[cfg_attr(coverage_nightly, coverage(off))]
 mod test {
-    use crate::model::data::FieldIdentifier;
+    use crate::model::{
+        Cell,
+        data::{FieldIdentifier, FieldKind, FieldMetadata, FormulaKind, RelationDeleteRule},
+    };
     use itertools::Itertools;
+    use sqlx::types::Json;
 
     #[test]
     fn select_columns() {
-        let field_idents = [1, 2, 3]
+        let fields = [1, 2, 3]
             .into_iter()
-            .map(FieldIdentifier::new)
+            .map(|field_id| FieldMetadata {
+                field_id,
+                field_kind: Json(FieldKind::Checkbox),
+            })
             .collect_vec();
-        let select_columns = super::select_columns(false, &field_idents);
+        let select_columns = super::select_columns(false, &fields);
         assert_eq!(
             select_columns,
             r#""f1", "f2", "f3", entry_id, created_at, updated_at"#
         );
 
-        let select_columns = super::select_columns(true, &field_idents);
+        let select_columns = super::select_columns(true, &fields);
         assert_eq!(
             select_columns,
             r#""f1", "f2", "f3", entry_id, created_at, updated_at, parent_id"#
         );
+
+        let fields = [FieldMetadata {
+            field_id: 1,
+            field_kind: Json(relation(true)),
+        }];
+        let select_columns = super::select_columns(false, &fields);
+        assert!(select_columns.contains(r#"FROM "data_table"."r1""#));
+        assert!(select_columns.ends_with(r#"AS "f1", entry_id, created_at, updated_at"#));
+    }
+
+    #[test]
+    fn split_relation_fields() {
+        let fields = [
+            FieldMetadata {
+                field_id: 1,
+                field_kind: Json(relation(false)),
+            },
+            FieldMetadata {
+                field_id: 2,
+                field_kind: Json(relation(true)),
+            },
+            FieldMetadata {
+                field_id: 3,
+                field_kind: Json(FieldKind::Checkbox),
+            },
+            FieldMetadata {
+                field_id: 4,
+                field_kind: Json(FieldKind::Formula {
+                    expression: "1".into(),
+                    result_kind: FormulaKind::Integer,
+                }),
+            },
+        ];
+        let (field_idents, relation_field_ids) = super::split_relation_fields(&fields);
+        assert_eq!(
+            field_idents.iter().map(|f| f.to_string()).collect_vec(),
+            [r#""f1""#, r#""f3""#]
+        );
+        assert_eq!(relation_field_ids, [2]);
+
+        let (cells, relation_ids) = super::split_relation_cells(
+            &fields,
+            vec![
+                Cell::Integer(1),
+                Cell::EntryIds(vec![2, 3]),
+                Cell::Boolean(true),
+                Cell::Null,
+            ],
+        );
+        assert_eq!(cells, [Cell::Integer(1), Cell::Boolean(true)]);
+        assert_eq!(relation_ids, [vec![2, 3]]);
+    }
+
+    fn relation(multiple: bool) -> FieldKind {
+        FieldKind::Relation {
+            is_required: false,
+            target_table_id: 1,
+            multiple,
+            on_delete: RelationDeleteRule::Restrict,
+        }
     }
 
     #[test]
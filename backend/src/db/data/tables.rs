@@ -6,7 +6,7 @@ use crate::{
     model::{
         access::AccessRole,
         data::{
-            CreateTable, Field, FieldIdentifier, FieldMetadata, GetTable, Table, TableData,
+            CreateAuditLog, CreateTable, Field, FieldMetadata, GetTable, Table, TableData,
             TableIdentifier, UpdateTable,
         },
     },
@@ -97,10 +97,13 @@ pub async fn update_table(
 }
 
 /// Delete this table along with the actual SQL table and the fields.
+/// Relation fields of other tables referencing this table are also deleted,
+/// as well as the axes of charts joining this table.
+/// Return the deleted relation fields of the other tables.
 pub async fn delete_table(
     conn: impl Acquire<'_, Database = Postgres>,
     table_id: Id,
-) -> sqlx::Result<()> {
+) -> sqlx::Result<Vec<Field>> {
     let mut tx = conn.begin().await?;
 
     let chart_ids: Vec<Id> = sqlx::query_scalar(
@@ -117,6 +120,41 @@ pub async fn delete_table(
         db::delete_chart(tx.as_mut(), chart_id).await?;
     }
 
+    // Charts of other tables joining this table lose the axes of the joined tables.
+    let joining_chart_ids: Vec<Id> = sqlx::query_scalar(
+        r#"
+            DELETE FROM axis
+            WHERE $1 = ANY(table_path)
+            RETURNING chart_id
+        "#,
+    )
+    .bind(table_id)
+    .fetch_all(tx.as_mut())
+    .await?;
+    db::rebuild_chart_views(
+        tx.as_mut(),
+        joining_chart_ids.into_iter().unique().collect(),
+    )
+    .await?;
+
+    let relation_fields: Vec<Field> = sqlx::query_as(
+        r#"
+            SELECT *
+            FROM meta_field
+            WHERE field_kind->>'type' = 'Relation'
+                AND (
+                    (field_kind->>'target_table_id')::int = $1
+                    OR (table_id = $1 AND (field_kind->>'multiple')::boolean)
+                )
+        "#,
+    )
+    .bind(table_id)
+    .fetch_all(tx.as_mut())
+    .await?;
+    for field in &relation_fields {
+        db::delete_field(tx.as_mut(), field.field_id).await?;
+    }
+
     sqlx::query(
         r#"
             DELETE FROM meta_table
@@ -135,7 +173,24 @@ pub async fn delete_table(
 
     tx.commit().await?;
 
-    Ok(())
+    Ok(relation_fields
+        .into_iter()
+        .filter(|field| field.table_id != table_id)
+        .collect())
+}
+
+/// Get the metadata of this table.
+pub async fn get_table(executor: impl PgExecutor<'_>, table_id: Id) -> sqlx::Result<Table> {
+    sqlx::query_as(
+        r#"
+            SELECT *
+            FROM meta_table
+            WHERE table_id = $1
+        "#,
+    )
+    .bind(table_id)
+    .fetch_one(executor)
+    .await
 }
 
 /// Get the parent ID of this table.
@@ -155,34 +210,60 @@ pub async fn get_table_parent_id(
     .await
 }
 
-/// Get all tables belonging to this user.
+/// Recursive query of the effective access roles of the user `$1` to the tables.
+/// A table without an access role of its own inherits the role of its parent table.
+const TABLE_ACCESS: &str = r#"
+    WITH RECURSIVE table_access AS (
+        SELECT resource_id AS table_id, access_role
+        FROM meta_table_access_v
+        WHERE user_id = $1
+        UNION ALL
+        SELECT t.table_id, a.access_role
+        FROM meta_table AS t
+        JOIN table_access AS a
+        ON t.parent_id = a.table_id
+        WHERE t.deleted_at IS NULL
+        AND NOT EXISTS (
+            SELECT 1
+            FROM meta_table_access_v AS v
+            WHERE v.resource_id = t.table_id AND v.user_id = $1
+        )
+    )
+"#;
+
+/// Get all tables belonging to this user, including the tables inherited from parent tables.
 pub async fn get_tables(executor: impl PgExecutor<'_>, user_id: Id) -> sqlx::Result<Vec<GetTable>> {
-    sqlx::query_as(
+    sqlx::query_as(&format!(
         r#"
+            {TABLE_ACCESS}
             SELECT *
             FROM meta_table AS t
-            JOIN meta_table_access_v AS a
-            ON t.table_id = a.resource_id
-            WHERE user_id = $1
-        "#,
-    )
+            JOIN table_access AS a
+            USING (table_id)
+        "#
+    ))
     .bind(user_id)
     .fetch_all(executor)
     .await
 }
 
-/// Get all the children tables of this table.
+/// Get all the children tables of this table with the access role of this user.
 pub async fn get_table_children(
     executor: impl PgExecutor<'_>,
     table_id: Id,
-) -> sqlx::Result<Vec<Table>> {
-    sqlx::query_as(
+    user_id: Id,
+) -> sqlx::Result<Vec<GetTable>> {
+    sqlx::query_as(&format!(
         r#"
+            {TABLE_ACCESS}
             SELECT *
-            FROM meta_table
-            WHERE parent_id = $1
-        "#,
-    )
+            FROM meta_table AS t
+            JOIN table_access AS a
+            USING (table_id)
+            WHERE t.parent_id = $2
+        "#
+    ))
+    .bind(user_id)
     .bind(table_id)
     .fetch_all(executor)
     .await
@@ -216,12 +297,13 @@ pub async fn get_table_data(
     .fetch_all(executor)
     .await?;
 
-    let field_idents = fields
+    let fields_metadata = fields
         .iter()
-        .map(|field| FieldIdentifier::new(field.field_id))
+        .cloned()
+        .map(FieldMetadata::from_field)
         .collect_vec();
 
-    let select_columns = select_columns(table.parent_id.is_some(), &field_idents);
+    let select_columns = select_columns(table.parent_id.is_some(), &fields_metadata);
 
     let table_ident = TableIdentifier::new(table_id, "data_table");
     let entries = sqlx::query::<Postgres>(&format!(
@@ -233,31 +315,14 @@ pub async fn get_table_data(
     .fetch_all(executor)
     .await?
     .into_iter()
-    .map(|row| {
-        entry_from_row(
-            row,
-            &fields
-                .iter()
-                .map(
-                    |Field {
-                         field_id,
-                         field_kind,
-                         ..
-                     }| FieldMetadata {
-                        field_id: *field_id,
-                        field_kind: field_kind.clone(),
-                    },
-                )
-                .collect_vec(),
-        )
-    })
+    .map(|row| entry_from_row(row, &fields_metadata))
     .try_collect()?;
 
     let children_ids = sqlx::query_scalar(
         r#"
             SELECT table_id
             FROM meta_table
-            WHERE parent_id = $1
+            WHERE parent_id = $1 AND deleted_at IS NULL
          "#,
     )
     .bind(table_id)
@@ -281,30 +346,56 @@ pub async fn get_table_data(
     })
 }
 
-/// Delete tables that have no users with the owner access role.
+/// Delete tables that have no users with the owner access role,
+/// neither their own nor inherited from a parent table.
+/// The relation fields removed from the remaining tables are recorded in the audit log.
 pub async fn delete_tables_without_owner(
     conn: impl Acquire<'_, Database = Postgres>,
 ) -> sqlx::Result<()> {
     let mut tx = conn.begin().await?;
+    // Child tables come before their parent tables.
     let table_ids: Vec<Id> = sqlx::query_scalar(
         r#"
-        SELECT table_id
+        WITH RECURSIVE ancestor AS (
+            SELECT table_id, table_id AS ancestor_id, parent_id
+            FROM meta_table
+            UNION ALL
+            SELECT a.table_id, t.table_id, t.parent_id
+            FROM meta_table AS t
+            JOIN ancestor AS a
+            ON t.table_id = a.parent_id
+        )
+        SELECT t.table_id
         FROM meta_table AS t
         WHERE NOT EXISTS (
             SELECT 1
-            FROM meta_table_access AS a
-            WHERE a.resource_id = t.table_id
+            FROM ancestor
+            JOIN meta_table_access AS a
+            ON a.resource_id = ancestor.ancestor_id
+            WHERE ancestor.table_id = t.table_id
             AND a.access_role = $1
         )
+        ORDER BY (
+            SELECT count(*)
+            FROM ancestor
+            WHERE ancestor.table_id = t.table_id
+        ) DESC
     "#,
     )
     .bind(AccessRole::Owner)
     .fetch_all(tx.as_mut())
     .await?;
 
-    for table_id in table_ids {
-        delete_table(tx.as_mut(), table_id).await?;
+    let mut relation_fields = Vec::new();
+    for table_id in &table_ids {
+        relation_fields.extend(delete_table(tx.as_mut(), *table_id).await?);
     }
+    let logs = relation_fields
+        .iter()
+        .filter(|field| !table_ids.contains(&field.table_id))
+        .map(|field| CreateAuditLog::field(Some(field), None))
+        .collect();
+    db::create_audit_logs(tx.as_mut(), None, logs).await?;
     tx.commit().await?;
     Ok(())
 }
@@ -315,8 +406,11 @@ mod test {
     use crate::{
         db::{self, create_user},
         model::{
-            access::AccessRole,
-            data::{CreateTable, UpdateTable},
+            access::{AccessRole, Resource},
+            data::{
+                CreateField, CreateTable, FieldKind, GetTable, RelationDeleteRule,
+                RelationIdentifier, UpdateTable,
+            },
         },
         test_util,
     };
@@ -408,8 +502,31 @@ mod test {
             },
         )
         .await?;
-
-        super::delete_table(&db, table.table_id).await?;
+        let other_table = super::create_table(
+            &db,
+            CreateTable {
+                parent_id: None,
+                name: "other".into(),
+                description: "".into(),
+            },
+        )
+        .await?;
+        let relation = |target_table_id, multiple| CreateField {
+            name: "relation".into(),
+            field_kind: FieldKind::Relation {
+                is_required: false,
+                target_table_id,
+                multiple,
+                on_delete: RelationDeleteRule::Restrict,
+            },
+        };
+        let other_field =
+            db::create_field(&db, other_table.table_id, relation(table.table_id, false)).await?;
+        let field =
+            db::create_field(&db, table.table_id, relation(other_table.table_id, true)).await?;
+
+        let deleted_fields = super::delete_table(&db, table.table_id).await?;
+        assert_eq!(deleted_fields, std::slice::from_ref(&other_field));
         let not_exists: bool = sqlx::query_scalar(
             r#"SELECT NOT EXISTS (SELECT 1 FROM meta_table WHERE table_id = $1)"#,
         )
@@ -419,6 +536,13 @@ mod test {
 
         assert!(not_exists);
 
+        assert!(!db::field_exists(&db, other_table.table_id, other_field.field_id).await?);
+        let relation_exists: bool = sqlx::query_scalar(r#"SELECT to_regclass($1) IS NOT NULL"#)
+            .bind(RelationIdentifier::new(field.field_id).to_string())
+            .fetch_one(&db)
+            .await?;
+        assert!(!relation_exists);
+
         Ok(())
     }
 
@@ -502,8 +626,79 @@ mod test {
         Ok(())
     }
 
+    #[sqlx::test]
+    async fn get_tables_inherited(db: PgPool) -> anyhow::Result<()> {
+        let user = create_user(&db, "test".into(), "password".into(), false).await?;
+        let parent = super::create_table(
+            &db,
+            CreateTable {
+                parent_id: None,
+                name: "Parent".into(),
+                description: "".into(),
+            },
+        )
+        .await?;
+        let child = super::create_table(
+            &db,
+            CreateTable {
+                parent_id: Some(parent.table_id),
+                name: "Child".into(),
+                description: "".into(),
+            },
+        )
+        .await?;
+        let grandchild = super::create_table(
+            &db,
+            CreateTable {
+                parent_id: Some(child.table_id),
+                name: "Grandchild".into(),
+                description: "".into(),
+            },
+        )
+        .await?;
+
+        db::create_access(
+            &db,
+            Resource::Table,
+            parent.table_id,
+            user.user_id,
+            AccessRole::Editor,
+        )
+        .await?;
+        db::create_access(
+            &db,
+            Resource::Table,
+            child.table_id,
+            user.user_id,
+            AccessRole::Viewer,
+        )
+        .await?;
+
+        let tables = super::get_tables(&db, user.user_id).await?;
+        test_util::assert_eq_vec(
+            tables,
+            vec![
+                GetTable {
+                    table: parent,
+                    access_role: AccessRole::Editor,
+                },
+                GetTable {
+                    table: child,
+                    access_role: AccessRole::Viewer,
+                },
+                GetTable {
+                    table: grandchild,
+                    access_role: AccessRole::Viewer,
+                },
+            ],
+            |t| t.table.table_id,
+        );
+        Ok(())
+    }
+
     #[sqlx::test]
     async fn get_table_children(db: PgPool) -> anyhow::Result<()> {
+        let user = create_user(&db, "test".into(), "password".into(), false).await?;
         let parent = super::create_table(
             &db,
             CreateTable {
@@ -524,9 +719,25 @@ mod test {
         )
         .await?;
 
-        let children = super::get_table_children(&db, parent.table_id).await?;
+        db::create_access(
+            &db,
+            Resource::Table,
+            parent.table_id,
+            user.user_id,
+            AccessRole::Owner,
+        )
+        .await?;
+
+        let children = super::get_table_children(&db, parent.table_id, user.user_id).await?;
 
-        test_util::assert_eq_vec(children, vec![child], |t| t.table_id);
+        test_util::assert_eq_vec(
+            children,
+            vec![GetTable {
+                table: child,
+                access_role: AccessRole::Owner,
+            }],
+            |t| t.table.table_id,
+        );
 
         Ok(())
     }
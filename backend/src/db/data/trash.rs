@@ -0,0 +1,1714 @@
+//! Database functions for the trash bin of deleted tables, fields and entries.
+
+use super::{
+    delete_field_axes, disable_relation_constraint, enable_relation_constraint, set_relation_links,
+    split_relation_fields,
+};
+use crate::{
+    Id, db,
+    model::{
+        access::AccessRole,
+        data::{
+            AuditResource, CreateAuditLog, Entry, Field, FieldIdentifier, FieldKind,
+            RelationDeleteRule, RelationIdentifier, TableIdentifier, TrashItem,
+        },
+        viz::Axis,
+    },
+};
+use chrono::{DateTime, TimeDelta, Utc};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use serde_json::Value;
+use sqlx::{Acquire, PgExecutor, Postgres, types::Json};
+use std::collections::HashMap;
+
+/// Move this table and its child tables to the trash.
+/// The SQL tables are kept and the views of the charts using them are emptied.
+pub async fn trash_table(
+    conn: impl Acquire<'_, Database = Postgres>,
+    user_id: Id,
+    table_id: Id,
+) -> sqlx::Result<TrashItem> {
+    let mut tx = conn.begin().await?;
+
+    let table = db::get_table(tx.as_mut(), table_id).await?;
+
+    let table_ids: Vec<Id> = sqlx::query_scalar(
+        r#"
+            WITH RECURSIVE tree AS (
+                SELECT table_id
+                FROM meta_table
+                WHERE table_id = $1
+                UNION ALL
+                SELECT t.table_id
+                FROM meta_table AS t
+                JOIN tree
+                ON t.parent_id = tree.table_id
+                WHERE t.deleted_at IS NULL
+            )
+            UPDATE meta_table
+            SET deleted_at = now()
+            WHERE table_id IN (SELECT table_id FROM tree)
+            RETURNING table_id
+        "#,
+    )
+    .bind(table_id)
+    .fetch_all(tx.as_mut())
+    .await?;
+
+    rebuild_table_chart_views(tx.as_mut(), table_ids).await?;
+
+    let item = insert_trash_item(
+        tx.as_mut(),
+        user_id,
+        table_id,
+        AuditResource::Table,
+        table_id,
+        Some(table.name.clone()),
+        to_value(&table),
+        None,
+    )
+    .await?;
+
+    tx.commit().await?;
+    Ok(item)
+}
+
+/// Move this field to the trash.
+/// The SQL column is kept and the axes using or ordered by the field are removed from their charts.
+/// The foreign key of a relation field is dropped until the field is restored.
+pub async fn trash_field(
+    conn: impl Acquire<'_, Database = Postgres>,
+    user_id: Id,
+    field_id: Id,
+) -> sqlx::Result<TrashItem> {
+    let mut tx = conn.begin().await?;
+
+    let field: Field = sqlx::query_as(
+        r#"
+            SELECT *
+            FROM meta_field
+            WHERE field_id = $1
+        "#,
+    )
+    .bind(field_id)
+    .fetch_one(tx.as_mut())
+    .await?;
+
+    let axes: Vec<Axis> = sqlx::query_as(
+        r#"
+            SELECT
+                axis_id,
+                chart_id,
+                field_id,
+                table_path,
+                axis_kind,
+                aggregate,
+                percentile,
+                order_field_id,
+                sort,
+                bins,
+                bucket,
+                created_at,
+                updated_at
+            FROM axis
+            WHERE field_id = $1 OR order_field_id = $1
+        "#,
+    )
+    .bind(field_id)
+    .fetch_all(tx.as_mut())
+    .await?;
+
+    delete_field_axes(tx.as_mut(), field_id).await?;
+
+    if let FieldKind::Relation { multiple, .. } = field.field_kind.0 {
+        disable_relation_constraint(tx.as_mut(), field.table_id, field_id, multiple).await?;
+    }
+
+    sqlx::query(
+        r#"
+            DELETE FROM meta_field
+            WHERE field_id = $1
+        "#,
+    )
+    .bind(field_id)
+    .execute(tx.as_mut())
+    .await?;
+
+    let item = insert_trash_item(
+        tx.as_mut(),
+        user_id,
+        field.table_id,
+        AuditResource::Field,
+        field_id,
+        Some(field.name.clone()),
+        to_value(&field),
+        Some(to_value(&axes)),
+    )
+    .await?;
+
+    tx.commit().await?;
+    Ok(item)
+}
+
+/// Snapshot of a trashed entry, restoring it along with the references to it.
+#[derive(Serialize, Deserialize)]
+struct EntrySnapshot {
+    /// The row of the entry in the SQL table.
+    row: Value,
+    /// The entries unlinked from the entry by the deletion, by relation field.
+    unlinked: Vec<(Id, Vec<Id>)>,
+    /// The trash items of the entries deleted along with the entry by cascading relations.
+    cascaded: Vec<Id>,
+}
+
+/// Entry trashed by [trash_entry].
+struct TrashedEntry {
+    table_id: Id,
+    entry_id: Id,
+    /// The entries of the relation fields unlinking the entry, by relation field.
+    unlinked: Vec<(Field, Vec<Id>)>,
+    /// The indexes of the entries deleted along with the entry by cascading relations.
+    cascaded: Vec<usize>,
+}
+
+/// Move this entry to the trash.
+/// The entry is deleted from the SQL table and its row is kept in the trash.
+/// Entries deleted along with it by cascading relations are trashed as well and the entries
+/// unlinked from it are recorded, so restoring the entry also restores them.
+/// These side effects are recorded in the audit log.
+pub async fn trash_entry(
+    conn: impl Acquire<'_, Database = Postgres>,
+    user_id: Id,
+    table_id: Id,
+    entry_id: Id,
+) -> sqlx::Result<TrashItem> {
+    let mut tx = conn.begin().await?;
+
+    // Each entry comes after the entries it is referenced by.
+    let mut trashed = vec![TrashedEntry {
+        table_id,
+        entry_id,
+        unlinked: Vec::new(),
+        cascaded: Vec::new(),
+    }];
+    let mut index = 0;
+    while index < trashed.len() {
+        let (table_id, entry_id) = (trashed[index].table_id, trashed[index].entry_id);
+        let fields: Vec<Field> = sqlx::query_as(
+            r#"
+                SELECT *
+                FROM meta_field
+                WHERE field_kind->>'type' = 'Relation'
+                    AND (field_kind->>'target_table_id')::int = $1
+                ORDER BY field_id
+            "#,
+        )
+        .bind(table_id)
+        .fetch_all(tx.as_mut())
+        .await?;
+
+        for field in fields {
+            let FieldKind::Relation {
+                multiple,
+                on_delete,
+                ..
+            } = field.field_kind.0
+            else {
+                continue;
+            };
+            if on_delete == RelationDeleteRule::Restrict {
+                continue;
+            }
+            let source_ids =
+                get_referencing_entry_ids(tx.as_mut(), &field, multiple, entry_id).await?;
+            if source_ids.is_empty() {
+                continue;
+            }
+            if on_delete == RelationDeleteRule::Unlink {
+                trashed[index].unlinked.push((field, source_ids));
+                continue;
+            }
+            for source_id in source_ids {
+                if !trashed
+                    .iter()
+                    .any(|entry| entry.table_id == field.table_id && entry.entry_id == source_id)
+                {
+                    let cascaded_index = trashed.len();
+                    trashed[index].cascaded.push(cascaded_index);
+                    trashed.push(TrashedEntry {
+                        table_id: field.table_id,
+                        entry_id: source_id,
+                        unlinked: Vec::new(),
+                        cascaded: Vec::new(),
+                    });
+                }
+            }
+        }
+        index += 1;
+    }
+
+    // Snapshot everything before the deletions change the references.
+    let mut snapshots = Vec::new();
+    for trashed_entry in &trashed {
+        let fields = db::get_fields_metadata(tx.as_mut(), trashed_entry.table_id).await?;
+        let entry = db::get_entry(
+            tx.as_mut(),
+            trashed_entry.table_id,
+            trashed_entry.entry_id,
+            &fields,
+        )
+        .await?;
+        let row =
+            get_entry_row(tx.as_mut(), trashed_entry.table_id, trashed_entry.entry_id).await?;
+        snapshots.push((entry, row));
+    }
+    let mut unlinked_entries: HashMap<Id, Vec<Id>> = HashMap::new();
+    for (field, source_ids) in trashed.iter().flat_map(|entry| &entry.unlinked) {
+        unlinked_entries.entry(field.table_id).or_default().extend(
+            source_ids.iter().copied().filter(|source_id| {
+                !trashed
+                    .iter()
+                    .any(|entry| entry.table_id == field.table_id && entry.entry_id == *source_id)
+            }),
+        );
+    }
+    let unlinked_entries = unlinked_entries.into_iter().sorted().collect_vec();
+    let mut old_entries = Vec::new();
+    for (table_id, source_ids) in &unlinked_entries {
+        old_entries.push(get_entries(tx.as_mut(), *table_id, source_ids.clone()).await?);
+    }
+
+    for trashed_entry in trashed.iter().rev() {
+        db::delete_entry(tx.as_mut(), trashed_entry.table_id, trashed_entry.entry_id).await?;
+    }
+
+    let mut logs = Vec::new();
+    let mut trash_ids = vec![0; trashed.len()];
+    let mut item = None;
+    for (index, (trashed_entry, (entry, row))) in trashed.iter().zip(snapshots).enumerate().rev() {
+        let snapshot = EntrySnapshot {
+            row,
+            unlinked: trashed_entry
+                .unlinked
+                .iter()
+                .map(|(field, source_ids)| (field.field_id, source_ids.clone()))
+                .collect(),
+            cascaded: trashed_entry
+                .cascaded
+                .iter()
+                .map(|index| trash_ids[*index])
+                .collect(),
+        };
+        let trash_item = insert_trash_item(
+            tx.as_mut(),
+            user_id,
+            trashed_entry.table_id,
+            AuditResource::Entry,
+            trashed_entry.entry_id,
+            None,
+            to_value(&entry.cells),
+            Some(to_value(&snapshot)),
+        )
+        .await?;
+        trash_ids[index] = trash_item.trash_id;
+        if index == 0 {
+            item = Some(trash_item);
+        } else {
+            logs.push(CreateAuditLog::entry(
+                trashed_entry.table_id,
+                Some(&entry),
+                None,
+            ));
+        }
+    }
+    for ((table_id, source_ids), old_entries) in unlinked_entries.into_iter().zip(old_entries) {
+        let entries = get_entries(tx.as_mut(), table_id, source_ids).await?;
+        logs.extend(changed_entry_logs(table_id, &old_entries, &entries));
+    }
+    db::create_audit_logs(tx.as_mut(), Some(user_id), logs).await?;
+
+    tx.commit().await?;
+    Ok(item.expect("trashed entry without a trash item"))
+}
+
+/// Get the IDs of the entries referencing this entry through this relation field.
+async fn get_referencing_entry_ids(
+    executor: impl PgExecutor<'_>,
+    field: &Field,
+    multiple: bool,
+    entry_id: Id,
+) -> sqlx::Result<Vec<Id>> {
+    let query = if multiple {
+        let relation_ident = RelationIdentifier::new(field.field_id);
+        format!(
+            r#"
+                SELECT source_id
+                FROM {relation_ident}
+                WHERE target_id = $1
+                ORDER BY source_id
+            "#
+        )
+    } else {
+        let table_ident = TableIdentifier::new(field.table_id, "data_table");
+        let field_ident = FieldIdentifier::new(field.field_id);
+        format!(
+            r#"
+                SELECT entry_id
+                FROM {table_ident}
+                WHERE {field_ident} = $1
+                ORDER BY entry_id
+            "#
+        )
+    };
+    sqlx::query_scalar(&query)
+        .bind(entry_id)
+        .fetch_all(executor)
+        .await
+}
+
+/// Get the row of this entry in the SQL table as JSON.
+async fn get_entry_row(
+    executor: impl PgExecutor<'_>,
+    table_id: Id,
+    entry_id: Id,
+) -> sqlx::Result<Value> {
+    let table_ident = TableIdentifier::new(table_id, "data_table");
+    let Json(row) = sqlx::query_scalar(&format!(
+        r#"
+            SELECT to_jsonb(t)
+            FROM {table_ident} AS t
+            WHERE entry_id = $1
+        "#
+    ))
+    .bind(entry_id)
+    .fetch_one(executor)
+    .await?;
+    Ok(row)
+}
+
+/// Get the existing entries of this table among these entries.
+async fn get_entries(
+    conn: impl Acquire<'_, Database = Postgres>,
+    table_id: Id,
+    entry_ids: Vec<Id>,
+) -> sqlx::Result<Vec<Entry>> {
+    let mut tx = conn.begin().await?;
+
+    let fields = db::get_fields_metadata(tx.as_mut(), table_id).await?;
+    let mut entries = Vec::new();
+    for entry_id in db::get_existing_entry_ids(tx.as_mut(), table_id, entry_ids).await? {
+        entries.push(db::get_entry(tx.as_mut(), table_id, entry_id, &fields).await?);
+    }
+
+    tx.commit().await?;
+    Ok(entries)
+}
+
+/// Record the updates of the entries whose cells changed.
+fn changed_entry_logs(
+    table_id: Id,
+    old_entries: &[Entry],
+    entries: &[Entry],
+) -> Vec<CreateAuditLog> {
+    entries
+        .iter()
+        .filter_map(|entry| {
+            let old_entry = old_entries
+                .iter()
+                .find(|old_entry| old_entry.entry_id == entry.entry_id)?;
+            (old_entry.cells != entry.cells)
+                .then(|| CreateAuditLog::entry(table_id, Some(old_entry), Some(entry)))
+        })
+        .collect()
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn insert_trash_item(
+    executor: impl PgExecutor<'_>,
+    user_id: Id,
+    table_id: Id,
+    resource: AuditResource,
+    resource_id: Id,
+    name: Option<String>,
+    value: Value,
+    snapshot: Option<Value>,
+) -> sqlx::Result<TrashItem> {
+    sqlx::query_as(
+        r#"
+            WITH tr AS (
+                INSERT INTO trash (
+                    table_id,
+                    resource,
+                    resource_id,
+                    name,
+                    value,
+                    snapshot,
+                    deleted_by
+                )
+                VALUES ($1, $2, $3, $4, $5, $6, $7)
+                RETURNING *
+            )
+            SELECT tr.*, u.username
+            FROM tr
+            LEFT JOIN app_user AS u
+            ON tr.deleted_by = u.user_id
+        "#,
+    )
+    .bind(table_id)
+    .bind(resource)
+    .bind(resource_id)
+    .bind(name)
+    .bind(Json(value))
+    .bind(snapshot.map(Json))
+    .bind(user_id)
+    .fetch_one(executor)
+    .await
+}
+
+/// Get the trashed items this user can restore, most recently deleted first.
+/// Owners can restore anything in their tables and editors can restore entries.
+/// The roles are resolved like [db::get_access_role], including group roles and the roles
+/// inherited from parent tables, but also for the tables in the trash.
+pub async fn get_trash(executor: impl PgExecutor<'_>, user_id: Id) -> sqlx::Result<Vec<TrashItem>> {
+    select_trash(executor, user_id, None).await
+}
+
+/// Get a trashed item if this user can restore it.
+pub async fn get_trash_item(
+    executor: impl PgExecutor<'_>,
+    user_id: Id,
+    trash_id: Id,
+) -> sqlx::Result<Option<TrashItem>> {
+    Ok(select_trash(executor, user_id, Some(trash_id)).await?.pop())
+}
+
+/// Select the trashed items this user can restore, or only this one.
+async fn select_trash(
+    executor: impl PgExecutor<'_>,
+    user_id: Id,
+    trash_id: Option<Id>,
+) -> sqlx::Result<Vec<TrashItem>> {
+    sqlx::query_as(
+        r#"
+            WITH RECURSIVE user_access AS (
+                SELECT a.resource_id, min(a.access_role) AS access_role
+                FROM (
+                    SELECT resource_id, access_role
+                    FROM meta_table_access
+                    WHERE user_id = $1
+                    UNION ALL
+                    SELECT g.resource_id, g.access_role
+                    FROM meta_table_group_access AS g
+                    JOIN user_group_member AS m
+                    ON g.group_id = m.group_id
+                    WHERE m.user_id = $1
+                ) AS a
+                GROUP BY a.resource_id
+            ),
+            ancestor AS (
+                SELECT table_id, table_id AS ancestor_id, parent_id, 0 AS depth
+                FROM meta_table
+                WHERE table_id IN (SELECT table_id FROM trash)
+                UNION ALL
+                SELECT a.table_id, t.table_id, t.parent_id, a.depth + 1
+                FROM meta_table AS t
+                JOIN ancestor AS a
+                ON t.table_id = a.parent_id
+            ),
+            table_access AS (
+                SELECT DISTINCT ON (a.table_id) a.table_id, u.access_role
+                FROM ancestor AS a
+                JOIN user_access AS u
+                ON u.resource_id = a.ancestor_id
+                ORDER BY a.table_id, a.depth
+            )
+            SELECT tr.*, u.username
+            FROM trash AS tr
+            JOIN meta_table AS t
+            ON tr.table_id = t.table_id
+            JOIN table_access AS a
+            ON tr.table_id = a.table_id
+            LEFT JOIN app_user AS u
+            ON tr.deleted_by = u.user_id
+            WHERE (
+                    a.access_role = $2
+                    OR (tr.resource = 'Entry' AND a.access_role = $3)
+                )
+                AND (tr.resource = 'Table' OR t.deleted_at IS NULL)
+                AND ($4::INT IS NULL OR tr.trash_id = $4)
+            ORDER BY tr.deleted_at DESC, tr.trash_id DESC
+        "#,
+    )
+    .bind(user_id)
+    .bind(AccessRole::Owner)
+    .bind(AccessRole::Editor)
+    .bind(trash_id)
+    .fetch_all(executor)
+    .await
+}
+
+/// Return true if this table or one of its parent tables is in the trash.
+pub async fn table_in_trash(executor: impl PgExecutor<'_>, table_id: Id) -> sqlx::Result<bool> {
+    sqlx::query_scalar(
+        r#"
+            WITH RECURSIVE tree AS (
+                SELECT table_id, parent_id, deleted_at
+                FROM meta_table
+                WHERE table_id = $1
+                UNION ALL
+                SELECT t.table_id, t.parent_id, t.deleted_at
+                FROM meta_table AS t
+                JOIN tree
+                ON t.table_id = tree.parent_id
+            )
+            SELECT EXISTS (
+                SELECT 1
+                FROM tree
+                WHERE deleted_at IS NOT NULL
+            )
+        "#,
+    )
+    .bind(table_id)
+    .fetch_one(executor)
+    .await
+}
+
+/// Restore this trashed item and rebuild the views of the charts depending on it.
+/// Entries trashed along with a restored entry are restored as well and the entries
+/// unlinked from it are linked again, which is recorded in the audit log.
+pub async fn restore_trash_item(
+    conn: impl Acquire<'_, Database = Postgres>,
+    user_id: Id,
+    trash_id: Id,
+) -> sqlx::Result<()> {
+    let mut tx = conn.begin().await?;
+
+    let mut logs = Vec::new();
+    let mut trash_ids = vec![(trash_id, false)];
+    while let Some((trash_id, cascaded)) = trash_ids.pop() {
+        let (table_id, resource, resource_id, Json(value), snapshot): (
+            Id,
+            AuditResource,
+            Id,
+            Json<Value>,
+            Option<Json<Value>>,
+        ) = sqlx::query_as(
+            r#"
+                DELETE FROM trash
+                WHERE trash_id = $1
+                RETURNING table_id, resource, resource_id, value, snapshot
+            "#,
+        )
+        .bind(trash_id)
+        .fetch_one(tx.as_mut())
+        .await?;
+        let snapshot = snapshot.map(|Json(snapshot)| snapshot);
+
+        match resource {
+            AuditResource::Table => restore_table(tx.as_mut(), table_id).await?,
+            AuditResource::Field => {
+                let field: Field = from_value(value)?;
+                let axes: Vec<Axis> = snapshot.map(from_value).transpose()?.unwrap_or_default();
+                restore_field(tx.as_mut(), field, axes).await?;
+            }
+            AuditResource::Entry => {
+                let cells: HashMap<Id, Value> = from_value(value)?;
+                let snapshot = snapshot.ok_or_else(|| {
+                    sqlx::Error::Decode("trashed entry without a snapshot".into())
+                })?;
+                // Entries trashed before their references were recorded only kept the row.
+                let snapshot = serde_json::from_value(snapshot.clone()).unwrap_or(EntrySnapshot {
+                    row: snapshot,
+                    unlinked: Vec::new(),
+                    cascaded: Vec::new(),
+                });
+                restore_entry(tx.as_mut(), table_id, resource_id, cells, snapshot.row).await?;
+                if cascaded {
+                    let fields = db::get_fields_metadata(tx.as_mut(), table_id).await?;
+                    let entry = db::get_entry(tx.as_mut(), table_id, resource_id, &fields).await?;
+                    logs.push(CreateAuditLog::entry(table_id, None, Some(&entry)));
+                }
+                logs.extend(
+                    relink_entries(tx.as_mut(), table_id, resource_id, snapshot.unlinked).await?,
+                );
+                let cascaded_ids: Vec<Id> = sqlx::query_scalar(
+                    r#"
+                        SELECT trash_id
+                        FROM trash
+                        WHERE trash_id = ANY($1)
+                        ORDER BY trash_id DESC
+                    "#,
+                )
+                .bind(snapshot.cascaded)
+                .fetch_all(tx.as_mut())
+                .await?;
+                trash_ids.extend(cascaded_ids.into_iter().map(|trash_id| (trash_id, true)));
+            }
+        }
+    }
+    db::create_audit_logs(tx.as_mut(), Some(user_id), logs).await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Link the source entries of these relation fields to this restored entry again,
+/// unless the fields changed or the entries were deleted or linked elsewhere since.
+async fn relink_entries(
+    conn: impl Acquire<'_, Database = Postgres>,
+    table_id: Id,
+    entry_id: Id,
+    unlinked: Vec<(Id, Vec<Id>)>,
+) -> sqlx::Result<Vec<CreateAuditLog>> {
+    let mut tx = conn.begin().await?;
+
+    let mut relations = Vec::new();
+    let mut source_entries: HashMap<Id, Vec<Id>> = HashMap::new();
+    for (field_id, source_ids) in unlinked {
+        let field: Option<Field> = sqlx::query_as(
+            r#"
+                SELECT *
+                FROM meta_field
+                WHERE field_id = $1
+            "#,
+        )
+        .bind(field_id)
+        .fetch_optional(tx.as_mut())
+        .await?;
+        if let Some(field) = field
+            && let FieldKind::Relation {
+                target_table_id,
+                multiple,
+                ..
+            } = field.field_kind.0
+            && target_table_id == table_id
+        {
+            source_entries
+                .entry(field.table_id)
+                .or_default()
+                .extend(&source_ids);
+            relations.push((field, multiple, source_ids));
+        }
+    }
+    let source_entries = source_entries.into_iter().sorted().collect_vec();
+    let mut old_entries = Vec::new();
+    for (source_table_id, source_ids) in &source_entries {
+        old_entries.push(get_entries(tx.as_mut(), *source_table_id, source_ids.clone()).await?);
+    }
+
+    for (field, multiple, source_ids) in relations {
+        let source_ids =
+            db::get_existing_entry_ids(tx.as_mut(), field.table_id, source_ids).await?;
+        if multiple {
+            let relation_ident = RelationIdentifier::new(field.field_id);
+            sqlx::query(&format!(
+                r#"
+                    INSERT INTO {relation_ident} (source_id, target_id)
+                    SELECT unnest($1::int[]), $2
+                    ON CONFLICT DO NOTHING
+                "#
+            ))
+            .bind(source_ids)
+            .bind(entry_id)
+            .execute(tx.as_mut())
+            .await?;
+        } else {
+            let table_ident = TableIdentifier::new(field.table_id, "data_table");
+            let field_ident = FieldIdentifier::new(field.field_id);
+            sqlx::query(&format!(
+                r#"
+                    UPDATE {table_ident}
+                    SET {field_ident} = $1
+                    WHERE entry_id = ANY($2) AND {field_ident} IS NULL
+                "#
+            ))
+            .bind(entry_id)
+            .bind(source_ids)
+            .execute(tx.as_mut())
+            .await?;
+        }
+    }
+
+    let mut logs = Vec::new();
+    for ((source_table_id, source_ids), old_entries) in source_entries.into_iter().zip(old_entries)
+    {
+        let entries = get_entries(tx.as_mut(), source_table_id, source_ids).await?;
+        logs.extend(changed_entry_logs(source_table_id, &old_entries, &entries));
+    }
+
+    tx.commit().await?;
+    Ok(logs)
+}
+
+/// Restore this table and the child tables trashed along with it.
+async fn restore_table(
+    conn: impl Acquire<'_, Database = Postgres>,
+    table_id: Id,
+) -> sqlx::Result<()> {
+    let mut tx = conn.begin().await?;
+
+    let deleted_at: Option<DateTime<Utc>> = sqlx::query_scalar(
+        r#"
+            SELECT deleted_at
+            FROM meta_table
+            WHERE table_id = $1
+        "#,
+    )
+    .bind(table_id)
+    .fetch_one(tx.as_mut())
+    .await?;
+
+    let table_ids: Vec<Id> = sqlx::query_scalar(
+        r#"
+            WITH RECURSIVE tree AS (
+                SELECT table_id
+                FROM meta_table
+                WHERE table_id = $1
+                UNION ALL
+                SELECT t.table_id
+                FROM meta_table AS t
+                JOIN tree
+                ON t.parent_id = tree.table_id
+                WHERE t.deleted_at = $2
+            )
+            UPDATE meta_table
+            SET deleted_at = NULL
+            WHERE table_id IN (SELECT table_id FROM tree)
+            RETURNING table_id
+        "#,
+    )
+    .bind(table_id)
+    .bind(deleted_at)
+    .fetch_all(tx.as_mut())
+    .await?;
+
+    rebuild_table_chart_views(tx.as_mut(), table_ids).await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Restore this field and its axes in the charts that still exist.
+/// References of a relation field to the entries deleted since it was trashed are removed.
+async fn restore_field(
+    conn: impl Acquire<'_, Database = Postgres>,
+    field: Field,
+    axes: Vec<Axis>,
+) -> sqlx::Result<()> {
+    let mut tx = conn.begin().await?;
+
+    // Put the field back at its position, after the last field if there are fewer fields now.
+    sqlx::query(
+        r#"
+            UPDATE meta_field
+            SET ordering = ordering + 1
+            WHERE table_id = $1 AND ordering >= $2
+        "#,
+    )
+    .bind(field.table_id)
+    .bind(field.ordering)
+    .execute(tx.as_mut())
+    .await?;
+
+    sqlx::query(
+        r#"
+            INSERT INTO meta_field (
+                field_id,
+                table_id,
+                name,
+                field_kind,
+                created_at,
+                updated_at,
+                ordering
+            )
+            VALUES (
+                $1,
+                $2,
+                $3,
+                $4,
+                $5,
+                $6,
+                (
+                    SELECT LEAST($7, COALESCE(max(ordering) + 1, 0))
+                    FROM meta_field
+                    WHERE table_id = $2
+                )
+            )
+        "#,
+    )
+    .bind(field.field_id)
+    .bind(field.table_id)
+    .bind(field.name)
+    .bind(&field.field_kind)
+    .bind(field.created_at)
+    .bind(field.updated_at)
+    .bind(field.ordering)
+    .execute(tx.as_mut())
+    .await?;
+
+    if let FieldKind::Relation {
+        target_table_id,
+        multiple,
+        on_delete,
+        ..
+    } = field.field_kind.0
+    {
+        enable_relation_constraint(
+            tx.as_mut(),
+            field.table_id,
+            field.field_id,
+            target_table_id,
+            multiple,
+            on_delete,
+        )
+        .await?;
+    }
+
+    let mut chart_ids = Vec::new();
+    for axis in axes {
+        let chart_id: Option<Id> = sqlx::query_scalar(
+            r#"
+                INSERT INTO axis (
+                    axis_id,
+                    chart_id,
+                    field_id,
+                    table_path,
+                    axis_kind,
+                    aggregate,
+                    percentile,
+                    order_field_id,
+                    sort,
+                    bins,
+                    bucket,
+                    created_at,
+                    updated_at
+                )
+                SELECT $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13
+                WHERE EXISTS (
+                    SELECT 1
+                    FROM chart
+                    WHERE chart_id = $2
+                )
+                AND EXISTS (
+                    SELECT 1
+                    FROM meta_field
+                    WHERE field_id = $3
+                )
+                AND ($8 IS NULL OR EXISTS (
+                    SELECT 1
+                    FROM meta_field
+                    WHERE field_id = $8
+                ))
+                AND NOT EXISTS (
+                    SELECT 1
+                    FROM unnest($4::INT[]) AS p(table_id)
+                    WHERE NOT EXISTS (
+                        SELECT 1
+                        FROM meta_table AS t
+                        WHERE t.table_id = p.table_id
+                    )
+                )
+                RETURNING chart_id
+            "#,
+        )
+        .bind(axis.axis_id)
+        .bind(axis.chart_id)
+        .bind(axis.field_id)
+        .bind(axis.table_path)
+        .bind(axis.axis_kind)
+        .bind(axis.aggregate)
+        .bind(axis.percentile)
+        .bind(axis.order_field_id)
+        .bind(axis.sort)
+        .bind(axis.bins)
+        .bind(axis.bucket)
+        .bind(axis.created_at)
+        .bind(axis.updated_at)
+        .fetch_optional(tx.as_mut())
+        .await?;
+        chart_ids.extend(chart_id);
+    }
+
+    db::rebuild_chart_views(tx.as_mut(), chart_ids.into_iter().unique().collect()).await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Insert the trashed row of this entry back into the SQL table.
+/// Values of fields created since the deletion take their default and
+/// references to entries deleted since are dropped.
+async fn restore_entry(
+    conn: impl Acquire<'_, Database = Postgres>,
+    table_id: Id,
+    entry_id: Id,
+    cells: HashMap<Id, Value>,
+    mut row: Value,
+) -> sqlx::Result<()> {
+    let mut tx = conn.begin().await?;
+
+    let fields = db::get_fields_metadata(tx.as_mut(), table_id).await?;
+    let with_parent = db::get_table_parent_id(tx.as_mut(), table_id)
+        .await?
+        .is_some();
+
+    for field in &fields {
+        if let FieldKind::Relation {
+            target_table_id,
+            multiple: false,
+            ..
+        } = field.field_kind.0
+        {
+            let column = FieldIdentifier::new(field.field_id).unquote();
+            if let Some(target_id) = row.get(&column).and_then(Value::as_i64)
+                && !db::entry_exists(tx.as_mut(), target_table_id, target_id as Id).await?
+            {
+                row[column] = Value::Null;
+            }
+        }
+    }
+
+    let (field_idents, relation_field_ids) = split_relation_fields(&fields);
+    let columns = ["entry_id", "created_at", "updated_at"]
+        .into_iter()
+        .chain(with_parent.then_some("parent_id"))
+        .map(str::to_string)
+        .chain(
+            field_idents
+                .iter()
+                .filter(|field_ident| row.get(field_ident.unquote()).is_some())
+                .map(|field_ident| field_ident.to_string()),
+        )
+        .join(", ");
+
+    let table_ident = TableIdentifier::new(table_id, "data_table");
+    sqlx::query(&format!(
+        r#"
+            INSERT INTO {table_ident} ({columns})
+            SELECT {columns}
+            FROM jsonb_populate_record(NULL::{table_ident}, $1)
+        "#
+    ))
+    .bind(Json(row))
+    .execute(tx.as_mut())
+    .await?;
+
+    for field_id in relation_field_ids {
+        let Some(FieldKind::Relation {
+            target_table_id, ..
+        }) = fields
+            .iter()
+            .find(|field| field.field_id == field_id)
+            .map(|field| &field.field_kind.0)
+        else {
+            continue;
+        };
+        let target_ids: Vec<Id> = cells
+            .get(&field_id)
+            .cloned()
+            .map(from_value)
+            .transpose()?
+            .unwrap_or_default();
+        let target_ids =
+            db::get_existing_entry_ids(tx.as_mut(), *target_table_id, target_ids).await?;
+        set_relation_links(tx.as_mut(), field_id, vec![(entry_id, target_ids)]).await?;
+    }
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Permanently delete the items trashed before the retention period.
+/// Trashed tables are dropped along with their child tables and trashed fields
+/// lose their SQL column.
+pub async fn purge_trash(
+    conn: impl Acquire<'_, Database = Postgres>,
+    retention: TimeDelta,
+) -> sqlx::Result<()> {
+    let mut tx = conn.begin().await?;
+
+    let trash_ids: Vec<Id> = sqlx::query_scalar(
+        r#"
+            SELECT trash_id
+            FROM trash
+            WHERE deleted_at < $1
+            ORDER BY trash_id
+        "#,
+    )
+    .bind(Utc::now() - retention)
+    .fetch_all(tx.as_mut())
+    .await?;
+
+    for trash_id in trash_ids {
+        // Items of a purged table were already deleted along with it.
+        let Some((table_id, resource, Json(value), deleted_by)): Option<(
+            Id,
+            AuditResource,
+            Json<Value>,
+            Option<Id>,
+        )> = sqlx::query_as(
+            r#"
+                DELETE FROM trash
+                WHERE trash_id = $1
+                RETURNING table_id, resource, value, deleted_by
+            "#,
+        )
+        .bind(trash_id)
+        .fetch_optional(tx.as_mut())
+        .await?
+        else {
+            continue;
+        };
+
+        match resource {
+            AuditResource::Table => {
+                // The other tables lose their relation fields targeting the purged tables.
+                let logs = purge_table(tx.as_mut(), table_id)
+                    .await?
+                    .iter()
+                    .map(|field| CreateAuditLog::field(Some(field), None))
+                    .collect();
+                db::create_audit_logs(tx.as_mut(), deleted_by, logs).await?;
+            }
+            AuditResource::Field => {
+                let field: Field = from_value(value)?;
+                if field.field_kind.is_multiple_relation() {
+                    let relation_ident = RelationIdentifier::new(field.field_id);
+                    sqlx::query(&format!(r#"DROP TABLE IF EXISTS {relation_ident}"#))
+                        .execute(tx.as_mut())
+                        .await?;
+                } else {
+                    let table_ident = TableIdentifier::new(table_id, "data_table");
+                    let field_ident = FieldIdentifier::new(field.field_id);
+                    sqlx::query(&format!(
+                        r#"
+                            ALTER TABLE {table_ident}
+                            DROP COLUMN IF EXISTS {field_ident}
+                        "#,
+                    ))
+                    .execute(tx.as_mut())
+                    .await?;
+                }
+            }
+            AuditResource::Entry => {}
+        }
+    }
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Delete this table and all its child tables, the deepest first.
+/// Return the deleted relation fields of the other tables.
+async fn purge_table(
+    conn: impl Acquire<'_, Database = Postgres>,
+    table_id: Id,
+) -> sqlx::Result<Vec<Field>> {
+    let mut tx = conn.begin().await?;
+
+    let table_ids: Vec<Id> = sqlx::query_scalar(
+        r#"
+            WITH RECURSIVE tree AS (
+                SELECT table_id, 0 AS depth
+                FROM meta_table
+                WHERE table_id = $1
+                UNION ALL
+                SELECT t.table_id, tree.depth + 1
+                FROM meta_table AS t
+                JOIN tree
+                ON t.parent_id = tree.table_id
+            )
+            SELECT table_id
+            FROM tree
+            ORDER BY depth DESC
+        "#,
+    )
+    .bind(table_id)
+    .fetch_all(tx.as_mut())
+    .await?;
+
+    let mut relation_fields = Vec::new();
+    for table_id in &table_ids {
+        relation_fields.extend(db::delete_table(tx.as_mut(), *table_id).await?);
+    }
+
+    tx.commit().await?;
+    Ok(relation_fields
+        .into_iter()
+        .filter(|field| !table_ids.contains(&field.table_id))
+        .collect())
+}
+
+/// Rebuild the views of the charts using these tables.
+async fn rebuild_table_chart_views(
+    conn: impl Acquire<'_, Database = Postgres>,
+    table_ids: Vec<Id>,
+) -> sqlx::Result<()> {
+    let mut tx = conn.begin().await?;
+
+    let chart_ids: Vec<Id> = sqlx::query_scalar(
+        r#"
+            SELECT chart_id
+            FROM chart
+            WHERE table_id = ANY($1)
+            UNION
+            SELECT chart_id
+            FROM axis
+            WHERE table_path && $1
+        "#,
+    )
+    .bind(table_ids)
+    .fetch_all(tx.as_mut())
+    .await?;
+    db::rebuild_chart_views(tx.as_mut(), chart_ids).await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+fn to_value<T: Serialize>(value: &T) -> Value {
+    serde_json::to_value(value).expect("trashed value is serializable")
+}
+
+fn from_value<T: DeserializeOwned>(value: Value) -> sqlx::Result<T> {
+    serde_json::from_value(value).map_err(|e| sqlx::Error::Decode(e.into()))
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod test {
+    use crate::{
+        db,
+        model::{
+            Cell,
+            access::{AccessRole, Resource},
+            data::{
+                AuditAction, AuditResource, CreateField, CreateTable, FieldKind, FieldMetadata,
+                RelationDeleteRule, TableIdentifier,
+            },
+            groups::CreateUserGroup,
+            viz::{AxisKind, ChartKind, CreateAxis, CreateChart, CreateDashboard},
+        },
+    };
+    use chrono::TimeDelta;
+    use itertools::Itertools;
+    use sqlx::PgPool;
+
+    #[sqlx::test]
+    async fn trash(db: PgPool) -> anyhow::Result<()> {
+        let user = db::create_user(&db, "test".into(), "".into(), false).await?;
+        let create_table = |name: &str| CreateTable {
+            parent_id: None,
+            name: name.into(),
+            description: "".into(),
+        };
+        let target_table_id = db::create_table(&db, create_table("target"))
+            .await?
+            .table_id;
+        let table_id = db::create_table(&db, create_table("source"))
+            .await?
+            .table_id;
+
+        let target_field = FieldMetadata::from_field(
+            db::create_field(
+                &db,
+                target_table_id,
+                CreateField {
+                    name: "name".into(),
+                    field_kind: FieldKind::Text { is_required: false },
+                },
+            )
+            .await?,
+        );
+        let target_ids = db::create_entries(
+            &db,
+            target_table_id,
+            None,
+            vec![target_field.clone()],
+            vec![
+                vec![Cell::String("a".into())],
+                vec![Cell::String("b".into())],
+            ],
+        )
+        .await?
+        .into_iter()
+        .map(|entry| entry.entry_id)
+        .collect_vec();
+
+        let fields = vec![
+            FieldMetadata::from_field(
+                db::create_field(
+                    &db,
+                    table_id,
+                    CreateField {
+                        name: "count".into(),
+                        field_kind: FieldKind::Integer {
+                            is_required: false,
+                            range_start: None,
+                            range_end: None,
+                        },
+                    },
+                )
+                .await?,
+            ),
+            FieldMetadata::from_field(
+                db::create_field(
+                    &db,
+                    table_id,
+                    CreateField {
+                        name: "links".into(),
+                        field_kind: FieldKind::Relation {
+                            is_required: false,
+                            target_table_id,
+                            multiple: true,
+                            on_delete: RelationDeleteRule::Restrict,
+                        },
+                    },
+                )
+                .await?,
+            ),
+        ];
+        let entries = db::create_entries(
+            &db,
+            table_id,
+            None,
+            fields.clone(),
+            vec![
+                vec![Cell::Integer(1), Cell::EntryIds(target_ids.clone())],
+                vec![Cell::Integer(2), Cell::EntryIds(vec![])],
+            ],
+        )
+        .await?;
+
+        let dashboard_id = db::create_dashboard(
+            &db,
+            CreateDashboard {
+                name: "test".into(),
+                description: "".into(),
+            },
+        )
+        .await?
+        .dashboard_id;
+        let chart_id = db::create_chart(
+            &db,
+            dashboard_id,
+            CreateChart {
+                table_id,
+                name: "test".into(),
+                chart_kind: ChartKind::Table,
+                top_n: None,
+            },
+        )
+        .await?
+        .chart_id;
+        let axis = db::set_axes(
+            &db,
+            chart_id,
+            table_id,
+            vec![CreateAxis {
+                field_id: fields[0].field_id,
+                table_path: Vec::new(),
+                axis_kind: AxisKind::X,
+                aggregate: None,
+                percentile: None,
+                order_field_id: None,
+                sort: None,
+                bins: None,
+                bucket: None,
+            }],
+        )
+        .await?
+        .remove(0);
+
+        // Entry
+        let item = super::trash_entry(&db, user.user_id, table_id, entries[0].entry_id).await?;
+        assert_eq!(item.username.as_deref(), Some("test"));
+        assert!(!db::entry_exists(&db, table_id, entries[0].entry_id).await?);
+        db::delete_entry(&db, target_table_id, target_ids[1]).await?;
+        super::restore_trash_item(&db, user.user_id, item.trash_id).await?;
+        let entry = db::get_entry(&db, table_id, entries[0].entry_id, &fields).await?;
+        assert_eq!(
+            entry.cells,
+            entries[0]
+                .cells
+                .clone()
+                .into_iter()
+                .map(|(field_id, cell)| {
+                    match cell {
+                        Cell::EntryIds(_) => (field_id, Cell::EntryIds(vec![target_ids[0]])),
+                        cell => (field_id, cell),
+                    }
+                })
+                .collect()
+        );
+        assert_eq!(entry.created_at, entries[0].created_at);
+
+        // Field
+        let item = super::trash_field(&db, user.user_id, fields[0].field_id).await?;
+        assert_eq!(db::get_chart_data(&db, chart_id, Vec::new()).await?.axes.len(), 0);
+        assert_eq!(db::get_trash(&db, user.user_id).await?, []);
+        super::restore_trash_item(&db, user.user_id, item.trash_id).await?;
+        assert_eq!(
+            db::get_fields(&db, table_id)
+                .await?
+                .iter()
+                .map(|field| (field.field_id, field.ordering))
+                .sorted()
+                .collect_vec(),
+            fields
+                .iter()
+                .enumerate()
+                .map(|(ordering, field)| (field.field_id, ordering as i32))
+                .collect_vec()
+        );
+        let chart_data = db::get_chart_data(&db, chart_id, Vec::new()).await?;
+        assert_eq!(chart_data.axes[0].axis, axis);
+        assert_eq!(chart_data.cells.len(), 2);
+
+        // Table
+        let item = super::trash_table(&db, user.user_id, table_id).await?;
+        assert!(super::table_in_trash(&db, table_id).await?);
+        assert_eq!(db::get_chart_data(&db, chart_id, Vec::new()).await?.cells.len(), 0);
+        super::restore_trash_item(&db, user.user_id, item.trash_id).await?;
+        assert!(!super::table_in_trash(&db, table_id).await?);
+        assert_eq!(db::get_chart_data(&db, chart_id, Vec::new()).await?.cells.len(), 2);
+
+        // Purge
+        super::trash_field(&db, user.user_id, fields[1].field_id).await?;
+        super::trash_entry(&db, user.user_id, table_id, entries[1].entry_id).await?;
+        super::purge_trash(&db, TimeDelta::days(1)).await?;
+        let trash_count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM trash")
+            .fetch_one(&db)
+            .await?;
+        assert_eq!(trash_count, 2);
+
+        db::delete_chart(&db, chart_id).await?;
+        super::trash_table(&db, user.user_id, table_id).await?;
+        super::purge_trash(&db, TimeDelta::zero()).await?;
+        let trash_count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM trash")
+            .fetch_one(&db)
+            .await?;
+        assert_eq!(trash_count, 0);
+        let table_ident = TableIdentifier::new(table_id, "data_table");
+        let table_exists: bool =
+            sqlx::query_scalar(&format!("SELECT to_regclass('{table_ident}') IS NOT NULL"))
+                .fetch_one(&db)
+                .await?;
+        assert!(!table_exists);
+        Ok(())
+    }
+    #[sqlx::test]
+    async fn trash_relation_field(db: PgPool) -> anyhow::Result<()> {
+        let user = db::create_user(&db, "test".into(), "".into(), false).await?;
+        let create_table = |name: &str| CreateTable {
+            parent_id: None,
+            name: name.into(),
+            description: "".into(),
+        };
+        let target_table_id = db::create_table(&db, create_table("target"))
+            .await?
+            .table_id;
+        let table_id = db::create_table(&db, create_table("source"))
+            .await?
+            .table_id;
+        let target_ids = db::create_entries(
+            &db,
+            target_table_id,
+            None,
+            Vec::new(),
+            vec![Vec::new(), Vec::new()],
+        )
+        .await?
+        .into_iter()
+        .map(|entry| entry.entry_id)
+        .collect_vec();
+
+        let mut fields = Vec::new();
+        for (multiple, on_delete) in [
+            (false, RelationDeleteRule::Restrict),
+            (true, RelationDeleteRule::Cascade),
+        ] {
+            fields.push(FieldMetadata::from_field(
+                db::create_field(
+                    &db,
+                    table_id,
+                    CreateField {
+                        name: "link".into(),
+                        field_kind: FieldKind::Relation {
+                            is_required: false,
+                            target_table_id,
+                            multiple,
+                            on_delete,
+                        },
+                    },
+                )
+                .await?,
+            ));
+        }
+        let entry_id = db::create_entries(
+            &db,
+            table_id,
+            None,
+            fields.clone(),
+            vec![vec![
+                Cell::Integer(target_ids[0] as i64),
+                Cell::EntryIds(vec![target_ids[0]]),
+            ]],
+        )
+        .await?[0]
+            .entry_id;
+
+        // Trashed relations neither restrict nor cascade the deletion of their targets.
+        let mut items = Vec::new();
+        for field in &fields {
+            items.push(super::trash_field(&db, user.user_id, field.field_id).await?);
+        }
+        db::delete_entry(&db, target_table_id, target_ids[0]).await?;
+        assert!(db::entry_exists(&db, table_id, entry_id).await?);
+
+        for item in items {
+            super::restore_trash_item(&db, user.user_id, item.trash_id).await?;
+        }
+        let entry = db::get_entry(&db, table_id, entry_id, &fields).await?;
+        assert_eq!(entry.cells[&fields[0].field_id], Cell::Null);
+        assert_eq!(entry.cells[&fields[1].field_id], Cell::EntryIds(Vec::new()));
+
+        db::update_entry(
+            &db,
+            table_id,
+            entry_id,
+            None,
+            fields.clone(),
+            vec![
+                Cell::Integer(target_ids[1] as i64),
+                Cell::EntryIds(vec![target_ids[1]]),
+            ],
+        )
+        .await?;
+        assert!(
+            db::delete_entry(&db, target_table_id, target_ids[1])
+                .await
+                .is_err()
+        );
+
+        // Purging the target table deletes the relation fields in the audit log.
+        super::trash_table(&db, user.user_id, target_table_id).await?;
+        super::purge_trash(&db, TimeDelta::zero()).await?;
+        assert!(db::get_fields_metadata(&db, table_id).await?.is_empty());
+        let page = db::get_table_activity(&db, table_id, 10, None).await?;
+        let delete_log = (AuditResource::Field, AuditAction::Delete, Some(user.user_id));
+        assert_eq!(
+            page.logs
+                .iter()
+                .map(|log| (log.resource, log.action, log.user_id))
+                .collect_vec(),
+            [delete_log; 2]
+        );
+        Ok(())
+    }
+    #[sqlx::test]
+    async fn trash_referenced_entry(db: PgPool) -> anyhow::Result<()> {
+        let user = db::create_user(&db, "test".into(), "".into(), false).await?;
+        let create_table = |name: &str| CreateTable {
+            parent_id: None,
+            name: name.into(),
+            description: "".into(),
+        };
+        let target_table_id = db::create_table(&db, create_table("target"))
+            .await?
+            .table_id;
+        let table_id = db::create_table(&db, create_table("source"))
+            .await?
+            .table_id;
+        for table_id in [target_table_id, table_id] {
+            db::create_access(
+                &db,
+                Resource::Table,
+                table_id,
+                user.user_id,
+                AccessRole::Owner,
+            )
+            .await?;
+        }
+        let target_id =
+            db::create_entries(&db, target_table_id, None, Vec::new(), vec![Vec::new()]).await?[0]
+                .entry_id;
+
+        let mut fields = Vec::new();
+        for (multiple, on_delete) in [
+            (false, RelationDeleteRule::Cascade),
+            (false, RelationDeleteRule::Unlink),
+            (true, RelationDeleteRule::Unlink),
+        ] {
+            fields.push(FieldMetadata::from_field(
+                db::create_field(
+                    &db,
+                    table_id,
+                    CreateField {
+                        name: "link".into(),
+                        field_kind: FieldKind::Relation {
+                            is_required: false,
+                            target_table_id,
+                            multiple,
+                            on_delete,
+                        },
+                    },
+                )
+                .await?,
+            ));
+        }
+        let entries = db::create_entries(
+            &db,
+            table_id,
+            None,
+            fields.clone(),
+            vec![
+                vec![
+                    Cell::Integer(target_id as i64),
+                    Cell::Null,
+                    Cell::EntryIds(Vec::new()),
+                ],
+                vec![
+                    Cell::Null,
+                    Cell::Integer(target_id as i64),
+                    Cell::EntryIds(vec![target_id]),
+                ],
+            ],
+        )
+        .await?;
+        let log_actions = async || -> anyhow::Result<_> {
+            let page = db::get_table_activity(&db, table_id, 10, None).await?;
+            Ok(page
+                .logs
+                .iter()
+                .map(|log| (log.action, log.resource_id))
+                .collect_vec())
+        };
+
+        // The cascaded entry is trashed and the unlinked entry is recorded.
+        let item = super::trash_entry(&db, user.user_id, target_table_id, target_id).await?;
+        assert!(!db::entry_exists(&db, table_id, entries[0].entry_id).await?);
+        assert_eq!(super::get_trash(&db, user.user_id).await?.len(), 2);
+        let entry = db::get_entry(&db, table_id, entries[1].entry_id, &fields).await?;
+        assert_eq!(entry.cells[&fields[1].field_id], Cell::Null);
+        assert_eq!(entry.cells[&fields[2].field_id], Cell::EntryIds(Vec::new()));
+        assert_eq!(
+            log_actions().await?,
+            [
+                (AuditAction::Update, entries[1].entry_id),
+                (AuditAction::Delete, entries[0].entry_id),
+            ]
+        );
+
+        // Restoring the entry restores the cascaded entry and links the unlinked entry again.
+        super::restore_trash_item(&db, user.user_id, item.trash_id).await?;
+        assert!(super::get_trash(&db, user.user_id).await?.is_empty());
+        for entry in &entries {
+            assert_eq!(
+                db::get_entry(&db, table_id, entry.entry_id, &fields)
+                    .await?
+                    .cells,
+                entry.cells
+            );
+        }
+        assert_eq!(
+            log_actions().await?[..2],
+            [
+                (AuditAction::Create, entries[0].entry_id),
+                (AuditAction::Update, entries[1].entry_id),
+            ]
+        );
+        Ok(())
+    }
+    #[sqlx::test]
+    async fn get_trash(db: PgPool) -> anyhow::Result<()> {
+        let user = db::create_user(&db, "test".into(), "".into(), false).await?;
+        let create_table = |name: &str, parent_id| CreateTable {
+            parent_id,
+            name: name.into(),
+            description: "".into(),
+        };
+        let parent_table_id = db::create_table(&db, create_table("parent", None))
+            .await?
+            .table_id;
+        let child_table_id = db::create_table(&db, create_table("child", Some(parent_table_id)))
+            .await?
+            .table_id;
+        let group_table_id = db::create_table(&db, create_table("group", None))
+            .await?
+            .table_id;
+        db::create_access(
+            &db,
+            Resource::Table,
+            parent_table_id,
+            user.user_id,
+            AccessRole::Owner,
+        )
+        .await?;
+        let group_id = db::create_user_group(
+            &db,
+            user.user_id,
+            CreateUserGroup {
+                name: "group".into(),
+            },
+        )
+        .await?
+        .group_id;
+        db::create_group_access(
+            &db,
+            Resource::Table,
+            group_table_id,
+            group_id,
+            AccessRole::Editor,
+        )
+        .await?;
+
+        let mut field_ids = Vec::new();
+        for table_id in [child_table_id, group_table_id] {
+            let field_id = db::create_field(
+                &db,
+                table_id,
+                CreateField {
+                    name: "name".into(),
+                    field_kind: FieldKind::Text { is_required: false },
+                },
+            )
+            .await?
+            .field_id;
+            field_ids.push(field_id);
+        }
+        let entry_id = db::create_entries(&db, group_table_id, None, Vec::new(), vec![Vec::new()])
+            .await?[0]
+            .entry_id;
+
+        // Owners inherit the trash of the child tables and group editors see the entries.
+        let trash_ids = async || {
+            db::get_trash(&db, user.user_id)
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|item| item.trash_id)
+                .collect_vec()
+        };
+        let child_field = super::trash_field(&db, user.user_id, field_ids[0]).await?;
+        super::trash_field(&db, user.user_id, field_ids[1]).await?;
+        let group_entry = super::trash_entry(&db, user.user_id, group_table_id, entry_id).await?;
+        assert_eq!(
+            trash_ids().await,
+            [group_entry.trash_id, child_field.trash_id]
+        );
+
+        let child_table = super::trash_table(&db, user.user_id, child_table_id).await?;
+        assert_eq!(
+            trash_ids().await,
+            [child_table.trash_id, group_entry.trash_id]
+        );
+        assert!(
+            db::get_trash_item(&db, user.user_id, child_table.trash_id)
+                .await?
+                .is_some()
+        );
+        Ok(())
+    }
+}
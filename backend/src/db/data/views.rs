@@ -0,0 +1,265 @@
+//! Database functions for managing saved table views.
+
+use crate::{
+    Id,
+    model::data::{CreateTableView, TableView, UpdateTableView},
+};
+use sqlx::{Acquire, PgExecutor, Postgres, types::Json};
+
+/// Create a view of the table for this user.
+pub async fn create_table_view(
+    conn: impl Acquire<'_, Database = Postgres>,
+    table_id: Id,
+    user_id: Id,
+    CreateTableView {
+        name,
+        is_shared,
+        filters,
+        sort,
+        hidden_field_ids,
+        column_widths,
+        group_field_id,
+    }: CreateTableView,
+) -> sqlx::Result<TableView> {
+    let mut tx = conn.begin().await?;
+
+    let view: TableView = sqlx::query_as(
+        r#"
+            INSERT INTO table_view (
+                table_id,
+                user_id,
+                name,
+                is_shared,
+                filters,
+                sort,
+                hidden_field_ids,
+                column_widths,
+                group_field_id
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            RETURNING *
+        "#,
+    )
+    .bind(table_id)
+    .bind(user_id)
+    .bind(name)
+    .bind(is_shared)
+    .bind(Json(filters))
+    .bind(Json(sort))
+    .bind(hidden_field_ids)
+    .bind(Json(column_widths))
+    .bind(group_field_id)
+    .fetch_one(tx.as_mut())
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(view)
+}
+
+/// Update the view.
+pub async fn update_table_view(
+    conn: impl Acquire<'_, Database = Postgres>,
+    view_id: Id,
+    UpdateTableView {
+        name,
+        is_shared,
+        filters,
+        sort,
+        hidden_field_ids,
+        column_widths,
+        group_field_id,
+    }: UpdateTableView,
+) -> sqlx::Result<TableView> {
+    let mut tx = conn.begin().await?;
+
+    let view: TableView = sqlx::query_as(
+        r#"
+            UPDATE table_view
+            SET
+                name = $1,
+                is_shared = $2,
+                filters = $3,
+                sort = $4,
+                hidden_field_ids = $5,
+                column_widths = $6,
+                group_field_id = $7
+            WHERE view_id = $8
+            RETURNING *
+        "#,
+    )
+    .bind(name)
+    .bind(is_shared)
+    .bind(Json(filters))
+    .bind(Json(sort))
+    .bind(hidden_field_ids)
+    .bind(Json(column_widths))
+    .bind(group_field_id)
+    .bind(view_id)
+    .fetch_one(tx.as_mut())
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(view)
+}
+
+/// Delete the view.
+pub async fn delete_table_view(executor: impl PgExecutor<'_>, view_id: Id) -> sqlx::Result<()> {
+    sqlx::query(
+        r#"
+            DELETE FROM table_view
+            WHERE view_id = $1
+        "#,
+    )
+    .bind(view_id)
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// Get the views of the table visible to this user,
+/// which are the shared views and the user's personal views.
+pub async fn get_table_views(
+    executor: impl PgExecutor<'_>,
+    table_id: Id,
+    user_id: Id,
+) -> sqlx::Result<Vec<TableView>> {
+    sqlx::query_as(
+        r#"
+            SELECT *
+            FROM table_view
+            WHERE table_id = $1 AND (is_shared OR user_id = $2)
+            ORDER BY view_id
+        "#,
+    )
+    .bind(table_id)
+    .bind(user_id)
+    .fetch_all(executor)
+    .await
+}
+
+/// Get a view of the table if it is visible to this user.
+pub async fn get_table_view(
+    executor: impl PgExecutor<'_>,
+    table_id: Id,
+    view_id: Id,
+    user_id: Id,
+) -> sqlx::Result<Option<TableView>> {
+    sqlx::query_as(
+        r#"
+            SELECT *
+            FROM table_view
+            WHERE table_id = $1 AND view_id = $2 AND (is_shared OR user_id = $3)
+        "#,
+    )
+    .bind(table_id)
+    .bind(view_id)
+    .bind(user_id)
+    .fetch_optional(executor)
+    .await
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod test {
+    use crate::{
+        db,
+        model::data::{
+            CreateTable, CreateTableView, EntryFilter, EntrySort, FilterOperator, SortDirection,
+            TableView, UpdateTableView,
+        },
+    };
+    use itertools::Itertools;
+    use sqlx::PgPool;
+    use std::collections::HashMap;
+
+    #[sqlx::test]
+    async fn table_views(db: PgPool) -> anyhow::Result<()> {
+        let user_id_1 = db::create_user(&db, "A".into(), "".into(), false)
+            .await?
+            .user_id;
+        let user_id_2 = db::create_user(&db, "B".into(), "".into(), false)
+            .await?
+            .user_id;
+        let table_id = db::create_table(
+            &db,
+            CreateTable {
+                name: "Test".into(),
+                description: "".into(),
+                parent_id: None,
+            },
+        )
+        .await?
+        .table_id;
+
+        let create_view = |name: &str, is_shared| CreateTableView {
+            name: name.into(),
+            is_shared,
+            filters: vec![EntryFilter {
+                field_id: 1,
+                operator: FilterOperator::IsNotNull,
+            }],
+            sort: vec![EntrySort {
+                field_id: 1,
+                direction: SortDirection::Descending,
+            }],
+            hidden_field_ids: vec![2],
+            column_widths: HashMap::from([(1, 200)]),
+            group_field_id: None,
+        };
+        let personal_view =
+            super::create_table_view(&db, table_id, user_id_1, create_view("A", false)).await?;
+        let shared_view =
+            super::create_table_view(&db, table_id, user_id_1, create_view("B", true)).await?;
+        assert_eq!(personal_view.user_id, user_id_1);
+        assert_eq!(personal_view.filters.0, create_view("A", false).filters);
+        assert_eq!(personal_view.column_widths.0, HashMap::from([(1, 200)]));
+
+        let view_ids =
+            |views: Vec<TableView>| views.into_iter().map(|view| view.view_id).collect_vec();
+        assert_eq!(
+            view_ids(super::get_table_views(&db, table_id, user_id_1).await?),
+            [personal_view.view_id, shared_view.view_id]
+        );
+        assert_eq!(
+            view_ids(super::get_table_views(&db, table_id, user_id_2).await?),
+            [shared_view.view_id]
+        );
+        assert!(
+            super::get_table_view(&db, table_id, personal_view.view_id, user_id_2)
+                .await?
+                .is_none()
+        );
+
+        let updated_view = super::update_table_view(
+            &db,
+            personal_view.view_id,
+            UpdateTableView {
+                name: "C".into(),
+                is_shared: true,
+                filters: Vec::new(),
+                sort: Vec::new(),
+                hidden_field_ids: Vec::new(),
+                column_widths: HashMap::new(),
+                group_field_id: None,
+            },
+        )
+        .await?;
+        assert_eq!(updated_view.name, "C");
+        assert!(updated_view.filters.is_empty());
+        assert_eq!(
+            super::get_table_view(&db, table_id, personal_view.view_id, user_id_2).await?,
+            Some(updated_view)
+        );
+
+        super::delete_table_view(&db, shared_view.view_id).await?;
+        assert_eq!(
+            view_ids(super::get_table_views(&db, table_id, user_id_2).await?),
+            [personal_view.view_id]
+        );
+
+        Ok(())
+    }
+}
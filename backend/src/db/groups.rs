@@ -0,0 +1,329 @@
+//! Database functions for managing user groups and their members.
+
+use crate::{
+    Id,
+    model::groups::{CreateUserGroup, UpdateUserGroup, UserGroup},
+};
+use sqlx::{Acquire, PgExecutor, Postgres};
+
+/// Select the columns of a user group along with its members ordered by username.
+const SELECT_USER_GROUP: &str = r#"
+    SELECT
+        g.group_id,
+        g.name,
+        COALESCE((
+            SELECT json_agg(
+                json_build_object(
+                    'user_id', u.user_id,
+                    'username', u.username,
+                    'is_owner', m.is_owner
+                )
+                ORDER BY u.username
+            )
+            FROM user_group_member AS m
+            JOIN app_user AS u
+            ON m.user_id = u.user_id
+            WHERE m.group_id = g.group_id
+        ), '[]') AS members,
+        g.created_at,
+        g.updated_at
+    FROM user_group AS g
+"#;
+
+/// Create a user group with this user as its owner.
+pub async fn create_user_group(
+    conn: impl Acquire<'_, Database = Postgres>,
+    user_id: Id,
+    CreateUserGroup { name }: CreateUserGroup,
+) -> sqlx::Result<UserGroup> {
+    let mut tx = conn.begin().await?;
+
+    let group_id: Id = sqlx::query_scalar(
+        r#"
+            INSERT INTO user_group (name)
+            VALUES ($1)
+            RETURNING group_id
+        "#,
+    )
+    .bind(name)
+    .fetch_one(tx.as_mut())
+    .await?;
+
+    create_group_member(tx.as_mut(), group_id, user_id, true).await?;
+    let group = get_user_group(tx.as_mut(), group_id).await?;
+
+    tx.commit().await?;
+
+    Ok(group)
+}
+
+/// Rename a user group.
+pub async fn update_user_group(
+    conn: impl Acquire<'_, Database = Postgres>,
+    group_id: Id,
+    UpdateUserGroup { name }: UpdateUserGroup,
+) -> sqlx::Result<UserGroup> {
+    let mut tx = conn.begin().await?;
+
+    sqlx::query(
+        r#"
+            UPDATE user_group
+            SET name = $1
+            WHERE group_id = $2
+        "#,
+    )
+    .bind(name)
+    .bind(group_id)
+    .execute(tx.as_mut())
+    .await?;
+
+    let group = get_user_group(tx.as_mut(), group_id).await?;
+
+    tx.commit().await?;
+
+    Ok(group)
+}
+
+/// Delete a user group, revoking the access of the group.
+pub async fn delete_user_group(executor: impl PgExecutor<'_>, group_id: Id) -> sqlx::Result<()> {
+    sqlx::query(
+        r#"
+            DELETE FROM user_group
+            WHERE group_id = $1
+        "#,
+    )
+    .bind(group_id)
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// Get a user group.
+pub async fn get_user_group(
+    executor: impl PgExecutor<'_>,
+    group_id: Id,
+) -> sqlx::Result<UserGroup> {
+    sqlx::query_as(&format!("{SELECT_USER_GROUP} WHERE g.group_id = $1"))
+        .bind(group_id)
+        .fetch_one(executor)
+        .await
+}
+
+/// Get the user groups this user is a member of, or all the user groups if not set.
+pub async fn get_user_groups(
+    executor: impl PgExecutor<'_>,
+    user_id: Option<Id>,
+) -> sqlx::Result<Vec<UserGroup>> {
+    sqlx::query_as(&format!(
+        r#"
+            {SELECT_USER_GROUP}
+            WHERE $1::INT IS NULL OR EXISTS (
+                SELECT 1
+                FROM user_group_member AS m
+                WHERE m.group_id = g.group_id AND m.user_id = $1
+            )
+            ORDER BY g.name
+        "#
+    ))
+    .bind(user_id)
+    .fetch_all(executor)
+    .await
+}
+
+/// Get the ID of the user group with that name.
+pub async fn get_user_group_id(
+    executor: impl PgExecutor<'_>,
+    name: &str,
+) -> sqlx::Result<Option<Id>> {
+    sqlx::query_scalar(
+        r#"
+            SELECT group_id
+            FROM user_group
+            WHERE name = $1
+        "#,
+    )
+    .bind(name)
+    .fetch_optional(executor)
+    .await
+}
+
+/// Return true if a user group with that ID exists.
+pub async fn user_group_exists(executor: impl PgExecutor<'_>, group_id: Id) -> sqlx::Result<bool> {
+    sqlx::query_scalar(
+        r#"
+            SELECT EXISTS (
+                SELECT 1
+                FROM user_group
+                WHERE group_id = $1
+            )
+        "#,
+    )
+    .bind(group_id)
+    .fetch_one(executor)
+    .await
+}
+
+/// Add a user to a user group.
+pub async fn create_group_member(
+    executor: impl PgExecutor<'_>,
+    group_id: Id,
+    user_id: Id,
+    is_owner: bool,
+) -> sqlx::Result<()> {
+    sqlx::query(
+        r#"
+            INSERT INTO user_group_member (group_id, user_id, is_owner)
+            VALUES ($1, $2, $3)
+        "#,
+    )
+    .bind(group_id)
+    .bind(user_id)
+    .bind(is_owner)
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// Change whether the member owns the user group.
+pub async fn update_group_member(
+    executor: impl PgExecutor<'_>,
+    group_id: Id,
+    user_id: Id,
+    is_owner: bool,
+) -> sqlx::Result<()> {
+    sqlx::query(
+        r#"
+            UPDATE user_group_member
+            SET is_owner = $1
+            WHERE group_id = $2 AND user_id = $3
+        "#,
+    )
+    .bind(is_owner)
+    .bind(group_id)
+    .bind(user_id)
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// Remove a user from a user group.
+pub async fn delete_group_member(
+    executor: impl PgExecutor<'_>,
+    group_id: Id,
+    user_id: Id,
+) -> sqlx::Result<()> {
+    sqlx::query(
+        r#"
+            DELETE FROM user_group_member
+            WHERE group_id = $1 AND user_id = $2
+        "#,
+    )
+    .bind(group_id)
+    .bind(user_id)
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// Get whether the user owns the user group if they are a member of it.
+pub async fn get_group_member_is_owner(
+    executor: impl PgExecutor<'_>,
+    group_id: Id,
+    user_id: Id,
+) -> sqlx::Result<Option<bool>> {
+    sqlx::query_scalar(
+        r#"
+            SELECT is_owner
+            FROM user_group_member
+            WHERE group_id = $1 AND user_id = $2
+        "#,
+    )
+    .bind(group_id)
+    .bind(user_id)
+    .fetch_optional(executor)
+    .await
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod test {
+    use crate::{
+        db,
+        model::groups::{CreateUserGroup, GroupMember, UpdateUserGroup},
+    };
+    use sqlx::PgPool;
+
+    #[sqlx::test]
+    async fn user_groups(db: PgPool) -> anyhow::Result<()> {
+        let user_1 = db::create_user(&db, "gary".into(), "".into(), false).await?;
+        let user_2 = db::create_user(&db, "mary".into(), "".into(), false).await?;
+
+        let group = super::create_user_group(
+            &db,
+            user_1.user_id,
+            CreateUserGroup {
+                name: "team".into(),
+            },
+        )
+        .await?;
+        assert_eq!(
+            group.members.0,
+            vec![GroupMember {
+                user_id: user_1.user_id,
+                username: user_1.username.clone(),
+                is_owner: true,
+            }]
+        );
+        assert_eq!(
+            super::get_user_group_id(&db, "TEAM").await?,
+            Some(group.group_id)
+        );
+
+        super::create_group_member(&db, group.group_id, user_2.user_id, false).await?;
+        assert_eq!(
+            super::get_group_member_is_owner(&db, group.group_id, user_2.user_id).await?,
+            Some(false)
+        );
+        super::update_group_member(&db, group.group_id, user_2.user_id, true).await?;
+        assert_eq!(
+            super::get_group_member_is_owner(&db, group.group_id, user_2.user_id).await?,
+            Some(true)
+        );
+        super::delete_group_member(&db, group.group_id, user_1.user_id).await?;
+        assert_eq!(
+            super::get_group_member_is_owner(&db, group.group_id, user_1.user_id).await?,
+            None
+        );
+
+        let group = super::update_user_group(
+            &db,
+            group.group_id,
+            UpdateUserGroup {
+                name: "squad".into(),
+            },
+        )
+        .await?;
+        assert_eq!(group.name, "squad");
+        assert_eq!(group.members.len(), 1);
+
+        assert!(
+            super::get_user_groups(&db, Some(user_1.user_id))
+                .await?
+                .is_empty()
+        );
+        assert_eq!(
+            super::get_user_groups(&db, Some(user_2.user_id)).await?,
+            vec![super::get_user_group(&db, group.group_id).await?]
+        );
+        assert_eq!(super::get_user_groups(&db, None).await?.len(), 1);
+
+        super::delete_user_group(&db, group.group_id).await?;
+        assert!(!super::user_group_exists(&db, group.group_id).await?);
+
+        Ok(())
+    }
+}
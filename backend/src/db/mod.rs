@@ -8,7 +8,10 @@
 
 mod access;
 mod data;
+mod groups;
+mod shares;
+mod tokens;
 mod users;
 mod viz;
 
-pub use {access::*, data::*, users::*, viz::*};
+pub use {access::*, data::*, groups::*, shares::*, tokens::*, users::*, viz::*};
@@ -0,0 +1,224 @@
+//! Database functions for managing public share links.
+
+use crate::{
+    Id,
+    model::shares::{CreatedShareLink, ShareLink, UsedShareLink},
+};
+use chrono::{DateTime, Utc};
+use sqlx::{Acquire, PgExecutor, Postgres};
+
+/// Create a share link for this user with a random secret.
+/// Only the hash of the secret is stored.
+pub async fn create_share_link(
+    conn: impl Acquire<'_, Database = Postgres>,
+    user_id: Id,
+    table_id: Option<Id>,
+    dashboard_id: Option<Id>,
+    password_hash: Option<String>,
+    expires_at: Option<DateTime<Utc>>,
+) -> sqlx::Result<CreatedShareLink> {
+    let mut tx = conn.begin().await?;
+
+    let secret: String = sqlx::query_scalar(
+        r#"
+            SELECT 'shr_'
+                || replace(gen_random_uuid()::text, '-', '')
+                || replace(gen_random_uuid()::text, '-', '')
+        "#,
+    )
+    .fetch_one(tx.as_mut())
+    .await?;
+
+    let link = sqlx::query_as(
+        r#"
+            INSERT INTO share_link (
+                user_id,
+                table_id,
+                dashboard_id,
+                secret_hash,
+                password_hash,
+                expires_at
+            )
+            VALUES ($1, $2, $3, encode(sha256(convert_to($4, 'UTF8')), 'hex'), $5, $6)
+            RETURNING
+                share_id,
+                user_id,
+                table_id,
+                dashboard_id,
+                password_hash IS NOT NULL AS has_password,
+                expires_at,
+                last_used_at,
+                created_at
+        "#,
+    )
+    .bind(user_id)
+    .bind(table_id)
+    .bind(dashboard_id)
+    .bind(&secret)
+    .bind(password_hash)
+    .bind(expires_at)
+    .fetch_one(tx.as_mut())
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(CreatedShareLink { link, secret })
+}
+
+/// Get all the share links created by this user.
+pub async fn get_share_links(
+    executor: impl PgExecutor<'_>,
+    user_id: Id,
+) -> sqlx::Result<Vec<ShareLink>> {
+    sqlx::query_as(
+        r#"
+            SELECT
+                share_id,
+                user_id,
+                table_id,
+                dashboard_id,
+                password_hash IS NOT NULL AS has_password,
+                expires_at,
+                last_used_at,
+                created_at
+            FROM share_link
+            WHERE user_id = $1
+            ORDER BY share_id
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(executor)
+    .await
+}
+
+/// Return true if the share link exists and was created by this user.
+pub async fn share_link_exists(
+    executor: impl PgExecutor<'_>,
+    user_id: Id,
+    share_id: Id,
+) -> sqlx::Result<bool> {
+    sqlx::query_scalar(
+        r#"
+            SELECT EXISTS (
+                SELECT 1
+                FROM share_link
+                WHERE user_id = $1 AND share_id = $2
+            )
+        "#,
+    )
+    .bind(user_id)
+    .bind(share_id)
+    .fetch_one(executor)
+    .await
+}
+
+/// Revoke the share link by deleting it.
+pub async fn delete_share_link(executor: impl PgExecutor<'_>, share_id: Id) -> sqlx::Result<()> {
+    sqlx::query(
+        r#"
+            DELETE FROM share_link
+            WHERE share_id = $1
+        "#,
+    )
+    .bind(share_id)
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// Get the unexpired share link with this secret and mark it as used now.
+pub async fn use_share_link(
+    executor: impl PgExecutor<'_>,
+    secret: &str,
+) -> sqlx::Result<Option<UsedShareLink>> {
+    sqlx::query_as(
+        r#"
+            UPDATE share_link
+            SET last_used_at = now()
+            WHERE secret_hash = encode(sha256(convert_to($1, 'UTF8')), 'hex')
+            AND (expires_at IS NULL OR expires_at > now())
+            RETURNING
+                user_id,
+                table_id,
+                dashboard_id,
+                password_hash
+        "#,
+    )
+    .bind(secret)
+    .fetch_optional(executor)
+    .await
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod test {
+    use crate::{db, model::viz::CreateDashboard};
+    use chrono::{TimeDelta, Utc};
+    use sqlx::PgPool;
+
+    #[sqlx::test]
+    async fn share_links(db: PgPool) -> anyhow::Result<()> {
+        let user_id = db::create_user(&db, "A".into(), "".into(), false)
+            .await?
+            .user_id;
+        let dashboard_id = db::create_dashboard(
+            &db,
+            CreateDashboard {
+                name: "Sales".into(),
+                description: "".into(),
+            },
+        )
+        .await?
+        .dashboard_id;
+
+        let created = super::create_share_link(
+            &db,
+            user_id,
+            None,
+            Some(dashboard_id),
+            Some("hash".into()),
+            None,
+        )
+        .await?;
+        assert!(created.secret.starts_with("shr_"));
+        assert_eq!(created.link.dashboard_id, Some(dashboard_id));
+        assert!(created.link.has_password);
+        let secret_hash: String =
+            sqlx::query_scalar(r#"SELECT secret_hash FROM share_link WHERE share_id = $1"#)
+                .bind(created.link.share_id)
+                .fetch_one(&db)
+                .await?;
+        assert!(!secret_hash.contains(&created.secret));
+
+        let link = super::use_share_link(&db, &created.secret).await?.unwrap();
+        assert_eq!(link.dashboard_id, Some(dashboard_id));
+        assert_eq!(link.password_hash.as_deref(), Some("hash"));
+        assert!(super::use_share_link(&db, "shr_wrong").await?.is_none());
+
+        let expired = super::create_share_link(
+            &db,
+            user_id,
+            None,
+            Some(dashboard_id),
+            None,
+            Some(Utc::now() - TimeDelta::days(1)),
+        )
+        .await?;
+        assert!(super::use_share_link(&db, &expired.secret).await?.is_none());
+
+        let links = super::get_share_links(&db, user_id).await?;
+        assert_eq!(links.len(), 2);
+        assert!(links[0].last_used_at.is_some());
+        assert!(!links[1].has_password);
+
+        assert!(super::share_link_exists(&db, user_id, created.link.share_id).await?);
+        assert!(!super::share_link_exists(&db, user_id + 1, created.link.share_id).await?);
+        super::delete_share_link(&db, created.link.share_id).await?;
+        assert!(super::use_share_link(&db, &created.secret).await?.is_none());
+
+        db::delete_dashboard(&db, dashboard_id).await?;
+        assert!(super::get_share_links(&db, user_id).await?.is_empty());
+        Ok(())
+    }
+}
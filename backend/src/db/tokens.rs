@@ -0,0 +1,221 @@
+//! Database functions for managing personal API tokens.
+
+use crate::{
+    Id,
+    model::tokens::{ApiToken, CreateApiToken, CreatedApiToken},
+};
+use sqlx::{Acquire, PgExecutor, Postgres};
+
+/// Create an API token for this user with a random secret.
+/// Only the hash of the secret is stored.
+pub async fn create_api_token(
+    conn: impl Acquire<'_, Database = Postgres>,
+    user_id: Id,
+    CreateApiToken {
+        name,
+        permission,
+        table_ids,
+        dashboard_ids,
+        expires_at,
+    }: CreateApiToken,
+) -> sqlx::Result<CreatedApiToken> {
+    let mut tx = conn.begin().await?;
+
+    let secret: String = sqlx::query_scalar(
+        r#"
+            SELECT 'chr_'
+                || replace(gen_random_uuid()::text, '-', '')
+                || replace(gen_random_uuid()::text, '-', '')
+        "#,
+    )
+    .fetch_one(tx.as_mut())
+    .await?;
+
+    let token = sqlx::query_as(
+        r#"
+            INSERT INTO api_token (
+                user_id,
+                name,
+                token_hash,
+                permission,
+                table_ids,
+                dashboard_ids,
+                expires_at
+            )
+            VALUES ($1, $2, encode(sha256(convert_to($3, 'UTF8')), 'hex'), $4, $5, $6, $7)
+            RETURNING
+                token_id,
+                user_id,
+                name,
+                permission,
+                table_ids,
+                dashboard_ids,
+                expires_at,
+                last_used_at,
+                created_at
+        "#,
+    )
+    .bind(user_id)
+    .bind(name)
+    .bind(&secret)
+    .bind(permission)
+    .bind(table_ids)
+    .bind(dashboard_ids)
+    .bind(expires_at)
+    .fetch_one(tx.as_mut())
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(CreatedApiToken { token, secret })
+}
+
+/// Get all the API tokens of this user.
+pub async fn get_api_tokens(
+    executor: impl PgExecutor<'_>,
+    user_id: Id,
+) -> sqlx::Result<Vec<ApiToken>> {
+    sqlx::query_as(
+        r#"
+            SELECT
+                token_id,
+                user_id,
+                name,
+                permission,
+                table_ids,
+                dashboard_ids,
+                expires_at,
+                last_used_at,
+                created_at
+            FROM api_token
+            WHERE user_id = $1
+            ORDER BY token_id
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(executor)
+    .await
+}
+
+/// Return true if the API token exists and belongs to this user.
+pub async fn api_token_exists(
+    executor: impl PgExecutor<'_>,
+    user_id: Id,
+    token_id: Id,
+) -> sqlx::Result<bool> {
+    sqlx::query_scalar(
+        r#"
+            SELECT EXISTS (
+                SELECT 1
+                FROM api_token
+                WHERE user_id = $1 AND token_id = $2
+            )
+        "#,
+    )
+    .bind(user_id)
+    .bind(token_id)
+    .fetch_one(executor)
+    .await
+}
+
+/// Revoke the API token by deleting it.
+pub async fn delete_api_token(executor: impl PgExecutor<'_>, token_id: Id) -> sqlx::Result<()> {
+    sqlx::query(
+        r#"
+            DELETE FROM api_token
+            WHERE token_id = $1
+        "#,
+    )
+    .bind(token_id)
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// Get the unexpired API token with this secret and mark it as used now.
+pub async fn use_api_token(
+    executor: impl PgExecutor<'_>,
+    secret: &str,
+) -> sqlx::Result<Option<ApiToken>> {
+    sqlx::query_as(
+        r#"
+            UPDATE api_token
+            SET last_used_at = now()
+            WHERE token_hash = encode(sha256(convert_to($1, 'UTF8')), 'hex')
+            AND (expires_at IS NULL OR expires_at > now())
+            RETURNING
+                token_id,
+                user_id,
+                name,
+                permission,
+                table_ids,
+                dashboard_ids,
+                expires_at,
+                last_used_at,
+                created_at
+        "#,
+    )
+    .bind(secret)
+    .fetch_optional(executor)
+    .await
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod test {
+    use crate::{
+        db,
+        model::tokens::{CreateApiToken, TokenPermission},
+    };
+    use chrono::{TimeDelta, Utc};
+    use sqlx::PgPool;
+
+    #[sqlx::test]
+    async fn api_tokens(db: PgPool) -> anyhow::Result<()> {
+        let user_id = db::create_user(&db, "A".into(), "".into(), false)
+            .await?
+            .user_id;
+        let create_token = |expires_at| CreateApiToken {
+            name: "ETL".into(),
+            permission: TokenPermission::ReadOnly,
+            table_ids: Some(vec![1]),
+            dashboard_ids: None,
+            expires_at,
+        };
+
+        let created = super::create_api_token(&db, user_id, create_token(None)).await?;
+        assert!(created.secret.starts_with("chr_"));
+        assert_eq!(created.token.table_ids, Some(vec![1]));
+        assert!(created.token.last_used_at.is_none());
+        let token_hash: String =
+            sqlx::query_scalar(r#"SELECT token_hash FROM api_token WHERE token_id = $1"#)
+                .bind(created.token.token_id)
+                .fetch_one(&db)
+                .await?;
+        assert!(!token_hash.contains(&created.secret));
+
+        let token = super::use_api_token(&db, &created.secret).await?.unwrap();
+        assert_eq!(token.token_id, created.token.token_id);
+        assert!(token.last_used_at.is_some());
+        assert!(super::use_api_token(&db, "chr_wrong").await?.is_none());
+
+        let expired = super::create_api_token(
+            &db,
+            user_id,
+            create_token(Some(Utc::now() - TimeDelta::days(1))),
+        )
+        .await?;
+        assert!(super::use_api_token(&db, &expired.secret).await?.is_none());
+
+        assert_eq!(super::get_api_tokens(&db, user_id).await?.len(), 2);
+        assert!(super::api_token_exists(&db, user_id, token.token_id).await?);
+        assert!(!super::api_token_exists(&db, user_id + 1, token.token_id).await?);
+
+        super::delete_api_token(&db, token.token_id).await?;
+        assert!(super::use_api_token(&db, &created.secret).await?.is_none());
+        assert_eq!(super::get_api_tokens(&db, user_id).await?.len(), 1);
+
+        Ok(())
+    }
+}
@@ -30,6 +30,31 @@ pub async fn create_user(
     Ok(user)
 }
 
+/// Create a user provisioned through OpenID Connect.
+/// The password hash should be of a random password, so the user can only log in
+/// through the identity provider.
+pub async fn create_oidc_user(
+    executor: impl PgExecutor<'_>,
+    oidc_subject: String,
+    username: String,
+    password_hash: String,
+    is_admin: bool,
+) -> sqlx::Result<User> {
+    sqlx::query_as(
+        r#"
+            INSERT INTO app_user (username, password_hash, is_admin, oidc_subject)
+            VALUES ($1, $2, $3, $4)
+            RETURNING *
+        "#,
+    )
+    .bind(username)
+    .bind(password_hash)
+    .bind(is_admin)
+    .bind(oidc_subject)
+    .fetch_one(executor)
+    .await
+}
+
 /// Update a user.
 pub async fn update_user(
     conn: impl Acquire<'_, Database = Postgres>,
@@ -152,6 +177,27 @@ pub async fn get_user_by_username(
     .await
 }
 
+/// Get a user from the OpenID Connect subject identifier.
+pub async fn get_user_by_oidc_subject(
+    executor: impl PgExecutor<'_>,
+    oidc_subject: &str,
+) -> sqlx::Result<Option<User>> {
+    sqlx::query_as(
+        r#"
+            SELECT
+                user_id,
+                username,
+                password_hash,
+                is_admin
+            FROM app_user
+            WHERE oidc_subject = $1
+        "#,
+    )
+    .bind(oidc_subject)
+    .fetch_optional(executor)
+    .await
+}
+
 /// Return true if a user with that username exists.
 pub async fn user_exists_by_username(
     executor: impl PgExecutor<'_>,
@@ -214,6 +260,19 @@ mod test {
         Ok(())
     }
 
+    #[sqlx::test]
+    async fn create_oidc_user(db: PgPool) -> anyhow::Result<()> {
+        assert!(super::get_user_by_oidc_subject(&db, "abc").await?.is_none());
+        let user_1 =
+            super::create_oidc_user(&db, "abc".into(), "test".into(), "1234".into(), true).await?;
+        assert_eq!("test", user_1.username);
+        assert_eq!("1234", user_1.password_hash);
+        assert!(user_1.is_admin);
+        let user_2 = super::get_user_by_oidc_subject(&db, "abc").await?.unwrap();
+        assert_eq!(user_1, user_2);
+        Ok(())
+    }
+
     #[sqlx::test]
     async fn update_user(db: PgPool) -> anyhow::Result<()> {
         let user = super::create_user(&db, "john".into(), "1234".into(), false).await?;
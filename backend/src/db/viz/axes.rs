@@ -1,13 +1,20 @@
 //! Database functions for managing chart axes.
 
+use super::get_chart_view_filters;
 use crate::{
-    Id,
+    Id, db,
     model::{
-        data::{FieldIdentifier, FieldKind, TableIdentifier},
+        Cell,
+        data::{EntryFilter, Field, FieldIdentifier, FieldKind, TableIdentifier},
         viz::{Axis, AxisIdentifier, ChartIdentifier, CreateAxis},
     },
 };
-use sqlx::{Acquire, Postgres, QueryBuilder, types::Json};
+use itertools::Itertools;
+use sqlx::{Acquire, PgExecutor, Postgres, QueryBuilder, types::Json};
+use std::collections::HashMap;
+
+/// Column of the chart view with a top N telling if the row aggregates the other groups.
+pub(super) const OTHER_ROW_COLUMN: &str = "other_row";
 
 /// Set the axes of this chart using the given table as data source and replace the SQL view.
 pub async fn set_axes(
@@ -28,52 +35,293 @@ pub async fn set_axes(
     .execute(tx.as_mut())
     .await?;
 
+    if axes.is_empty() {
+        replace_chart_view(tx.as_mut(), chart_id, table_id, &[], false).await?;
+        tx.commit().await?;
+        return Ok(Vec::new());
+    }
+
+    let axes: Vec<Axis> = QueryBuilder::new(
+        r#"
+            INSERT INTO axis (
+                chart_id,
+                field_id,
+                table_path,
+                axis_kind,
+                aggregate,
+                percentile,
+                order_field_id,
+                sort,
+                bins,
+                bucket
+            )
+        "#,
+    )
+    .push_values(axes, |mut builder, axis| {
+        builder
+            .push_bind(chart_id)
+            .push_bind(axis.field_id)
+            .push_bind(axis.table_path)
+            .push_bind(axis.axis_kind)
+            .push_bind(axis.aggregate)
+            .push_bind(axis.percentile)
+            .push_bind(axis.order_field_id)
+            .push_bind(axis.sort.map(Json))
+            .push_bind(axis.bins)
+            .push_bind(axis.bucket.map(Json));
+    })
+    .push(
+        r#"
+            RETURNING
+                axis_id,
+                chart_id,
+                field_id,
+                table_path,
+                axis_kind,
+                aggregate,
+                percentile,
+                order_field_id,
+                sort,
+                bins,
+                bucket,
+                created_at,
+                updated_at
+        "#,
+    )
+    .build_query_as()
+    .fetch_all(tx.as_mut())
+    .await?;
+
+    replace_chart_view(tx.as_mut(), chart_id, table_id, &axes, false).await?;
+
+    tx.commit().await?;
+    Ok(axes)
+}
+
+/// Rebuild the SQL views of these charts from their current axes.
+/// The views of charts on a trashed table or joining a trashed table return zero rows.
+pub async fn rebuild_chart_views(
+    conn: impl Acquire<'_, Database = Postgres>,
+    chart_ids: Vec<Id>,
+) -> sqlx::Result<()> {
+    let mut tx = conn.begin().await?;
+
+    for chart_id in chart_ids {
+        let (table_id, axes, is_trashed) = get_chart_view_source(tx.as_mut(), chart_id).await?;
+        replace_chart_view(tx.as_mut(), chart_id, table_id, &axes, is_trashed).await?;
+    }
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Get the table and the axes of this chart, and whether the rows of its SQL view are hidden
+/// because the chart is on a trashed table or joins a trashed table.
+pub(super) async fn get_chart_view_source(
+    conn: impl Acquire<'_, Database = Postgres>,
+    chart_id: Id,
+) -> sqlx::Result<(Id, Vec<Axis>, bool)> {
+    let mut tx = conn.begin().await?;
+
+    let (table_id, is_trashed): (Id, bool) = sqlx::query_as(
+        r#"
+            SELECT c.table_id, EXISTS (
+                SELECT 1
+                FROM meta_table AS t
+                WHERE t.deleted_at IS NOT NULL
+                AND (
+                    t.table_id = c.table_id
+                    OR t.table_id IN (
+                        SELECT unnest(a.table_path)
+                        FROM axis AS a
+                        WHERE a.chart_id = c.chart_id
+                    )
+                )
+            )
+            FROM chart AS c
+            WHERE c.chart_id = $1
+        "#,
+    )
+    .bind(chart_id)
+    .fetch_one(tx.as_mut())
+    .await?;
+
+    let axes: Vec<Axis> = sqlx::query_as(
+        r#"
+            SELECT
+                axis_id,
+                chart_id,
+                field_id,
+                table_path,
+                axis_kind,
+                aggregate,
+                percentile,
+                order_field_id,
+                sort,
+                bins,
+                bucket,
+                created_at,
+                updated_at
+            FROM axis
+            WHERE chart_id = $1
+            ORDER BY axis_id
+        "#,
+    )
+    .bind(chart_id)
+    .fetch_all(tx.as_mut())
+    .await?;
+
+    tx.commit().await?;
+    Ok((table_id, axes, is_trashed))
+}
+
+/// Replace the SQL view of this chart with the query selecting these axes.
+async fn replace_chart_view(
+    conn: impl Acquire<'_, Database = Postgres>,
+    chart_id: Id,
+    table_id: Id,
+    axes: &[Axis],
+    hide_rows: bool,
+) -> sqlx::Result<()> {
+    let mut tx = conn.begin().await?;
+
     let chart_ident = ChartIdentifier::new(chart_id, "data_view");
     sqlx::query(&format!(r#"DROP VIEW {chart_ident}"#))
         .execute(tx.as_mut())
         .await?;
 
+    let view_select =
+        chart_view_select(tx.as_mut(), chart_id, table_id, axes, hide_rows, Vec::new()).await?;
+
+    sqlx::query(&format!(
+        r#"
+            CREATE VIEW {chart_ident} AS
+            {view_select}
+        "#
+    ))
+    .execute(tx.as_mut())
+    .await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Build the query of the SQL view of this chart selecting these axes from the rows
+/// matching the chart filters and the additional filters.
+/// Axes with a time bucket select the timestamps truncated to the start of their bucket and
+/// axes with bins select the lower bound of their equal-width bin as a float.
+/// The rows are ordered by the sorted axes. If the chart has a top N, only the first N
+/// groups are kept, followed by a row aggregating the rows of the other groups, and the
+/// query has an additional column telling if the row is this other row. Without sorted
+/// axes, the groups of a top N are ordered by their aggregated axes in descending order,
+/// then by their grouped axes.
+/// Axes with a table path select their field from the tables joined along their parent,
+/// so the rows of a table are repeated for each of its joined child entries. The axes are
+/// validated to only join child tables in a single line of descent and only aggregate the
+/// fields of the deepest one.
+/// The query returns zero rows if there are no axes or if the rows are hidden.
+pub(super) async fn chart_view_select(
+    conn: impl Acquire<'_, Database = Postgres>,
+    chart_id: Id,
+    table_id: Id,
+    axes: &[Axis],
+    hide_rows: bool,
+    additional_filters: Vec<EntryFilter<Cell>>,
+) -> sqlx::Result<String> {
+    let mut tx = conn.begin().await?;
+
     if axes.is_empty() {
-        sqlx::query(&format!(
-            r#"
-                CREATE VIEW {chart_ident} AS
-                SELECT NULL WHERE FALSE
-            "#
-        ))
-        .execute(tx.as_mut())
-        .await?;
-        return Ok(Vec::new());
+        return Ok("SELECT NULL WHERE FALSE".to_string());
     }
 
-    let axes: Vec<Axis> =
-        QueryBuilder::new(r#"INSERT INTO axis (chart_id, field_id, axis_kind, aggregate)"#)
-            .push_values(axes, |mut builder, axis| {
-                builder
-                    .push_bind(chart_id)
-                    .push_bind(axis.field_id)
-                    .push_bind(axis.axis_kind)
-                    .push_bind(axis.aggregate);
-            })
-            .push(
-                r#"
-                    RETURNING
-                        axis_id,
-                        chart_id,
-                        field_id,
-                        axis_kind,
-                        aggregate,
-                        created_at,
-                        updated_at
-                "#,
-            )
-            .build_query_as()
-            .fetch_all(tx.as_mut())
-            .await?;
+    let top_n: Option<i32> = sqlx::query_scalar(
+        r#"
+            SELECT top_n
+            FROM chart
+            WHERE chart_id = $1
+        "#,
+    )
+    .bind(chart_id)
+    .fetch_one(tx.as_mut())
+    .await?;
+
+    // Views cannot take parameters, so the filter values are inlined.
+    let mut filters = get_chart_view_filters(tx.as_mut(), chart_id).await?;
+    filters.extend(additional_filters);
+    let mut where_statement = QueryBuilder::new("WHERE TRUE");
+    db::push_entry_filters(&mut where_statement, filters, true);
+    let where_statement = where_statement.sql();
 
+    // Join the tables of the axis table paths along their parent, each table at most once.
+    let parent_ids: HashMap<Id, Option<Id>> = sqlx::query_as(
+        r#"
+            SELECT table_id, parent_id
+            FROM meta_table
+            WHERE table_id = ANY($1)
+        "#,
+    )
+    .bind(
+        axes.iter()
+            .flat_map(|axis| axis.table_path.iter().copied())
+            .chain([table_id])
+            .unique()
+            .collect_vec(),
+    )
+    .fetch_all(tx.as_mut())
+    .await?
+    .into_iter()
+    .collect();
+    let mut joined_table_ids = vec![table_id];
+    let mut from_statement = format!("FROM {}", TableIdentifier::new(table_id, "data_table"));
+    for axis in axes {
+        let mut previous_id = table_id;
+        for &next_id in &axis.table_path {
+            if !joined_table_ids.contains(&next_id) {
+                let previous_ident = TableIdentifier::new(previous_id, "data_table");
+                let next_ident = TableIdentifier::new(next_id, "data_table");
+                let condition = if parent_ids.get(&next_id) == Some(&Some(previous_id)) {
+                    format!("{next_ident}.parent_id = {previous_ident}.entry_id")
+                } else {
+                    format!("{next_ident}.entry_id = {previous_ident}.parent_id")
+                };
+                from_statement.push_str(&format!(" LEFT JOIN {next_ident} ON {condition}"));
+                joined_table_ids.push(next_id);
+            }
+            previous_id = next_id;
+        }
+    }
+
+    // Group columns and select items with the ID of their axis.
     let mut group_by_columns = Vec::new();
     let mut select_columns = Vec::new();
-    for axis in &axes {
+    for axis in axes {
         let field_ident = FieldIdentifier::new(axis.field_id);
+        let mut column = field_ident.to_string();
+        if let Some(Json(bucket)) = &axis.bucket {
+            column = format!(
+                "date_trunc('{}', {column}, {})",
+                bucket.unit.get_sql_unit(),
+                Cell::String(bucket.time_zone.clone()).to_sql_literal(),
+            );
+        }
+        if let Some(bins) = axis.bins {
+            // Lower bound of the bin between the minimum and maximum of the matching rows.
+            let bound = |function: &str| {
+                format!(
+                    "(SELECT {function}({column}) {from_statement} {where_statement})\
+                    ::DOUBLE PRECISION"
+                )
+            };
+            let (min, max) = (bound("MIN"), bound("MAX"));
+            let bin = format!(
+                "LEAST(width_bucket({column}::DOUBLE PRECISION, {min}, {max}, {bins}), {bins})"
+            );
+            column = format!(
+                "CASE WHEN {max} > {min} \
+                THEN {min} + ({bin} - 1) * ({max} - {min}) / {bins} \
+                ELSE {min} END"
+            );
+        }
         let item = if let Some(aggregate) = &axis.aggregate {
             let Json(field_kind): Json<FieldKind> = sqlx::query_scalar(
                 r#"
@@ -85,59 +333,241 @@ pub async fn set_axes(
             .bind(axis.field_id)
             .fetch_one(tx.as_mut())
             .await?;
-            &format!(
-                "{}({})::{}",
-                aggregate.get_sql_aggregate(),
-                field_ident,
+            let order_column = axis
+                .order_field_id
+                .map(|order_field_id| FieldIdentifier::new(order_field_id).to_string());
+            format!(
+                "{}::{}",
+                aggregate.get_sql_aggregate(&column, axis.percentile, order_column.as_deref()),
                 aggregate.get_sql_type(&field_kind),
             )
         } else {
-            group_by_columns.push(field_ident.to_string());
-            &field_ident.to_string()
+            group_by_columns.push((column.clone(), axis.axis_id));
+            column
         };
-        let axis_ident = AxisIdentifier::new(axis.axis_id);
-        select_columns.push(format!("{item} AS {axis_ident}"));
+        select_columns.push((item, axis.axis_id));
     }
-    let group_by_columns = group_by_columns.join(", ");
-    let select_columns = select_columns.join(", ");
+
+    let order_by_columns = axes
+        .iter()
+        .enumerate()
+        .filter_map(|(i, axis)| axis.sort.as_ref().map(|Json(sort)| (sort, i, axis.axis_id)))
+        .sorted_by_key(|(sort, i, _)| (sort.priority, *i))
+        .map(|(sort, _, axis_id)| {
+            format!(
+                "{} {}",
+                AxisIdentifier::new(axis_id),
+                sort.direction.get_sql_direction(),
+            )
+        })
+        .collect_vec();
 
     let group_by_statement = if !group_by_columns.is_empty() {
-        format!("GROUP BY {group_by_columns}")
+        format!(
+            "GROUP BY {}",
+            group_by_columns.iter().map(|(column, _)| column).join(", ")
+        )
     } else {
         String::new()
     };
 
-    let table_ident = TableIdentifier::new(table_id, "data_table");
-    sqlx::query(&format!(
+    // Also filters out the single row of aggregates without a GROUP BY.
+    let having_statement = if hide_rows { "HAVING FALSE" } else { "" };
+
+    let grouped_select = format!(
         r#"
-            CREATE VIEW {chart_ident} AS
-            SELECT {select_columns}
-            FROM {table_ident}
+            SELECT {}
+            {from_statement}
+            {where_statement}
             {group_by_statement}
-        "#
-    ))
-    .execute(tx.as_mut())
-    .await?;
+            {having_statement}
+        "#,
+        select_columns
+            .iter()
+            .map(|(item, axis_id)| format!("{item} AS {}", AxisIdentifier::new(*axis_id)))
+            .join(", "),
+    );
+
+    let view_select = match top_n {
+        Some(top_n) => {
+            let axis_columns = select_columns
+                .iter()
+                .map(|(_, axis_id)| AxisIdentifier::new(*axis_id))
+                .join(", ");
+            // The top N must be deterministic, so the groups are ordered by their values.
+            let order_by_columns = if order_by_columns.is_empty() {
+                let (grouped_columns, aggregated_columns): (Vec<_>, Vec<_>) = select_columns
+                    .iter()
+                    .map(|(_, axis_id)| *axis_id)
+                    .partition(|axis_id| group_by_columns.iter().any(|(_, id)| id == axis_id));
+                aggregated_columns
+                    .into_iter()
+                    .map(|axis_id| format!("{} DESC NULLS LAST", AxisIdentifier::new(axis_id)))
+                    .chain(
+                        grouped_columns
+                            .into_iter()
+                            .map(|axis_id| AxisIdentifier::new(axis_id).to_string()),
+                    )
+                    .collect_vec()
+            } else {
+                order_by_columns
+            };
+            let rank_order = if order_by_columns.is_empty() {
+                String::new()
+            } else {
+                format!("ORDER BY {}", order_by_columns.join(", "))
+            };
+            // The other row aggregates the rows of the groups beyond the top N,
+            // with null grouped axes.
+            let other_columns = select_columns
+                .iter()
+                .map(|(item, axis_id)| {
+                    let axis_ident = AxisIdentifier::new(*axis_id);
+                    if group_by_columns.iter().any(|(_, id)| id == axis_id) {
+                        format!("NULL AS {axis_ident}")
+                    } else {
+                        format!("{item} AS {axis_ident}")
+                    }
+                })
+                .join(", ");
+            let top_group_condition = group_by_columns
+                .iter()
+                .map(|(column, axis_id)| {
+                    let axis_ident = AxisIdentifier::new(*axis_id);
+                    format!("r.{axis_ident} IS NOT DISTINCT FROM {column}")
+                })
+                .join(" AND ");
+            let other_select = if group_by_columns.is_empty() {
+                // Without grouped axes there is a single row and nothing to aggregate.
+                String::new()
+            } else {
+                format!(
+                    r#"
+                        UNION ALL
+                        SELECT {other_columns}, TRUE AS {OTHER_ROW_COLUMN}
+                        {from_statement}
+                        {where_statement}
+                        AND NOT EXISTS (
+                            SELECT 1
+                            FROM ranked AS r
+                            WHERE r."rank" <= {top_n} AND {top_group_condition}
+                        )
+                        HAVING COUNT(*) > 0 {}
+                    "#,
+                    if hide_rows { "AND FALSE" } else { "" },
+                )
+            };
+            let order_by_columns = [OTHER_ROW_COLUMN.to_string()]
+                .into_iter()
+                .chain(order_by_columns)
+                .join(", ");
+            format!(
+                r#"
+                    WITH ranked AS (
+                        SELECT *, row_number() OVER ({rank_order}) AS "rank"
+                        FROM ({grouped_select}) AS g
+                    )
+                    SELECT {axis_columns}, FALSE AS {OTHER_ROW_COLUMN}
+                    FROM ranked
+                    WHERE "rank" <= {top_n}
+                    {other_select}
+                    ORDER BY {order_by_columns}
+                "#
+            )
+        }
+        None if !order_by_columns.is_empty() => {
+            format!("{grouped_select} ORDER BY {}", order_by_columns.join(", "))
+        }
+        None => grouped_select,
+    };
 
     tx.commit().await?;
-    Ok(axes)
+    Ok(view_select)
+}
+
+/// Get the axes of this chart in the order they were set.
+pub async fn get_chart_axes(
+    executor: impl PgExecutor<'_>,
+    chart_id: Id,
+) -> sqlx::Result<Vec<CreateAxis>> {
+    sqlx::query_as(
+        r#"
+            SELECT *
+            FROM axis
+            WHERE chart_id = $1
+            ORDER BY axis_id
+        "#,
+    )
+    .bind(chart_id)
+    .fetch_all(executor)
+    .await
+}
+
+/// Get the fields of this chart table and of the tables in the paths of these axes.
+pub async fn get_axes_fields(
+    executor: impl PgExecutor<'_>,
+    table_id: Id,
+    axes: &[CreateAxis],
+) -> sqlx::Result<Vec<Field>> {
+    sqlx::query_as(
+        r#"
+            SELECT *
+            FROM meta_field
+            WHERE table_id = ANY($1)
+        "#,
+    )
+    .bind(
+        axes.iter()
+            .flat_map(|axis| axis.table_path.iter().copied())
+            .chain([table_id])
+            .unique()
+            .collect_vec(),
+    )
+    .fetch_all(executor)
+    .await
+}
+
+/// Return true if PostgreSQL knows this time zone name.
+pub async fn time_zone_exists(
+    executor: impl PgExecutor<'_>,
+    time_zone: &str,
+) -> sqlx::Result<bool> {
+    sqlx::query_scalar(
+        r#"
+            SELECT EXISTS (
+                SELECT 1
+                FROM pg_timezone_names
+                WHERE name = $1
+            )
+        "#,
+    )
+    .bind(time_zone)
+    .fetch_one(executor)
+    .await
 }
 
 #[cfg(test)]
 #[cfg_attr(coverage_nightly, coverage(off))]
 mod test {
     use crate::{
-        db,
+        Id, db,
         model::{
             Cell,
-            data::{CreateField, CreateTable, FieldKind, FieldMetadata},
-            viz::{Aggregate, AxisKind, ChartKind, CreateAxis, CreateChart, CreateDashboard},
+            data::{CreateField, CreateTable, FieldKind, FieldMetadata, SortDirection},
+            viz::{
+                Aggregate, AxisKind, AxisSort, ChartKind, CreateAxis, CreateChart, CreateDashboard,
+                TimeBucket, TimeUnit, UpdateChart,
+            },
         },
     };
-    use chrono::DateTime;
+    use chrono::{DateTime, Utc};
     use itertools::Itertools;
+    use rust_decimal::Decimal;
     use sqlx::PgPool;
-    use std::collections::{HashMap, HashSet};
+    use std::{
+        collections::{HashMap, HashSet},
+        str::FromStr,
+    };
 
     const TIMESTAMP: i64 = 1761696082;
     const ROW_COUNT: usize = 10;
@@ -391,6 +821,7 @@ mod test {
                 table_id,
                 name: "Test".into(),
                 chart_kind: ChartKind::Bar,
+                top_n: None,
             },
         )
         .await?
@@ -400,16 +831,28 @@ mod test {
 
         let mut create_axes_1 = vec![CreateAxis {
             field_id: group_by_column.0.field_id,
+            table_path: Vec::new(),
             axis_kind: AxisKind::X,
             aggregate: None,
+            percentile: None,
+            order_field_id: None,
+            sort: None,
+            bins: None,
+            bucket: None,
         }];
 
         for (field, _, aggregates) in &columns {
             for aggregate in aggregates {
                 create_axes_1.push(CreateAxis {
                     field_id: field.field_id,
+                    table_path: Vec::new(),
                     axis_kind: AxisKind::Y,
                     aggregate: Some(*aggregate),
+                    percentile: None,
+                    order_field_id: None,
+                    sort: None,
+                    bins: None,
+                    bucket: None,
                 });
             }
         }
@@ -419,8 +862,14 @@ mod test {
             .iter()
             .map(|a| CreateAxis {
                 field_id: a.field_id,
+                table_path: a.table_path.clone(),
                 axis_kind: a.axis_kind,
                 aggregate: a.aggregate,
+                percentile: a.percentile,
+                order_field_id: a.order_field_id,
+                sort: a.sort.clone().map(|sort| sort.0),
+                bins: a.bins,
+                bucket: a.bucket.clone().map(|bucket| bucket.0),
             })
             .collect();
         assert_eq!(create_axes_1.len(), create_axes_2.len());
@@ -432,4 +881,788 @@ mod test {
 
         Ok(())
     }
+
+    #[sqlx::test]
+    async fn set_axes_time_bucket(db: PgPool) -> anyhow::Result<()> {
+        let dashboard_id = db::create_dashboard(
+            &db,
+            CreateDashboard {
+                name: "test".into(),
+                description: "".into(),
+            },
+        )
+        .await?
+        .dashboard_id;
+        let table_id = db::create_table(
+            &db,
+            CreateTable {
+                name: "test".into(),
+                description: "".into(),
+                parent_id: None,
+            },
+        )
+        .await?
+        .table_id;
+        let field = FieldMetadata::from_field(
+            db::create_field(
+                &db,
+                table_id,
+                CreateField {
+                    name: "Date".into(),
+                    field_kind: FieldKind::DateTime {
+                        is_required: true,
+                        range_start: None,
+                        range_end: None,
+                    },
+                },
+            )
+            .await?,
+        );
+        let date = |s: &str| DateTime::<Utc>::from_str(s).unwrap();
+        db::create_entries(
+            &db,
+            table_id,
+            None,
+            vec![field.clone()],
+            [
+                "2025-01-31T23:30:00Z",
+                "2025-02-10T12:00:00Z",
+                "2025-03-01T00:30:00Z",
+            ]
+            .map(|s| vec![Cell::DateTime(date(s))])
+            .into(),
+        )
+        .await?;
+        let chart_id = db::create_chart(
+            &db,
+            dashboard_id,
+            CreateChart {
+                table_id,
+                name: "test".into(),
+                chart_kind: ChartKind::Line,
+                top_n: None,
+            },
+        )
+        .await?
+        .chart_id;
+
+        for (time_zone, expected) in [
+            (
+                "UTC",
+                vec![
+                    ("2025-01-01T00:00:00Z", 1),
+                    ("2025-02-01T00:00:00Z", 1),
+                    ("2025-03-01T00:00:00Z", 1),
+                ],
+            ),
+            (
+                "Europe/Berlin",
+                vec![("2025-01-31T23:00:00Z", 2), ("2025-02-28T23:00:00Z", 1)],
+            ),
+        ] {
+            let bucket = TimeBucket {
+                unit: TimeUnit::Month,
+                time_zone: time_zone.into(),
+            };
+            let axes = super::set_axes(
+                &db,
+                chart_id,
+                table_id,
+                vec![
+                    CreateAxis {
+                        field_id: field.field_id,
+                        table_path: Vec::new(),
+                        axis_kind: AxisKind::X,
+                        aggregate: None,
+                        percentile: None,
+                        order_field_id: None,
+                        sort: None,
+                        bins: None,
+                        bucket: Some(bucket.clone()),
+                    },
+                    CreateAxis {
+                        field_id: field.field_id,
+                        table_path: Vec::new(),
+                        axis_kind: AxisKind::Y,
+                        aggregate: Some(Aggregate::Count),
+                        percentile: None,
+                        order_field_id: None,
+                        sort: None,
+                        bins: None,
+                        bucket: Some(bucket.clone()),
+                    },
+                ],
+            )
+            .await?;
+            let (x_axis, y_axis) = axes.iter().collect_tuple().unwrap();
+            assert_eq!(x_axis.bucket.as_ref().map(|b| &b.0), Some(&bucket));
+
+            let data = db::get_chart_data(&db, chart_id, Vec::new()).await?;
+            let actual = data
+                .cells
+                .iter()
+                .map(|cells| {
+                    (
+                        cells[&x_axis.axis_id].clone(),
+                        cells[&y_axis.axis_id].clone(),
+                    )
+                })
+                .sorted_by_key(|(x, _)| match x {
+                    Cell::DateTime(x) => *x,
+                    _ => panic!("bucket is not a timestamp"),
+                })
+                .collect_vec();
+            let expected = expected
+                .into_iter()
+                .map(|(x, y)| (Cell::DateTime(date(x)), Cell::Integer(y)))
+                .collect_vec();
+            assert_eq!(actual, expected);
+        }
+
+        assert!(super::time_zone_exists(&db, "Europe/Berlin").await?);
+        assert!(!super::time_zone_exists(&db, "Mars/Olympus_Mons").await?);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn set_axes_statistics(db: PgPool) -> anyhow::Result<()> {
+        let dashboard_id = db::create_dashboard(
+            &db,
+            CreateDashboard {
+                name: "test".into(),
+                description: "".into(),
+            },
+        )
+        .await?
+        .dashboard_id;
+        let table_id = db::create_table(
+            &db,
+            CreateTable {
+                name: "test".into(),
+                description: "".into(),
+                parent_id: None,
+            },
+        )
+        .await?
+        .table_id;
+        let mut fields = Vec::new();
+        for (name, field_kind) in [
+            (
+                "Value",
+                FieldKind::Integer {
+                    is_required: true,
+                    range_start: None,
+                    range_end: None,
+                },
+            ),
+            (
+                "Date",
+                FieldKind::DateTime {
+                    is_required: true,
+                    range_start: None,
+                    range_end: None,
+                },
+            ),
+        ] {
+            fields.push(FieldMetadata::from_field(
+                db::create_field(
+                    &db,
+                    table_id,
+                    CreateField {
+                        name: name.into(),
+                        field_kind,
+                    },
+                )
+                .await?,
+            ));
+        }
+        let (value_field, date_field) = fields.iter().collect_tuple().unwrap();
+        db::create_entries(
+            &db,
+            table_id,
+            None,
+            fields.clone(),
+            [(2, 3), (1, 2), (3, 1), (10, 5), (2, 4)]
+                .map(|(value, day)| {
+                    vec![
+                        Cell::Integer(value),
+                        Cell::DateTime(DateTime::from_timestamp(day * 86400, 0).unwrap()),
+                    ]
+                })
+                .into(),
+        )
+        .await?;
+        let chart_id = db::create_chart(
+            &db,
+            dashboard_id,
+            CreateChart {
+                table_id,
+                name: "test".into(),
+                chart_kind: ChartKind::Bar,
+                top_n: None,
+            },
+        )
+        .await?
+        .chart_id;
+
+        let axes = super::set_axes(
+            &db,
+            chart_id,
+            table_id,
+            [
+                (Aggregate::CountDistinct, None, None),
+                (Aggregate::Median, None, None),
+                (Aggregate::Percentile, Some(75), None),
+                (Aggregate::Variance, None, None),
+                (Aggregate::StdDev, None, None),
+                (Aggregate::First, None, Some(date_field.field_id)),
+                (Aggregate::Last, None, Some(date_field.field_id)),
+            ]
+            .map(|(aggregate, percentile, order_field_id)| CreateAxis {
+                field_id: value_field.field_id,
+                table_path: Vec::new(),
+                axis_kind: AxisKind::Y,
+                aggregate: Some(aggregate),
+                percentile,
+                order_field_id,
+                sort: None,
+                bins: None,
+                bucket: None,
+            })
+            .into(),
+        )
+        .await?;
+
+        let data = db::get_chart_data(&db, chart_id, Vec::new()).await?;
+        let (cells,) = data.cells.iter().collect_tuple().unwrap();
+        let cells = axes
+            .iter()
+            .map(|axis| cells[&axis.axis_id].clone())
+            .collect_vec();
+        let Cell::Decimal(std_dev) = cells[4] else {
+            panic!("standard deviation is not a decimal");
+        };
+        assert_eq!(
+            cells,
+            vec![
+                Cell::Integer(4),
+                Cell::Float(2.0),
+                Cell::Float(3.0),
+                Cell::Decimal(Decimal::new(133, 1)),
+                Cell::Decimal(std_dev),
+                Cell::Integer(3),
+                Cell::Integer(10),
+            ]
+        );
+        assert!((std_dev * std_dev - Decimal::new(133, 1)).abs() < Decimal::new(1, 10));
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn set_axes_sort_top_n(db: PgPool) -> anyhow::Result<()> {
+        let dashboard_id = db::create_dashboard(
+            &db,
+            CreateDashboard {
+                name: "test".into(),
+                description: "".into(),
+            },
+        )
+        .await?
+        .dashboard_id;
+        let table_id = db::create_table(
+            &db,
+            CreateTable {
+                name: "test".into(),
+                description: "".into(),
+                parent_id: None,
+            },
+        )
+        .await?
+        .table_id;
+        let mut fields = Vec::new();
+        for (name, field_kind) in [
+            ("Member", FieldKind::Text { is_required: true }),
+            (
+                "Budget",
+                FieldKind::Money {
+                    is_required: true,
+                    range_start: None,
+                    range_end: None,
+                },
+            ),
+        ] {
+            fields.push(FieldMetadata::from_field(
+                db::create_field(
+                    &db,
+                    table_id,
+                    CreateField {
+                        name: name.into(),
+                        field_kind,
+                    },
+                )
+                .await?,
+            ));
+        }
+        db::create_entries(
+            &db,
+            table_id,
+            None,
+            fields.clone(),
+            member_row()
+                .into_iter()
+                .zip(budget_row())
+                .map(|(member, budget)| vec![member, budget])
+                .collect(),
+        )
+        .await?;
+        let (member_field, budget_field) = fields.iter().collect_tuple().unwrap();
+        let chart_id = db::create_chart(
+            &db,
+            dashboard_id,
+            CreateChart {
+                table_id,
+                name: "test".into(),
+                chart_kind: ChartKind::Bar,
+                top_n: None,
+            },
+        )
+        .await?
+        .chart_id;
+
+        let axes = super::set_axes(
+            &db,
+            chart_id,
+            table_id,
+            vec![
+                CreateAxis {
+                    field_id: member_field.field_id,
+                    table_path: Vec::new(),
+                    axis_kind: AxisKind::X,
+                    aggregate: None,
+                    percentile: None,
+                    order_field_id: None,
+                    sort: Some(AxisSort {
+                        direction: SortDirection::Ascending,
+                        priority: 1,
+                    }),
+                    bins: None,
+                    bucket: None,
+                },
+                CreateAxis {
+                    field_id: budget_field.field_id,
+                    table_path: Vec::new(),
+                    axis_kind: AxisKind::Y,
+                    aggregate: Some(Aggregate::Sum),
+                    percentile: None,
+                    order_field_id: None,
+                    sort: Some(AxisSort {
+                        direction: SortDirection::Descending,
+                        priority: 0,
+                    }),
+                    bins: None,
+                    bucket: None,
+                },
+            ],
+        )
+        .await?;
+        let (member_axis, budget_axis) = axes.iter().collect_tuple().unwrap();
+        let to_pairs = |cells: &HashMap<i32, Cell>| {
+            (
+                cells[&member_axis.axis_id].clone(),
+                cells[&budget_axis.axis_id].clone(),
+            )
+        };
+        let pair =
+            |member: &str, budget: i64| (Cell::String(member.into()), Cell::Decimal(budget.into()));
+
+        let data = db::get_chart_data(&db, chart_id, Vec::new()).await?;
+        assert_eq!(
+            data.cells.iter().map(to_pairs).collect_vec(),
+            vec![
+                pair("Paul", 425_000),
+                pair("Jane", 270_000),
+                pair("Chris", 150_000)
+            ]
+        );
+        assert_eq!(data.other, None);
+
+        for (top_n, expected, expected_other) in [
+            (
+                1,
+                vec![pair("Paul", 425_000)],
+                Some((Cell::Null, Cell::Decimal(420_000.into()))),
+            ),
+            (
+                2,
+                vec![pair("Paul", 425_000), pair("Jane", 270_000)],
+                Some((Cell::Null, Cell::Decimal(150_000.into()))),
+            ),
+            (
+                3,
+                vec![
+                    pair("Paul", 425_000),
+                    pair("Jane", 270_000),
+                    pair("Chris", 150_000),
+                ],
+                None,
+            ),
+        ] {
+            db::update_chart(
+                &db,
+                chart_id,
+                UpdateChart {
+                    name: "test".into(),
+                    chart_kind: ChartKind::Bar,
+                    top_n: Some(top_n),
+                },
+            )
+            .await?;
+            let data = db::get_chart_data(&db, chart_id, Vec::new()).await?;
+            assert_eq!(data.cells.iter().map(to_pairs).collect_vec(), expected);
+            assert_eq!(data.other.as_ref().map(to_pairs), expected_other);
+        }
+
+        // Without sorted axes, the top N groups have the largest aggregated axes.
+        let axes = super::set_axes(
+            &db,
+            chart_id,
+            table_id,
+            axes.into_iter()
+                .map(|axis| CreateAxis {
+                    field_id: axis.field_id,
+                    table_path: Vec::new(),
+                    axis_kind: axis.axis_kind,
+                    aggregate: axis.aggregate,
+                    percentile: None,
+                    order_field_id: None,
+                    sort: None,
+                    bins: None,
+                    bucket: None,
+                })
+                .collect(),
+        )
+        .await?;
+        db::update_chart(
+            &db,
+            chart_id,
+            UpdateChart {
+                name: "test".into(),
+                chart_kind: ChartKind::Bar,
+                top_n: Some(2),
+            },
+        )
+        .await?;
+        let (member_axis, budget_axis) = axes.iter().collect_tuple().unwrap();
+        let data = db::get_chart_data(&db, chart_id, Vec::new()).await?;
+        assert_eq!(
+            data.cells
+                .iter()
+                .map(|cells| (
+                    cells[&member_axis.axis_id].clone(),
+                    cells[&budget_axis.axis_id].clone(),
+                ))
+                .collect_vec(),
+            vec![pair("Paul", 425_000), pair("Jane", 270_000)]
+        );
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn set_axes_bins(db: PgPool) -> anyhow::Result<()> {
+        let dashboard_id = db::create_dashboard(
+            &db,
+            CreateDashboard {
+                name: "test".into(),
+                description: "".into(),
+            },
+        )
+        .await?
+        .dashboard_id;
+        let table_id = db::create_table(
+            &db,
+            CreateTable {
+                name: "test".into(),
+                description: "".into(),
+                parent_id: None,
+            },
+        )
+        .await?
+        .table_id;
+        let field = FieldMetadata::from_field(
+            db::create_field(
+                &db,
+                table_id,
+                CreateField {
+                    name: "Time".into(),
+                    field_kind: FieldKind::Integer {
+                        is_required: true,
+                        range_start: None,
+                        range_end: None,
+                    },
+                },
+            )
+            .await?,
+        );
+        db::create_entries(
+            &db,
+            table_id,
+            None,
+            vec![field.clone()],
+            time_row().into_iter().map(|cell| vec![cell]).collect(),
+        )
+        .await?;
+        let chart_id = db::create_chart(
+            &db,
+            dashboard_id,
+            CreateChart {
+                table_id,
+                name: "test".into(),
+                chart_kind: ChartKind::Histogram,
+                top_n: None,
+            },
+        )
+        .await?
+        .chart_id;
+
+        let axes = super::set_axes(
+            &db,
+            chart_id,
+            table_id,
+            vec![
+                CreateAxis {
+                    field_id: field.field_id,
+                    table_path: Vec::new(),
+                    axis_kind: AxisKind::X,
+                    aggregate: None,
+                    percentile: None,
+                    order_field_id: None,
+                    sort: Some(AxisSort {
+                        direction: SortDirection::Ascending,
+                        priority: 0,
+                    }),
+                    bins: Some(4),
+                    bucket: None,
+                },
+                CreateAxis {
+                    field_id: field.field_id,
+                    table_path: Vec::new(),
+                    axis_kind: AxisKind::Y,
+                    aggregate: Some(Aggregate::Count),
+                    percentile: None,
+                    order_field_id: None,
+                    sort: None,
+                    bins: None,
+                    bucket: None,
+                },
+            ],
+        )
+        .await?;
+        let (x_axis, y_axis) = axes.iter().collect_tuple().unwrap();
+
+        // Values from 1 to 5 in 4 bins of width 1, the maximum being in the last bin.
+        let data = db::get_chart_data(&db, chart_id, Vec::new()).await?;
+        let actual = data
+            .cells
+            .iter()
+            .map(|cells| {
+                (
+                    cells[&x_axis.axis_id].clone(),
+                    cells[&y_axis.axis_id].clone(),
+                )
+            })
+            .collect_vec();
+        assert_eq!(
+            actual,
+            [(1.0, 2), (2.0, 1), (3.0, 3), (4.0, 4)]
+                .map(|(x, y)| (Cell::Float(x), Cell::Integer(y)))
+                .to_vec()
+        );
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn set_axes_table_path(db: PgPool) -> anyhow::Result<()> {
+        let dashboard_id = db::create_dashboard(
+            &db,
+            CreateDashboard {
+                name: "test".into(),
+                description: "".into(),
+            },
+        )
+        .await?
+        .dashboard_id;
+        let parent_table_id = db::create_table(
+            &db,
+            CreateTable {
+                name: "Orders".into(),
+                description: "".into(),
+                parent_id: None,
+            },
+        )
+        .await?
+        .table_id;
+        let child_table_id = db::create_table(
+            &db,
+            CreateTable {
+                name: "Line Items".into(),
+                description: "".into(),
+                parent_id: Some(parent_table_id),
+            },
+        )
+        .await?
+        .table_id;
+        let category_field = FieldMetadata::from_field(
+            db::create_field(
+                &db,
+                parent_table_id,
+                CreateField {
+                    name: "Category".into(),
+                    field_kind: FieldKind::Text { is_required: true },
+                },
+            )
+            .await?,
+        );
+        let amount_field = FieldMetadata::from_field(
+            db::create_field(
+                &db,
+                child_table_id,
+                CreateField {
+                    name: "Amount".into(),
+                    field_kind: FieldKind::Integer {
+                        is_required: true,
+                        range_start: None,
+                        range_end: None,
+                    },
+                },
+            )
+            .await?,
+        );
+        let orders = db::create_entries(
+            &db,
+            parent_table_id,
+            None,
+            vec![category_field.clone()],
+            ["A", "B", "A", "C"]
+                .map(|category| vec![Cell::String(category.into())])
+                .to_vec(),
+        )
+        .await?;
+        for (order, amounts) in orders.iter().zip([vec![1, 2], vec![3], vec![4, 5, 6]]) {
+            db::create_entries(
+                &db,
+                child_table_id,
+                Some(order.entry_id),
+                vec![amount_field.clone()],
+                amounts
+                    .into_iter()
+                    .map(|amount| vec![Cell::Integer(amount)])
+                    .collect(),
+            )
+            .await?;
+        }
+
+        let axis = |field_id, table_path, axis_kind, aggregate| CreateAxis {
+            field_id,
+            table_path,
+            axis_kind,
+            aggregate,
+            percentile: None,
+            order_field_id: None,
+            sort: (axis_kind == AxisKind::X).then_some(AxisSort {
+                direction: SortDirection::Ascending,
+                priority: 0,
+            }),
+            bins: None,
+            bucket: None,
+        };
+        let chart_data = async |table_id, axes| -> anyhow::Result<Vec<(Cell, Cell)>> {
+            let chart_id = db::create_chart(
+                &db,
+                dashboard_id,
+                CreateChart {
+                    table_id,
+                    name: "test".into(),
+                    chart_kind: ChartKind::Bar,
+                    top_n: None,
+                },
+            )
+            .await?
+            .chart_id;
+            let axes = super::set_axes(&db, chart_id, table_id, axes).await?;
+            let (x_axis, y_axis) = axes.iter().collect_tuple().unwrap();
+            let data = db::get_chart_data(&db, chart_id, Vec::new()).await?;
+            Ok(data
+                .cells
+                .iter()
+                .map(|cells| {
+                    (
+                        cells[&x_axis.axis_id].clone(),
+                        cells[&y_axis.axis_id].clone(),
+                    )
+                })
+                .collect_vec())
+        };
+
+        // Sum of the child line items per parent category, from either table.
+        let expected = [
+            (Cell::String("A".into()), Cell::Decimal(Decimal::from(18))),
+            (Cell::String("B".into()), Cell::Decimal(Decimal::from(3))),
+            (Cell::String("C".into()), Cell::Null),
+        ];
+        let actual = chart_data(
+            parent_table_id,
+            vec![
+                axis(category_field.field_id, vec![], AxisKind::X, None),
+                axis(
+                    amount_field.field_id,
+                    vec![child_table_id],
+                    AxisKind::Y,
+                    Some(Aggregate::Sum),
+                ),
+            ],
+        )
+        .await?;
+        assert_eq!(actual, expected);
+        let actual = chart_data(
+            child_table_id,
+            vec![
+                axis(
+                    category_field.field_id,
+                    vec![parent_table_id],
+                    AxisKind::X,
+                    None,
+                ),
+                axis(
+                    amount_field.field_id,
+                    vec![],
+                    AxisKind::Y,
+                    Some(Aggregate::Sum),
+                ),
+            ],
+        )
+        .await?;
+        assert_eq!(actual, expected[..2]);
+
+        // Deleting the joined table removes the joined axes from the chart.
+        db::delete_table(&db, child_table_id).await?;
+        let axis_table_paths: Vec<Vec<Id>> = sqlx::query_scalar(
+            r#"
+                SELECT a.table_path
+                FROM axis AS a
+                JOIN chart AS c
+                ON a.chart_id = c.chart_id
+                WHERE c.table_id = $1
+            "#,
+        )
+        .bind(parent_table_id)
+        .fetch_all(&db)
+        .await?;
+        assert_eq!(axis_table_paths, [Vec::<Id>::new()]);
+
+        Ok(())
+    }
 }
@@ -1,16 +1,19 @@
 //! Database functions for managing dashboard charts.
 
+use super::{OTHER_ROW_COLUMN, chart_view_select, get_chart_view_source};
 use crate::{
-    Id,
+    Id, db,
     model::{
         Cell,
+        data::{EntryFilter, FieldKind},
         viz::{
-            AxisField, AxisIdentifier, Chart, ChartData, ChartIdentifier, CreateChart, UpdateChart,
+            AxisField, AxisIdentifier, Chart, ChartData, ChartIdentifier, ChartKind, CreateChart,
+            UpdateChart,
         },
     },
 };
 use itertools::Itertools;
-use sqlx::{Acquire, PgExecutor, Postgres};
+use sqlx::{Acquire, PgExecutor, Postgres, Row};
 use std::collections::HashMap;
 
 /// Add a chart to this dashboard and create the actual SQL view.
@@ -21,20 +24,23 @@ pub async fn create_chart(
         table_id,
         name,
         chart_kind,
+        top_n,
     }: CreateChart,
 ) -> sqlx::Result<Chart> {
     let mut tx = conn.begin().await?;
 
     let chart: Chart = sqlx::query_as(
         r#"
-            INSERT INTO chart (dashboard_id, table_id, name, chart_kind)
-            VALUES ($1, $2, $3, $4)
+            INSERT INTO chart (dashboard_id, table_id, name, chart_kind, top_n)
+            VALUES ($1, $2, $3, $4, $5)
             RETURNING
                 chart_id,
                 dashboard_id,
                 table_id,
                 name,
                 chart_kind,
+                top_n,
+                layout,
                 created_at,
                 updated_at
         "#,
@@ -43,6 +49,7 @@ pub async fn create_chart(
     .bind(table_id)
     .bind(name)
     .bind(chart_kind)
+    .bind(top_n)
     .fetch_one(tx.as_mut())
     .await?;
 
@@ -62,35 +69,44 @@ pub async fn create_chart(
     Ok(chart)
 }
 
-/// Update the chart metadata.
+/// Update the chart metadata and rebuild the actual SQL view.
 pub async fn update_chart(
     conn: impl Acquire<'_, Database = Postgres>,
     chart_id: Id,
-    UpdateChart { name, chart_kind }: UpdateChart,
+    UpdateChart {
+        name,
+        chart_kind,
+        top_n,
+    }: UpdateChart,
 ) -> sqlx::Result<Chart> {
     let mut tx = conn.begin().await?;
 
     let chart = sqlx::query_as(
         r#"
             UPDATE chart
-            SET name = $1, chart_kind = $2
-            WHERE chart_id = $3
+            SET name = $1, chart_kind = $2, top_n = $3
+            WHERE chart_id = $4
             RETURNING
                 chart_id,
                 dashboard_id,
                 table_id,
                 name,
                 chart_kind,
+                top_n,
+                layout,
                 created_at,
                 updated_at
         "#,
     )
     .bind(name)
     .bind(chart_kind)
+    .bind(top_n)
     .bind(chart_id)
     .fetch_one(tx.as_mut())
     .await?;
 
+    db::rebuild_chart_views(tx.as_mut(), vec![chart_id]).await?;
+
     tx.commit().await?;
 
     Ok(chart)
@@ -137,9 +153,47 @@ pub async fn get_chart_table_id(executor: impl PgExecutor<'_>, chart_id: Id) ->
     .await
 }
 
+/// Get the table of this chart and the tables joined by the paths of its axes.
+pub async fn get_chart_table_ids(
+    executor: impl PgExecutor<'_>,
+    chart_id: Id,
+) -> sqlx::Result<Vec<Id>> {
+    sqlx::query_scalar(
+        r#"
+            SELECT table_id
+            FROM chart
+            WHERE chart_id = $1
+            UNION
+            SELECT unnest(table_path)
+            FROM axis
+            WHERE chart_id = $1
+        "#,
+    )
+    .bind(chart_id)
+    .fetch_all(executor)
+    .await
+}
+
+/// Get the kind of this chart.
+pub async fn get_chart_kind(
+    executor: impl PgExecutor<'_>,
+    chart_id: Id,
+) -> sqlx::Result<ChartKind> {
+    sqlx::query_scalar(
+        r#"
+            SELECT chart_kind
+            FROM chart
+            WHERE chart_id = $1
+        "#,
+    )
+    .bind(chart_id)
+    .fetch_one(executor)
+    .await
+}
+
 /// Get all the charts of this dashboard.
 pub async fn get_charts(
-    executor: impl PgExecutor<'_> + Copy,
+    executor: impl PgExecutor<'_>,
     dashboard_id: Id,
 ) -> sqlx::Result<Vec<Chart>> {
     sqlx::query_as(
@@ -150,6 +204,8 @@ pub async fn get_charts(
                 table_id,
                 name,
                 chart_kind,
+                top_n,
+                layout,
                 created_at,
                 updated_at
             FROM chart
@@ -161,11 +217,16 @@ pub async fn get_charts(
     .await
 }
 
-/// Get the chart, its axes and associated fields, and its data points.
+/// Get the chart, its axes and associated fields, and its data points in the order of the view.
+/// With additional filters, the data points are selected from the query of the view
+/// with the filters applied on top of the chart filters.
 pub async fn get_chart_data(
-    executor: impl PgExecutor<'_> + Copy,
+    conn: impl Acquire<'_, Database = Postgres>,
     chart_id: Id,
+    additional_filters: Vec<EntryFilter<Cell>>,
 ) -> sqlx::Result<ChartData> {
+    let mut tx = conn.begin().await?;
+
     let chart: Chart = sqlx::query_as(
         r#"
             SELECT
@@ -174,6 +235,8 @@ pub async fn get_chart_data(
                 table_id,
                 name,
                 chart_kind,
+                top_n,
+                layout,
                 created_at,
                 updated_at
             FROM chart
@@ -181,7 +244,7 @@ pub async fn get_chart_data(
         "#,
     )
     .bind(chart_id)
-    .fetch_one(executor)
+    .fetch_one(tx.as_mut())
     .await?;
 
     let axes: Vec<AxisField> = sqlx::query_as(
@@ -190,8 +253,14 @@ pub async fn get_chart_data(
                 a.axis_id,
                 a.chart_id,
                 a.field_id,
+                a.table_path,
                 a.axis_kind,
                 a.aggregate,
+                a.percentile,
+                a.order_field_id,
+                a.sort,
+                a.bins,
+                a.bucket,
                 a.created_at,
                 a.updated_at,
                 f.name AS field_name,
@@ -203,24 +272,48 @@ pub async fn get_chart_data(
         "#,
     )
     .bind(chart_id)
-    .fetch_all(executor)
+    .fetch_all(tx.as_mut())
     .await?;
 
     let chart_ident = ChartIdentifier::new(chart_id, "data_view");
+    let has_other_row = chart.top_n.is_some() && !axes.is_empty();
     let select_columns = axes
         .iter()
-        .map(|axis_field| AxisIdentifier::new(axis_field.axis.axis_id))
+        .map(|axis_field| AxisIdentifier::new(axis_field.axis.axis_id).to_string())
+        .chain(has_other_row.then(|| OTHER_ROW_COLUMN.to_string()))
         .join(", ");
+    let from_statement = if additional_filters.is_empty() {
+        chart_ident.to_string()
+    } else {
+        let (table_id, axes, is_trashed) = get_chart_view_source(tx.as_mut(), chart_id).await?;
+        let view_select = chart_view_select(
+            tx.as_mut(),
+            chart_id,
+            table_id,
+            &axes,
+            is_trashed,
+            additional_filters,
+        )
+        .await?;
+        format!("({view_select}) AS v")
+    };
     let rows = sqlx::query(&format!(
         r#"
             SELECT {select_columns}
-            FROM {chart_ident}
+            FROM {from_statement}
         "#
     ))
-    .fetch_all(executor)
+    .fetch_all(tx.as_mut())
     .await?;
 
     let mut cells: Vec<HashMap<Id, Cell>> = Vec::new();
+    let mut other = None;
+    // Binned axes select the lower bound of their bin.
+    let bin_field_kind = FieldKind::Float {
+        is_required: false,
+        range_start: None,
+        range_end: None,
+    };
 
     for row in rows {
         let mut entry = HashMap::new();
@@ -229,6 +322,11 @@ pub async fn get_chart_data(
         } in &axes
         {
             let axis_ident = AxisIdentifier::new(axis.axis_id);
+            let field_kind = if axis.bins.is_some() {
+                &bin_field_kind
+            } else {
+                &field_kind.0
+            };
             entry.insert(
                 axis.axis_id,
                 axis.aggregate.as_ref().map_or_else(
@@ -244,10 +342,21 @@ pub async fn get_chart_data(
                 )?,
             );
         }
-        cells.push(entry);
+        if has_other_row && row.try_get(OTHER_ROW_COLUMN)? {
+            other = Some(entry);
+        } else {
+            cells.push(entry);
+        }
     }
 
-    Ok(ChartData { chart, axes, cells })
+    tx.commit().await?;
+
+    Ok(ChartData {
+        chart,
+        axes,
+        cells,
+        other,
+    })
 }
 
 /// Return true if the chart exists.
@@ -314,6 +423,7 @@ mod test {
             table_id,
             name: "test".into(),
             chart_kind: ChartKind::Bar,
+            top_n: None,
         };
         let chart_1 = super::create_chart(&db, dashboard_id, create_chart.clone()).await?;
         assert_eq!(create_chart.table_id, chart_1.table_id);
@@ -360,6 +470,7 @@ mod test {
                 table_id,
                 name: "test".into(),
                 chart_kind: ChartKind::Bar,
+                top_n: None,
             },
         )
         .await?
@@ -367,6 +478,7 @@ mod test {
         let update_chart = UpdateChart {
             name: "X Over Time".into(),
             chart_kind: ChartKind::Line,
+            top_n: None,
         };
         let chart_1 = super::update_chart(&db, chart_id, update_chart.clone()).await?;
         let chart_2 = sqlx::query_as(r#"SELECT * FROM chart WHERE chart_id = $1"#)
@@ -415,6 +527,7 @@ mod test {
                 table_id,
                 name: "test".into(),
                 chart_kind: ChartKind::Bar,
+                top_n: None,
             },
         )
         .await?
@@ -425,8 +538,14 @@ mod test {
             table_id,
             vec![CreateAxis {
                 field_id,
+                table_path: Vec::new(),
                 axis_kind: AxisKind::X,
                 aggregate: None,
+                percentile: None,
+                order_field_id: None,
+                sort: None,
+                bins: None,
+                bucket: None,
             }],
         )
         .await?
@@ -477,6 +596,7 @@ mod test {
                 table_id: table_id_1,
                 name: "test".into(),
                 chart_kind: ChartKind::Bar,
+                top_n: None,
             },
         )
         .await?
@@ -520,6 +640,7 @@ mod test {
                         table_id,
                         name: idx.to_string(),
                         chart_kind,
+                        top_n: None,
                     },
                 )
                 .await?,
@@ -601,6 +722,7 @@ mod test {
                 table_id,
                 name: "test".into(),
                 chart_kind: ChartKind::Bar,
+                top_n: None,
             },
         )
         .await?;
@@ -611,20 +733,32 @@ mod test {
             vec![
                 CreateAxis {
                     field_id: checkbox_field.field_id,
+                    table_path: Vec::new(),
                     axis_kind: AxisKind::X,
                     aggregate: None,
+                    percentile: None,
+                    order_field_id: None,
+                    sort: None,
+                    bins: None,
+                    bucket: None,
                 },
                 CreateAxis {
                     field_id: integer_field.field_id,
+                    table_path: Vec::new(),
                     axis_kind: AxisKind::Y,
                     aggregate: Some(Aggregate::Sum),
+                    percentile: None,
+                    order_field_id: None,
+                    sort: None,
+                    bins: None,
+                    bucket: None,
                 },
             ],
         )
         .await?;
         axes.sort_by_key(|a| a.field_id);
 
-        let chart_data = super::get_chart_data(&db, chart.chart_id).await?;
+        let chart_data = super::get_chart_data(&db, chart.chart_id, Vec::new()).await?;
         assert_eq!(chart, chart_data.chart);
 
         let mut fields = vec![checkbox_field, integer_field];
@@ -688,6 +822,7 @@ mod test {
                 table_id: table_id_1,
                 name: "test".into(),
                 chart_kind: ChartKind::Bar,
+                top_n: None,
             },
         )
         .await?
@@ -0,0 +1,299 @@
+//! Database functions for managing dashboard filter controls.
+
+use crate::{
+    Id,
+    model::viz::{ControlBinding, CreateControl, DashboardControl, UpdateControl},
+};
+use sqlx::{Acquire, PgExecutor, Postgres};
+
+/// Select the columns of a control along with its bindings ordered by chart.
+const SELECT_CONTROL: &str = r#"
+    SELECT
+        c.control_id,
+        c.dashboard_id,
+        c.name,
+        c.control_kind,
+        COALESCE((
+            SELECT json_agg(
+                json_build_object('chart_id', b.chart_id, 'field_id', b.field_id)
+                ORDER BY b.chart_id
+            )
+            FROM dashboard_control_binding AS b
+            WHERE b.control_id = c.control_id
+        ), '[]') AS bindings,
+        c.created_at,
+        c.updated_at
+    FROM dashboard_control AS c
+"#;
+
+/// Create a filter control on this dashboard.
+pub async fn create_control(
+    conn: impl Acquire<'_, Database = Postgres>,
+    dashboard_id: Id,
+    CreateControl {
+        name,
+        control_kind,
+        bindings,
+    }: CreateControl,
+) -> sqlx::Result<DashboardControl> {
+    let mut tx = conn.begin().await?;
+
+    let control_id: Id = sqlx::query_scalar(
+        r#"
+            INSERT INTO dashboard_control (dashboard_id, name, control_kind)
+            VALUES ($1, $2, $3)
+            RETURNING control_id
+        "#,
+    )
+    .bind(dashboard_id)
+    .bind(name)
+    .bind(control_kind)
+    .fetch_one(tx.as_mut())
+    .await?;
+
+    set_control_bindings(tx.as_mut(), control_id, bindings).await?;
+    let control = get_control(tx.as_mut(), control_id).await?;
+
+    tx.commit().await?;
+
+    Ok(control)
+}
+
+/// Update the control and replace its bindings.
+pub async fn update_control(
+    conn: impl Acquire<'_, Database = Postgres>,
+    control_id: Id,
+    UpdateControl {
+        name,
+        control_kind,
+        bindings,
+    }: UpdateControl,
+) -> sqlx::Result<DashboardControl> {
+    let mut tx = conn.begin().await?;
+
+    sqlx::query(
+        r#"
+            UPDATE dashboard_control
+            SET name = $1, control_kind = $2
+            WHERE control_id = $3
+        "#,
+    )
+    .bind(name)
+    .bind(control_kind)
+    .bind(control_id)
+    .execute(tx.as_mut())
+    .await?;
+
+    set_control_bindings(tx.as_mut(), control_id, bindings).await?;
+    let control = get_control(tx.as_mut(), control_id).await?;
+
+    tx.commit().await?;
+
+    Ok(control)
+}
+
+/// Delete the control along with its bindings.
+pub async fn delete_control(executor: impl PgExecutor<'_>, control_id: Id) -> sqlx::Result<()> {
+    sqlx::query(
+        r#"
+            DELETE FROM dashboard_control
+            WHERE control_id = $1
+        "#,
+    )
+    .bind(control_id)
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// Get all the controls of this dashboard.
+pub async fn get_controls(
+    executor: impl PgExecutor<'_>,
+    dashboard_id: Id,
+) -> sqlx::Result<Vec<DashboardControl>> {
+    sqlx::query_as(&format!(
+        r#"
+            {SELECT_CONTROL}
+            WHERE c.dashboard_id = $1
+            ORDER BY c.control_id
+        "#
+    ))
+    .bind(dashboard_id)
+    .fetch_all(executor)
+    .await
+}
+
+/// Return true if the control exists on this dashboard.
+pub async fn control_exists(
+    executor: impl PgExecutor<'_>,
+    dashboard_id: Id,
+    control_id: Id,
+) -> sqlx::Result<bool> {
+    sqlx::query_scalar(
+        r#"
+            SELECT EXISTS (
+                SELECT 1
+                FROM dashboard_control
+                WHERE dashboard_id = $1 AND control_id = $2
+            )
+        "#,
+    )
+    .bind(dashboard_id)
+    .bind(control_id)
+    .fetch_one(executor)
+    .await
+}
+
+async fn get_control(
+    executor: impl PgExecutor<'_>,
+    control_id: Id,
+) -> sqlx::Result<DashboardControl> {
+    sqlx::query_as(&format!(
+        r#"
+            {SELECT_CONTROL}
+            WHERE c.control_id = $1
+        "#
+    ))
+    .bind(control_id)
+    .fetch_one(executor)
+    .await
+}
+
+/// Replace the bindings of this control.
+async fn set_control_bindings(
+    conn: impl Acquire<'_, Database = Postgres>,
+    control_id: Id,
+    bindings: Vec<ControlBinding>,
+) -> sqlx::Result<()> {
+    let mut tx = conn.begin().await?;
+
+    sqlx::query(
+        r#"
+            DELETE FROM dashboard_control_binding
+            WHERE control_id = $1
+        "#,
+    )
+    .bind(control_id)
+    .execute(tx.as_mut())
+    .await?;
+
+    let (chart_ids, field_ids): (Vec<Id>, Vec<Id>) = bindings
+        .into_iter()
+        .map(|binding| (binding.chart_id, binding.field_id))
+        .unzip();
+    sqlx::query(
+        r#"
+            INSERT INTO dashboard_control_binding (control_id, chart_id, field_id)
+            SELECT $1, * FROM UNNEST($2::int[], $3::int[])
+        "#,
+    )
+    .bind(control_id)
+    .bind(chart_ids)
+    .bind(field_ids)
+    .execute(tx.as_mut())
+    .await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod test {
+    use crate::{
+        db,
+        model::{
+            data::{CreateField, CreateTable, FieldKind},
+            viz::{
+                ChartKind, ControlBinding, ControlKind, CreateChart, CreateControl,
+                CreateDashboard, UpdateControl,
+            },
+        },
+    };
+    use sqlx::PgPool;
+
+    #[sqlx::test]
+    async fn controls(db: PgPool) -> anyhow::Result<()> {
+        let dashboard_id = db::create_dashboard(
+            &db,
+            CreateDashboard {
+                name: "A".into(),
+                description: "".into(),
+            },
+        )
+        .await?
+        .dashboard_id;
+        let table_id = db::create_table(
+            &db,
+            CreateTable {
+                name: "B".into(),
+                description: "".into(),
+                parent_id: None,
+            },
+        )
+        .await?
+        .table_id;
+        let field_id = db::create_field(
+            &db,
+            table_id,
+            CreateField {
+                name: "C".into(),
+                field_kind: FieldKind::DateTime {
+                    is_required: false,
+                    range_start: None,
+                    range_end: None,
+                },
+            },
+        )
+        .await?
+        .field_id;
+        let chart_id = db::create_chart(
+            &db,
+            dashboard_id,
+            CreateChart {
+                table_id,
+                name: "D".into(),
+                chart_kind: ChartKind::Bar,
+                top_n: None,
+            },
+        )
+        .await?
+        .chart_id;
+
+        let binding = ControlBinding { chart_id, field_id };
+        let control = super::create_control(
+            &db,
+            dashboard_id,
+            CreateControl {
+                name: "Period".into(),
+                control_kind: ControlKind::DateRange,
+                bindings: vec![binding.clone()],
+            },
+        )
+        .await?;
+        assert_eq!(control.control_kind, ControlKind::DateRange);
+        assert_eq!(control.bindings.0, [binding]);
+        assert!(super::control_exists(&db, dashboard_id, control.control_id).await?);
+
+        let control = super::update_control(
+            &db,
+            control.control_id,
+            UpdateControl {
+                name: "Date".into(),
+                control_kind: ControlKind::DateRange,
+                bindings: Vec::new(),
+            },
+        )
+        .await?;
+        assert_eq!(control.name, "Date");
+        assert!(control.bindings.is_empty());
+        assert_eq!(super::get_controls(&db, dashboard_id).await?, [control]);
+
+        let control_id = super::get_controls(&db, dashboard_id).await?[0].control_id;
+        super::delete_control(&db, control_id).await?;
+        assert!(!super::control_exists(&db, dashboard_id, control_id).await?);
+
+        Ok(())
+    }
+}
@@ -1,13 +1,16 @@
-/// Database functions for managing dashboards.
+//! Database functions for managing dashboards.
 
 use crate::{
     Id, db,
     model::{
         access::AccessRole,
-        viz::{CreateDashboard, Dashboard, GetDashboard, UpdateDashboard},
+        viz::{
+            Chart, CreateDashboard, Dashboard, GetDashboard, SetChartLayout, SetLayout,
+            UpdateDashboard,
+        },
     },
 };
-use sqlx::{Acquire, PgExecutor, Postgres};
+use sqlx::{Acquire, PgExecutor, Postgres, types::Json};
 
 /// Create a dashboard.
 pub async fn create_dashboard(
@@ -60,6 +63,60 @@ pub async fn update_dashboard(
     Ok(dashboard)
 }
 
+/// Set the tabs of the dashboard and the layout of its charts.
+/// The charts missing from the layout are removed from the grid.
+pub async fn set_dashboard_layout(
+    conn: impl Acquire<'_, Database = Postgres>,
+    dashboard_id: Id,
+    SetLayout { tabs, charts }: SetLayout,
+) -> sqlx::Result<Vec<Chart>> {
+    let mut tx = conn.begin().await?;
+
+    sqlx::query(
+        r#"
+            UPDATE dashboard
+            SET tabs = $1
+            WHERE dashboard_id = $2
+        "#,
+    )
+    .bind(tabs)
+    .bind(dashboard_id)
+    .execute(tx.as_mut())
+    .await?;
+
+    sqlx::query(
+        r#"
+            UPDATE chart
+            SET layout = NULL
+            WHERE dashboard_id = $1
+        "#,
+    )
+    .bind(dashboard_id)
+    .execute(tx.as_mut())
+    .await?;
+
+    for SetChartLayout { chart_id, layout } in charts {
+        sqlx::query(
+            r#"
+                UPDATE chart
+                SET layout = $1
+                WHERE chart_id = $2 AND dashboard_id = $3
+            "#,
+        )
+        .bind(Json(layout))
+        .bind(chart_id)
+        .bind(dashboard_id)
+        .execute(tx.as_mut())
+        .await?;
+    }
+
+    let charts = db::get_charts(tx.as_mut(), dashboard_id).await?;
+
+    tx.commit().await?;
+
+    Ok(charts)
+}
+
 /// Delete the dashboard along with its charts.
 pub async fn delete_dashboard(
     conn: impl Acquire<'_, Database = Postgres>,
@@ -96,6 +153,23 @@ pub async fn delete_dashboard(
     Ok(())
 }
 
+/// Get the metadata of this dashboard.
+pub async fn get_dashboard(
+    executor: impl PgExecutor<'_>,
+    dashboard_id: Id,
+) -> sqlx::Result<Dashboard> {
+    sqlx::query_as(
+        r#"
+            SELECT *
+            FROM dashboard
+            WHERE dashboard_id = $1
+        "#,
+    )
+    .bind(dashboard_id)
+    .fetch_one(executor)
+    .await
+}
+
 /// Get all dashboards viewable by this user.
 pub async fn get_dashboards_for_user(
     executor: impl PgExecutor<'_>,
@@ -150,7 +224,11 @@ mod test {
         db::{self, create_user},
         model::{
             access::AccessRole,
-            viz::{CreateDashboard, UpdateDashboard},
+            data::CreateTable,
+            viz::{
+                ChartKind, ChartLayout, CreateChart, CreateDashboard, SetChartLayout, SetLayout,
+                UpdateDashboard,
+            },
         },
         test_util,
     };
@@ -227,6 +305,89 @@ mod test {
         Ok(())
     }
 
+    #[sqlx::test]
+    async fn set_dashboard_layout(db: PgPool) -> anyhow::Result<()> {
+        let dashboard_id = super::create_dashboard(
+            &db,
+            CreateDashboard {
+                name: "A".into(),
+                description: "B".into(),
+            },
+        )
+        .await?
+        .dashboard_id;
+        let table_id = db::create_table(
+            &db,
+            CreateTable {
+                name: "Test".into(),
+                description: "".into(),
+                parent_id: None,
+            },
+        )
+        .await?
+        .table_id;
+        let create_chart = |name: &str| CreateChart {
+            table_id,
+            name: name.into(),
+            chart_kind: ChartKind::Bar,
+            top_n: None,
+        };
+        let chart_id_1 = db::create_chart(&db, dashboard_id, create_chart("C"))
+            .await?
+            .chart_id;
+        let chart_id_2 = db::create_chart(&db, dashboard_id, create_chart("D"))
+            .await?
+            .chart_id;
+
+        let layout = ChartLayout {
+            x: 2,
+            y: 3,
+            width: 4,
+            height: 5,
+            tab: Some("Overview".into()),
+        };
+        let set_layout = |chart_id| SetLayout {
+            tabs: vec!["Overview".into()],
+            charts: vec![SetChartLayout {
+                chart_id,
+                layout: layout.clone(),
+            }],
+        };
+
+        let charts = super::set_dashboard_layout(&db, dashboard_id, set_layout(chart_id_1)).await?;
+        assert_eq!(charts.len(), 2);
+        let tabs: Vec<String> =
+            sqlx::query_scalar(r#"SELECT tabs FROM dashboard WHERE dashboard_id = $1"#)
+                .bind(dashboard_id)
+                .fetch_one(&db)
+                .await?;
+        assert_eq!(tabs, ["Overview"]);
+        let chart_layouts = db::get_charts(&db, dashboard_id)
+            .await?
+            .into_iter()
+            .map(|chart| (chart.chart_id, chart.layout.map(|layout| layout.0)))
+            .sorted_by_key(|(chart_id, _)| *chart_id)
+            .collect_vec();
+        assert_eq!(
+            chart_layouts,
+            [(chart_id_1, Some(layout.clone())), (chart_id_2, None)]
+        );
+
+        super::set_dashboard_layout(&db, dashboard_id, set_layout(chart_id_2)).await?;
+        let chart_layouts = db::get_charts(&db, dashboard_id)
+            .await?
+            .into_iter()
+            .map(|chart| (chart.chart_id, chart.layout.map(|layout| layout.0)))
+            .sorted_by_key(|(chart_id, _)| *chart_id)
+            .collect_vec();
+        assert_eq!(
+            chart_layouts,
+            [(chart_id_1, None), (chart_id_2, Some(layout))]
+        );
+
+        Ok(())
+    }
+
     #[sqlx::test]
     async fn delete_dashboard(db: PgPool) -> anyhow::Result<()> {
         let name: String = "blazinglyfast".into();
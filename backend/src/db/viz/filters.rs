@@ -0,0 +1,116 @@
+//! Database functions for managing chart filters.
+
+use crate::{
+    Id, db,
+    model::{
+        Cell,
+        data::{EntryFilter, FieldKind, FilterOperator},
+        viz::ChartFilter,
+    },
+};
+use sqlx::{Acquire, PgExecutor, Postgres, QueryBuilder, types::Json};
+
+/// Set the filters of this chart and rebuild the SQL view.
+pub async fn set_chart_filters(
+    conn: impl Acquire<'_, Database = Postgres>,
+    chart_id: Id,
+    filters: Vec<EntryFilter>,
+) -> sqlx::Result<Vec<ChartFilter>> {
+    let mut tx = conn.begin().await?;
+
+    sqlx::query(
+        r#"
+            DELETE FROM chart_filter
+            WHERE chart_id = $1
+        "#,
+    )
+    .bind(chart_id)
+    .execute(tx.as_mut())
+    .await?;
+
+    let filters: Vec<ChartFilter> = if filters.is_empty() {
+        Vec::new()
+    } else {
+        QueryBuilder::new(r#"INSERT INTO chart_filter (chart_id, field_id, operator)"#)
+            .push_values(filters, |mut builder, filter| {
+                builder
+                    .push_bind(chart_id)
+                    .push_bind(filter.field_id)
+                    .push_bind(Json(filter.operator));
+            })
+            .push(
+                r#"
+                    RETURNING
+                        chart_filter_id,
+                        chart_id,
+                        field_id,
+                        operator,
+                        created_at,
+                        updated_at
+                "#,
+            )
+            .build_query_as()
+            .fetch_all(tx.as_mut())
+            .await?
+    };
+
+    db::rebuild_chart_views(tx.as_mut(), vec![chart_id]).await?;
+
+    tx.commit().await?;
+    Ok(filters)
+}
+
+/// Get all the filters of this chart.
+pub async fn get_chart_filters(
+    executor: impl PgExecutor<'_>,
+    chart_id: Id,
+) -> sqlx::Result<Vec<ChartFilter>> {
+    sqlx::query_as(
+        r#"
+            SELECT
+                chart_filter_id,
+                chart_id,
+                field_id,
+                operator,
+                created_at,
+                updated_at
+            FROM chart_filter
+            WHERE chart_id = $1
+            ORDER BY chart_filter_id
+        "#,
+    )
+    .bind(chart_id)
+    .fetch_all(executor)
+    .await
+}
+
+/// Get the filters of this chart with their values converted to the kinds of their fields.
+/// Filters whose values no longer match the kind of their field are left out.
+pub(super) async fn get_chart_view_filters(
+    executor: impl PgExecutor<'_>,
+    chart_id: Id,
+) -> sqlx::Result<Vec<EntryFilter<Cell>>> {
+    let filters: Vec<(Id, Json<FilterOperator>, Json<FieldKind>)> = sqlx::query_as(
+        r#"
+            SELECT f.field_id, f.operator, m.field_kind
+            FROM chart_filter AS f
+            JOIN meta_field AS m
+            ON f.field_id = m.field_id
+            WHERE f.chart_id = $1
+            ORDER BY f.chart_filter_id
+        "#,
+    )
+    .bind(chart_id)
+    .fetch_all(executor)
+    .await?;
+
+    Ok(filters
+        .into_iter()
+        .filter_map(|(field_id, Json(operator), Json(field_kind))| {
+            let operator = operator
+                .try_map(|value| Cell::from_filter_value(value, &field_kind).ok_or(()))
+                .ok()?;
+            Some(EntryFilter { field_id, operator })
+        })
+        .collect())
+}
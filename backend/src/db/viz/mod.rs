@@ -1,7 +1,9 @@
-/// Database functions for the data visualization features.
+//! Database functions for the data visualization features.
 
 mod axes;
 mod charts;
+mod controls;
 mod dashboards;
+mod filters;
 
-pub use {axes::*, charts::*, dashboards::*};
+pub use {axes::*, charts::*, controls::*, dashboards::*, filters::*};
@@ -20,17 +20,28 @@ use crate::{
 
 pub const AUTHENTICATION_TAG: &str = "Authentication";
 pub const USERS_TAG: &str = "Users";
+pub const TOKENS_TAG: &str = "Tokens";
+pub const GROUPS_TAG: &str = "Groups";
+pub const SHARES_TAG: &str = "Shares";
 pub const ACCESS_TAG: &str = "Access";
 
 pub const TABLES_TAG: &str = "Tables";
 pub const FIELDS_TAG: &str = "Fields";
 pub const ENTRIES_TAG: &str = "Entries";
+pub const AUDIT_TAG: &str = "Audit";
+pub const TRASH_TAG: &str = "Trash";
+pub const VIEWS_TAG: &str = "Views";
 
 pub const DASHBOARDS_TAG: &str = "Dashboards";
 pub const CHARTS_TAG: &str = "Charts";
 pub const AXES_TAG: &str = "Axes";
+pub const FILTERS_TAG: &str = "Filters";
+pub const CONTROLS_TAG: &str = "Controls";
+
+pub const PUBLIC_TAG: &str = "Public";
 
 pub const SECURITY_SCHEME: &str = "cookieAuth";
+pub const BEARER_SECURITY_SCHEME: &str = "bearerAuth";
 
 pub trait TransformOperationExt {
     fn response_description<const N: u16, R: OperationOutput>(self, description: &str) -> Self;
@@ -75,6 +86,7 @@ pub fn template<'a, R: OperationOutput>(
         op = op
             .response_description::<401, ()>("User is not authenticated")
             .security_requirement(SECURITY_SCHEME)
+            .security_requirement(BEARER_SECURITY_SCHEME)
     }
     op.summary(summary)
         .description(description)
@@ -110,7 +122,17 @@ fn api_docs_transform(api: TransformOpenApi) -> TransformOpenApi {
                 extensions: Default::default(),
             },
         )
+        .security_scheme(
+            BEARER_SECURITY_SCHEME,
+            SecurityScheme::Http {
+                scheme: "bearer".into(),
+                bearer_format: None,
+                description: Some("Personal API token".into()),
+                extensions: Default::default(),
+            },
+        )
         .security_requirement(SECURITY_SCHEME)
+        .security_requirement(BEARER_SECURITY_SCHEME)
 }
 
 fn router() -> Router<AppState> {
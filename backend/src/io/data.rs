@@ -2,214 +2,665 @@
 
 use crate::{
     Id,
+    io::{infer_field_kind, text_to_cell},
     model::{
         Cell,
-        data::{CreateField, CreateTable, CreateTableData, Field, FieldKind, TableData},
+        data::{CreateField, CreateTable, CreateTableData, Entry, Field, FieldKind, TableData},
     },
 };
+use chrono::{DateTime, NaiveDate, NaiveTime, TimeDelta, Utc};
 use itertools::Itertools;
+use rust_decimal::prelude::ToPrimitive;
 use std::{
     collections::{HashMap, HashSet},
     io,
 };
-use umya_spreadsheet::Spreadsheet;
+use umya_spreadsheet::{
+    DataValidation, DataValidationValues, DataValidations, NumberingFormat, SheetStateValues,
+    Spreadsheet, helper::coordinate::string_from_column_index,
+};
+use zip::{ZipArchive, ZipWriter, result::ZipResult, write::SimpleFileOptions};
 
 const EXCEL_IMPORT_TABLE_DESCRIPTION: &str = "This table was imported from Excel";
 const CSV_IMPORT_TABLE_DESCRIPTION: &str = "This table was imported from CSV";
+pub const FIELDS_COLUMNS_MISMATCH: &str =
+    "The number of fields does not match the number of columns";
 
-/// Create the [CreateTableData] DTOs from an Excel spreadsheet.
-pub fn import_table_from_excel(spreadsheet: Spreadsheet) -> Vec<CreateTableData> {
-    let mut tables: Vec<CreateTableData> = Vec::new();
+/// Header of the column holding the entry IDs of exported tables with child tables.
+const ENTRY_ID_COLUMN: &str = "@entry_id";
+/// Prefix of the header of the column holding the parent entry IDs of exported child tables.
+/// It is followed by the name of the sheet or file of the parent table.
+const PARENT_ID_COLUMN_PREFIX: &str = "@parent_id:";
+/// Name of the hidden sheet holding the kinds of the fields of exported Excel workbooks.
+const METADATA_SHEET: &str = "@metadata";
+const DATE_TIME_FORMAT: &str = "yyyy-mm-dd hh:mm:ss";
+const MILLISECONDS_PER_DAY: f64 = 86_400_000.0;
+const EXCEL_MAX_ROW: u32 = 1_048_576;
 
-    for sheet in spreadsheet.get_sheet_collection() {
-        let table = CreateTable {
-            parent_id: None,
-            name: sheet.get_name().to_string(),
-            description: EXCEL_IMPORT_TABLE_DESCRIPTION.to_string(),
+/// Text values of a table read from an imported file, before the kinds of its fields are known.
+#[derive(Debug, PartialEq)]
+pub struct ImportTable {
+    pub table: CreateTable,
+    /// Unique column names taken from the header row.
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+    /// Name of the sheet or file of the parent table, if this table was exported as a child table.
+    pub parent_name: Option<String>,
+    /// Exported entry IDs of the rows. Empty if the file has no entry ID column.
+    pub entry_ids: Vec<Option<Id>>,
+    /// Exported parent entry IDs of the rows. Empty if the file has no parent ID column.
+    pub parent_ids: Vec<Option<Id>>,
+    /// Kinds of the columns restored from the metadata of an exported file,
+    /// used instead of the inferred kinds. Empty if the file has no metadata.
+    pub field_kinds: Vec<Option<FieldKind>>,
+}
+
+impl ImportTable {
+    /// Create the table from the header row and the rows of a file.
+    /// The columns linking exported child tables to their parent are taken out of the rows.
+    fn new(table: CreateTable, headers: Vec<String>, mut rows: Vec<Vec<String>>) -> Self {
+        let mut take_column = |col: usize| {
+            rows.iter_mut()
+                .map(|row| {
+                    (col < row.len())
+                        .then(|| row.remove(col))
+                        .and_then(|value| value.trim().parse().ok())
+                })
+                .collect_vec()
         };
-        let mut fields: Vec<CreateField> = Vec::new();
-        let (columns, rows) = sheet.get_highest_column_and_row();
 
-        let mut fields_names = HashSet::new();
-        for col in 1..=columns {
-            let original_name = sheet.get_value((col, 1));
-            let mut name = original_name.clone();
-            let mut count = 1;
-            while fields_names.contains(&name) {
-                name = format!("{original_name} ({count})");
-                count += 1;
-            }
-            fields_names.insert(name.clone());
+        let mut columns = unique_column_names(headers.iter().map(String::as_str));
+        let mut parent_name = None;
+        let mut parent_ids = Vec::new();
+        if let Some(col) = columns
+            .iter()
+            .position(|name| name.starts_with(PARENT_ID_COLUMN_PREFIX))
+        {
+            let name = columns.remove(col);
+            parent_name = Some(name[PARENT_ID_COLUMN_PREFIX.len()..].to_string());
+            parent_ids = take_column(col);
+        }
+        let mut entry_ids = Vec::new();
+        if let Some(col) = columns.iter().position(|name| name == ENTRY_ID_COLUMN) {
+            columns.remove(col);
+            entry_ids = take_column(col);
+        }
 
-            fields.push(CreateField {
-                name,
-                field_kind: FieldKind::Text { is_required: false },
-            });
+        ImportTable {
+            table,
+            columns,
+            rows,
+            parent_name,
+            entry_ids,
+            parent_ids,
+            field_kinds: Vec::new(),
         }
+    }
+
+    /// Infer one field per column from the values of the column,
+    /// unless the kind of the column is known from the metadata of the file
+    /// and accepts all the values of the column.
+    pub fn infer_fields(&self) -> Vec<CreateField> {
+        self.columns
+            .iter()
+            .enumerate()
+            .map(|(col, name)| {
+                let values = || {
+                    self.rows
+                        .iter()
+                        .map(move |row| row.get(col).map_or("", String::as_str))
+                };
+                CreateField {
+                    name: name.clone(),
+                    field_kind: self
+                        .field_kinds
+                        .get(col)
+                        .cloned()
+                        .flatten()
+                        .filter(|field_kind| {
+                            values().all(|value| text_to_cell(value, field_kind).is_ok())
+                        })
+                        .unwrap_or_else(|| infer_field_kind(values())),
+                }
+            })
+            .collect()
+    }
 
-        let entries: Vec<Vec<Cell>> = (2..=rows)
-            .map(|row| {
-                (1..=columns)
-                    .map(|col| {
-                        let value = sheet.get_value((col, row));
-                        if value.is_empty() {
+    /// Convert the rows to entries of the given fields, which map to the columns in order.
+    /// Returns an error message for each value that cannot be converted,
+    /// prefixed with its row number in the file and its column name.
+    pub fn into_table_data(self, fields: Vec<CreateField>) -> Result<CreateTableData, Vec<String>> {
+        if fields.len() != self.columns.len() {
+            return Err(vec![FIELDS_COLUMNS_MISMATCH.to_string()]);
+        }
+
+        let mut error_messages = Vec::new();
+        let entries = self
+            .rows
+            .into_iter()
+            .enumerate()
+            .map(|(i, row)| {
+                fields
+                    .iter()
+                    .zip(&self.columns)
+                    .enumerate()
+                    .map(|(col, (field, column))| {
+                        let value = row.get(col).map_or("", String::as_str);
+                        text_to_cell(value, &field.field_kind).unwrap_or_else(|message| {
+                            error_messages.push(format!("{}: {column}: {message}", i + 2));
                             Cell::Null
-                        } else {
-                            Cell::String(value)
-                        }
+                        })
                     })
                     .collect()
             })
             .collect();
 
-        tables.push(CreateTableData {
-            table,
+        if !error_messages.is_empty() {
+            return Err(error_messages);
+        }
+
+        Ok(CreateTableData {
+            table: self.table,
             fields,
             entries,
-        });
+            parent_name: self.parent_name,
+            entry_ids: self.entry_ids,
+            parent_ids: self.parent_ids,
+        })
+    }
+
+    /// Convert at most `max_rows` rows using the fields inferred from all the rows.
+    /// Returns the error messages of [ImportTable::into_table_data].
+    pub fn into_inferred_table_data(
+        mut self,
+        max_rows: usize,
+    ) -> Result<CreateTableData, Vec<String>> {
+        let fields = self.infer_fields();
+        self.rows.truncate(max_rows);
+        self.entry_ids.truncate(max_rows);
+        self.parent_ids.truncate(max_rows);
+        self.into_table_data(fields)
+    }
+}
+
+/// Make the column names unique by appending a counter to the repeated names.
+fn unique_column_names<'a>(names: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let mut fields_names = HashSet::new();
+    names
+        .into_iter()
+        .map(|original_name| {
+            let mut name = original_name.to_string();
+            let mut count = 1;
+            while fields_names.contains(&name) {
+                name = format!("{original_name} ({count})");
+                count += 1;
+            }
+            fields_names.insert(name.clone());
+            name
+        })
+        .collect()
+}
+
+/// Read the text values of each sheet of an Excel spreadsheet.
+/// Workbooks exported by [export_table_to_excel] also restore the kinds of the fields
+/// and the values written as dates and percentages.
+pub fn read_table_from_excel(spreadsheet: Spreadsheet) -> Vec<ImportTable> {
+    let metadata = read_excel_metadata(&spreadsheet);
+
+    spreadsheet
+        .get_sheet_collection()
+        .iter()
+        .filter(|sheet| sheet.get_name() != METADATA_SHEET)
+        .map(|sheet| {
+            let (columns, rows) = sheet.get_highest_column_and_row();
+            let field_kinds = metadata.get(sheet.get_name());
+            let field_kind =
+                |column: &str| field_kinds.and_then(|field_kinds| field_kinds.get(column));
+
+            let headers = (1..=columns)
+                .map(|col| sheet.get_value((col, 1)))
+                .collect_vec();
+            let rows = (2..=rows)
+                .map(|row| {
+                    headers
+                        .iter()
+                        .zip(1..)
+                        .map(|(header, col)| {
+                            excel_value_to_text(sheet.get_value((col, row)), field_kind(header))
+                        })
+                        .collect()
+                })
+                .collect();
+
+            let mut import_table = ImportTable::new(
+                CreateTable {
+                    parent_id: None,
+                    name: sheet.get_name().to_string(),
+                    description: EXCEL_IMPORT_TABLE_DESCRIPTION.to_string(),
+                },
+                headers,
+                rows,
+            );
+            import_table.field_kinds = import_table
+                .columns
+                .iter()
+                .map(|column| field_kind(column).cloned())
+                .collect();
+            import_table
+        })
+        .collect()
+}
+
+/// Read the field kinds of the metadata sheet by sheet name and field name.
+/// Relation and formula fields cannot be imported and are left out.
+fn read_excel_metadata(spreadsheet: &Spreadsheet) -> HashMap<String, HashMap<String, FieldKind>> {
+    let mut metadata: HashMap<String, HashMap<String, FieldKind>> = HashMap::new();
+    let Some(sheet) = spreadsheet.get_sheet_by_name(METADATA_SHEET) else {
+        return metadata;
+    };
+
+    for row in 2..=sheet.get_highest_row() {
+        let Ok(field_kind) = serde_json::from_str(&sheet.get_value((3, row))) else {
+            continue;
+        };
+        if let FieldKind::Relation { .. } | FieldKind::Formula { .. } = field_kind {
+            continue;
+        }
+        metadata
+            .entry(sheet.get_value((1, row)))
+            .or_default()
+            .insert(sheet.get_value((2, row)), field_kind);
     }
+    metadata
+}
+
+/// Convert the value of a cell written by [export_table_to_excel] back to the text
+/// expected for the field kind.
+fn excel_value_to_text(value: String, field_kind: Option<&FieldKind>) -> String {
+    match field_kind {
+        Some(FieldKind::DateTime { .. }) => value
+            .parse()
+            .ok()
+            .and_then(excel_serial_to_date_time)
+            .map_or(value, |date_time| date_time.to_rfc3339()),
+        Some(FieldKind::Progress { total_steps }) => value.parse::<f64>().map_or(value, |ratio| {
+            ((ratio * *total_steps as f64).round() as i64).to_string()
+        }),
+        _ => value,
+    }
+}
 
-    tables
+/// The day before the first day of the Excel 1900 date system,
+/// shifted by a day to account for its leap year bug.
+fn excel_epoch() -> DateTime<Utc> {
+    NaiveDate::from_ymd_opt(1899, 12, 30)
+        .unwrap()
+        .and_time(NaiveTime::MIN)
+        .and_utc()
 }
 
-/// Convert a [TableData] DTO into the Excel spreadsheet. Currently, child tables are ignored.
-pub fn export_table_to_excel(
+/// Convert a date and time to the number of days since the Excel epoch.
+fn date_time_to_excel_serial(date_time: DateTime<Utc>) -> f64 {
+    (date_time - excel_epoch()).num_milliseconds() as f64 / MILLISECONDS_PER_DAY
+}
+
+/// Convert a number of days since the Excel epoch to a date and time,
+/// rounded to the millisecond.
+fn excel_serial_to_date_time(serial: f64) -> Option<DateTime<Utc>> {
+    if !serial.is_finite() {
+        return None;
+    }
+    let milliseconds = TimeDelta::try_milliseconds((serial * MILLISECONDS_PER_DAY).round() as i64)?;
+    excel_epoch().checked_add_signed(milliseconds)
+}
+
+/// Convert a [TableData] DTO into the Excel spreadsheet, writing each child table to its own sheet.
+/// Tables with child tables get an entry ID column and child tables get a column
+/// referencing their parent entries, so that the hierarchy is restored on import.
+///
+/// Dates, money and progress are written as formatted numbers and enumerations get
+/// a dropdown list of their values. The kinds of the fields are written to a hidden
+/// metadata sheet, so that they are restored on import.
+pub fn export_table_to_excel(spreadsheet: &mut Spreadsheet, table_data: TableData) {
+    export_table_to_excel_sheet(spreadsheet, table_data, None);
+}
+
+fn export_table_to_excel_sheet(
     spreadsheet: &mut Spreadsheet,
     TableData {
         table,
         fields,
         entries,
-        ..
+        children,
     }: TableData,
+    parent_sheet_name: Option<&str>,
 ) {
     let mut sheet_name = table.name.clone();
 
     let mut i = 1;
-    let sheet = loop {
-        match spreadsheet.new_sheet(&sheet_name) {
-            Ok(sheet) => break sheet, // Exit the loop and return the sheet
-            Err(_) => {
-                sheet_name = format!("{} ({i})", table.name);
-                i += 1;
-            }
-        }
-    };
+    while spreadsheet.new_sheet(&sheet_name).is_err() {
+        sheet_name = format!("{} ({i})", table.name);
+        i += 1;
+    }
 
-    let fields: HashMap<Id, Field> = fields
+    let fields = fields
         .into_iter()
-        .map(|field| (field.field_id, field))
+        .sorted_by_key(|field| field.ordering)
+        .collect_vec();
+    let metadata_rows = write_excel_metadata(spreadsheet, &sheet_name, &fields);
+    let sheet = spreadsheet.get_sheet_by_name_mut(&sheet_name).unwrap();
+
+    let has_children = !children.is_empty();
+    let headers = hierarchy_headers(has_children, parent_sheet_name);
+    let offset = headers.len() as u32;
+    for (col, header) in headers.into_iter().enumerate() {
+        sheet
+            .get_cell_mut((col as u32 + 1, 1))
+            .set_value_string(header);
+    }
+
+    let fields: HashMap<Id, (u32, Field)> = fields
+        .into_iter()
+        .enumerate()
+        .map(|(col, field)| (field.field_id, (offset + col as u32 + 1, field)))
         .collect();
 
-    for field in fields.values() {
-        let col = field.ordering as u32 + 1;
+    for (col, field) in fields.values() {
         sheet
-            .get_cell_mut((col, 0))
+            .get_cell_mut((*col, 1))
             .set_value_string(field.name.clone());
+
+        // Offer the values of enumerations as a dropdown list, referencing the metadata sheet.
+        if let FieldKind::Enumeration { values, .. } = &field.field_kind.0
+            && !values.is_empty()
+        {
+            let column = string_from_column_index(col);
+            let metadata_row = metadata_rows[&field.field_id];
+            let last_value_column = string_from_column_index(&(values.len() as u32 + 3));
+            let mut data_validation = DataValidation::default();
+            data_validation
+                .set_type(DataValidationValues::List)
+                .set_allow_blank(!field.field_kind.is_required())
+                .set_show_error_message(true)
+                .set_formula1(format!(
+                    "'{METADATA_SHEET}'!$D${metadata_row}:${last_value_column}${metadata_row}"
+                ))
+                .get_sequence_of_references_mut()
+                .set_sqref(format!("{column}2:{column}{EXCEL_MAX_ROW}"));
+            match sheet.get_data_validations_mut() {
+                Some(data_validations) => {
+                    data_validations.add_data_validation_list(data_validation);
+                }
+                None => {
+                    let mut data_validations = DataValidations::default();
+                    data_validations.add_data_validation_list(data_validation);
+                    sheet.set_data_validations(data_validations);
+                }
+            }
+        }
     }
 
     for (row, entry) in entries.into_iter().enumerate() {
         let row = row as u32 + 2;
+        for (col, id) in hierarchy_values(&entry, has_children, parent_sheet_name.is_some())
+            .into_iter()
+            .enumerate()
+        {
+            sheet
+                .get_cell_mut((col as u32 + 1, row))
+                .set_value_number(id as f64);
+        }
+
         for (field_id, cell) in entry.cells.into_iter() {
             if let Cell::Null = cell {
                 continue;
             }
 
-            let field = fields.get(&field_id).unwrap();
-            let col = field.ordering as u32 + 1;
-            let sheet_cell = sheet.get_cell_mut((col, row));
-
-            match cell {
-                Cell::String(v) => sheet_cell.set_value_string(v),
-                Cell::Integer(v) => {
-                    if let FieldKind::Enumeration { values, .. } = &field.field_kind.0 {
-                        sheet_cell.set_value_string(values.get(&v).unwrap())
-                    } else {
-                        sheet_cell.set_value_number(v as f64)
-                    }
+            let (col, field) = fields.get(&field_id).unwrap();
+            let sheet_cell = sheet.get_cell_mut((*col, row));
+
+            match (cell, &field.field_kind.0) {
+                (Cell::String(v), _) => {
+                    sheet_cell.set_value_string(v);
                 }
-                Cell::Float(v) => sheet_cell.set_value_number(v),
-                Cell::Decimal(v) => sheet_cell.set_value_string(v.to_string()),
-                Cell::DateTime(v) => sheet_cell.set_value_string(v.to_rfc3339()),
-                Cell::Boolean(v) => sheet_cell.set_value_bool(v),
-                Cell::Null => unreachable!(),
+                (Cell::Integer(v), FieldKind::Enumeration { values, .. }) => {
+                    sheet_cell.set_value_string(values.get(&v).unwrap());
+                }
+                (Cell::Integer(v), FieldKind::Progress { total_steps }) if *total_steps > 0 => {
+                    sheet_cell.set_value_number(v as f64 / *total_steps as f64);
+                    set_number_format(sheet_cell, NumberingFormat::FORMAT_PERCENTAGE);
+                }
+                (Cell::Integer(v), _) => {
+                    sheet_cell.set_value_number(v as f64);
+                }
+                (Cell::Float(v), _) => {
+                    sheet_cell.set_value_number(v);
+                }
+                (Cell::Decimal(v), FieldKind::Money { .. }) => {
+                    sheet_cell.set_value_number(v.to_f64().unwrap());
+                    set_number_format(sheet_cell, NumberingFormat::FORMAT_CURRENCY_USD_SIMPLE);
+                }
+                (Cell::Decimal(v), _) => {
+                    sheet_cell.set_value_string(v.to_string());
+                }
+                (Cell::DateTime(v), _) => {
+                    sheet_cell.set_value_number(date_time_to_excel_serial(v));
+                    set_number_format(sheet_cell, DATE_TIME_FORMAT);
+                }
+                (Cell::Boolean(v), _) => {
+                    sheet_cell.set_value_bool(v);
+                }
+                (Cell::EntryIds(v), _) => {
+                    sheet_cell.set_value_string(v.iter().join(", "));
+                }
+                (Cell::Null, _) => unreachable!(),
             };
         }
     }
+
+    for child in children {
+        export_table_to_excel_sheet(spreadsheet, child, Some(&sheet_name));
+    }
 }
 
-/// Create a [CreateTableData] DTO from a CSV file.
-pub fn import_table_from_csv<R>(
+/// Append the kinds of the fields of a sheet to the hidden metadata sheet,
+/// creating it if needed, followed by the values of enumerations.
+/// Return the metadata row of each field.
+fn write_excel_metadata(
+    spreadsheet: &mut Spreadsheet,
+    sheet_name: &str,
+    fields: &[Field],
+) -> HashMap<Id, u32> {
+    if spreadsheet.get_sheet_by_name(METADATA_SHEET).is_none() {
+        let sheet = spreadsheet.new_sheet(METADATA_SHEET).unwrap();
+        sheet.set_state(SheetStateValues::Hidden);
+        for (header, col) in ["sheet", "field", "field_kind", "values"]
+            .into_iter()
+            .zip(1..)
+        {
+            sheet.get_cell_mut((col, 1)).set_value_string(header);
+        }
+    }
+    let sheet = spreadsheet.get_sheet_by_name_mut(METADATA_SHEET).unwrap();
+
+    let mut row = sheet.get_highest_row();
+    fields
+        .iter()
+        .map(|field| {
+            row += 1;
+            sheet.get_cell_mut((1, row)).set_value_string(sheet_name);
+            sheet
+                .get_cell_mut((2, row))
+                .set_value_string(field.name.clone());
+            sheet
+                .get_cell_mut((3, row))
+                .set_value_string(serde_json::to_string(&field.field_kind.0).unwrap());
+            if let FieldKind::Enumeration { values, .. } = &field.field_kind.0 {
+                for ((_, value), col) in values.iter().sorted_by_key(|(k, _)| **k).zip(4..) {
+                    sheet.get_cell_mut((col, row)).set_value_string(value);
+                }
+            }
+            (field.field_id, row)
+        })
+        .collect()
+}
+
+fn set_number_format(cell: &mut umya_spreadsheet::Cell, format_code: &str) {
+    cell.get_style_mut()
+        .get_number_format_mut()
+        .set_format_code(format_code);
+}
+
+/// Headers of the columns linking the entries of exported tables to their parent entries.
+fn hierarchy_headers(has_children: bool, parent_name: Option<&str>) -> Vec<String> {
+    parent_name
+        .map(|name| format!("{PARENT_ID_COLUMN_PREFIX}{name}"))
+        .into_iter()
+        .chain(has_children.then(|| ENTRY_ID_COLUMN.to_string()))
+        .collect()
+}
+
+/// Values of the columns named by [hierarchy_headers] for an entry.
+fn hierarchy_values(entry: &Entry, has_children: bool, has_parent: bool) -> Vec<Id> {
+    entry
+        .parent_id
+        .filter(|_| has_parent)
+        .into_iter()
+        .chain(has_children.then_some(entry.entry_id))
+        .collect()
+}
+
+/// Read the text values of a CSV file.
+pub fn read_table_from_csv<R>(
     mut csv_reader: csv::Reader<R>,
     name: &str,
-) -> csv::Result<CreateTableData>
+) -> csv::Result<ImportTable>
 where
-    R: std::io::Read,
+    R: io::Read,
 {
-    let table = CreateTable {
-        parent_id: None,
-        name: name.to_string(),
-        description: CSV_IMPORT_TABLE_DESCRIPTION.to_string(),
-    };
+    let headers = csv_reader.headers()?.iter().map(str::to_string).collect();
 
-    let mut fields: Vec<CreateField> = Vec::new();
+    let rows = csv_reader
+        .records()
+        .map(|record| Ok(record?.iter().map(str::to_string).collect()))
+        .collect::<csv::Result<_>>()?;
 
-    let mut fields_names = HashSet::new();
-    for original_name in csv_reader.headers()? {
-        let mut name = original_name.to_string();
-        let mut count = 1;
-        while fields_names.contains(&name) {
-            name = format!("{original_name} ({count})");
-            count += 1;
-        }
-        fields_names.insert(name.clone());
+    Ok(ImportTable::new(
+        CreateTable {
+            parent_id: None,
+            name: name.to_string(),
+            description: CSV_IMPORT_TABLE_DESCRIPTION.to_string(),
+        },
+        headers,
+        rows,
+    ))
+}
 
-        fields.push(CreateField {
-            name,
-            field_kind: FieldKind::Text { is_required: false },
-        });
+/// Read the text values of each CSV file of a ZIP archive.
+/// Tables are named after their file name without the extension.
+pub fn read_table_from_csv_zip<R>(reader: R) -> ZipResult<Vec<ImportTable>>
+where
+    R: io::Read + io::Seek,
+{
+    let mut archive = ZipArchive::new(reader)?;
+    let mut tables = Vec::new();
+    for i in 0..archive.len() {
+        let file = archive.by_index(i)?;
+        let Some(name) = file.name().strip_suffix(".csv").map(str::to_string) else {
+            continue;
+        };
+        tables.push(
+            read_table_from_csv(csv::Reader::from_reader(file), &name).map_err(io::Error::from)?,
+        );
     }
+    Ok(tables)
+}
 
-    let entries: Vec<Vec<Cell>> = csv_reader
-        .records()
-        .map(|record| {
-            Ok(record?
-                .into_iter()
-                .map(|value| {
-                    if value.is_empty() {
-                        Cell::Null
-                    } else {
-                        Cell::String(value.to_string())
-                    }
-                })
-                .collect())
-        })
-        .collect::<csv::Result<_>>()?;
+/// Convert a [TableData] DTO into the CSV file. Child tables are ignored,
+/// use [export_table_to_csv_zip] to export them as well.
+pub fn export_table_to_csv<W>(
+    csv_writer: csv::Writer<W>,
+    TableData {
+        fields, entries, ..
+    }: TableData,
+) -> csv::Result<()>
+where
+    W: io::Write,
+{
+    write_csv(csv_writer, fields, entries, false, None)
+}
 
-    Ok(CreateTableData {
-        table,
-        fields,
-        entries,
-    })
+/// Convert a [TableData] DTO into a ZIP archive with one CSV file per table,
+/// including the child tables.
+/// Tables with child tables get an entry ID column and child tables get a column
+/// referencing their parent entries, so that the hierarchy is restored on import.
+pub fn export_table_to_csv_zip<W>(writer: W, table_data: TableData) -> ZipResult<()>
+where
+    W: io::Write + io::Seek,
+{
+    let mut zip_writer = ZipWriter::new(writer);
+    write_csv_zip_file(&mut zip_writer, &mut HashSet::new(), table_data, None)?;
+    zip_writer.finish()?;
+    Ok(())
 }
 
-/// Convert a [TableData] DTO into the CSV file. Currently, child tables are ignored.
-pub fn export_table_to_csv<W>(
-    mut csv_writer: csv::Writer<W>,
+fn write_csv_zip_file<W>(
+    zip_writer: &mut ZipWriter<W>,
+    file_names: &mut HashSet<String>,
     TableData {
-        table: _,
+        table,
         fields,
         entries,
-        ..
+        children,
     }: TableData,
+    parent_name: Option<&str>,
+) -> ZipResult<()>
+where
+    W: io::Write + io::Seek,
+{
+    let base_name = table.name.replace(['/', '\\'], "_");
+    let mut name = base_name.clone();
+    let mut i = 1;
+    while !file_names.insert(name.clone()) {
+        name = format!("{base_name} ({i})");
+        i += 1;
+    }
+
+    zip_writer.start_file(format!("{name}.csv"), SimpleFileOptions::default())?;
+    write_csv(
+        csv::Writer::from_writer(&mut *zip_writer),
+        fields,
+        entries,
+        !children.is_empty(),
+        parent_name,
+    )
+    .map_err(io::Error::from)?;
+
+    for child in children {
+        write_csv_zip_file(zip_writer, file_names, child, Some(&name))?;
+    }
+    Ok(())
+}
+
+fn write_csv<W>(
+    mut csv_writer: csv::Writer<W>,
+    fields: Vec<Field>,
+    entries: Vec<Entry>,
+    has_children: bool,
+    parent_name: Option<&str>,
 ) -> csv::Result<()>
 where
     W: io::Write,
 {
     csv_writer.write_record(
-        fields
-            .iter()
-            .sorted_by_key(|field| field.ordering)
-            .map(|field| field.name.clone()),
+        hierarchy_headers(has_children, parent_name)
+            .into_iter()
+            .chain(
+                fields
+                    .iter()
+                    .sorted_by_key(|field| field.ordering)
+                    .map(|field| field.name.clone()),
+            ),
     )?;
 
     let fields: HashMap<_, _> = fields
@@ -218,37 +669,43 @@ where
         .collect();
 
     for entry in entries {
+        let ids = hierarchy_values(&entry, has_children, parent_name.is_some());
         csv_writer.write_record(
-            entry
-                .cells
-                .into_iter()
-                .sorted_by_key(|(entry_id, _)| fields.get(entry_id).unwrap().ordering)
-                .map(|(entry_id, cell)| match cell {
-                    Cell::Integer(v) => {
-                        if let FieldKind::Enumeration { values, .. } =
-                            &fields.get(&entry_id).unwrap().field_kind.0
-                        {
-                            values.get(&v).unwrap().clone()
-                        } else {
-                            v.to_string()
+            ids.into_iter().map(|id| id.to_string()).chain(
+                entry
+                    .cells
+                    .into_iter()
+                    .sorted_by_key(|(entry_id, _)| fields.get(entry_id).unwrap().ordering)
+                    .map(|(entry_id, cell)| match cell {
+                        Cell::Integer(v) => {
+                            if let FieldKind::Enumeration { values, .. } =
+                                &fields.get(&entry_id).unwrap().field_kind.0
+                            {
+                                values.get(&v).unwrap().clone()
+                            } else {
+                                v.to_string()
+                            }
                         }
-                    }
-                    Cell::Float(v) => v.to_string(),
-                    Cell::Decimal(v) => v.to_string(),
-                    Cell::Boolean(v) => v.to_string(),
-                    Cell::DateTime(v) => v.to_rfc3339(),
-                    Cell::String(v) => v,
-                    Cell::Null => String::new(),
-                }),
+                        Cell::Float(v) => v.to_string(),
+                        Cell::Decimal(v) => v.to_string(),
+                        Cell::Boolean(v) => v.to_string(),
+                        Cell::DateTime(v) => v.to_rfc3339(),
+                        Cell::String(v) => v,
+                        Cell::EntryIds(v) => v.iter().join(", "),
+                        Cell::Null => String::new(),
+                    }),
+            ),
         )?;
     }
 
+    csv_writer.flush()?;
     Ok(())
 }
 
 #[cfg(test)]
 #[cfg_attr(coverage_nightly, coverage(off))]
 mod test {
+    use super::{FIELDS_COLUMNS_MISMATCH, ImportTable};
     use crate::{
         error::IntoAnyhow,
         model::{
@@ -261,16 +718,21 @@ mod test {
     };
     use anyhow::{Ok, Result};
     use chrono::Utc;
+    use itertools::Itertools;
+    use rust_decimal::Decimal;
     use sqlx::types::Json;
     use std::collections::HashMap;
-    use umya_spreadsheet::{new_file_empty_worksheet, reader, writer};
+    use umya_spreadsheet::{SheetStateValues, new_file_empty_worksheet, reader, writer};
 
     #[test]
     fn import_table_from_excel() -> Result<()> {
         let path = std::path::Path::new("./testing/import.xlsx");
         let spreadsheet = reader::xlsx::read(path)?;
 
-        let test_data = crate::io::import_table_from_excel(spreadsheet);
+        let test_data = crate::io::read_table_from_excel(spreadsheet)
+            .into_iter()
+            .map(|import_table| import_table.into_inferred_table_data(usize::MAX).unwrap())
+            .collect_vec();
 
         let base_data = [CreateTableData {
             table: CreateTable {
@@ -288,6 +750,9 @@ mod test {
                     field_kind: crate::model::data::FieldKind::Text { is_required: false },
                 },
             ],
+            parent_name: None,
+            entry_ids: Vec::new(),
+            parent_ids: Vec::new(),
             entries: vec![
                 vec![
                     Cell::String("c1".to_string()),
@@ -339,7 +804,9 @@ mod test {
         let path = std::path::Path::new("./testing/import.csv");
         let csv = csv::Reader::from_path(path)?;
 
-        let test_data = crate::io::import_table_from_csv(csv, "Sheet1")?;
+        let test_data = crate::io::read_table_from_csv(csv, "Sheet1")?
+            .into_inferred_table_data(usize::MAX)
+            .unwrap();
 
         let base_data = CreateTableData {
             table: CreateTable {
@@ -357,6 +824,9 @@ mod test {
                     field_kind: crate::model::data::FieldKind::Text { is_required: false },
                 },
             ],
+            parent_name: None,
+            entry_ids: Vec::new(),
+            parent_ids: Vec::new(),
             entries: vec![
                 vec![
                     Cell::String("c1".to_string()),
@@ -394,6 +864,96 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn into_table_data() {
+        let import_table = || {
+            ImportTable::new(
+                CreateTable {
+                    parent_id: None,
+                    name: "test".into(),
+                    description: "".into(),
+                },
+                vec!["a".into(), "b".into()],
+                vec![vec!["1".into(), "x".into()], vec!["y".into(), "2".into()]],
+            )
+        };
+        let integer_field = |name: &str| CreateField {
+            name: name.into(),
+            field_kind: FieldKind::Integer {
+                is_required: false,
+                range_start: None,
+                range_end: None,
+            },
+        };
+
+        assert_eq!(
+            import_table().into_table_data(vec![integer_field("a")]),
+            Err(vec![FIELDS_COLUMNS_MISMATCH.to_string()])
+        );
+        assert_eq!(
+            import_table()
+                .into_table_data(vec![integer_field("a"), integer_field("b")])
+                .unwrap_err()
+                .len(),
+            2
+        );
+        assert_eq!(
+            import_table()
+                .into_table_data(vec![
+                    CreateField {
+                        name: "a".into(),
+                        field_kind: FieldKind::Text { is_required: false },
+                    },
+                    CreateField {
+                        name: "b".into(),
+                        field_kind: FieldKind::Text { is_required: false },
+                    },
+                ])
+                .unwrap()
+                .entries,
+            vec![
+                vec![Cell::String("1".into()), Cell::String("x".into())],
+                vec![Cell::String("y".into()), Cell::String("2".into())],
+            ]
+        );
+    }
+
+    #[test]
+    fn infer_fields_rejected_by_metadata() {
+        let mut import_table = ImportTable::new(
+            CreateTable {
+                parent_id: None,
+                name: "test".into(),
+                description: "".into(),
+            },
+            vec!["a".into(), "b".into()],
+            vec![vec!["1".into(), "x".into()], vec!["2".into(), "y".into()]],
+        );
+        let integer = FieldKind::Integer {
+            is_required: false,
+            range_start: None,
+            range_end: Some(1),
+        };
+        import_table.field_kinds = vec![Some(integer.clone()), Some(integer)];
+
+        let table_data = import_table.into_inferred_table_data(usize::MAX).unwrap();
+        assert_eq!(
+            table_data
+                .fields
+                .into_iter()
+                .map(|field| field.field_kind)
+                .collect_vec(),
+            [
+                FieldKind::Integer {
+                    is_required: false,
+                    range_start: None,
+                    range_end: None,
+                },
+                FieldKind::Text { is_required: false },
+            ]
+        );
+    }
+
     #[test]
     fn export_table_to_excel() -> Result<()> {
         // Base data gen
@@ -466,6 +1026,120 @@ mod test {
         // without manually checking. So if it wrote out the file, go check it.
     }
 
+    #[test]
+    fn excel_round_trip_restores_field_kinds() -> Result<()> {
+        let now = Utc::now();
+        let table_id = 123;
+        let field_kinds = [
+            FieldKind::DateTime {
+                is_required: true,
+                range_start: None,
+                range_end: None,
+            },
+            FieldKind::Money {
+                is_required: false,
+                range_start: Some(Decimal::ZERO),
+                range_end: None,
+            },
+            FieldKind::Progress { total_steps: 4 },
+            FieldKind::Enumeration {
+                is_required: false,
+                values: HashMap::from([(0, "Low".into()), (1, "High".into())]),
+                default_value: 0,
+            },
+            FieldKind::Checkbox,
+        ];
+        let fields = field_kinds
+            .iter()
+            .enumerate()
+            .map(|(i, field_kind)| Field {
+                field_id: i as i32,
+                name: format!("Field {i}"),
+                table_id,
+                ordering: i as i32,
+                field_kind: Json(field_kind.clone()),
+                created_at: now,
+                updated_at: None,
+            })
+            .collect_vec();
+        let cells = vec![
+            Cell::DateTime("2024-05-06T07:08:09Z".parse()?),
+            Cell::Decimal(Decimal::new(1250, 2)),
+            Cell::Integer(3),
+            Cell::Integer(1),
+            Cell::Boolean(true),
+        ];
+        let table_data = TableData {
+            table: Table {
+                table_id,
+                name: "Kinds".to_string(),
+                description: "".to_string(),
+                parent_id: None,
+                created_at: now,
+                updated_at: None,
+            },
+            fields,
+            entries: vec![Entry {
+                entry_id: 1,
+                parent_id: None,
+                created_at: now,
+                updated_at: None,
+                cells: cells
+                    .iter()
+                    .cloned()
+                    .enumerate()
+                    .map(|(i, cell)| (i as i32, cell))
+                    .collect(),
+            }],
+            children: Vec::new(),
+        };
+
+        let mut spreadsheet = new_file_empty_worksheet();
+        crate::io::export_table_to_excel(&mut spreadsheet, table_data);
+        let mut buffer = std::io::Cursor::new(Vec::new());
+        writer::xlsx::write_writer(&spreadsheet, &mut buffer)?;
+        let spreadsheet =
+            reader::xlsx::read_reader(std::io::Cursor::new(buffer.into_inner()), true)?;
+
+        let sheet = spreadsheet.get_sheet_by_name("Kinds").unwrap();
+        assert_eq!(sheet.get_formatted_value("C2"), "75%");
+        assert_eq!(
+            sheet
+                .get_data_validations()
+                .unwrap()
+                .get_data_validation_list()
+                .len(),
+            1
+        );
+        assert!(matches!(
+            spreadsheet
+                .get_sheet_by_name("@metadata")
+                .unwrap()
+                .get_state(),
+            SheetStateValues::Hidden
+        ));
+
+        let import_tables = crate::io::read_table_from_excel(spreadsheet);
+        assert_eq!(import_tables.len(), 1);
+        let table_data = import_tables
+            .into_iter()
+            .next()
+            .unwrap()
+            .into_inferred_table_data(usize::MAX)
+            .unwrap();
+
+        assert_eq!(
+            table_data
+                .fields
+                .into_iter()
+                .map(|field| field.field_kind)
+                .collect_vec(),
+            field_kinds
+        );
+        assert_eq!(table_data.entries, [cells]);
+        Ok(())
+    }
+
     #[test]
     fn export_table_to_csv() -> Result<()> {
         // Base data gen
@@ -0,0 +1,375 @@
+//! Module for inferring field kinds from the text values of imported files
+//! and converting those values to [Cell]s.
+
+use crate::model::{Cell, data::FieldKind};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use itertools::Itertools;
+use rust_decimal::Decimal;
+use std::{collections::HashMap, str::FromStr};
+
+pub const IS_REQUIRED: &str = "A value is required";
+pub const OUT_OF_RANGE: &str = "Value is out of range";
+pub const ENUMERATION_VALUE_MISSING: &str = "Enumeration value does not exist";
+const INVALID_VALUE: &str = "Value cannot be converted to the field kind";
+
+/// Text columns with at most this many distinct values can become enumerations.
+const MAX_ENUMERATION_VALUES: usize = 10;
+
+const CURRENCY_SYMBOLS: [char; 5] = ['$', '€', '£', '¥', '₩'];
+const TRUE_VALUES: [&str; 2] = ["true", "yes"];
+const FALSE_VALUES: [&str; 2] = ["false", "no"];
+const DATE_TIME_FORMATS: [&str; 4] = [
+    "%Y-%m-%d %H:%M:%S",
+    "%Y-%m-%dT%H:%M:%S",
+    "%Y-%m-%d %H:%M",
+    "%m/%d/%Y %H:%M",
+];
+const DATE_FORMATS: [&str; 4] = ["%Y-%m-%d", "%Y/%m/%d", "%m/%d/%Y", "%d.%m.%Y"];
+
+/// Infer the kind of a field from the text values of its column.
+/// Empty values are ignored and columns without any value are inferred as text.
+pub fn infer_field_kind<'a>(values: impl IntoIterator<Item = &'a str>) -> FieldKind {
+    let values = values
+        .into_iter()
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .collect_vec();
+
+    if values.is_empty() {
+        return FieldKind::Text { is_required: false };
+    }
+
+    if values.iter().all(|value| parse_bool(value).is_some()) {
+        FieldKind::Checkbox
+    } else if values.iter().all(|value| value.parse::<i64>().is_ok()) {
+        FieldKind::Integer {
+            is_required: false,
+            range_start: None,
+            range_end: None,
+        }
+    } else if values.iter().all(|value| parse_float(value).is_some()) {
+        FieldKind::Float {
+            is_required: false,
+            range_start: None,
+            range_end: None,
+        }
+    } else if values.iter().any(|value| value.contains(CURRENCY_SYMBOLS))
+        && values.iter().all(|value| parse_money(value).is_some())
+    {
+        FieldKind::Money {
+            is_required: false,
+            range_start: None,
+            range_end: None,
+        }
+    } else if values.iter().all(|value| parse_date_time(value).is_some()) {
+        FieldKind::DateTime {
+            is_required: false,
+            range_start: None,
+            range_end: None,
+        }
+    } else if values.iter().all(|value| is_web_link(value)) {
+        FieldKind::WebLink { is_required: false }
+    } else {
+        let distinct_count = values.iter().unique().count();
+        if distinct_count <= MAX_ENUMERATION_VALUES && distinct_count * 2 <= values.len() {
+            enumeration_from_values(values)
+        } else {
+            FieldKind::Text { is_required: false }
+        }
+    }
+}
+
+/// Convert the text value of an imported cell to a [Cell] of the given field kind.
+pub fn text_to_cell(value: &str, field_kind: &FieldKind) -> Result<Cell, &'static str> {
+    let value = value.trim();
+
+    if value.is_empty() {
+        return match field_kind {
+            FieldKind::Text { is_required }
+            | FieldKind::Integer { is_required, .. }
+            | FieldKind::Float { is_required, .. }
+            | FieldKind::Money { is_required, .. }
+            | FieldKind::DateTime { is_required, .. }
+            | FieldKind::WebLink { is_required }
+            | FieldKind::Enumeration { is_required, .. }
+            | FieldKind::Relation { is_required, .. } => {
+                if *is_required {
+                    Err(IS_REQUIRED)
+                } else {
+                    Ok(Cell::Null)
+                }
+            }
+            FieldKind::Progress { .. } => Ok(Cell::Integer(0)),
+            FieldKind::Checkbox => Ok(Cell::Boolean(false)),
+            FieldKind::Formula { .. } => Ok(Cell::Null),
+        };
+    }
+
+    match field_kind {
+        FieldKind::Text { .. } => Ok(Cell::String(value.to_string())),
+        FieldKind::Integer {
+            range_start,
+            range_end,
+            ..
+        } => {
+            let value = value.parse::<i64>().map_err(|_| INVALID_VALUE)?;
+            check_range(&value, range_start.as_ref(), range_end.as_ref())?;
+            Ok(Cell::Integer(value))
+        }
+        FieldKind::Float {
+            range_start,
+            range_end,
+            ..
+        } => {
+            let value = parse_float(value).ok_or(INVALID_VALUE)?;
+            check_range(&value, range_start.as_ref(), range_end.as_ref())?;
+            Ok(Cell::Float(value))
+        }
+        FieldKind::Money {
+            range_start,
+            range_end,
+            ..
+        } => {
+            let value = parse_money(value).ok_or(INVALID_VALUE)?;
+            check_range(&value, range_start.as_ref(), range_end.as_ref())?;
+            Ok(Cell::Decimal(value))
+        }
+        FieldKind::Progress { total_steps } => {
+            let value = value.parse::<i64>().map_err(|_| INVALID_VALUE)?;
+            check_range(&value, Some(&0), Some(total_steps))?;
+            Ok(Cell::Integer(value))
+        }
+        FieldKind::DateTime {
+            range_start,
+            range_end,
+            ..
+        } => {
+            let value = parse_date_time(value).ok_or(INVALID_VALUE)?;
+            check_range(&value, range_start.as_ref(), range_end.as_ref())?;
+            Ok(Cell::DateTime(value))
+        }
+        FieldKind::WebLink { .. } => {
+            if is_web_link(value) {
+                Ok(Cell::String(value.to_string()))
+            } else {
+                Err(INVALID_VALUE)
+            }
+        }
+        FieldKind::Checkbox => parse_bool(value).map(Cell::Boolean).ok_or(INVALID_VALUE),
+        FieldKind::Enumeration { values, .. } => values
+            .iter()
+            .find(|(_, v)| v.as_str() == value)
+            .map(|(k, _)| Cell::Integer(*k))
+            .ok_or(ENUMERATION_VALUE_MISSING),
+        FieldKind::Relation { .. } => Err(INVALID_VALUE),
+        FieldKind::Formula { .. } => Ok(Cell::Null),
+    }
+}
+
+/// Build an enumeration field kind whose values are the distinct values of the column.
+fn enumeration_from_values<'a>(values: impl IntoIterator<Item = &'a str>) -> FieldKind {
+    let values: HashMap<i64, String> = values
+        .into_iter()
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .unique()
+        .enumerate()
+        .map(|(i, value)| (i as i64, value.to_string()))
+        .collect();
+    FieldKind::Enumeration {
+        is_required: false,
+        values,
+        default_value: 0,
+    }
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    let value = value.to_lowercase();
+    if TRUE_VALUES.contains(&value.as_str()) {
+        Some(true)
+    } else if FALSE_VALUES.contains(&value.as_str()) {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+/// Parse a finite float, rejecting the special values accepted by [f64::from_str].
+fn parse_float(value: &str) -> Option<f64> {
+    if !value
+        .chars()
+        .all(|c| c.is_ascii_digit() || matches!(c, '.' | '-' | '+' | 'e' | 'E'))
+    {
+        return None;
+    }
+    value.parse::<f64>().ok().filter(|value| value.is_finite())
+}
+
+/// Parse an amount of money with an optional currency symbol before or after the number
+/// and optional thousands separators, such as `$1,250.50` or `-12 €`.
+fn parse_money(value: &str) -> Option<Decimal> {
+    let (negative, value) = match value.strip_prefix('-') {
+        Some(value) => (true, value),
+        None => (false, value),
+    };
+    let value = value
+        .trim_start_matches(CURRENCY_SYMBOLS)
+        .trim_end_matches(CURRENCY_SYMBOLS)
+        .trim()
+        .replace(',', "");
+    if value.is_empty() || value.starts_with(['-', '+']) {
+        return None;
+    }
+    let value = Decimal::from_str(&value).ok()?;
+    Some(if negative { -value } else { value })
+}
+
+/// Parse a date and time in RFC 3339 or one of the common formats.
+/// Values without a time zone are interpreted as UTC.
+fn parse_date_time(value: &str) -> Option<DateTime<Utc>> {
+    if let Ok(value) = DateTime::parse_from_rfc3339(value) {
+        return Some(value.to_utc());
+    }
+    DATE_TIME_FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .or_else(|| {
+            DATE_FORMATS
+                .iter()
+                .find_map(|format| NaiveDate::parse_from_str(value, format).ok())
+                .map(|date| date.and_time(Default::default()))
+        })
+        .map(|value| value.and_utc())
+}
+
+fn is_web_link(value: &str) -> bool {
+    (value.starts_with("http://") || value.starts_with("https://"))
+        && !value.contains(char::is_whitespace)
+}
+
+/// Check that cell value is within the range specified by the field options.
+pub fn check_range<T>(
+    value: &T,
+    range_start: Option<&T>,
+    range_end: Option<&T>,
+) -> Result<(), &'static str>
+where
+    T: PartialOrd,
+{
+    if range_start.is_some_and(|start| value < start) || range_end.is_some_and(|end| value > end) {
+        Err(OUT_OF_RANGE)
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod test {
+    use crate::model::{Cell, data::FieldKind};
+    use chrono::{TimeZone, Utc};
+    use rust_decimal::Decimal;
+    use std::collections::HashMap;
+
+    #[test]
+    fn infer_field_kind() {
+        let infer = |values: &[&str]| super::infer_field_kind(values.iter().copied());
+
+        assert_eq!(infer(&[]), FieldKind::Text { is_required: false });
+        assert_eq!(infer(&["", " "]), FieldKind::Text { is_required: false });
+        assert_eq!(infer(&["Yes", "no", "TRUE", ""]), FieldKind::Checkbox);
+        assert!(matches!(
+            infer(&["1", "-20", ""]),
+            FieldKind::Integer { .. }
+        ));
+        assert!(matches!(
+            infer(&["1", "2.5", "1e3"]),
+            FieldKind::Float { .. }
+        ));
+        assert!(matches!(
+            infer(&["$1,200.50", "30", "-$4"]),
+            FieldKind::Money { .. }
+        ));
+        assert!(matches!(
+            infer(&["2024-01-31", "2024-02-01 10:30:00", "2024-02-01T10:30:00Z"]),
+            FieldKind::DateTime { .. }
+        ));
+        assert!(matches!(
+            infer(&["https://example.com", "http://example.com/a?b=c"]),
+            FieldKind::WebLink { .. }
+        ));
+        assert_eq!(
+            infer(&["red", "blue", "red", "blue"]),
+            FieldKind::Enumeration {
+                is_required: false,
+                values: HashMap::from_iter([(0, "red".into()), (1, "blue".into())]),
+                default_value: 0,
+            }
+        );
+        assert_eq!(
+            infer(&["red", "blue", "green"]),
+            FieldKind::Text { is_required: false }
+        );
+        assert_eq!(
+            infer(&["1", "inf", "NaN"]),
+            FieldKind::Text { is_required: false }
+        );
+    }
+
+    #[test]
+    fn text_to_cell() {
+        let integer = FieldKind::Integer {
+            is_required: true,
+            range_start: Some(0),
+            range_end: None,
+        };
+        assert_eq!(super::text_to_cell(" 12 ", &integer), Ok(Cell::Integer(12)));
+        assert!(super::text_to_cell("", &integer).is_err());
+        assert!(super::text_to_cell("-1", &integer).is_err());
+        assert!(super::text_to_cell("a", &integer).is_err());
+
+        let money = FieldKind::Money {
+            is_required: false,
+            range_start: None,
+            range_end: None,
+        };
+        assert_eq!(
+            super::text_to_cell("-$1,200.50", &money),
+            Ok(Cell::Decimal(Decimal::new(-120050, 2)))
+        );
+        assert_eq!(
+            super::text_to_cell("12 €", &money),
+            Ok(Cell::Decimal(Decimal::new(12, 0)))
+        );
+        assert_eq!(super::text_to_cell("", &money), Ok(Cell::Null));
+
+        let date_time = FieldKind::DateTime {
+            is_required: false,
+            range_start: None,
+            range_end: None,
+        };
+        assert_eq!(
+            super::text_to_cell("31.01.2024", &date_time),
+            Ok(Cell::DateTime(
+                Utc.with_ymd_and_hms(2024, 1, 31, 0, 0, 0).unwrap()
+            ))
+        );
+
+        assert_eq!(
+            super::text_to_cell("", &FieldKind::Checkbox),
+            Ok(Cell::Boolean(false))
+        );
+        assert_eq!(
+            super::text_to_cell("no", &FieldKind::Checkbox),
+            Ok(Cell::Boolean(false))
+        );
+
+        let enumeration = super::enumeration_from_values(["a", "b", "a"]);
+        assert_eq!(super::text_to_cell("b", &enumeration), Ok(Cell::Integer(1)));
+        assert!(super::text_to_cell("c", &enumeration).is_err());
+
+        assert!(
+            super::text_to_cell("not a link", &FieldKind::WebLink { is_required: false }).is_err()
+        );
+    }
+}
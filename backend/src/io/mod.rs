@@ -2,5 +2,6 @@
 //! from and to various file formats.
 
 mod data;
+mod inference;
 
-pub use data::*;
+pub use {data::*, inference::*};
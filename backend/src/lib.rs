@@ -12,12 +12,13 @@ mod docs;
 #[cfg_attr(coverage_nightly, coverage(off))]
 pub mod test_util;
 
-use crate::model::users::Credentials;
+use crate::{auth::OidcConfig, model::users::Credentials};
 use axum::{
     Router,
-    http::{HeaderValue, Method, header},
+    http::{HeaderName, HeaderValue, Method, header},
 };
 use base64::{Engine, prelude::BASE64_STANDARD};
+use chrono::TimeDelta;
 use config::{Config, ConfigError, Environment};
 use itertools::Itertools;
 use serde::Deserialize;
@@ -67,6 +68,12 @@ struct AppConfig {
     admin: Credentials,
     /// Database connection info.
     database: DatabaseConfig,
+    /// Number of days deleted tables, fields and entries are kept in the trash.
+    #[serde(default = "default_trash_retention_days")]
+    trash_retention_days: i64,
+    /// OpenID Connect single sign-on, disabled if not set.
+    #[serde(default)]
+    oidc: Option<OidcConfig>,
 }
 
 impl AppConfig {
@@ -78,6 +85,10 @@ impl AppConfig {
     }
 }
 
+fn default_trash_retention_days() -> i64 {
+    30
+}
+
 /// Database connection info and credentials
 #[derive(Clone, Deserialize)]
 struct DatabaseConfig {
@@ -120,7 +131,14 @@ pub async fn serve() -> anyhow::Result<()> {
 
     let router = api::router();
     let router = docs::init(router)?;
-    let router = auth::init(router, db.clone(), config.session_key).await?;
+    let router = auth::init(
+        router,
+        db.clone(),
+        config.session_key,
+        TimeDelta::days(config.trash_retention_days),
+        config.oidc,
+    )
+    .await?;
     let router = init_layers(router, config.allowed_origin)?;
     let router = router.with_state(AppState { db });
 
@@ -151,7 +169,11 @@ fn init_layers(
                     Method::OPTIONS,
                     Method::HEAD,
                 ])
-                .allow_headers([header::CONTENT_TYPE, header::AUTHORIZATION])
+                .allow_headers([
+                    header::CONTENT_TYPE,
+                    header::AUTHORIZATION,
+                    HeaderName::from_static(api::SHARE_PASSWORD_HEADER),
+                ])
                 .allow_credentials(true),
         )
         .layer(CompressionLayer::new())
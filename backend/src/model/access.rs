@@ -6,7 +6,6 @@ use crate::{
 };
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use sqlx::prelude::FromRow;
 
 /// The access role for a user and a resource.
 #[derive(
@@ -72,6 +71,23 @@ impl Resource {
             Resource::Dashboard => "dashboard_access",
         }
     }
+
+    /// SQL tables for the access relationship table of user groups.
+    pub fn group_access_tablename(&self) -> &'static str {
+        match self {
+            Resource::Table => "meta_table_group_access",
+            Resource::Dashboard => "dashboard_group_access",
+        }
+    }
+
+    /// SQL views of the effective access roles used for access checks.
+    /// The role of a user is the highest of their own role and the roles of their groups.
+    pub fn access_viewname(&self) -> &'static str {
+        match self {
+            Resource::Table => "meta_table_access_v",
+            Resource::Dashboard => "dashboard_access_v",
+        }
+    }
 }
 
 /// Resource ID path extractor.
@@ -81,30 +97,42 @@ pub struct SelectResource {
     pub resource_id: Id,
 }
 
+/// A user or a user group granted access to a resource.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum Grantee {
+    User { username: String },
+    Group { group_name: String },
+}
+
 /// Create access request.
 #[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct CreateAccess {
-    pub username: String,
+    #[serde(flatten)]
+    pub grantee: Grantee,
     pub access_role: AccessRole,
 }
 
 /// Update access request.
 #[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct UpdateAccess {
-    pub username: String,
+    #[serde(flatten)]
+    pub grantee: Grantee,
     pub access_role: AccessRole,
 }
 
 /// Delete access request.
 #[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct DeleteAccess {
-    pub username: String,
+    #[serde(flatten)]
+    pub grantee: Grantee,
 }
 
 /// Get access response.
-#[derive(Debug, Serialize, Deserialize, FromRow, JsonSchema, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
 pub struct GetAccess {
-    pub username: String,
+    #[serde(flatten)]
+    pub grantee: Grantee,
     pub access_role: AccessRole,
 }
 
@@ -0,0 +1,204 @@
+//! Types for the audit log of user tables.
+
+use crate::{
+    Id,
+    model::data::{Entry, Field, Table},
+};
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::{FromRow, types::Json};
+use std::collections::HashMap;
+
+/// Audit log record entity.
+#[derive(Debug, Serialize, Deserialize, FromRow, PartialEq, JsonSchema)]
+pub struct AuditLog {
+    pub audit_id: Id,
+    pub table_id: Id,
+    pub resource: AuditResource,
+    pub resource_id: Id,
+    pub action: AuditAction,
+    /// The user who made the change. Missing if the user was deleted.
+    pub user_id: Option<Id>,
+    pub username: Option<String>,
+    /// The table, the field or the cells of the entry before the change.
+    /// Only the changed cells are kept when an entry is updated.
+    #[schemars(with = "Option<Value>")]
+    pub old_value: Option<Json<Value>>,
+    /// The table, the field or the cells of the entry after the change.
+    #[schemars(with = "Option<Value>")]
+    pub new_value: Option<Json<Value>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// The kind of change recorded in the audit log.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, sqlx::Type, JsonSchema)]
+#[sqlx(type_name = "audit_action")]
+pub enum AuditAction {
+    Create,
+    Update,
+    Delete,
+}
+
+/// The kind of resource changed.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, sqlx::Type, JsonSchema)]
+#[sqlx(type_name = "audit_resource")]
+pub enum AuditResource {
+    Table,
+    Field,
+    Entry,
+}
+
+/// DTO for recording a change in the audit log.
+/// The action is derived from which of the old and new values are present.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CreateAuditLog {
+    pub table_id: Id,
+    pub resource: AuditResource,
+    pub resource_id: Id,
+    pub action: AuditAction,
+    pub old_value: Option<Value>,
+    pub new_value: Option<Value>,
+}
+
+impl CreateAuditLog {
+    /// Record the creation, update or deletion of a table.
+    pub fn table(old: Option<&Table>, new: Option<&Table>) -> Self {
+        let table = old.or(new).expect("table change without a value");
+        Self::new(
+            table.table_id,
+            AuditResource::Table,
+            table.table_id,
+            old.map(to_value),
+            new.map(to_value),
+        )
+    }
+
+    /// Record the creation, update or deletion of a field.
+    pub fn field(old: Option<&Field>, new: Option<&Field>) -> Self {
+        let field = old.or(new).expect("field change without a value");
+        Self::new(
+            field.table_id,
+            AuditResource::Field,
+            field.field_id,
+            old.map(to_value),
+            new.map(to_value),
+        )
+    }
+
+    /// Record the creation, update or deletion of an entry.
+    /// Only the changed cells are recorded for an update.
+    pub fn entry(table_id: Id, old: Option<&Entry>, new: Option<&Entry>) -> Self {
+        let entry_id = old.or(new).expect("entry change without a value").entry_id;
+        let (old_cells, new_cells) = match (old, new) {
+            (Some(old), Some(new)) => {
+                let changed = |field_id: &Id| old.cells.get(field_id) != new.cells.get(field_id);
+                (
+                    Some(
+                        old.cells
+                            .iter()
+                            .filter(|(field_id, _)| changed(field_id))
+                            .collect::<HashMap<_, _>>(),
+                    ),
+                    Some(
+                        new.cells
+                            .iter()
+                            .filter(|(field_id, _)| changed(field_id))
+                            .collect::<HashMap<_, _>>(),
+                    ),
+                )
+            }
+            (old, new) => (
+                old.map(|entry| entry.cells.iter().collect()),
+                new.map(|entry| entry.cells.iter().collect()),
+            ),
+        };
+        Self::new(
+            table_id,
+            AuditResource::Entry,
+            entry_id,
+            old_cells.as_ref().map(to_value),
+            new_cells.as_ref().map(to_value),
+        )
+    }
+
+    fn new(
+        table_id: Id,
+        resource: AuditResource,
+        resource_id: Id,
+        old_value: Option<Value>,
+        new_value: Option<Value>,
+    ) -> Self {
+        let action = match (&old_value, &new_value) {
+            (None, _) => AuditAction::Create,
+            (Some(_), Some(_)) => AuditAction::Update,
+            (Some(_), None) => AuditAction::Delete,
+        };
+        Self {
+            table_id,
+            resource,
+            resource_id,
+            action,
+            old_value,
+            new_value,
+        }
+    }
+}
+
+fn to_value<T: Serialize>(value: &T) -> Value {
+    serde_json::to_value(value).expect("audit value is serializable")
+}
+
+/// Table activity query parameters.
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
+pub struct AuditLogQuery {
+    pub limit: Option<i64>,
+    /// The `next_cursor` of the previous page.
+    pub cursor: Option<Id>,
+}
+
+/// Page of audit log records response, newest first.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct AuditLogPage {
+    pub logs: Vec<AuditLog>,
+    /// Cursor for fetching the next page. Only set if there are older records.
+    pub next_cursor: Option<Id>,
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod test {
+    use super::{AuditAction, CreateAuditLog};
+    use crate::model::{Cell, data::Entry};
+    use chrono::Utc;
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    #[test]
+    fn entry() {
+        let entry = |cells: [(i32, Cell); 2]| Entry {
+            entry_id: 1,
+            parent_id: None,
+            created_at: Utc::now(),
+            updated_at: None,
+            cells: HashMap::from(cells),
+        };
+        let old = entry([(1, Cell::Integer(1)), (2, Cell::String("a".into()))]);
+        let new = entry([(1, Cell::Integer(2)), (2, Cell::String("a".into()))]);
+
+        let create = CreateAuditLog::entry(3, None, Some(&old));
+        assert_eq!(create.action, AuditAction::Create);
+        assert_eq!(create.new_value, Some(json!({"1": 1, "2": "a"})));
+
+        let update = CreateAuditLog::entry(3, Some(&old), Some(&new));
+        assert_eq!(update.action, AuditAction::Update);
+        assert_eq!(update.old_value, Some(json!({"1": 1})));
+        assert_eq!(update.new_value, Some(json!({"1": 2})));
+
+        let delete = CreateAuditLog::entry(3, Some(&new), None);
+        assert_eq!(delete.action, AuditAction::Delete);
+        assert_eq!(delete.resource_id, 1);
+        assert_eq!(delete.new_value, None);
+    }
+}
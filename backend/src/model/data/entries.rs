@@ -1,6 +1,9 @@
 //! Types for table entries.
 
-use crate::{Id, model::Cell};
+use crate::{
+    Id,
+    model::{Cell, data::EntryFilter},
+};
 use chrono::{DateTime, Utc};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
@@ -34,3 +37,28 @@ pub struct UpdateEntry {
     /// Keys map to field IDs.
     pub cells: HashMap<Id, Value>,
 }
+
+/// Update many entries request.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct UpdateEntries {
+    pub entries: Vec<UpdateEntriesItem>,
+}
+
+/// An entry to update in an [UpdateEntries] request.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct UpdateEntriesItem {
+    pub entry_id: Id,
+    pub parent_id: Option<Id>,
+    /// Keys map to field IDs.
+    pub cells: HashMap<Id, Value>,
+}
+
+/// Delete many entries request.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type")]
+pub enum DeleteEntries {
+    /// Delete the entries with these IDs.
+    Ids { entry_ids: Vec<Id> },
+    /// Delete the entries matching all the filters.
+    Filters { filters: Vec<EntryFilter> },
+}
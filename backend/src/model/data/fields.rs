@@ -1,13 +1,13 @@
 //! Types for table fields.
 
-use crate::Id;
+use crate::{Id, model::data::FormulaKind};
 use chrono::{DateTime, Utc};
 use rust_decimal::Decimal;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_with::{DisplayFromStr, serde_as};
 use sqlx::{FromRow, types::Json};
-use std::{collections::HashMap, fmt};
+use std::{borrow::Cow, collections::HashMap, fmt};
 
 /// Table field entity.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, FromRow, JsonSchema)]
@@ -68,6 +68,47 @@ pub enum FieldKind {
         values: HashMap<i64, String>,
         default_value: i64,
     },
+    /// A reference to entries of another table.
+    Relation {
+        is_required: bool,
+        target_table_id: Id,
+        /// Allow referencing many entries.
+        multiple: bool,
+        #[serde(default)]
+        on_delete: RelationDeleteRule,
+    },
+    /// A value computed from the other fields of the entry.
+    /// Formula values are read-only and ignored when creating or updating entries.
+    Formula {
+        /// Expression referencing fields by ID, for example `{12} * {15}`.
+        expression: String,
+        result_kind: FormulaKind,
+    },
+}
+
+/// What happens to the referencing entries when an entry referenced by a relation is deleted.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+pub enum RelationDeleteRule {
+    /// The referenced entry cannot be deleted.
+    #[default]
+    Restrict,
+    /// The reference is removed from the referencing entries.
+    Unlink,
+    /// The referencing entries are deleted.
+    Cascade,
+}
+
+impl RelationDeleteRule {
+    /// Map the rule to the `ON DELETE` action of the foreign key referencing the target entry.
+    /// For relations to many entries, the foreign key is in the join table so cascading
+    /// only removes the link and deleting the referencing entries is done by a trigger.
+    pub fn get_sql_action(&self, multiple: bool) -> &'static str {
+        match (self, multiple) {
+            (RelationDeleteRule::Restrict, _) => "RESTRICT",
+            (RelationDeleteRule::Unlink, false) => "SET NULL",
+            (RelationDeleteRule::Unlink, true) | (RelationDeleteRule::Cascade, _) => "CASCADE",
+        }
+    }
 }
 
 impl FieldKind {
@@ -83,6 +124,8 @@ impl FieldKind {
             FieldKind::WebLink { .. } => "TEXT COLLATE case_insensitive",
             FieldKind::Checkbox => "BOOLEAN NOT NULL DEFAULT FALSE",
             FieldKind::Enumeration { .. } => "BIGINT",
+            FieldKind::Relation { .. } => "INT",
+            FieldKind::Formula { result_kind, .. } => result_kind.to_field_kind().get_sql_type(),
         }
     }
 
@@ -98,8 +141,46 @@ impl FieldKind {
             FieldKind::WebLink { .. } => "TEXT",
             FieldKind::Checkbox => "BOOLEAN",
             FieldKind::Enumeration { .. } => "BIGINT",
+            FieldKind::Relation { .. } => "INT",
+            FieldKind::Formula { result_kind, .. } => result_kind.to_field_kind().get_sql_type(),
+        }
+    }
+
+    /// Get the field kind describing the values of this field.
+    /// Formula fields are described by the field kind of their result.
+    pub fn value_kind(&self) -> Cow<'_, FieldKind> {
+        match self {
+            FieldKind::Formula { result_kind, .. } => Cow::Owned(result_kind.to_field_kind()),
+            field_kind => Cow::Borrowed(field_kind),
+        }
+    }
+
+    /// Return true if the field is a formula, which is computed by a generated column
+    /// and cannot be written to.
+    pub fn is_formula(&self) -> bool {
+        matches!(self, FieldKind::Formula { .. })
+    }
+
+    /// Return true if entries must have a value for this field.
+    pub fn is_required(&self) -> bool {
+        match self {
+            FieldKind::Text { is_required }
+            | FieldKind::Integer { is_required, .. }
+            | FieldKind::Float { is_required, .. }
+            | FieldKind::Money { is_required, .. }
+            | FieldKind::DateTime { is_required, .. }
+            | FieldKind::WebLink { is_required }
+            | FieldKind::Enumeration { is_required, .. }
+            | FieldKind::Relation { is_required, .. } => *is_required,
+            FieldKind::Progress { .. } | FieldKind::Checkbox | FieldKind::Formula { .. } => false,
         }
     }
+
+    /// Return true if the field is a relation to many entries,
+    /// which is stored in a join table instead of a column.
+    pub fn is_multiple_relation(&self) -> bool {
+        matches!(self, FieldKind::Relation { multiple: true, .. })
+    }
 }
 
 /// Create field request.
@@ -162,3 +243,19 @@ impl fmt::Display for FieldIdentifier {
         write!(f, r#""f{}""#, self.field_id)
     }
 }
+
+/// Database identifier of the SQL join table of a relation field allowing multiple entries.
+#[derive(Debug)]
+pub struct RelationIdentifier {
+    field_id: Id,
+}
+impl RelationIdentifier {
+    pub fn new(field_id: Id) -> Self {
+        Self { field_id }
+    }
+}
+impl fmt::Display for RelationIdentifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, r#""data_table"."r{}""#, self.field_id)
+    }
+}
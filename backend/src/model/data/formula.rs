@@ -0,0 +1,762 @@
+//! Types and compiler for formula fields.
+//!
+//! A formula is an expression referencing other fields of the same table by ID,
+//! for example `{12} * {15}` or `IF({3} > 0, "yes", "no")`. It is type checked against
+//! the field kinds of the table and compiled into the expression of a PostgreSQL
+//! generated column.
+
+use crate::{
+    Id,
+    model::data::{FieldIdentifier, FieldKind, FieldMetadata},
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fmt, iter::Peekable, str::Chars};
+use thiserror::Error;
+
+/// The kind of value computed by a formula.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+pub enum FormulaKind {
+    Text,
+    Integer,
+    Float,
+    Money,
+    DateTime,
+    Checkbox,
+}
+
+impl FormulaKind {
+    /// Get the [FieldKind] of a regular field holding the same values.
+    pub fn to_field_kind(self) -> FieldKind {
+        match self {
+            FormulaKind::Text => FieldKind::Text { is_required: false },
+            FormulaKind::Integer => FieldKind::Integer {
+                is_required: false,
+                range_start: None,
+                range_end: None,
+            },
+            FormulaKind::Float => FieldKind::Float {
+                is_required: false,
+                range_start: None,
+                range_end: None,
+            },
+            FormulaKind::Money => FieldKind::Money {
+                is_required: false,
+                range_start: None,
+                range_end: None,
+            },
+            FormulaKind::DateTime => FieldKind::DateTime {
+                is_required: false,
+                range_start: None,
+                range_end: None,
+            },
+            FormulaKind::Checkbox => FieldKind::Checkbox,
+        }
+    }
+
+    fn value_type(self) -> ValueType {
+        match self {
+            FormulaKind::Text => ValueType::Text,
+            FormulaKind::Integer => ValueType::Integer,
+            FormulaKind::Float => ValueType::Float,
+            FormulaKind::Money => ValueType::Decimal,
+            FormulaKind::DateTime => ValueType::DateTime,
+            FormulaKind::Checkbox => ValueType::Boolean,
+        }
+    }
+}
+
+/// Error returned when a formula expression is invalid.
+#[derive(Debug, Error, PartialEq)]
+pub enum FormulaError {
+    #[error("Unexpected character '{0}'")]
+    UnexpectedCharacter(char),
+    #[error("Unexpected end of the expression")]
+    UnexpectedEnd,
+    #[error("Unexpected token '{0}'")]
+    UnexpectedToken(String),
+    #[error("Field {0} not found")]
+    FieldNotFound(Id),
+    #[error("Field {0} cannot be used in a formula")]
+    InvalidField(Id),
+    #[error("Unknown function '{0}'")]
+    UnknownFunction(String),
+    #[error("Wrong number of arguments for '{0}'")]
+    InvalidArgumentCount(String),
+    #[error("Invalid types for '{0}'")]
+    InvalidTypes(String),
+    #[error("Formula result cannot be converted to {0:?}")]
+    InvalidResult(FormulaKind),
+}
+
+/// Return the IDs of the fields referenced by the formula expression.
+/// Return an empty list if the expression cannot be tokenized.
+pub fn formula_dependencies(expression: &str) -> Vec<Id> {
+    tokenize(expression)
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|token| match token {
+            Token::Field(field_id) => Some(field_id),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Type check the formula expression against the fields of the table and compile
+/// it into an SQL expression returning the PostgreSQL type of the result kind.
+pub fn compile_formula(
+    expression: &str,
+    result_kind: FormulaKind,
+    fields: &[FieldMetadata],
+) -> Result<String, FormulaError> {
+    let fields = fields
+        .iter()
+        .map(|field| (field.field_id, &field.field_kind.0))
+        .collect();
+    let mut parser = Parser {
+        tokens: tokenize(expression)?.into_iter().peekable(),
+        fields,
+    };
+    let expr = parser.parse_or()?;
+    if let Some(token) = parser.tokens.next() {
+        return Err(FormulaError::UnexpectedToken(token.to_string()));
+    }
+
+    let result_type = result_kind.value_type();
+    if expr.value_type != result_type && !(expr.value_type.is_numeric() && result_type.is_numeric())
+    {
+        return Err(FormulaError::InvalidResult(result_kind));
+    }
+    Ok(format!(
+        "({})::{}",
+        expr.sql,
+        result_kind.to_field_kind().get_sql_type()
+    ))
+}
+
+/// The type of a value in a formula expression.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ValueType {
+    Text,
+    Integer,
+    Decimal,
+    Float,
+    DateTime,
+    Boolean,
+}
+
+impl ValueType {
+    fn is_numeric(self) -> bool {
+        matches!(
+            self,
+            ValueType::Integer | ValueType::Decimal | ValueType::Float
+        )
+    }
+
+    /// Get the common type of two values, promoting numbers to the wider type.
+    fn common(self, other: ValueType) -> Option<ValueType> {
+        if self == other {
+            Some(self)
+        } else if self.is_numeric() && other.is_numeric() {
+            Some(if self == ValueType::Float || other == ValueType::Float {
+                ValueType::Float
+            } else {
+                ValueType::Decimal
+            })
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(String),
+    String(String),
+    Field(Id),
+    Ident(String),
+    Operator(&'static str),
+    LeftParen,
+    RightParen,
+    Comma,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Number(v) | Token::Ident(v) => write!(f, "{v}"),
+            Token::String(v) => write!(f, "\"{v}\""),
+            Token::Field(v) => write!(f, "{{{v}}}"),
+            Token::Operator(v) => write!(f, "{v}"),
+            Token::LeftParen => write!(f, "("),
+            Token::RightParen => write!(f, ")"),
+            Token::Comma => write!(f, ","),
+        }
+    }
+}
+
+/// Split the expression into tokens.
+fn tokenize(expression: &str) -> Result<Vec<Token>, FormulaError> {
+    let mut chars = expression.chars().peekable();
+    let mut tokens = Vec::new();
+    while let Some(&c) = chars.peek() {
+        let token = match c {
+            c if c.is_whitespace() => {
+                chars.next();
+                continue;
+            }
+            '0'..='9' | '.' => {
+                Token::Number(take_while(&mut chars, |c| c.is_ascii_digit() || c == '.'))
+            }
+            'a'..='z' | 'A'..='Z' | '_' => Token::Ident(
+                take_while(&mut chars, |c| c.is_ascii_alphanumeric() || c == '_').to_uppercase(),
+            ),
+            '"' => {
+                chars.next();
+                let mut value = String::new();
+                loop {
+                    match chars.next().ok_or(FormulaError::UnexpectedEnd)? {
+                        '"' if chars.peek() == Some(&'"') => {
+                            chars.next();
+                            value.push('"');
+                        }
+                        '"' => break,
+                        c => value.push(c),
+                    }
+                }
+                Token::String(value)
+            }
+            '{' => {
+                chars.next();
+                let field_id = take_while(&mut chars, |c| c.is_ascii_digit());
+                if chars.next() != Some('}') {
+                    return Err(FormulaError::UnexpectedCharacter('{'));
+                }
+                Token::Field(
+                    field_id
+                        .parse()
+                        .map_err(|_| FormulaError::UnexpectedCharacter('{'))?,
+                )
+            }
+            _ => {
+                chars.next();
+                match (c, chars.peek()) {
+                    ('<', Some('=')) => {
+                        chars.next();
+                        Token::Operator("<=")
+                    }
+                    ('>', Some('=')) => {
+                        chars.next();
+                        Token::Operator(">=")
+                    }
+                    ('!', Some('=')) | ('<', Some('>')) => {
+                        chars.next();
+                        Token::Operator("!=")
+                    }
+                    ('<', _) => Token::Operator("<"),
+                    ('>', _) => Token::Operator(">"),
+                    ('=', _) => Token::Operator("="),
+                    ('+', _) => Token::Operator("+"),
+                    ('-', _) => Token::Operator("-"),
+                    ('*', _) => Token::Operator("*"),
+                    ('/', _) => Token::Operator("/"),
+                    ('%', _) => Token::Operator("%"),
+                    ('&', _) => Token::Operator("&"),
+                    ('(', _) => Token::LeftParen,
+                    (')', _) => Token::RightParen,
+                    (',', _) => Token::Comma,
+                    (c, _) => return Err(FormulaError::UnexpectedCharacter(c)),
+                }
+            }
+        };
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+fn take_while(chars: &mut Peekable<Chars>, predicate: impl Fn(char) -> bool) -> String {
+    let mut value = String::new();
+    while let Some(&c) = chars.peek() {
+        if !predicate(c) {
+            break;
+        }
+        value.push(c);
+        chars.next();
+    }
+    value
+}
+
+/// A type checked SQL expression.
+struct Expr {
+    sql: String,
+    value_type: ValueType,
+}
+
+impl Expr {
+    fn new(sql: String, value_type: ValueType) -> Self {
+        Self { sql, value_type }
+    }
+}
+
+/// Recursive descent parser producing type checked SQL expressions.
+struct Parser<'a> {
+    tokens: Peekable<std::vec::IntoIter<Token>>,
+    fields: HashMap<Id, &'a FieldKind>,
+}
+
+impl Parser<'_> {
+    fn next(&mut self) -> Result<Token, FormulaError> {
+        self.tokens.next().ok_or(FormulaError::UnexpectedEnd)
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), FormulaError> {
+        let token = self.next()?;
+        if token == expected {
+            Ok(())
+        } else {
+            Err(FormulaError::UnexpectedToken(token.to_string()))
+        }
+    }
+
+    fn next_if_keyword(&mut self, keyword: &str) -> bool {
+        self.tokens
+            .next_if(|token| matches!(token, Token::Ident(ident) if ident == keyword))
+            .is_some()
+    }
+
+    fn next_if_operator(&mut self, operators: &[&'static str]) -> Option<&'static str> {
+        match self.tokens.peek() {
+            Some(Token::Operator(operator)) if operators.contains(operator) => {
+                let operator = *operator;
+                self.tokens.next();
+                Some(operator)
+            }
+            _ => None,
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, FormulaError> {
+        let mut left = self.parse_and()?;
+        while self.next_if_keyword("OR") {
+            let right = self.parse_and()?;
+            left = boolean_operation("OR", left, right)?;
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, FormulaError> {
+        let mut left = self.parse_not()?;
+        while self.next_if_keyword("AND") {
+            let right = self.parse_not()?;
+            left = boolean_operation("AND", left, right)?;
+        }
+        Ok(left)
+    }
+
+    fn parse_not(&mut self) -> Result<Expr, FormulaError> {
+        if self.next_if_keyword("NOT") {
+            let expr = self.parse_not()?;
+            if expr.value_type != ValueType::Boolean {
+                return Err(FormulaError::InvalidTypes("NOT".into()));
+            }
+            return Ok(Expr::new(format!("(NOT {})", expr.sql), ValueType::Boolean));
+        }
+        self.parse_comparison()
+    }
+
+    fn parse_comparison(&mut self) -> Result<Expr, FormulaError> {
+        let left = self.parse_additive()?;
+        let Some(operator) = self.next_if_operator(&["=", "!=", "<", "<=", ">", ">="]) else {
+            return Ok(left);
+        };
+        let right = self.parse_additive()?;
+        if left.value_type.common(right.value_type).is_none() {
+            return Err(FormulaError::InvalidTypes(operator.into()));
+        }
+        let sql_operator = if operator == "!=" { "<>" } else { operator };
+        Ok(Expr::new(
+            format!("({} {sql_operator} {})", left.sql, right.sql),
+            ValueType::Boolean,
+        ))
+    }
+
+    fn parse_additive(&mut self) -> Result<Expr, FormulaError> {
+        let mut left = self.parse_multiplicative()?;
+        while let Some(operator) = self.next_if_operator(&["+", "-", "&"]) {
+            let right = self.parse_multiplicative()?;
+            left = if operator == "&" {
+                if left.value_type != ValueType::Text || right.value_type != ValueType::Text {
+                    return Err(FormulaError::InvalidTypes(operator.into()));
+                }
+                Expr::new(format!("({} || {})", left.sql, right.sql), ValueType::Text)
+            } else {
+                numeric_operation(operator, left, right)?
+            };
+        }
+        Ok(left)
+    }
+
+    fn parse_multiplicative(&mut self) -> Result<Expr, FormulaError> {
+        let mut left = self.parse_unary()?;
+        while let Some(operator) = self.next_if_operator(&["*", "/", "%"]) {
+            let right = self.parse_unary()?;
+            left = match operator {
+                "/" => {
+                    if !left.value_type.is_numeric() || !right.value_type.is_numeric() {
+                        return Err(FormulaError::InvalidTypes(operator.into()));
+                    }
+                    // Dividing two integers gives a float and dividing by zero gives NULL.
+                    let (left_sql, value_type) = match left.value_type.common(right.value_type) {
+                        Some(ValueType::Integer) => {
+                            (format!("{}::DOUBLE PRECISION", left.sql), ValueType::Float)
+                        }
+                        value_type => (left.sql, value_type.unwrap()),
+                    };
+                    Expr::new(
+                        format!("({left_sql} / NULLIF({}, 0))", right.sql),
+                        value_type,
+                    )
+                }
+                "%" if left.value_type == ValueType::Float
+                    || right.value_type == ValueType::Float =>
+                {
+                    return Err(FormulaError::InvalidTypes(operator.into()));
+                }
+                "%" => {
+                    let right = Expr::new(format!("NULLIF({}, 0)", right.sql), right.value_type);
+                    numeric_operation(operator, left, right)?
+                }
+                _ => numeric_operation(operator, left, right)?,
+            };
+        }
+        Ok(left)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, FormulaError> {
+        if self.next_if_operator(&["-"]).is_some() {
+            let expr = self.parse_unary()?;
+            if !expr.value_type.is_numeric() {
+                return Err(FormulaError::InvalidTypes("-".into()));
+            }
+            return Ok(Expr::new(format!("(-{})", expr.sql), expr.value_type));
+        }
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, FormulaError> {
+        match self.next()? {
+            Token::Number(value) => {
+                if value.parse::<i64>().is_ok() {
+                    Ok(Expr::new(format!("{value}::BIGINT"), ValueType::Integer))
+                } else if value.parse::<f64>().is_ok() {
+                    Ok(Expr::new(format!("{value}::NUMERIC"), ValueType::Decimal))
+                } else {
+                    Err(FormulaError::UnexpectedToken(value))
+                }
+            }
+            Token::String(value) => Ok(Expr::new(
+                format!("'{}'::TEXT", value.replace('\'', "''")),
+                ValueType::Text,
+            )),
+            Token::Field(field_id) => {
+                let field_kind = self
+                    .fields
+                    .get(&field_id)
+                    .ok_or(FormulaError::FieldNotFound(field_id))?;
+                let value_type = match field_kind {
+                    FieldKind::Text { .. } | FieldKind::WebLink { .. } => ValueType::Text,
+                    FieldKind::Integer { .. }
+                    | FieldKind::Progress { .. }
+                    | FieldKind::Enumeration { .. }
+                    | FieldKind::Relation {
+                        multiple: false, ..
+                    } => ValueType::Integer,
+                    FieldKind::Float { .. } => ValueType::Float,
+                    FieldKind::Money { .. } => ValueType::Decimal,
+                    FieldKind::DateTime { .. } => ValueType::DateTime,
+                    FieldKind::Checkbox => ValueType::Boolean,
+                    FieldKind::Relation { multiple: true, .. } | FieldKind::Formula { .. } => {
+                        return Err(FormulaError::InvalidField(field_id));
+                    }
+                };
+                let field_ident = FieldIdentifier::new(field_id);
+                let sql = if value_type == ValueType::Text {
+                    format!(r#"{field_ident} COLLATE "default""#)
+                } else {
+                    field_ident.to_string()
+                };
+                Ok(Expr::new(sql, value_type))
+            }
+            Token::Ident(ident) if ident == "TRUE" || ident == "FALSE" => {
+                Ok(Expr::new(ident, ValueType::Boolean))
+            }
+            Token::Ident(ident) => {
+                self.expect(Token::LeftParen)?;
+                let mut args = Vec::new();
+                if self.tokens.next_if_eq(&Token::RightParen).is_none() {
+                    loop {
+                        args.push(self.parse_or()?);
+                        match self.next()? {
+                            Token::Comma => continue,
+                            Token::RightParen => break,
+                            token => return Err(FormulaError::UnexpectedToken(token.to_string())),
+                        }
+                    }
+                }
+                function(ident, args)
+            }
+            Token::LeftParen => {
+                let expr = self.parse_or()?;
+                self.expect(Token::RightParen)?;
+                Ok(expr)
+            }
+            token => Err(FormulaError::UnexpectedToken(token.to_string())),
+        }
+    }
+}
+
+fn boolean_operation(operator: &str, left: Expr, right: Expr) -> Result<Expr, FormulaError> {
+    if left.value_type != ValueType::Boolean || right.value_type != ValueType::Boolean {
+        return Err(FormulaError::InvalidTypes(operator.into()));
+    }
+    Ok(Expr::new(
+        format!("({} {operator} {})", left.sql, right.sql),
+        ValueType::Boolean,
+    ))
+}
+
+fn numeric_operation(operator: &str, left: Expr, right: Expr) -> Result<Expr, FormulaError> {
+    if !left.value_type.is_numeric() || !right.value_type.is_numeric() {
+        return Err(FormulaError::InvalidTypes(operator.into()));
+    }
+    Ok(Expr::new(
+        format!("({} {operator} {})", left.sql, right.sql),
+        left.value_type.common(right.value_type).unwrap(),
+    ))
+}
+
+/// Type check and compile a function call.
+fn function(name: String, args: Vec<Expr>) -> Result<Expr, FormulaError> {
+    let types = args.iter().map(|arg| arg.value_type).collect::<Vec<_>>();
+    let invalid_types = || Err(FormulaError::InvalidTypes(name.clone()));
+    let expr = match (name.as_str(), types.as_slice()) {
+        ("IF", [ValueType::Boolean, then_type, else_type]) => {
+            let Some(value_type) = then_type.common(*else_type) else {
+                return invalid_types();
+            };
+            Expr::new(
+                format!(
+                    "(CASE WHEN {} THEN {} ELSE {} END)",
+                    args[0].sql, args[1].sql, args[2].sql
+                ),
+                value_type,
+            )
+        }
+        ("ABS", [value_type]) if value_type.is_numeric() => {
+            Expr::new(format!("abs({})", args[0].sql), *value_type)
+        }
+        ("ROUND", [value_type]) if value_type.is_numeric() => {
+            Expr::new(format!("round({})", args[0].sql), *value_type)
+        }
+        ("ROUND", [value_type, ValueType::Integer]) if value_type.is_numeric() => Expr::new(
+            format!("round(({})::NUMERIC, ({})::INT)", args[0].sql, args[1].sql),
+            ValueType::Decimal,
+        ),
+        ("UPPER", [ValueType::Text]) => {
+            Expr::new(format!("upper({})", args[0].sql), ValueType::Text)
+        }
+        ("LOWER", [ValueType::Text]) => {
+            Expr::new(format!("lower({})", args[0].sql), ValueType::Text)
+        }
+        ("LENGTH", [ValueType::Text]) => Expr::new(
+            format!("length({})::BIGINT", args[0].sql),
+            ValueType::Integer,
+        ),
+        ("TEXT", [value_type]) if *value_type != ValueType::DateTime => {
+            Expr::new(format!("({})::TEXT", args[0].sql), ValueType::Text)
+        }
+        ("COALESCE", [first, rest @ ..]) => {
+            let Some(value_type) = rest
+                .iter()
+                .try_fold(*first, |value_type, other| value_type.common(*other))
+            else {
+                return invalid_types();
+            };
+            let args = args.into_iter().map(|arg| arg.sql).collect::<Vec<_>>();
+            Expr::new(format!("COALESCE({})", args.join(", ")), value_type)
+        }
+        ("IF", [_, _, _])
+        | ("ABS" | "ROUND" | "UPPER" | "LOWER" | "LENGTH" | "TEXT", [_])
+        | ("ROUND", [_, _]) => return invalid_types(),
+        ("IF" | "ABS" | "ROUND" | "UPPER" | "LOWER" | "LENGTH" | "TEXT" | "COALESCE", _) => {
+            return Err(FormulaError::InvalidArgumentCount(name));
+        }
+        _ => return Err(FormulaError::UnknownFunction(name)),
+    };
+    Ok(expr)
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod test {
+    use super::{FormulaError, FormulaKind};
+    use crate::model::data::{FieldKind, FieldMetadata, RelationDeleteRule};
+    use sqlx::types::Json;
+
+    fn fields() -> Vec<FieldMetadata> {
+        [
+            (1, FieldKind::Text { is_required: false }),
+            (2, FormulaKind::Integer.to_field_kind()),
+            (3, FormulaKind::Float.to_field_kind()),
+            (4, FormulaKind::Money.to_field_kind()),
+            (5, FormulaKind::DateTime.to_field_kind()),
+            (6, FieldKind::Checkbox),
+            (
+                7,
+                FieldKind::Relation {
+                    is_required: false,
+                    target_table_id: 1,
+                    multiple: true,
+                    on_delete: RelationDeleteRule::Restrict,
+                },
+            ),
+            (
+                8,
+                FieldKind::Formula {
+                    expression: "1".into(),
+                    result_kind: FormulaKind::Integer,
+                },
+            ),
+        ]
+        .into_iter()
+        .map(|(field_id, field_kind)| FieldMetadata {
+            field_id,
+            field_kind: Json(field_kind),
+        })
+        .collect()
+    }
+
+    #[test]
+    fn compile_formula() {
+        let fields = fields();
+        for (expression, result_kind, sql) in [
+            (
+                "{2} * {3}",
+                FormulaKind::Float,
+                r#"(("f2" * "f3"))::DOUBLE PRECISION"#,
+            ),
+            (
+                "{2} / 2",
+                FormulaKind::Float,
+                r#"(("f2"::DOUBLE PRECISION / NULLIF(2::BIGINT, 0)))::DOUBLE PRECISION"#,
+            ),
+            (
+                "{2} % 2",
+                FormulaKind::Integer,
+                r#"(("f2" % NULLIF(2::BIGINT, 0)))::BIGINT"#,
+            ),
+            (
+                r#"IF({2} > 0, "yes", "it's no")"#,
+                FormulaKind::Text,
+                r#"((CASE WHEN ("f2" > 0::BIGINT) THEN 'yes'::TEXT ELSE 'it''s no'::TEXT END))::TEXT"#,
+            ),
+            (
+                r#"upper({1}) & TEXT({4})"#,
+                FormulaKind::Text,
+                r#"((upper("f1" COLLATE "default") || ("f4")::TEXT))::TEXT"#,
+            ),
+            (
+                "NOT {6} AND {5} <= {5} OR FALSE",
+                FormulaKind::Checkbox,
+                r#"((((NOT "f6") AND ("f5" <= "f5")) OR FALSE))::BOOLEAN"#,
+            ),
+            (
+                "-ROUND({4} + 1.5, 1)",
+                FormulaKind::Money,
+                r#"((-round((("f4" + 1.5::NUMERIC))::NUMERIC, (1::BIGINT)::INT)))::numeric_money"#,
+            ),
+            (
+                "COALESCE({2}, {4}, 0)",
+                FormulaKind::Integer,
+                r#"(COALESCE("f2", "f4", 0::BIGINT))::BIGINT"#,
+            ),
+        ] {
+            assert_eq!(
+                super::compile_formula(expression, result_kind, &fields),
+                Ok(sql.to_string()),
+                "{expression}"
+            );
+        }
+
+        for (expression, result_kind, error) in [
+            ("{2} +", FormulaKind::Integer, FormulaError::UnexpectedEnd),
+            (
+                "{2} $ 1",
+                FormulaKind::Integer,
+                FormulaError::UnexpectedCharacter('$'),
+            ),
+            (
+                "{2} 1",
+                FormulaKind::Integer,
+                FormulaError::UnexpectedToken("1".into()),
+            ),
+            (
+                "{100}",
+                FormulaKind::Integer,
+                FormulaError::FieldNotFound(100),
+            ),
+            ("{7}", FormulaKind::Integer, FormulaError::InvalidField(7)),
+            ("{8}", FormulaKind::Integer, FormulaError::InvalidField(8)),
+            (
+                "FOO(1)",
+                FormulaKind::Integer,
+                FormulaError::UnknownFunction("FOO".into()),
+            ),
+            (
+                "ABS(1, 2)",
+                FormulaKind::Integer,
+                FormulaError::InvalidArgumentCount("ABS".into()),
+            ),
+            (
+                "{1} + 1",
+                FormulaKind::Integer,
+                FormulaError::InvalidTypes("+".into()),
+            ),
+            (
+                "{3} % 1",
+                FormulaKind::Integer,
+                FormulaError::InvalidTypes("%".into()),
+            ),
+            (
+                r#"IF({6}, 1, "a")"#,
+                FormulaKind::Integer,
+                FormulaError::InvalidTypes("IF".into()),
+            ),
+            (
+                "TEXT({5})",
+                FormulaKind::Text,
+                FormulaError::InvalidTypes("TEXT".into()),
+            ),
+            (
+                "{2}",
+                FormulaKind::Text,
+                FormulaError::InvalidResult(FormulaKind::Text),
+            ),
+        ] {
+            assert_eq!(
+                super::compile_formula(expression, result_kind, &fields),
+                Err(error),
+                "{expression}"
+            );
+        }
+    }
+
+    #[test]
+    fn formula_dependencies() {
+        assert_eq!(
+            super::formula_dependencies(r#"IF({3} > 0, {12} * {15}, "{4}")"#),
+            vec![3, 12, 15]
+        );
+        assert!(super::formula_dependencies("{3").is_empty());
+    }
+}
@@ -1,7 +1,12 @@
 //! Types for data management features.
 
+mod audit;
 mod entries;
 mod fields;
+mod formula;
+mod query;
 mod tables;
+mod trash;
+mod views;
 
-pub use {entries::*, fields::*, tables::*};
+pub use {audit::*, entries::*, fields::*, formula::*, query::*, tables::*, trash::*, views::*};
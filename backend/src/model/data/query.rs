@@ -0,0 +1,155 @@
+//! Types for querying table entries.
+
+use crate::{
+    Id,
+    model::{Cell, data::Entry},
+};
+use base64::{Engine, prelude::BASE64_URL_SAFE_NO_PAD};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Predicate applied to the cells of a field.
+/// Values are given in the same JSON format as entry cells.
+/// Relations to many entries cannot be filtered.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(tag = "operator")]
+pub enum FilterOperator<T = Value> {
+    /// Text contains the value, ignoring case. Only for `Text` and `WebLink` fields.
+    Contains { value: T },
+    /// Cell is equal to the value.
+    Equals { value: T },
+    /// Cell is within the inclusive bounds. Only for `Integer`, `Float`, `Money`,
+    /// `Progress` and `DateTime` fields.
+    Range { start: Option<T>, end: Option<T> },
+    /// Cell is one of the values. Only for `Enumeration` and `Relation` fields.
+    In { values: Vec<T> },
+    /// Cell has no value.
+    IsNull,
+    /// Cell has a value.
+    IsNotNull,
+    /// Date is within the last number of days up to now, at most 36500 days.
+    /// Only for `DateTime` fields.
+    WithinLastDays { days: u32 },
+}
+
+impl<T> FilterOperator<T> {
+    /// Convert the values of this operator, stopping at the first error.
+    pub fn try_map<U, E>(
+        self,
+        mut f: impl FnMut(T) -> Result<U, E>,
+    ) -> Result<FilterOperator<U>, E> {
+        Ok(match self {
+            FilterOperator::Contains { value } => FilterOperator::Contains { value: f(value)? },
+            FilterOperator::Equals { value } => FilterOperator::Equals { value: f(value)? },
+            FilterOperator::Range { start, end } => FilterOperator::Range {
+                start: start.map(&mut f).transpose()?,
+                end: end.map(&mut f).transpose()?,
+            },
+            FilterOperator::In { values } => FilterOperator::In {
+                values: values.into_iter().map(f).collect::<Result<_, _>>()?,
+            },
+            FilterOperator::IsNull => FilterOperator::IsNull,
+            FilterOperator::IsNotNull => FilterOperator::IsNotNull,
+            FilterOperator::WithinLastDays { days } => FilterOperator::WithinLastDays { days },
+        })
+    }
+}
+
+/// A filter on the entries of a table.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct EntryFilter<T = Value> {
+    pub field_id: Id,
+    #[serde(flatten)]
+    pub operator: FilterOperator<T>,
+}
+
+/// Direction of a sort.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, Hash, JsonSchema)]
+pub enum SortDirection {
+    #[default]
+    Ascending,
+    Descending,
+}
+
+impl SortDirection {
+    /// Get the SQL keyword of this direction.
+    pub fn get_sql_direction(&self) -> &'static str {
+        match self {
+            SortDirection::Ascending => "ASC",
+            SortDirection::Descending => "DESC",
+        }
+    }
+}
+
+/// Sort the entries of a table by a field.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+pub struct EntrySort {
+    pub field_id: Id,
+    #[serde(default)]
+    pub direction: SortDirection,
+}
+
+/// Query entries request.
+///
+/// Filters are combined with `AND`. Sorts are applied in order and
+/// entries are always sorted by entry ID last.
+/// Either `offset` or `cursor` can be used for pagination, but not both.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct QueryEntries {
+    /// Only return entries belonging to this parent entry.
+    pub parent_id: Option<Id>,
+    #[serde(default)]
+    pub filters: Vec<EntryFilter>,
+    #[serde(default)]
+    pub sort: Vec<EntrySort>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+    /// The `next_cursor` of the previous page.
+    pub cursor: Option<String>,
+}
+
+/// Validated entry query passed to the database.
+#[derive(Debug, Clone, Default)]
+pub struct EntryQuery {
+    pub parent_id: Option<Id>,
+    pub filters: Vec<EntryFilter<Cell>>,
+    pub sort: Vec<EntrySort>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+    pub cursor: Option<EntryCursor>,
+}
+
+/// Position of the last entry of a page for keyset pagination.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EntryCursor {
+    /// Cells of the last entry for each sorted field.
+    pub cells: Vec<Cell>,
+    pub entry_id: Id,
+}
+
+impl EntryCursor {
+    /// Encode the cursor into an opaque string.
+    pub fn encode(&self) -> String {
+        let json = serde_json::json!([self.entry_id, self.cells]);
+        BASE64_URL_SAFE_NO_PAD.encode(json.to_string())
+    }
+
+    /// Decode an opaque cursor string into the entry ID and the raw JSON cell values.
+    /// Return `None` if the string is not a valid cursor.
+    pub fn decode(cursor: &str) -> Option<(Id, Vec<Value>)> {
+        let bytes = BASE64_URL_SAFE_NO_PAD.decode(cursor).ok()?;
+        serde_json::from_slice(&bytes).ok()
+    }
+}
+
+/// Page of entries response.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct EntryPage {
+    pub entries: Vec<Entry>,
+    /// Number of entries matching the filters.
+    pub total_count: i64,
+    /// Cursor for fetching the next page. Only set if there is a limit
+    /// and the page is full.
+    pub next_cursor: Option<String>,
+}
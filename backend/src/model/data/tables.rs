@@ -8,7 +8,7 @@ use chrono::{DateTime, Utc};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
-use std::fmt;
+use std::{collections::HashMap, fmt};
 
 use super::{CreateField, Entry, Field};
 
@@ -69,11 +69,59 @@ pub struct GetTableData {
 }
 
 /// DTO for creating tables from file imports.
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Serialize, JsonSchema)]
 pub struct CreateTableData {
     pub table: CreateTable,
     pub fields: Vec<CreateField>,
     pub entries: Vec<Vec<Cell>>,
+    /// Name of the parent table in the same import, if this table was exported as a child table.
+    pub parent_name: Option<String>,
+    /// Exported entry IDs of the entries. Empty if the file has no entry ID column.
+    pub entry_ids: Vec<Option<Id>>,
+    /// Exported parent entry IDs of the entries. Empty if the file has no parent ID column.
+    pub parent_ids: Vec<Option<Id>>,
+}
+
+/// Options for importing a file into an existing table.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct ImportIntoTable {
+    /// Keys are the column names of the file and values are the IDs of the fields they are imported into.
+    /// Columns which are not mapped are ignored.
+    pub mapping: HashMap<String, Id>,
+    pub mode: ImportMode,
+    /// Name of the Excel sheet to import. Defaults to the first sheet.
+    #[serde(default)]
+    pub sheet: Option<String>,
+}
+
+/// How the imported rows are merged with the existing entries.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(tag = "type")]
+pub enum ImportMode {
+    /// Add every row as a new entry.
+    Append,
+    /// Move all the entries of the table to the trash, then add every row as a new entry.
+    Replace,
+    /// Update the entry whose key field has the same value as the row,
+    /// or add the row as a new entry if there is none.
+    Upsert { key_field_id: Id },
+}
+
+/// Result of importing a file into an existing table.
+#[derive(Debug, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct ImportReport {
+    pub created: usize,
+    pub updated: usize,
+    pub deleted: usize,
+    pub rejected: Vec<RejectedRow>,
+}
+
+/// A row of the imported file which was not imported.
+#[derive(Debug, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct RejectedRow {
+    /// Row number in the file, counting the header row.
+    pub row: usize,
+    pub reason: String,
 }
 
 /// Database identifier of the actual SQL table that this user table points to.
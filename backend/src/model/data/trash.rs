@@ -0,0 +1,32 @@
+//! Types for the trash bin of deleted tables, fields and entries.
+
+use crate::{Id, model::data::AuditResource};
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::{FromRow, types::Json};
+
+/// Trashed table, field or entry entity.
+#[derive(Debug, Serialize, Deserialize, FromRow, PartialEq, JsonSchema)]
+pub struct TrashItem {
+    pub trash_id: Id,
+    pub table_id: Id,
+    pub resource: AuditResource,
+    pub resource_id: Id,
+    /// The name of the table or the field. Missing for entries.
+    pub name: Option<String>,
+    /// The table, the field or the cells of the entry when it was deleted.
+    #[schemars(with = "Value")]
+    pub value: Json<Value>,
+    /// The user who deleted the item. Missing if the user was deleted.
+    pub deleted_by: Option<Id>,
+    pub username: Option<String>,
+    pub deleted_at: DateTime<Utc>,
+}
+
+/// Trash item ID path extractor.
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct SelectTrashItem {
+    pub trash_id: Id,
+}
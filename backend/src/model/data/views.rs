@@ -0,0 +1,77 @@
+//! Types for saved table views.
+
+use crate::{
+    Id,
+    model::data::{EntryFilter, EntrySort},
+};
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, types::Json};
+use std::collections::HashMap;
+
+/// Saved view of a table entity.
+/// Shared views are visible to every user with access to the table,
+/// personal views only to the user who created them.
+#[derive(Debug, Serialize, Deserialize, FromRow, JsonSchema, PartialEq)]
+pub struct TableView {
+    pub view_id: Id,
+    pub table_id: Id,
+    /// The user who created the view.
+    pub user_id: Id,
+    pub name: String,
+    pub is_shared: bool,
+    #[schemars(with = "Vec<EntryFilter>")]
+    pub filters: Json<Vec<EntryFilter>>,
+    #[schemars(with = "Vec<EntrySort>")]
+    pub sort: Json<Vec<EntrySort>>,
+    pub hidden_field_ids: Vec<Id>,
+    /// Width of the column of each field in pixels.
+    #[schemars(with = "HashMap<Id, i32>")]
+    pub column_widths: Json<HashMap<Id, i32>>,
+    /// Entries are grouped by this field, before the other sorts.
+    pub group_field_id: Option<Id>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+/// Create table view request.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct CreateTableView {
+    pub name: String,
+    #[serde(default)]
+    pub is_shared: bool,
+    #[serde(default)]
+    pub filters: Vec<EntryFilter>,
+    #[serde(default)]
+    pub sort: Vec<EntrySort>,
+    #[serde(default)]
+    pub hidden_field_ids: Vec<Id>,
+    #[serde(default)]
+    pub column_widths: HashMap<Id, i32>,
+    pub group_field_id: Option<Id>,
+}
+
+/// Update table view request.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct UpdateTableView {
+    pub name: String,
+    #[serde(default)]
+    pub is_shared: bool,
+    #[serde(default)]
+    pub filters: Vec<EntryFilter>,
+    #[serde(default)]
+    pub sort: Vec<EntrySort>,
+    #[serde(default)]
+    pub hidden_field_ids: Vec<Id>,
+    #[serde(default)]
+    pub column_widths: HashMap<Id, i32>,
+    pub group_field_id: Option<Id>,
+}
+
+/// Table view ID path extractor.
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct SelectTableView {
+    pub table_id: Id,
+    pub view_id: Id,
+}
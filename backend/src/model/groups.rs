@@ -0,0 +1,66 @@
+//! Types for user groups.
+
+use crate::Id;
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, types::Json};
+
+/// Group of users sharing access to tables and dashboards.
+#[derive(Debug, Serialize, Deserialize, FromRow, JsonSchema, PartialEq, Eq)]
+pub struct UserGroup {
+    pub group_id: Id,
+    pub name: String,
+    #[schemars(with = "Vec<GroupMember>")]
+    pub members: Json<Vec<GroupMember>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+/// Member of a user group.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+pub struct GroupMember {
+    pub user_id: Id,
+    pub username: String,
+    /// Owners can manage the group and its members.
+    pub is_owner: bool,
+}
+
+/// Create user group request.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct CreateUserGroup {
+    pub name: String,
+}
+
+/// Update user group request.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct UpdateUserGroup {
+    pub name: String,
+}
+
+/// Add group member request.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct CreateGroupMember {
+    pub username: String,
+    #[serde(default)]
+    pub is_owner: bool,
+}
+
+/// Update group member request.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct UpdateGroupMember {
+    pub is_owner: bool,
+}
+
+/// User group ID path extractor.
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct SelectUserGroup {
+    pub group_id: Id,
+}
+
+/// Group member path extractor.
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct SelectGroupMember {
+    pub group_id: Id,
+    pub user_id: Id,
+}
@@ -11,22 +11,28 @@
 
 pub mod access;
 pub mod data;
+pub mod groups;
+pub mod shares;
+pub mod tokens;
 pub mod users;
 pub mod viz;
 
+use crate::Id;
 use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
 use data::FieldKind;
+use itertools::Itertools;
 use num_traits::{FromPrimitive, ToPrimitive};
 use rust_decimal::Decimal;
 use schemars::JsonSchema;
 use serde::{Serialize, Serializer};
+use serde_json::Value;
 use sqlx::{
     Encode, Postgres, Row,
     postgres::{PgArgumentBuffer, PgArguments, PgRow},
     query::Query,
-    query_builder::Separated,
+    query_builder::{QueryBuilder, Separated},
 };
-use std::fmt;
+use std::{fmt, str::FromStr};
 use viz::Aggregate;
 
 /// This represents a cell in user entries and charts which can be any type.
@@ -38,6 +44,7 @@ pub enum Cell {
     Boolean(bool),
     DateTime(DateTime<Utc>),
     String(String),
+    EntryIds(Vec<Id>),
     Null,
 }
 
@@ -53,6 +60,7 @@ impl Serialize for Cell {
             Cell::Boolean(value) => serializer.serialize_bool(*value),
             Cell::DateTime(value) => serializer.serialize_str(&value.to_rfc3339()),
             Cell::String(value) => serializer.serialize_str(value),
+            Cell::EntryIds(value) => value.serialize(serializer),
             Cell::Null => serializer.serialize_none(),
         }
     }
@@ -70,6 +78,7 @@ impl<'q> Encode<'q, Postgres> for Cell {
             Cell::Boolean(value) => <bool as Encode<Postgres>>::encode_by_ref(value, buf),
             Cell::DateTime(value) => <DateTime<Utc> as Encode<Postgres>>::encode_by_ref(value, buf),
             Cell::String(value) => <String as Encode<Postgres>>::encode_by_ref(value, buf),
+            Cell::EntryIds(value) => <Vec<Id> as Encode<Postgres>>::encode_by_ref(value, buf),
             Cell::Null => <Option<bool> as Encode<Postgres>>::encode_by_ref(&None, buf),
         }
     }
@@ -84,6 +93,7 @@ impl fmt::Display for Cell {
             Cell::Boolean(v) => write!(f, "{v}"),
             Cell::DateTime(v) => write!(f, "{}", v.to_rfc3339()),
             Cell::String(v) => write!(f, "{v}"),
+            Cell::EntryIds(v) => write!(f, "{}", v.iter().join(", ")),
             Cell::Null => write!(f, "null"),
         }
     }
@@ -99,6 +109,7 @@ impl Cell {
             Cell::Boolean(v) => query.bind(v),
             Cell::DateTime(v) => query.bind(v),
             Cell::String(v) => query.bind(v),
+            Cell::EntryIds(v) => query.bind(v),
             Cell::Null => query.bind(None::<bool>),
         }
     }
@@ -112,10 +123,66 @@ impl Cell {
             Cell::Boolean(v) => builder.push_bind(v),
             Cell::DateTime(v) => builder.push_bind(v),
             Cell::String(v) => builder.push_bind(v),
+            Cell::EntryIds(v) => builder.push_bind(v),
             Cell::Null => builder.push("NULL"),
         };
     }
 
+    /// Call [QueryBuilder::push_bind] on the Cell value.
+    pub fn push_bind_query(self, builder: &mut QueryBuilder<'_, Postgres>) {
+        match self {
+            Cell::Integer(v) => builder.push_bind(v),
+            Cell::Float(v) => builder.push_bind(v),
+            Cell::Decimal(v) => builder.push_bind(v),
+            Cell::Boolean(v) => builder.push_bind(v),
+            Cell::DateTime(v) => builder.push_bind(v),
+            Cell::String(v) => builder.push_bind(v),
+            Cell::EntryIds(v) => builder.push_bind(v),
+            Cell::Null => builder.push("NULL"),
+        };
+    }
+
+    /// Get the Cell value as an SQL literal, for statements which cannot take parameters.
+    pub fn to_sql_literal(&self) -> String {
+        match self {
+            Cell::Integer(v) => v.to_string(),
+            Cell::Float(v) => format!("'{v}'::DOUBLE PRECISION"),
+            Cell::Decimal(v) => format!("'{v}'::NUMERIC"),
+            Cell::Boolean(v) => v.to_string().to_uppercase(),
+            Cell::DateTime(v) => format!("'{}'::TIMESTAMPTZ", v.to_rfc3339()),
+            Cell::String(v) => format!("'{}'", v.replace('\'', "''")),
+            Cell::EntryIds(v) => format!("ARRAY[{}]::INT[]", v.iter().join(", ")),
+            Cell::Null => "NULL".into(),
+        }
+    }
+
+    /// Convert a JSON filter value to a Cell of this field kind.
+    /// Unlike entry values, the value is not checked against the field options and cannot be null.
+    /// Return `None` if the value is not of the correct type.
+    pub fn from_filter_value(value: Value, field_kind: &FieldKind) -> Option<Self> {
+        match (value, &*field_kind.value_kind()) {
+            (
+                Value::Number(value),
+                FieldKind::Integer { .. }
+                | FieldKind::Progress { .. }
+                | FieldKind::Enumeration { .. }
+                | FieldKind::Relation { .. },
+            ) => value.as_i64().map(Cell::Integer),
+            (Value::Number(value), FieldKind::Float { .. }) => value.as_f64().map(Cell::Float),
+            (Value::String(value), FieldKind::Money { .. }) => {
+                Decimal::from_str_radix(&value, 10).ok().map(Cell::Decimal)
+            }
+            (Value::String(value), FieldKind::DateTime { .. }) => {
+                DateTime::<Utc>::from_str(&value).ok().map(Cell::DateTime)
+            }
+            (Value::String(value), FieldKind::Text { .. } | FieldKind::WebLink { .. }) => {
+                Some(Cell::String(value))
+            }
+            (Value::Bool(value), FieldKind::Checkbox) => Some(Cell::Boolean(value)),
+            _ => None,
+        }
+    }
+
     /// Get the `Cell` from this PostgreSQL row into the proper type based on `FieldKind`.
     pub fn from_field_row(row: &PgRow, index: &str, field_kind: &FieldKind) -> sqlx::Result<Self> {
         if let Ok(None) = row.try_get::<Option<bool>, _>(index) {
@@ -130,6 +197,16 @@ impl Cell {
             FieldKind::Money { .. } => Cell::Decimal(row.try_get(index)?),
             FieldKind::DateTime { .. } => Cell::DateTime(row.try_get(index)?),
             FieldKind::Checkbox => Cell::Boolean(row.try_get(index)?),
+            FieldKind::Relation { multiple, .. } => {
+                if *multiple {
+                    Cell::EntryIds(row.try_get(index)?)
+                } else {
+                    Cell::Integer(row.try_get::<Id, _>(index)?.into())
+                }
+            }
+            FieldKind::Formula { result_kind, .. } => {
+                Self::from_field_row(row, index, &result_kind.to_field_kind())?
+            }
         })
     }
 
@@ -144,12 +221,17 @@ impl Cell {
             return Ok(Cell::Null);
         }
         Ok(match aggregate {
-            Aggregate::Sum | Aggregate::Average => match field_kind {
-                FieldKind::Float { .. } => Cell::Float(row.try_get(index)?),
-                _ => Cell::Decimal(row.try_get(index)?),
-            },
-            Aggregate::Min | Aggregate::Max => Self::from_field_row(row, index, field_kind)?,
-            Aggregate::Count => Cell::Integer(row.try_get(index)?),
+            Aggregate::Sum | Aggregate::Average | Aggregate::StdDev | Aggregate::Variance => {
+                match *field_kind.value_kind() {
+                    FieldKind::Float { .. } => Cell::Float(row.try_get(index)?),
+                    _ => Cell::Decimal(row.try_get(index)?),
+                }
+            }
+            Aggregate::Min | Aggregate::Max | Aggregate::First | Aggregate::Last => {
+                Self::from_field_row(row, index, field_kind)?
+            }
+            Aggregate::Count | Aggregate::CountDistinct => Cell::Integer(row.try_get(index)?),
+            Aggregate::Median | Aggregate::Percentile => Cell::Float(row.try_get(index)?),
         })
     }
 
@@ -163,6 +245,7 @@ impl Cell {
                 Cell::Decimal(v) => v.to_string(),
                 Cell::Boolean(v) => v.to_string(),
                 Cell::DateTime(v) => v.to_string(),
+                Cell::EntryIds(_) => return None,
                 Cell::String(_) | Cell::Null => return Some(self),
             })),
             FieldKind::Integer { .. } | FieldKind::Progress { .. } => {
@@ -172,6 +255,7 @@ impl Cell {
                     Cell::Boolean(v) => v.into(),
                     Cell::DateTime(v) => v.timestamp(),
                     Cell::String(v) => v.parse().ok()?,
+                    Cell::EntryIds(_) => return None,
                     Cell::Integer(_) | Cell::Null => return Some(self),
                 }))
             }
@@ -180,14 +264,14 @@ impl Cell {
                 Cell::Decimal(v) => v.to_f64()?,
                 Cell::Boolean(v) => v.into(),
                 Cell::String(v) => v.parse().ok()?,
-                Cell::DateTime(_) => return None,
+                Cell::DateTime(_) | Cell::EntryIds(_) => return None,
                 Cell::Float(_) | Cell::Null => return Some(self),
             })),
             FieldKind::Money { .. } => Some(Cell::Decimal(match self {
                 Cell::Integer(v) => Decimal::from_i64(v)?,
                 Cell::Float(v) => Decimal::from_f64(v)?,
                 Cell::String(v) => v.parse().ok()?,
-                Cell::Boolean(_) | Cell::DateTime(_) => return None,
+                Cell::Boolean(_) | Cell::DateTime(_) | Cell::EntryIds(_) => return None,
                 Cell::Decimal(_) | Cell::Null => return Some(self),
             })),
             FieldKind::DateTime { .. } => Some(Cell::DateTime(match self {
@@ -199,13 +283,17 @@ impl Cell {
                             .map(|v| NaiveDateTime::from(v).and_utc())
                     })
                     .ok()?,
-                Cell::Float(_) | Cell::Decimal(_) | Cell::Boolean(_) => return None,
+                Cell::Float(_) | Cell::Decimal(_) | Cell::Boolean(_) | Cell::EntryIds(_) => {
+                    return None;
+                }
                 Cell::DateTime(_) | Cell::Null => return Some(self),
             })),
             FieldKind::Checkbox => Some(Cell::Boolean(match self {
                 Cell::Integer(v) => v != 0,
                 Cell::String(v) => v.parse().ok()?,
-                Cell::Float(_) | Cell::Decimal(_) | Cell::DateTime(_) => return None,
+                Cell::Float(_) | Cell::Decimal(_) | Cell::DateTime(_) | Cell::EntryIds(_) => {
+                    return None;
+                }
                 Cell::Boolean(_) | Cell::Null => return Some(self),
             })),
             FieldKind::Enumeration {
@@ -220,6 +308,7 @@ impl Cell {
                     Cell::Boolean(v) => v.to_string(),
                     Cell::DateTime(v) => v.to_string(),
                     Cell::String(v) => v,
+                    Cell::EntryIds(_) => return None,
                     Cell::Null => return Some(self),
                 };
                 Some(
@@ -230,6 +319,7 @@ impl Cell {
                     },
                 )
             }
+            FieldKind::Relation { .. } | FieldKind::Formula { .. } => None,
         }
     }
 }
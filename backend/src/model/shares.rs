@@ -0,0 +1,100 @@
+//! Types for public read-only share links.
+
+use crate::{
+    Id,
+    model::{
+        data::TableData,
+        viz::{Chart, Dashboard, DashboardControl},
+    },
+};
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// Public share link entity giving anonymous read-only access to a table or a dashboard.
+/// The secret of the link and its password are only stored as hashes.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, JsonSchema, PartialEq, Eq)]
+pub struct ShareLink {
+    pub share_id: Id,
+    pub user_id: Id,
+    /// Shared table, if the link shares a table.
+    pub table_id: Option<Id>,
+    /// Shared dashboard, if the link shares a dashboard.
+    pub dashboard_id: Option<Id>,
+    /// The password must be sent in the `X-Share-Password` header if set.
+    pub has_password: bool,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Create share link request. Exactly one of the table and the dashboard must be set.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct CreateShareLink {
+    #[serde(default)]
+    pub table_id: Option<Id>,
+    #[serde(default)]
+    pub dashboard_id: Option<Id>,
+    /// The link is not password-protected if not set.
+    #[serde(default)]
+    pub password: Option<String>,
+    /// The link never expires if not set.
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// Create share link response.
+#[derive(Debug, Serialize, Deserialize, FromRow, JsonSchema)]
+pub struct CreatedShareLink {
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    pub link: ShareLink,
+    /// Secret of the public path `/api/public/{secret}`.
+    /// It is only returned once, when the link is created.
+    pub secret: String,
+}
+
+/// Share link with the password hash, used to serve the shared resource.
+#[derive(Debug, FromRow)]
+pub struct UsedShareLink {
+    pub user_id: Id,
+    pub table_id: Option<Id>,
+    pub dashboard_id: Option<Id>,
+    pub password_hash: Option<String>,
+}
+
+/// Shared dashboard with its charts and filter controls.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct SharedDashboard {
+    pub dashboard: Dashboard,
+    pub charts: Vec<Chart>,
+    pub controls: Vec<DashboardControl>,
+}
+
+/// Resource shared by a public share link.
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(tag = "resource")]
+pub enum SharedResource {
+    Table(TableData),
+    Dashboard(SharedDashboard),
+}
+
+/// Share link ID path extractor.
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct SelectShareLink {
+    pub share_id: Id,
+}
+
+/// Share link secret path extractor.
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct SelectSharedResource {
+    pub secret: String,
+}
+
+/// Shared chart path extractor.
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct SelectSharedChart {
+    pub secret: String,
+    pub chart_id: Id,
+}
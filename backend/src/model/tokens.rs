@@ -0,0 +1,66 @@
+//! Types for personal API tokens.
+
+use crate::Id;
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// What an API token is allowed to do.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, JsonSchema, PartialEq, Eq)]
+#[sqlx(type_name = "token_permission")]
+pub enum TokenPermission {
+    /// Only requests reading data, which are `GET` requests and entry queries.
+    ReadOnly,
+    /// Any request allowed to the user.
+    ReadWrite,
+}
+
+/// Personal API token entity. The secret of the token is only stored as a hash.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, JsonSchema, PartialEq, Eq)]
+pub struct ApiToken {
+    pub token_id: Id,
+    pub user_id: Id,
+    pub name: String,
+    pub permission: TokenPermission,
+    /// Tables the token is limited to, including the tables read by charts.
+    /// All the tables of the user if not set.
+    pub table_ids: Option<Vec<Id>>,
+    /// Dashboards the token is limited to. All the dashboards of the user if not set.
+    /// A token limited to tables or dashboards can only reach the routes of these resources.
+    pub dashboard_ids: Option<Vec<Id>>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Create API token request.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct CreateApiToken {
+    pub name: String,
+    pub permission: TokenPermission,
+    #[serde(default)]
+    pub table_ids: Option<Vec<Id>>,
+    #[serde(default)]
+    pub dashboard_ids: Option<Vec<Id>>,
+    /// The token never expires if not set.
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// Create API token response.
+#[derive(Debug, Serialize, Deserialize, FromRow, JsonSchema)]
+pub struct CreatedApiToken {
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    pub token: ApiToken,
+    /// Secret to send in the `Authorization: Bearer` header.
+    /// It is only returned once, when the token is created.
+    pub secret: String,
+}
+
+/// API token ID path extractor.
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct SelectApiToken {
+    pub token_id: Id,
+}
@@ -49,6 +49,45 @@ pub struct Credentials {
     pub password: String,
 }
 
+/// Credentials accepted by the authentication backend.
+#[derive(Debug, Clone)]
+pub enum AuthCredentials {
+    /// Username and password of a local user.
+    Password(Credentials),
+    /// Authorization code returned by the OpenID Connect provider
+    /// for the authorization request started by the session.
+    Oidc { code: String, request: OidcRequest },
+}
+
+impl From<Credentials> for AuthCredentials {
+    fn from(creds: Credentials) -> Self {
+        AuthCredentials::Password(creds)
+    }
+}
+
+/// OpenID Connect authorization request, kept in the session until the callback.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OidcRequest {
+    pub state: String,
+    pub nonce: String,
+    pub code_verifier: String,
+}
+
+/// OpenID Connect login response.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct OidcLogin {
+    /// URL of the identity provider to redirect the user to.
+    pub authorization_url: String,
+}
+
+/// OpenID Connect callback request, with the query parameters
+/// the identity provider redirected the user with.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct OidcCallback {
+    pub code: String,
+    pub state: String,
+}
+
 /// User ID path extractor.
 #[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct SelectUser {
@@ -1,6 +1,9 @@
 //! Types for chart axes.
 
-use crate::{Id, model::data::FieldKind};
+use crate::{
+    Id,
+    model::data::{FieldKind, SortDirection},
+};
 use chrono::{DateTime, Utc};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
@@ -13,13 +16,22 @@ pub struct Axis {
     pub axis_id: Id,
     pub chart_id: Id,
     pub field_id: Id,
+    pub table_path: Vec<Id>,
     pub axis_kind: AxisKind,
     pub aggregate: Option<Aggregate>,
+    pub percentile: Option<i16>,
+    pub order_field_id: Option<Id>,
+    #[schemars(with = "Option<AxisSort>")]
+    pub sort: Option<Json<AxisSort>>,
+    pub bins: Option<i32>,
+    #[schemars(with = "Option<TimeBucket>")]
+    pub bucket: Option<Json<TimeBucket>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: Option<DateTime<Utc>>,
 }
 
 /// The kind of axis for constructing the actual chart.
+/// The allowed number of axes of each kind depends on the chart kind.
 #[derive(
     Debug, Copy, Clone, Serialize, Deserialize, PartialEq, Eq, Hash, sqlx::Type, JsonSchema,
 )]
@@ -45,39 +57,151 @@ pub enum Aggregate {
     Min,
     Max,
     Count,
+    CountDistinct,
+    Median,
+    /// Continuous percentile given by the axis `percentile`.
+    Percentile,
+    /// Sample standard deviation.
+    StdDev,
+    /// Sample variance.
+    Variance,
+    /// Value of the row with the earliest axis `order_field_id`.
+    First,
+    /// Value of the row with the latest axis `order_field_id`.
+    Last,
 }
 
 impl Aggregate {
-    /// Get the SQL function of this aggregate.
-    pub fn get_sql_aggregate(&self) -> &'static str {
+    /// Get the SQL expression aggregating this column.
+    /// The rows of `First` and `Last` are ordered by `order_column`, or by the column itself
+    /// if there is none.
+    pub fn get_sql_aggregate(
+        &self,
+        column: &str,
+        percentile: Option<i16>,
+        order_column: Option<&str>,
+    ) -> String {
+        let order_column = order_column.unwrap_or(column);
         match self {
-            Aggregate::Sum => "SUM",
-            Aggregate::Average => "AVG",
-            Aggregate::Min => "MIN",
-            Aggregate::Max => "MAX",
-            Aggregate::Count => "COUNT",
+            Aggregate::Sum => format!("SUM({column})"),
+            Aggregate::Average => format!("AVG({column})"),
+            Aggregate::Min => format!("MIN({column})"),
+            Aggregate::Max => format!("MAX({column})"),
+            Aggregate::Count => format!("COUNT({column})"),
+            Aggregate::CountDistinct => format!("COUNT(DISTINCT {column})"),
+            Aggregate::Median => format!("percentile_cont(0.5) WITHIN GROUP (ORDER BY {column})"),
+            Aggregate::Percentile => format!(
+                "percentile_cont({}) WITHIN GROUP (ORDER BY {column})",
+                f64::from(percentile.unwrap_or(50)) / 100.0
+            ),
+            Aggregate::StdDev => format!("stddev_samp({column})"),
+            Aggregate::Variance => format!("var_samp({column})"),
+            Aggregate::First => {
+                format!("(array_agg({column} ORDER BY {order_column} ASC NULLS LAST))[1]")
+            }
+            Aggregate::Last => {
+                format!("(array_agg({column} ORDER BY {order_column} DESC NULLS LAST))[1]")
+            }
         }
     }
 
     /// Get the SQL type of this aggregate based on field kind.
     pub fn get_sql_type(&self, field_kind: &FieldKind) -> &'static str {
         match self {
-            Aggregate::Sum | Aggregate::Average => match field_kind {
-                FieldKind::Float { .. } => "DOUBLE PRECISION",
-                _ => "NUMERIC",
-            },
-            Aggregate::Min | Aggregate::Max => field_kind.get_sql_type(),
-            Aggregate::Count => "BIGINT",
+            Aggregate::Sum | Aggregate::Average | Aggregate::StdDev | Aggregate::Variance => {
+                match *field_kind.value_kind() {
+                    FieldKind::Float { .. } => "DOUBLE PRECISION",
+                    _ => "NUMERIC",
+                }
+            }
+            Aggregate::Min | Aggregate::Max | Aggregate::First | Aggregate::Last => {
+                field_kind.get_sql_type()
+            }
+            Aggregate::Count | Aggregate::CountDistinct => "BIGINT",
+            Aggregate::Median | Aggregate::Percentile => "DOUBLE PRECISION",
         }
     }
 }
 
+/// Unit of time that timestamps are truncated to.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema, PartialEq, Eq, Hash)]
+pub enum TimeUnit {
+    Hour,
+    Day,
+    Week,
+    Month,
+    Quarter,
+    Year,
+}
+
+impl TimeUnit {
+    /// Get the `date_trunc` field of this unit.
+    pub fn get_sql_unit(&self) -> &'static str {
+        match self {
+            TimeUnit::Hour => "hour",
+            TimeUnit::Day => "day",
+            TimeUnit::Week => "week",
+            TimeUnit::Month => "month",
+            TimeUnit::Quarter => "quarter",
+            TimeUnit::Year => "year",
+        }
+    }
+}
+
+/// Time bucket of a `DateTime` axis.
+/// Weeks start on Monday.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq, Eq, Hash)]
+pub struct TimeBucket {
+    pub unit: TimeUnit,
+    /// IANA time zone name in which the buckets start.
+    #[serde(default = "TimeBucket::default_time_zone")]
+    pub time_zone: String,
+}
+
+impl TimeBucket {
+    fn default_time_zone() -> String {
+        "UTC".into()
+    }
+}
+
+/// Sort of the chart rows by an axis.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq, Eq, Hash)]
+pub struct AxisSort {
+    #[serde(default)]
+    pub direction: SortDirection,
+    /// Axes with a lower priority are sorted first.
+    #[serde(default)]
+    pub priority: i32,
+}
+
 /// Create axis request.
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow, JsonSchema, PartialEq, Eq, Hash)]
 pub struct CreateAxis {
     pub field_id: Id,
+    /// Tables joined along their parent from the chart table to the table of the field,
+    /// excluding the chart table. Empty if the field belongs to the chart table.
+    #[serde(default)]
+    pub table_path: Vec<Id>,
     pub axis_kind: AxisKind,
     pub aggregate: Option<Aggregate>,
+    /// Percentile of the `Percentile` aggregate, between 0 and 100.
+    #[serde(default)]
+    pub percentile: Option<i16>,
+    /// `DateTime` field ordering the rows of the `First` and `Last` aggregates.
+    #[serde(default)]
+    pub order_field_id: Option<Id>,
+    /// Order the rows of the chart by this axis.
+    #[serde(default)]
+    #[sqlx(json(nullable))]
+    pub sort: Option<AxisSort>,
+    /// Number of equal-width bins of the X axis of a `Histogram` chart.
+    /// Only for numeric fields.
+    #[serde(default)]
+    pub bins: Option<i32>,
+    /// Group the timestamps into buckets. Only for `DateTime` fields.
+    #[serde(default)]
+    #[sqlx(json(nullable))]
+    pub bucket: Option<TimeBucket>,
 }
 
 /// Set a chart's axes request.
@@ -1,12 +1,12 @@
 //! Types for dashboard charts.
 
-use super::AxisField;
+use super::{AxisField, AxisKind, ChartLayout};
 use crate::{Id, model::Cell};
 use chrono::{DateTime, Utc};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use sqlx::prelude::FromRow;
-use std::{collections::HashMap, fmt};
+use sqlx::{prelude::FromRow, types::Json};
+use std::{collections::HashMap, fmt, ops::RangeInclusive};
 
 /// Dashboard chart entity.
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow, JsonSchema, PartialEq)]
@@ -16,6 +16,12 @@ pub struct Chart {
     pub table_id: Id,
     pub name: String,
     pub chart_kind: ChartKind,
+    /// Only keep the first rows of the sorted chart data and aggregate the others.
+    /// Without sorted axes, the rows with the largest aggregated axes are kept.
+    pub top_n: Option<i32>,
+    /// Position of the chart on the dashboard grid, if it was placed.
+    #[schemars(with = "Option<ChartLayout>")]
+    pub layout: Option<Json<ChartLayout>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: Option<DateTime<Utc>>,
 }
@@ -27,6 +33,81 @@ pub enum ChartKind {
     Table,
     Bar,
     Line,
+    Area,
+    StackedBar,
+    Pie,
+    Donut,
+    Scatter,
+    /// Counts of the X axis values in equal-width bins computed by the server.
+    Histogram,
+    Heatmap,
+    /// Single aggregated value.
+    Kpi,
+}
+
+impl ChartKind {
+    /// Get the allowed number of axes of this kind.
+    pub fn axis_count(&self, axis_kind: AxisKind) -> RangeInclusive<usize> {
+        const ANY: RangeInclusive<usize> = 0..=usize::MAX;
+        match (self, axis_kind) {
+            (ChartKind::Table, _) => ANY,
+            (
+                ChartKind::Bar | ChartKind::Line | ChartKind::Area | ChartKind::StackedBar,
+                axis_kind,
+            ) => match axis_kind {
+                AxisKind::X => 1..=1,
+                AxisKind::Y => 1..=usize::MAX,
+                AxisKind::Color | AxisKind::Label => 0..=1,
+                AxisKind::Size => 0..=0,
+                AxisKind::Tooltip | AxisKind::Detail => ANY,
+            },
+            (ChartKind::Pie | ChartKind::Donut, axis_kind) => match axis_kind {
+                AxisKind::X | AxisKind::Y => 1..=1,
+                AxisKind::Label => 0..=1,
+                AxisKind::Color | AxisKind::Size => 0..=0,
+                AxisKind::Tooltip | AxisKind::Detail => ANY,
+            },
+            (ChartKind::Scatter, axis_kind) => match axis_kind {
+                AxisKind::X | AxisKind::Y => 1..=1,
+                AxisKind::Color | AxisKind::Size | AxisKind::Label => 0..=1,
+                AxisKind::Tooltip | AxisKind::Detail => ANY,
+            },
+            (ChartKind::Histogram, axis_kind) => match axis_kind {
+                AxisKind::X | AxisKind::Y => 1..=1,
+                AxisKind::Color => 0..=1,
+                AxisKind::Size | AxisKind::Label | AxisKind::Detail => 0..=0,
+                AxisKind::Tooltip => ANY,
+            },
+            (ChartKind::Heatmap, axis_kind) => match axis_kind {
+                AxisKind::X | AxisKind::Y | AxisKind::Color => 1..=1,
+                AxisKind::Label => 0..=1,
+                AxisKind::Size => 0..=0,
+                AxisKind::Tooltip | AxisKind::Detail => ANY,
+            },
+            (ChartKind::Kpi, axis_kind) => match axis_kind {
+                AxisKind::Y => 1..=1,
+                AxisKind::Label => 0..=1,
+                AxisKind::Tooltip => ANY,
+                AxisKind::X | AxisKind::Color | AxisKind::Size | AxisKind::Detail => 0..=0,
+            },
+        }
+    }
+
+    /// Get the kind of the axes holding the values of this chart,
+    /// which must be aggregated when the other axes are grouped.
+    pub fn value_axis_kind(&self) -> Option<AxisKind> {
+        match self {
+            ChartKind::Bar
+            | ChartKind::Line
+            | ChartKind::Area
+            | ChartKind::StackedBar
+            | ChartKind::Pie
+            | ChartKind::Donut
+            | ChartKind::Histogram => Some(AxisKind::Y),
+            ChartKind::Heatmap => Some(AxisKind::Color),
+            ChartKind::Table | ChartKind::Scatter | ChartKind::Kpi => None,
+        }
+    }
 }
 
 /// Create chart request.
@@ -35,6 +116,8 @@ pub struct CreateChart {
     pub table_id: Id,
     pub name: String,
     pub chart_kind: ChartKind,
+    #[serde(default)]
+    pub top_n: Option<i32>,
 }
 
 /// Update chart request.
@@ -42,6 +125,8 @@ pub struct CreateChart {
 pub struct UpdateChart {
     pub name: String,
     pub chart_kind: ChartKind,
+    #[serde(default)]
+    pub top_n: Option<i32>,
 }
 
 /// Chart ID path extractor.
@@ -57,6 +142,9 @@ pub struct ChartData {
     pub chart: Chart,
     pub axes: Vec<AxisField>,
     pub cells: Vec<HashMap<Id, Cell>>,
+    /// Aggregates of the rows beyond the top N, with null grouped axes.
+    /// Only set if the chart has a top N and there are more groups.
+    pub other: Option<HashMap<Id, Cell>>,
 }
 
 /// Database identifier of the actual SQL view that this user chart points to.
@@ -0,0 +1,124 @@
+//! Types for dashboard filter controls.
+
+use crate::{
+    Id,
+    model::data::{FieldKind, FilterOperator},
+};
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_with::{json::JsonString, serde_as};
+use sqlx::{FromRow, types::Json};
+
+/// Dashboard filter control entity.
+/// The control filters each bound chart on a field of the chart's table.
+#[derive(Debug, Serialize, Deserialize, FromRow, JsonSchema, PartialEq)]
+pub struct DashboardControl {
+    pub control_id: Id,
+    pub dashboard_id: Id,
+    pub name: String,
+    pub control_kind: ControlKind,
+    #[schemars(with = "Vec<ControlBinding>")]
+    pub bindings: Json<Vec<ControlBinding>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+/// The kind of dashboard filter control.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, JsonSchema, PartialEq, Eq)]
+#[sqlx(type_name = "control_kind")]
+pub enum ControlKind {
+    /// Date range picker for `DateTime` fields.
+    /// Takes the `Range` and `WithinLastDays` operators.
+    DateRange,
+    /// Number range for `Integer`, `Float`, `Money` and `Progress` fields.
+    /// Takes the `Range` operator.
+    NumberRange,
+    /// Selector of enumeration values by name for `Enumeration` fields.
+    /// Takes the `In` operator.
+    Enumeration,
+}
+
+impl ControlKind {
+    /// Return true if the control can be bound to fields of this kind.
+    pub fn supports_field(&self, field_kind: &FieldKind) -> bool {
+        let field_kind = &*field_kind.value_kind();
+        match self {
+            ControlKind::DateRange => matches!(field_kind, FieldKind::DateTime { .. }),
+            ControlKind::NumberRange => matches!(
+                field_kind,
+                FieldKind::Integer { .. }
+                    | FieldKind::Float { .. }
+                    | FieldKind::Money { .. }
+                    | FieldKind::Progress { .. }
+            ),
+            ControlKind::Enumeration => matches!(field_kind, FieldKind::Enumeration { .. }),
+        }
+    }
+
+    /// Return true if the control takes values with this operator.
+    pub fn supports_operator<T>(&self, operator: &FilterOperator<T>) -> bool {
+        match self {
+            ControlKind::DateRange => matches!(
+                operator,
+                FilterOperator::Range { .. } | FilterOperator::WithinLastDays { .. }
+            ),
+            ControlKind::NumberRange => matches!(operator, FilterOperator::Range { .. }),
+            ControlKind::Enumeration => matches!(operator, FilterOperator::In { .. }),
+        }
+    }
+}
+
+/// The field of a chart's table filtered by a dashboard control.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, JsonSchema, PartialEq, Eq)]
+pub struct ControlBinding {
+    pub chart_id: Id,
+    pub field_id: Id,
+}
+
+/// Create dashboard control request.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct CreateControl {
+    pub name: String,
+    pub control_kind: ControlKind,
+    #[serde(default)]
+    pub bindings: Vec<ControlBinding>,
+}
+
+/// Update dashboard control request.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct UpdateControl {
+    pub name: String,
+    pub control_kind: ControlKind,
+    #[serde(default)]
+    pub bindings: Vec<ControlBinding>,
+}
+
+/// Dashboard control ID path extractor.
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct SelectControl {
+    pub dashboard_id: Id,
+    pub control_id: Id,
+}
+
+/// Runtime value of a dashboard control.
+/// Values are given in the same JSON format as entry cells,
+/// except for enumeration values which are given by name.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct ControlFilter {
+    pub control_id: Id,
+    #[serde(flatten)]
+    pub operator: FilterOperator,
+}
+
+/// Chart data query parameters.
+#[serde_as]
+#[derive(Debug, Default, Serialize, Deserialize, JsonSchema)]
+pub struct ChartDataQuery {
+    /// JSON array of the values of the dashboard controls, applied on top of the
+    /// chart filters. Controls not bound to the chart are ignored.
+    #[serde(default)]
+    #[serde_as(as = "JsonString")]
+    #[schemars(with = "Option<String>")]
+    pub controls: Vec<ControlFilter>,
+}
@@ -12,6 +12,8 @@ pub struct Dashboard {
     pub dashboard_id: Id,
     pub name: String,
     pub description: String,
+    /// Names of the tabs in display order.
+    pub tabs: Vec<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: Option<DateTime<Utc>>,
 }
@@ -38,6 +40,64 @@ pub struct GetDashboard {
     pub access_role: AccessRole,
 }
 
+/// Number of columns of the dashboard grid.
+pub const GRID_COLUMNS: i32 = 12;
+
+/// Maximum number of rows of the dashboard grid.
+pub const GRID_ROWS: i32 = 1000;
+
+/// Position and size of a chart on the dashboard grid, in grid cells.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+pub struct ChartLayout {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+    /// Name of the dashboard tab containing the chart.
+    #[serde(default)]
+    pub tab: Option<String>,
+}
+
+impl ChartLayout {
+    /// Return true if the chart is not empty and fits in the columns and rows of the grid.
+    pub fn is_in_grid(&self) -> bool {
+        self.x >= 0
+            && self.y >= 0
+            && self.width > 0
+            && self.height > 0
+            && self.width <= GRID_COLUMNS - self.x
+            && self.height <= GRID_ROWS - self.y
+    }
+
+    /// Return true if both charts are in the same tab and share at least one grid cell.
+    /// The ends of the charts are computed in `i64`, so charts outside the grid do not overflow.
+    pub fn overlaps(&self, other: &ChartLayout) -> bool {
+        let end = |start: i32, size: i32| i64::from(start) + i64::from(size);
+        self.tab == other.tab
+            && i64::from(self.x) < end(other.x, other.width)
+            && i64::from(other.x) < end(self.x, self.width)
+            && i64::from(self.y) < end(other.y, other.height)
+            && i64::from(other.y) < end(self.y, self.height)
+    }
+}
+
+/// Layout of a chart in a set layout request.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SetChartLayout {
+    pub chart_id: Id,
+    #[serde(flatten)]
+    pub layout: ChartLayout,
+}
+
+/// Set a dashboard's layout request.
+/// The charts missing from the request are removed from the grid.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct SetLayout {
+    #[serde(default)]
+    pub tabs: Vec<String>,
+    pub charts: Vec<SetChartLayout>,
+}
+
 /// Dashboard ID path extractor.
 #[derive(Deserialize, JsonSchema)]
 pub struct SelectDashboard {
@@ -0,0 +1,28 @@
+//! Types for chart filters.
+
+use crate::{
+    Id,
+    model::data::{EntryFilter, FilterOperator},
+};
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, types::Json};
+
+/// Chart filter entity.
+/// Only the rows matching all the filters of a chart are aggregated.
+#[derive(Debug, Serialize, Deserialize, FromRow, JsonSchema, PartialEq)]
+pub struct ChartFilter {
+    pub chart_filter_id: Id,
+    pub chart_id: Id,
+    pub field_id: Id,
+    #[serde(flatten)]
+    #[schemars(with = "FilterOperator")]
+    pub operator: Json<FilterOperator>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+/// Set a chart's filters request.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct SetChartFilters(pub Vec<EntryFilter>);
@@ -2,6 +2,8 @@
 
 mod axes;
 mod charts;
+mod controls;
 mod dashboards;
+mod filters;
 
-pub use {axes::*, charts::*, dashboards::*};
+pub use {axes::*, charts::*, controls::*, dashboards::*, filters::*};